@@ -20,7 +20,6 @@ use cortex_a::asm;
 pub use asm::nop;
 
 /// Spin for `n` cycles.
-#[cfg(feature = "bsp_rpi3")]
 #[inline(always)]
 pub fn spin_for_cycles(n: usize) {
     for _ in 0..n {
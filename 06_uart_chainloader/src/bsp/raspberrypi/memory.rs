@@ -4,6 +4,8 @@
 
 //! BSP Memory Management.
 
+use core::ops::Range;
+
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
 //--------------------------------------------------------------------------------------------------
@@ -13,6 +15,10 @@
 pub(super) mod map {
     pub const BOARD_DEFAULT_LOAD_ADDRESS: usize =        0x8_0000;
 
+    /// Received payloads are buffered here, right below the loader's own link address.
+    pub const PAYLOAD_STAGING_START:      usize =        0x0100_0000;
+    pub const PAYLOAD_STAGING_END_EXCL:   usize =        0x0200_0000;
+
     pub const GPIO_OFFSET:                usize =        0x0020_0000;
     pub const UART_OFFSET:                usize =        0x0020_1000;
 
@@ -41,8 +47,17 @@ pub(super) mod map {
 // Public Code
 //--------------------------------------------------------------------------------------------------
 
-/// The address on which the Raspberry firmware loads every binary by default.
+/// The memory into which a payload is received before it is loaded.
+#[inline(always)]
+pub fn payload_staging_area() -> Range<usize> {
+    map::PAYLOAD_STAGING_START..map::PAYLOAD_STAGING_END_EXCL
+}
+
+/// The memory into which a payload may be loaded.
+///
+/// Starts at the default load address, so that the firmware's spin tables for the secondary cores
+/// below it stay intact.
 #[inline(always)]
-pub fn board_default_load_addr() -> *const u64 {
-    map::BOARD_DEFAULT_LOAD_ADDRESS as _
+pub fn payload_load_window() -> Range<usize> {
+    map::BOARD_DEFAULT_LOAD_ADDRESS..map::PAYLOAD_STAGING_START
 }
@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! ELF64 payload loading.
+//!
+//! Copies the file-backed part of every `PT_LOAD` segment to its physical address. Zero-initialized
+//! memory like `.bss` is left alone, because the payload clears it during its own runtime init,
+//! just like it has to when booted as a raw binary.
+
+use core::ops::Range;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ET_EXEC: u16 = 2;
+const EM_AARCH64: u16 = 183;
+
+const ELF64_PHDR_SIZE: usize = 56;
+
+const PT_LOAD: u32 = 1;
+
+/// The parts of a program header that are needed for loading.
+struct Segment {
+    offset: usize,
+    vaddr: usize,
+    paddr: usize,
+    file_size: usize,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, &'static str> {
+    offset
+        .checked_add(2)
+        .and_then(|end| data.get(offset..end))
+        .map(|x| u16::from_le_bytes(x.try_into().unwrap()))
+        .ok_or("ELF: Read out of bounds")
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, &'static str> {
+    offset
+        .checked_add(4)
+        .and_then(|end| data.get(offset..end))
+        .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+        .ok_or("ELF: Read out of bounds")
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<usize, &'static str> {
+    offset
+        .checked_add(8)
+        .and_then(|end| data.get(offset..end))
+        .map(|x| u64::from_le_bytes(x.try_into().unwrap()) as usize)
+        .ok_or("ELF: Read out of bounds")
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl Segment {
+    /// The physical address range of the segment, or `None` if it wraps around.
+    fn phys_range(&self) -> Option<Range<usize>> {
+        Some(self.paddr..self.paddr.checked_add(self.file_size)?)
+    }
+
+    /// Translate an address inside the segment's virtual range into its physical counterpart.
+    fn virt_to_phys(&self, addr: usize) -> Option<usize> {
+        if (self.vaddr..self.vaddr.checked_add(self.file_size)?).contains(&addr) {
+            (addr - self.vaddr).checked_add(self.paddr)
+        } else {
+            None
+        }
+    }
+}
+
+/// The file-backed `PT_LOAD` segments of a validated ELF file.
+fn load_segments(data: &[u8]) -> Result<impl Iterator<Item = Segment> + '_, &'static str> {
+    let phoff = read_u64(data, 32)?;
+    let phentsize = read_u16(data, 54)? as usize;
+    let phnum = read_u16(data, 56)? as usize;
+
+    if phentsize != ELF64_PHDR_SIZE {
+        return Err("ELF: Unexpected program header size");
+    }
+
+    let segments = (0..phnum)
+        .map(move |i| phoff + i * ELF64_PHDR_SIZE)
+        .filter(move |&ph| read_u32(data, ph) == Ok(PT_LOAD))
+        .map(move |ph| Segment {
+            offset: read_u64(data, ph + 8).unwrap_or(0),
+            vaddr: read_u64(data, ph + 16).unwrap_or(0),
+            paddr: read_u64(data, ph + 24).unwrap_or(0),
+            file_size: read_u64(data, ph + 32).unwrap_or(0),
+        })
+        .filter(|segment| segment.file_size != 0);
+
+    // Check that all program headers are readable. The iterator above can then ignore errors.
+    let ph_end = phnum
+        .checked_mul(ELF64_PHDR_SIZE)
+        .and_then(|x| x.checked_add(phoff))
+        .ok_or("ELF: Program header table overflow")?;
+    data.get(phoff..ph_end)
+        .ok_or("ELF: Program headers out of bounds")?;
+
+    Ok(segments)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Check whether `data` starts like an ELF file.
+pub fn is_elf(data: &[u8]) -> bool {
+    data.starts_with(&ELF_MAGIC)
+}
+
+/// Copy the loadable segments of an `aarch64` ELF executable to their physical addresses.
+///
+/// Every segment must lie inside of `window`. Returns the physical address of the entry point,
+/// which must be inside of a loaded segment. Malformed files, including ones whose offsets or
+/// addresses wrap around, are rejected before any memory is written.
+///
+/// # Safety
+///
+/// - `window` must be memory that can be overwritten and that does not overlap `data`.
+pub unsafe fn load(data: &[u8], window: Range<usize>) -> Result<usize, &'static str> {
+    if data.get(4) != Some(&ELFCLASS64) || data.get(5) != Some(&ELFDATA2LSB) {
+        return Err("ELF: Not a little endian ELF64 file");
+    }
+
+    if read_u16(data, 16)? != ET_EXEC || read_u16(data, 18)? != EM_AARCH64 {
+        return Err("ELF: Not an aarch64 executable");
+    }
+
+    // Validate everything before overwriting any memory.
+    for segment in load_segments(data)? {
+        let range = segment
+            .phys_range()
+            .ok_or("ELF: Segment address range overflow")?;
+
+        if range.start < window.start || range.end > window.end {
+            return Err("ELF: Segment outside of the load window");
+        }
+
+        segment
+            .offset
+            .checked_add(segment.file_size)
+            .and_then(|end| data.get(segment.offset..end))
+            .ok_or("ELF: Segment data out of bounds")?;
+    }
+
+    let entry = read_u64(data, 24)?;
+    let phys_entry = load_segments(data)?
+        .find_map(|segment| {
+            if segment.phys_range()?.contains(&entry) {
+                Some(entry)
+            } else {
+                segment.virt_to_phys(entry)
+            }
+        })
+        .ok_or("ELF: Entry point outside of the loaded segments")?;
+
+    for segment in load_segments(data)? {
+        let src = &data[segment.offset..segment.offset + segment.file_size];
+
+        core::ptr::copy_nonoverlapping(src.as_ptr(), segment.paddr as *mut u8, src.len());
+    }
+
+    Ok(phys_entry)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ELF64_HEADER_SIZE: usize = 64;
+    const TEST_FILE_SIZE: usize = ELF64_HEADER_SIZE + ELF64_PHDR_SIZE + 16;
+    const TEXT_VADDR: u64 = 0x8_0000;
+
+    /// Construct an executable with a single segment that holds the last 16 bytes of the file.
+    fn test_file(paddr: u64) -> [u8; TEST_FILE_SIZE] {
+        let mut f = [0_u8; TEST_FILE_SIZE];
+
+        f[0..4].copy_from_slice(&ELF_MAGIC);
+        f[4] = ELFCLASS64;
+        f[5] = ELFDATA2LSB;
+        f[16..18].copy_from_slice(&ET_EXEC.to_le_bytes());
+        f[18..20].copy_from_slice(&EM_AARCH64.to_le_bytes());
+        f[24..32].copy_from_slice(&(TEXT_VADDR + 4).to_le_bytes());
+        f[32..40].copy_from_slice(&(ELF64_HEADER_SIZE as u64).to_le_bytes());
+        f[54..56].copy_from_slice(&(ELF64_PHDR_SIZE as u16).to_le_bytes());
+        f[56..58].copy_from_slice(&1_u16.to_le_bytes());
+
+        let text = ELF64_HEADER_SIZE;
+        let data = ELF64_HEADER_SIZE + ELF64_PHDR_SIZE;
+        f[text..text + 4].copy_from_slice(&PT_LOAD.to_le_bytes());
+        f[text + 8..text + 16].copy_from_slice(&(data as u64).to_le_bytes());
+        f[text + 16..text + 24].copy_from_slice(&TEXT_VADDR.to_le_bytes());
+        f[text + 24..text + 32].copy_from_slice(&paddr.to_le_bytes());
+        f[text + 32..text + 40].copy_from_slice(&16_u64.to_le_bytes());
+
+        for (i, byte) in f[data..].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        f
+    }
+
+    /// Check that a segment is copied and the entry point is translated.
+    #[test]
+    fn elf_load_sanity() {
+        let mut memory = [0_u8; 16];
+        let paddr = memory.as_mut_ptr() as usize;
+        let file = test_file(paddr as u64);
+
+        let entry = unsafe { load(&file, paddr..paddr + memory.len()) };
+
+        assert_eq!(entry, Ok(paddr + 4));
+        assert_eq!(memory, file[TEST_FILE_SIZE - 16..]);
+    }
+
+    /// Check that a segment whose physical address range wraps around is rejected.
+    #[test]
+    fn elf_load_rejects_wrapping_segment() {
+        let file = test_file(u64::MAX - 7);
+
+        let result = unsafe { load(&file, 0..usize::MAX) };
+
+        assert_eq!(result, Err("ELF: Segment address range overflow"));
+    }
+
+    /// Check that a program header table which wraps around is rejected.
+    #[test]
+    fn elf_load_rejects_wrapping_program_headers() {
+        let mut file = test_file(0);
+        file[32..40].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        let result = unsafe { load(&file, 0..usize::MAX) };
+
+        assert_eq!(result, Err("ELF: Program header table overflow"));
+    }
+}
@@ -105,7 +105,6 @@
 //! 2. Once finished with architectural setup, the arch code calls `kernel_init()`.
 
 #![allow(clippy::upper_case_acronyms)]
-#![feature(const_fn_fn_ptr_basics)]
 #![feature(format_args_nl)]
 #![feature(panic_info_message)]
 #![feature(trait_alias)]
@@ -116,9 +115,11 @@ mod bsp;
 mod console;
 mod cpu;
 mod driver;
+mod elf;
 mod panic_wait;
 mod print;
 mod synchronization;
+mod xmodem;
 
 /// Early init code.
 ///
@@ -163,29 +164,39 @@ fn kernel_main() -> ! {
     console().clear_rx();
 
     // Notify `Minipush` to send the binary.
-    for _ in 0..3 {
-        console().write_char(3 as char);
+    for c in chainload_protocol::REQUEST_TOKEN {
+        console().write_char(c as char);
     }
 
-    // Read the binary's size.
-    let mut size: u32 = u32::from(console().read_char() as u8);
-    size |= u32::from(console().read_char() as u8) << 8;
-    size |= u32::from(console().read_char() as u8) << 16;
-    size |= u32::from(console().read_char() as u8) << 24;
-
-    // Trust it's not too big.
-    console().write_char('O');
-    console().write_char('K');
-
-    let kernel_addr: *mut u8 = bsp::memory::board_default_load_addr() as *mut u8;
-    unsafe {
-        // Read the kernel byte by byte.
-        for i in 0..size {
-            core::ptr::write_volatile(kernel_addr.offset(i as isize), console().read_char() as u8)
+    // Receive the binary with XMODEM-CRC.
+    let staging = bsp::memory::payload_staging_area();
+    let size = match unsafe { xmodem::receive(staging.start as *mut u8, staging.len()) } {
+        Ok(size) => size,
+        Err(x) => panic!("Receiving the binary failed: {}", x),
+    };
+    let payload = unsafe { core::slice::from_raw_parts(staging.start as *const u8, size) };
+
+    // ELF files describe their load addresses themselves. Raw binaries go to the start of the load
+    // window, where the firmware would have put them.
+    let window = bsp::memory::payload_load_window();
+    let kernel_addr = if elf::is_elf(payload) {
+        match unsafe { elf::load(payload, window) } {
+            Ok(entry) => entry,
+            Err(x) => panic!("Loading the binary failed: {}", x),
         }
-    }
+    } else {
+        if size > window.len() {
+            panic!("Loading the binary failed: Too big for the load window");
+        }
+
+        unsafe { core::ptr::copy_nonoverlapping(payload.as_ptr(), window.start as *mut u8, size) };
+        window.start
+    };
 
-    println!("[ML] Loaded! Executing the payload now\n");
+    println!(
+        "[ML] Loaded! Executing the payload at {:#x} now\n",
+        kernel_addr
+    );
     console().flush();
 
     // Use black magic to create a function pointer.
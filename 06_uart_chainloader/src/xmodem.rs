@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! XMODEM-CRC receiver.
+//!
+//! The sender transmits the payload in numbered blocks of 128 (`SOH`) or 1024 (`STX`) bytes, each
+//! protected by a CRC-16. The receiver starts the transfer by sending `C`, acknowledges every
+//! intact block with `ACK` and requests a retransmission with `NAK`. The sender ends the transfer
+//! with `EOT`. The last block is padded, so the received data can be up to 1023 bytes longer than
+//! the payload.
+//!
+//! The protocol definitions are shared with the host's push tool through the `chainload-protocol`
+//! crate.
+
+use crate::{bsp, console, cpu};
+use chainload_protocol::{
+    crc16, ACK, BLOCK_SIZE, CAN, CRC_MODE, EOT, MAX_RETRIES, NAK, SMALL_BLOCK_SIZE, SOH, STX,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The outcome of receiving a block.
+enum Block {
+    /// An intact block with the expected block number.
+    Next(usize),
+
+    /// An intact retransmission of the previous block, whose `ACK` was lost.
+    Repeated,
+
+    /// A corrupted or out of sequence block.
+    Bad,
+
+    /// The sender finished the transfer.
+    End,
+
+    /// The sender cancelled the transfer.
+    Cancelled,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn read_byte() -> u8 {
+    use console::interface::Read;
+
+    bsp::console::console().read_char() as u8
+}
+
+fn write_byte(byte: u8) {
+    use console::interface::Write;
+
+    bsp::console::console().write_char(byte as char);
+}
+
+/// Discard received data until the line has been quiet for a while.
+///
+/// After a corrupted block, remainders of it or of a retransmission might still be in flight. They
+/// must not be mistaken for the start of the next block.
+fn purge() {
+    use console::interface::Read;
+
+    for _ in 0..16 {
+        cpu::spin_for_cycles(1_000_000);
+        bsp::console::console().clear_rx();
+    }
+}
+
+/// Receive a block into `buf` and check it against the expected block number.
+fn receive_block(buf: &mut [u8; BLOCK_SIZE], expected: u8) -> Block {
+    let len = match read_byte() {
+        SOH => SMALL_BLOCK_SIZE,
+        STX => BLOCK_SIZE,
+        EOT => return Block::End,
+        CAN => return Block::Cancelled,
+        _ => return Block::Bad,
+    };
+
+    let number = read_byte();
+    let number_complement = read_byte();
+
+    for byte in buf[..len].iter_mut() {
+        *byte = read_byte();
+    }
+
+    let crc = u16::from_be_bytes([read_byte(), read_byte()]);
+
+    if number != !number_complement || crc != crc16(&buf[..len]) {
+        return Block::Bad;
+    }
+
+    if number == expected {
+        Block::Next(len)
+    } else if number == expected.wrapping_sub(1) {
+        Block::Repeated
+    } else {
+        Block::Bad
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Receive a payload of up to `max_len` bytes and store it at `dst`.
+///
+/// Returns the number of received bytes, including the padding of the last block.
+///
+/// # Safety
+///
+/// - `dst` must be valid for writes of `max_len` bytes.
+pub unsafe fn receive(dst: *mut u8, max_len: usize) -> Result<usize, &'static str> {
+    let mut buf = [0_u8; BLOCK_SIZE];
+    let mut expected: u8 = 1;
+    let mut received: usize = 0;
+    let mut retries = 0;
+
+    write_byte(CRC_MODE);
+
+    loop {
+        match receive_block(&mut buf, expected) {
+            Block::Next(len) => {
+                if received.checked_add(len).map_or(true, |end| end > max_len) {
+                    write_byte(CAN);
+                    write_byte(CAN);
+
+                    return Err("Payload too big");
+                }
+
+                for (i, &byte) in buf[..len].iter().enumerate() {
+                    core::ptr::write_volatile(dst.add(received + i), byte);
+                }
+
+                received += len;
+                expected = expected.wrapping_add(1);
+                retries = 0;
+                write_byte(ACK);
+            }
+            Block::Repeated => write_byte(ACK),
+            Block::Bad => {
+                retries += 1;
+                if retries > MAX_RETRIES {
+                    write_byte(CAN);
+                    write_byte(CAN);
+
+                    return Err("Too many corrupted blocks");
+                }
+
+                purge();
+                write_byte(NAK);
+            }
+            Block::End => {
+                write_byte(ACK);
+
+                return Ok(received);
+            }
+            Block::Cancelled => return Err("Transfer cancelled by the sender"),
+        }
+    }
+}
@@ -42,7 +42,7 @@ macro_rules! println {
 macro_rules! info {
     ($string:expr) => ({
         #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
+        use $crate::time::interface::TimeManager;
 
         let timestamp = $crate::time::time_manager().uptime();
         let timestamp_subsec_us = timestamp.subsec_micros();
@@ -56,7 +56,7 @@ macro_rules! info {
     });
     ($format_string:expr, $($arg:tt)*) => ({
         #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
+        use $crate::time::interface::TimeManager;
 
         let timestamp = $crate::time::time_manager().uptime();
         let timestamp_subsec_us = timestamp.subsec_micros();
@@ -76,7 +76,7 @@ macro_rules! info {
 macro_rules! warn {
     ($string:expr) => ({
         #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
+        use $crate::time::interface::TimeManager;
 
         let timestamp = $crate::time::time_manager().uptime();
         let timestamp_subsec_us = timestamp.subsec_micros();
@@ -90,7 +90,7 @@ macro_rules! warn {
     });
     ($format_string:expr, $($arg:tt)*) => ({
         #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
+        use $crate::time::interface::TimeManager;
 
         let timestamp = $crate::time::time_manager().uptime();
         let timestamp_subsec_us = timestamp.subsec_micros();
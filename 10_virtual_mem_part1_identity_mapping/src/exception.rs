@@ -21,7 +21,7 @@ pub use arch_exception::current_privilege_level;
 
 /// Kernel privilege levels.
 #[allow(missing_docs)]
-#[derive(PartialEq)]
+#[derive(PartialEq, Eq)]
 pub enum PrivilegeLevel {
     User,
     Kernel,
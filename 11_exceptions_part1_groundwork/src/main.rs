@@ -106,7 +106,6 @@
 
 #![allow(clippy::upper_case_acronyms)]
 #![allow(incomplete_features)]
-#![feature(const_fn_fn_ptr_basics)]
 #![feature(core_intrinsics)]
 #![feature(format_args_nl)]
 #![feature(panic_info_message)]
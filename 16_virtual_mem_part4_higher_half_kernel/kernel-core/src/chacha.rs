@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! ChaCha20-based random number generation.
+//!
+//! [ChaChaRng] uses fast key erasure: every request first derives the key for the next request, so
+//! that a leaked state does not reveal earlier output.
+//!
+//! # Resources
+//!
+//! - <https://www.rfc-editor.org/rfc/rfc8439>
+//! - <https://blog.cr.yp.to/20170723-random.html>
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// "expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// The nonce of the blocks that derive the next key.
+const REKEY_NONCE: [u32; 3] = [0, 0, 0];
+
+/// The nonce of the blocks that are handed out.
+const OUTPUT_NONCE: [u32; 3] = [1, 0, 0];
+
+/// The nonce of the blocks that stir in new entropy.
+const RESEED_NONCE: [u32; 3] = [2, 0, 0];
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The size of a ChaCha20 key in bytes.
+pub const KEY_SIZE: usize = 32;
+
+/// The size of a ChaCha20 block in bytes.
+pub const BLOCK_SIZE: usize = 64;
+
+/// A random number generator around a ChaCha20 key.
+#[derive(Clone)]
+pub struct ChaChaRng {
+    key: [u32; 8],
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// The ChaCha20 block function.
+pub fn chacha20_block(key: &[u32; 8], counter: u32, nonce: &[u32; 3]) -> [u32; 16] {
+    let mut initial = [0; 16];
+    initial[..4].copy_from_slice(&CONSTANTS);
+    initial[4..12].copy_from_slice(key);
+    initial[12] = counter;
+    initial[13..].copy_from_slice(nonce);
+
+    let mut s = initial;
+    for _ in 0..10 {
+        quarter_round(&mut s, 0, 4, 8, 12);
+        quarter_round(&mut s, 1, 5, 9, 13);
+        quarter_round(&mut s, 2, 6, 10, 14);
+        quarter_round(&mut s, 3, 7, 11, 15);
+        quarter_round(&mut s, 0, 5, 10, 15);
+        quarter_round(&mut s, 1, 6, 11, 12);
+        quarter_round(&mut s, 2, 7, 8, 13);
+        quarter_round(&mut s, 3, 4, 9, 14);
+    }
+
+    for (x, i) in s.iter_mut().zip(initial) {
+        *x = x.wrapping_add(i);
+    }
+
+    s
+}
+
+impl ChaChaRng {
+    /// Create an instance with an all-zero key.
+    ///
+    /// The output is predictable until enough entropy was added with [ChaChaRng::reseed()].
+    pub const fn new() -> Self {
+        Self { key: [0; 8] }
+    }
+
+    /// Mix `data` into the key.
+    ///
+    /// `data` is absorbed in key-sized chunks. Each chunk is XORed into the key, which is then
+    /// replaced by a block derived from it.
+    pub fn reseed(&mut self, data: &[u8]) {
+        for chunk in data.chunks(KEY_SIZE) {
+            for (i, byte) in chunk.iter().enumerate() {
+                self.key[i / 4] ^= u32::from(*byte) << (8 * (i % 4));
+            }
+
+            let block = chacha20_block(&self.key, 0, &RESEED_NONCE);
+            self.key.copy_from_slice(&block[..8]);
+        }
+    }
+
+    /// Fill `buf` with random bytes.
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        let key = self.key;
+
+        let next = chacha20_block(&key, 0, &REKEY_NONCE);
+        self.key.copy_from_slice(&next[..8]);
+
+        for (counter, chunk) in buf.chunks_mut(BLOCK_SIZE).enumerate() {
+            let block = chacha20_block(&key, counter as u32, &OUTPUT_NONCE);
+            let bytes = block.iter().flat_map(|word| word.to_le_bytes());
+
+            for (dst, src) in chunk.iter_mut().zip(bytes) {
+                *dst = src;
+            }
+        }
+    }
+}
+
+impl Default for ChaChaRng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The block function test vector from RFC 8439, section 2.3.2.
+    #[test]
+    fn chacha20_block_matches_rfc8439() {
+        let key = [
+            0x0302_0100,
+            0x0706_0504,
+            0x0b0a_0908,
+            0x0f0e_0d0c,
+            0x1312_1110,
+            0x1716_1514,
+            0x1b1a_1918,
+            0x1f1e_1d1c,
+        ];
+        let nonce = [0x0900_0000, 0x4a00_0000, 0x0000_0000];
+
+        let expected = [
+            0xe4e7_f110,
+            0x1559_3bd1,
+            0x1fdd_0f50,
+            0xc471_20a3,
+            0xc7f4_d1c7,
+            0x0368_c033,
+            0x9aaa_2204,
+            0x4e6c_d4c3,
+            0x4664_82d2,
+            0x09aa_9f07,
+            0x05d7_c214,
+            0xa202_8bd9,
+            0xd19c_12b5,
+            0xb94e_16de,
+            0xe883_d0cb,
+            0x4e3c_50a2,
+        ];
+
+        assert_eq!(chacha20_block(&key, 1, &nonce), expected);
+    }
+
+    /// Consecutive requests and different seeds must give different output.
+    #[test]
+    fn chacha_rng_output_changes() {
+        let mut a = ChaChaRng::new();
+        let mut b = ChaChaRng::new();
+        b.reseed(b"entropy");
+
+        let mut first = [0; 100];
+        let mut second = [0; 100];
+        a.fill(&mut first);
+        a.fill(&mut second);
+        assert_ne!(first, second);
+
+        let mut other = [0; 100];
+        b.fill(&mut other);
+        assert_ne!(first, other);
+
+        // The same seed gives the same output.
+        let mut c = ChaChaRng::new();
+        c.reseed(b"entropy");
+        let mut again = [0; 100];
+        c.fill(&mut again);
+        assert_eq!(other, again);
+    }
+}
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2020-2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! General purpose code.
+
+/// Check if a value is aligned to a given size.
+#[inline(always)]
+pub const fn is_aligned(value: usize, alignment: usize) -> bool {
+    assert!(alignment.is_power_of_two());
+
+    (value & (alignment - 1)) == 0
+}
+
+/// Align down.
+#[inline(always)]
+pub const fn align_down(value: usize, alignment: usize) -> usize {
+    assert!(alignment.is_power_of_two());
+
+    value & !(alignment - 1)
+}
+
+/// Align up.
+///
+/// Panics if the aligned value does not fit into a `usize`.
+#[inline(always)]
+pub const fn align_up(value: usize, alignment: usize) -> usize {
+    assert!(alignment.is_power_of_two());
+
+    match value.checked_add(alignment - 1) {
+        None => panic!("Overflow on align_up"),
+        Some(x) => x & !(alignment - 1),
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare against the definitions of alignment for all small values and alignments.
+    #[test]
+    fn alignment_exhaustive() {
+        for shift in 0..12 {
+            let alignment = 1 << shift;
+
+            for value in 0..(8 * 4096) {
+                let down = align_down(value, alignment);
+                let up = align_up(value, alignment);
+
+                assert_eq!(is_aligned(value, alignment), value % alignment == 0);
+                assert_eq!(down, value - (value % alignment));
+                assert!(is_aligned(down, alignment) && is_aligned(up, alignment));
+                assert!(down <= value && value <= up);
+                assert!(up - down == 0 || up - down == alignment);
+                assert_eq!(up == value, is_aligned(value, alignment));
+            }
+        }
+    }
+
+    /// The largest values must not wrap around.
+    #[test]
+    fn alignment_at_the_top() {
+        assert_eq!(align_down(usize::MAX, 4096), usize::MAX - 4095);
+        assert_eq!(align_up(usize::MAX - 4095, 4096), usize::MAX - 4095);
+        assert_eq!(align_up(usize::MAX, 1), usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Overflow on align_up")]
+    fn align_up_overflow_panics() {
+        align_up(usize::MAX - 4094, 4096);
+    }
+
+    #[test]
+    #[should_panic]
+    fn alignment_must_be_power_of_two() {
+        is_aligned(0, 3);
+    }
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architecture and board independent kernel code.
+//!
+//! Everything in here is plain computation without any access to hardware: address and page math,
+//! the page allocator, ring buffers and the ChaCha20 random number generator. The kernel re-exports
+//! these items from its own subsystem modules, for example `memory::Address` or
+//! `synchronization::ringbuffer`.
+//!
+//! The crate is `no_std` when used by the kernel, but is built with `std` for its unit tests. That
+//! way, the tests run on the host with a plain `cargo test` and do not need QEMU:
+//!
+//! ```console
+//! $ cd kernel-core
+//! $ cargo test
+//! ```
+
+#![cfg_attr(not(test), no_std)]
+#![feature(step_trait)]
+
+pub mod chacha;
+pub mod common;
+pub mod memory;
+pub mod ringbuffer;
@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2018-2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Memory Management.
+
+pub mod mmu;
+
+use crate::{common, memory::mmu::PageGranule};
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::{Add, Sub},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Metadata trait for marking the type of an address.
+pub trait AddressType: Copy + Clone + PartialOrd + PartialEq {}
+
+/// Zero-sized type to mark a physical address.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+pub enum Physical {}
+
+/// Zero-sized type to mark a virtual address.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+pub enum Virtual {}
+
+/// Generic address type.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+pub struct Address<ATYPE: AddressType> {
+    value: usize,
+    _address_type: PhantomData<fn() -> ATYPE>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl AddressType for Physical {}
+impl AddressType for Virtual {}
+
+impl<ATYPE: AddressType> Address<ATYPE> {
+    /// Create an instance.
+    pub const fn new(value: usize) -> Self {
+        Self {
+            value,
+            _address_type: PhantomData,
+        }
+    }
+
+    /// Convert to usize.
+    pub const fn as_usize(self) -> usize {
+        self.value
+    }
+
+    /// Align down to page size.
+    #[must_use]
+    pub const fn align_down_page(self) -> Self {
+        let aligned = common::align_down(self.value, PageGranule::SIZE);
+
+        Self::new(aligned)
+    }
+
+    /// Align up to page size.
+    #[must_use]
+    pub const fn align_up_page(self) -> Self {
+        let aligned = common::align_up(self.value, PageGranule::SIZE);
+
+        Self::new(aligned)
+    }
+
+    /// Checks if the address is page aligned.
+    pub const fn is_page_aligned(&self) -> bool {
+        common::is_aligned(self.value, PageGranule::SIZE)
+    }
+
+    /// Return the address' offset into the corresponding page.
+    pub const fn offset_into_page(&self) -> usize {
+        self.value & PageGranule::MASK
+    }
+}
+
+impl<ATYPE: AddressType> Add<usize> for Address<ATYPE> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: usize) -> Self::Output {
+        match self.value.checked_add(rhs) {
+            None => panic!("Overflow on Address::add"),
+            Some(x) => Self::new(x),
+        }
+    }
+}
+
+impl<ATYPE: AddressType> Sub<Address<ATYPE>> for Address<ATYPE> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Address<ATYPE>) -> Self::Output {
+        match self.value.checked_sub(rhs.value) {
+            None => panic!("Overflow on Address::sub"),
+            Some(x) => Self::new(x),
+        }
+    }
+}
+
+impl fmt::Display for Address<Physical> {
+    // Don't expect to see physical addresses greater than 40 bit.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let q3: u8 = ((self.value >> 32) & 0xff) as u8;
+        let q2: u16 = ((self.value >> 16) & 0xffff) as u16;
+        let q1: u16 = (self.value & 0xffff) as u16;
+
+        write!(f, "0x")?;
+        write!(f, "{:02x}_", q3)?;
+        write!(f, "{:04x}_", q2)?;
+        write!(f, "{:04x}", q1)
+    }
+}
+
+impl fmt::Display for Address<Virtual> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let q4: u16 = ((self.value >> 48) & 0xffff) as u16;
+        let q3: u16 = ((self.value >> 32) & 0xffff) as u16;
+        let q2: u16 = ((self.value >> 16) & 0xffff) as u16;
+        let q1: u16 = (self.value & 0xffff) as u16;
+
+        write!(f, "0x")?;
+        write!(f, "{:04x}_", q4)?;
+        write!(f, "{:04x}_", q3)?;
+        write!(f, "{:04x}_", q2)?;
+        write!(f, "{:04x}", q1)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sanity of [Address] methods.
+    #[test]
+    fn address_type_method_sanity() {
+        let addr = Address::<Virtual>::new(PageGranule::SIZE + 100);
+
+        assert_eq!(addr.align_down_page().as_usize(), PageGranule::SIZE);
+
+        assert_eq!(addr.align_up_page().as_usize(), PageGranule::SIZE * 2);
+
+        assert!(!addr.is_page_aligned());
+
+        assert_eq!(addr.offset_into_page(), 100);
+    }
+
+    /// Page rounding around every page boundary of the first pages.
+    #[test]
+    fn address_page_rounding_exhaustive() {
+        for value in 0..(4 * PageGranule::SIZE) {
+            let addr = Address::<Physical>::new(value);
+            let page_start = value / PageGranule::SIZE * PageGranule::SIZE;
+
+            assert_eq!(addr.align_down_page().as_usize(), page_start);
+            assert_eq!(addr.offset_into_page(), value - page_start);
+            assert_eq!(addr.is_page_aligned(), value == page_start);
+
+            let up = addr.align_up_page().as_usize();
+            if value == page_start {
+                assert_eq!(up, value);
+            } else {
+                assert_eq!(up, page_start + PageGranule::SIZE);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Overflow on Address::add")]
+    fn address_add_overflow_panics() {
+        let _ = Address::<Virtual>::new(usize::MAX) + 1;
+    }
+
+    #[test]
+    #[should_panic(expected = "Overflow on Address::sub")]
+    fn address_sub_overflow_panics() {
+        let _ = Address::<Virtual>::new(0) - Address::new(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Overflow on align_up")]
+    fn address_align_up_overflow_panics() {
+        let _ = Address::<Virtual>::new(usize::MAX).align_up_page();
+    }
+
+    /// Physical addresses are shown with 40 bit, virtual ones with 64 bit.
+    #[test]
+    fn address_display() {
+        assert_eq!(
+            Address::<Physical>::new(0xfe_0020_1000).to_string(),
+            "0xfe_0020_1000"
+        );
+        assert_eq!(
+            Address::<Virtual>::new(0xffff_ffff_c008_0000).to_string(),
+            "0xffff_ffff_c008_0000"
+        );
+    }
+}
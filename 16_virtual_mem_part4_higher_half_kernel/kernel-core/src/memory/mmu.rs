@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2020-2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Memory Management Unit math.
+
+mod alloc;
+mod types;
+
+pub use alloc::*;
+pub use types::*;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Describes the characteristics of a translation granule.
+pub struct TranslationGranule<const GRANULE_SIZE: usize>;
+
+/// The granule that all page-based types, for example [PageAddress], derive their page size from.
+///
+/// BSPs must use this granule for the kernel's translation tables.
+pub type PageGranule = TranslationGranule<{ 64 * 1024 }>;
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl<const GRANULE_SIZE: usize> TranslationGranule<GRANULE_SIZE> {
+    /// The granule's size.
+    pub const SIZE: usize = Self::size_checked();
+
+    /// The granule's mask.
+    pub const MASK: usize = Self::SIZE - 1;
+
+    /// The granule's shift, aka log2(size).
+    pub const SHIFT: usize = Self::SIZE.trailing_zeros() as usize;
+
+    const fn size_checked() -> usize {
+        assert!(GRANULE_SIZE.is_power_of_two());
+
+        GRANULE_SIZE
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The derived constants must agree with each other.
+    #[test]
+    fn translation_granule_constants() {
+        assert_eq!(PageGranule::SIZE, 65536);
+        assert_eq!(PageGranule::MASK, 0xffff);
+        assert_eq!(PageGranule::SHIFT, 16);
+
+        assert_eq!(TranslationGranule::<{ 512 * 1024 * 1024 }>::SHIFT, 29);
+        assert_eq!(TranslationGranule::<1>::MASK, 0);
+    }
+}
@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2021-2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Allocation.
+
+use super::MemoryRegion;
+use crate::memory::AddressType;
+use core::num::NonZeroUsize;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A page allocator that can be lazyily initialized.
+pub struct PageAllocator<ATYPE: AddressType> {
+    pool: Option<MemoryRegion<ATYPE>>,
+    num_total_pages: usize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl<ATYPE: AddressType> PageAllocator<ATYPE> {
+    /// Create an instance.
+    pub const fn new() -> Self {
+        Self {
+            pool: None,
+            num_total_pages: 0,
+        }
+    }
+
+    /// Initialize the allocator.
+    ///
+    /// An allocator can only be initialized once. Later calls leave the pool untouched.
+    pub fn initialize(&mut self, pool: MemoryRegion<ATYPE>) -> Result<(), &'static str> {
+        if self.pool.is_some() {
+            return Err("Already initialized");
+        }
+
+        self.num_total_pages = pool.num_pages();
+        self.pool = Some(pool);
+
+        Ok(())
+    }
+
+    /// The number of pages that the pool had when it was initialized.
+    pub fn num_total_pages(&self) -> usize {
+        self.num_total_pages
+    }
+
+    /// The number of pages that are still free.
+    pub fn num_free_pages(&self) -> usize {
+        self.pool.as_ref().map_or(0, |pool| pool.num_pages())
+    }
+
+    /// Allocate a number of pages.
+    pub fn alloc(
+        &mut self,
+        num_requested_pages: NonZeroUsize,
+    ) -> Result<MemoryRegion<ATYPE>, &'static str> {
+        if self.pool.is_none() {
+            return Err("Allocator not initialized");
+        }
+
+        self.pool
+            .as_mut()
+            .unwrap()
+            .take_first_n_pages(num_requested_pages)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{mmu::PageAddress, Virtual};
+
+    fn pages(num: usize) -> NonZeroUsize {
+        NonZeroUsize::new(num).unwrap()
+    }
+
+    /// Allocations are handed out back to back until the pool is exhausted.
+    #[test]
+    fn page_allocator_sanity() {
+        let mut allocator = PageAllocator::<Virtual>::new();
+        assert_eq!(allocator.alloc(pages(1)), Err("Allocator not initialized"));
+
+        let start = PageAddress::from(0x10_0000);
+        let end = start.checked_offset(4).unwrap();
+        assert_eq!(allocator.initialize(MemoryRegion::new(start, end)), Ok(()));
+        assert_eq!(
+            allocator.initialize(MemoryRegion::new(end, end)),
+            Err("Already initialized")
+        );
+
+        assert_eq!(allocator.num_total_pages(), 4);
+        assert_eq!(allocator.num_free_pages(), 4);
+
+        let first = allocator.alloc(pages(3)).unwrap();
+        assert_eq!(first.start_page_addr(), start);
+        assert_eq!(first.num_pages(), 3);
+        assert_eq!(allocator.num_free_pages(), 1);
+
+        assert_eq!(allocator.alloc(pages(2)), Err("Not enough free pages"));
+
+        let second = allocator.alloc(pages(1)).unwrap();
+        assert_eq!(second.start_page_addr(), first.end_exclusive_page_addr());
+        assert_eq!(second.end_exclusive_page_addr(), end);
+
+        assert_eq!(allocator.alloc(pages(1)), Err("Not enough free pages"));
+        assert_eq!(allocator.num_free_pages(), 0);
+        assert_eq!(allocator.num_total_pages(), 4);
+    }
+}
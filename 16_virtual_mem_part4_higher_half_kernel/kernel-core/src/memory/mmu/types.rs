@@ -0,0 +1,383 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2020-2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Memory Management Unit types.
+
+use super::PageGranule;
+use crate::{
+    common,
+    memory::{Address, AddressType},
+};
+use core::{convert::From, iter::Step, num::NonZeroUsize, ops::Range};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A wrapper type around [Address] that ensures page alignment.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+pub struct PageAddress<ATYPE: AddressType> {
+    inner: Address<ATYPE>,
+}
+
+/// A type that describes a region of memory in quantities of pages.
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
+pub struct MemoryRegion<ATYPE: AddressType> {
+    start: PageAddress<ATYPE>,
+    end_exclusive: PageAddress<ATYPE>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+//------------------------------------------------------------------------------
+// PageAddress
+//------------------------------------------------------------------------------
+impl<ATYPE: AddressType> PageAddress<ATYPE> {
+    /// The largest value that can be represented by this type.
+    pub const MAX: Self = PageAddress {
+        inner: Address::new(usize::MAX).align_down_page(),
+    };
+
+    /// Unwraps the value.
+    pub fn into_inner(self) -> Address<ATYPE> {
+        self.inner
+    }
+
+    /// Calculates the offset from the page address.
+    ///
+    /// `count` is in units of [PageAddress]. For example, a count of 2 means `result = self + 2 *
+    /// page_size`.
+    pub fn checked_offset(self, count: isize) -> Option<Self> {
+        if count == 0 {
+            return Some(self);
+        }
+
+        let delta = count.unsigned_abs().checked_mul(PageGranule::SIZE)?;
+        let result = if count.is_positive() {
+            self.inner.as_usize().checked_add(delta)?
+        } else {
+            self.inner.as_usize().checked_sub(delta)?
+        };
+
+        Some(Self {
+            inner: Address::new(result),
+        })
+    }
+}
+
+impl<ATYPE: AddressType> From<usize> for PageAddress<ATYPE> {
+    fn from(addr: usize) -> Self {
+        assert!(
+            common::is_aligned(addr, PageGranule::SIZE),
+            "Input usize not page aligned"
+        );
+
+        Self {
+            inner: Address::new(addr),
+        }
+    }
+}
+
+impl<ATYPE: AddressType> From<Address<ATYPE>> for PageAddress<ATYPE> {
+    fn from(addr: Address<ATYPE>) -> Self {
+        assert!(addr.is_page_aligned(), "Input Address not page aligned");
+
+        Self { inner: addr }
+    }
+}
+
+impl<ATYPE: AddressType> Step for PageAddress<ATYPE> {
+    fn steps_between(start: &Self, end: &Self) -> Option<usize> {
+        if start > end {
+            return None;
+        }
+
+        // Since start <= end, do unchecked arithmetic.
+        Some((end.inner.as_usize() - start.inner.as_usize()) >> PageGranule::SHIFT)
+    }
+
+    fn forward_checked(start: Self, count: usize) -> Option<Self> {
+        start.checked_offset(isize::try_from(count).ok()?)
+    }
+
+    fn backward_checked(start: Self, count: usize) -> Option<Self> {
+        start.checked_offset(-isize::try_from(count).ok()?)
+    }
+}
+
+//------------------------------------------------------------------------------
+// MemoryRegion
+//------------------------------------------------------------------------------
+impl<ATYPE: AddressType> MemoryRegion<ATYPE> {
+    /// Create an instance.
+    pub fn new(start: PageAddress<ATYPE>, end_exclusive: PageAddress<ATYPE>) -> Self {
+        assert!(start <= end_exclusive);
+
+        Self {
+            start,
+            end_exclusive,
+        }
+    }
+
+    fn as_range(&self) -> Range<PageAddress<ATYPE>> {
+        self.into_iter()
+    }
+
+    /// Returns the start page address.
+    pub fn start_page_addr(&self) -> PageAddress<ATYPE> {
+        self.start
+    }
+
+    /// Returns the start address.
+    pub fn start_addr(&self) -> Address<ATYPE> {
+        self.start.into_inner()
+    }
+
+    /// Returns the exclusive end page address.
+    pub fn end_exclusive_page_addr(&self) -> PageAddress<ATYPE> {
+        self.end_exclusive
+    }
+
+    /// Returns the exclusive end page address.
+    pub fn end_inclusive_page_addr(&self) -> PageAddress<ATYPE> {
+        self.end_exclusive.checked_offset(-1).unwrap()
+    }
+
+    /// Checks if self contains an address.
+    pub fn contains(&self, addr: Address<ATYPE>) -> bool {
+        let page_addr = PageAddress::from(addr.align_down_page());
+        self.as_range().contains(&page_addr)
+    }
+
+    /// Checks if there is an overlap with another memory region.
+    ///
+    /// Empty regions do not overlap with anything.
+    pub fn overlaps(&self, other_region: &Self) -> bool {
+        let is_empty = |region: &Self| region.start == region.end_exclusive;
+
+        !is_empty(self)
+            && !is_empty(other_region)
+            && self.start < other_region.end_exclusive
+            && other_region.start < self.end_exclusive
+    }
+
+    /// Returns the number of pages contained in this region.
+    pub fn num_pages(&self) -> usize {
+        PageAddress::steps_between(&self.start, &self.end_exclusive).unwrap()
+    }
+
+    /// Returns the size in bytes of this region.
+    pub fn size(&self) -> usize {
+        // Invariant: start <= end_exclusive, so do unchecked arithmetic.
+        let end_exclusive = self.end_exclusive.into_inner().as_usize();
+        let start = self.start.into_inner().as_usize();
+
+        end_exclusive - start
+    }
+
+    /// Splits the MemoryRegion like:
+    ///
+    /// --------------------------------------------------------------------------------
+    /// |   |   |   |   |   |   |   |   |   |   |   |   |   |   |   |   |   |   |
+    /// --------------------------------------------------------------------------------
+    ///   ^                               ^                                       ^
+    ///   |                               |                                       |
+    ///   left_start     left_end_exclusive                                       |
+    ///                                                                           |
+    ///                                   ^                                       |
+    ///                                   |                                       |
+    ///                                   right_start           right_end_exclusive
+    ///
+    /// Left region is returned to the caller. Right region is the new region for this struct.
+    pub fn take_first_n_pages(&mut self, num_pages: NonZeroUsize) -> Result<Self, &'static str> {
+        let count: usize = num_pages.into();
+
+        let left_end_exclusive = self.start.checked_offset(count as isize);
+        let left_end_exclusive = match left_end_exclusive {
+            None => return Err("Overflow while calculating left_end_exclusive"),
+            Some(x) => x,
+        };
+
+        if left_end_exclusive > self.end_exclusive {
+            return Err("Not enough free pages");
+        }
+
+        let allocation = Self {
+            start: self.start,
+            end_exclusive: left_end_exclusive,
+        };
+        self.start = left_end_exclusive;
+
+        Ok(allocation)
+    }
+}
+
+impl<ATYPE: AddressType> IntoIterator for MemoryRegion<ATYPE> {
+    type Item = PageAddress<ATYPE>;
+    type IntoIter = Range<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Range {
+            start: self.start,
+            end: self.end_exclusive,
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Virtual;
+
+    /// Sanity of [PageAddress] methods.
+    #[test]
+    fn pageaddress_type_method_sanity() {
+        let page_addr: PageAddress<Virtual> = PageAddress::from(PageGranule::SIZE * 2);
+
+        assert_eq!(
+            page_addr.checked_offset(-2),
+            Some(PageAddress::<Virtual>::from(0))
+        );
+
+        assert_eq!(
+            page_addr.checked_offset(2),
+            Some(PageAddress::<Virtual>::from(PageGranule::SIZE * 4))
+        );
+
+        assert_eq!(
+            PageAddress::<Virtual>::from(0).checked_offset(0),
+            Some(PageAddress::<Virtual>::from(0))
+        );
+        assert_eq!(PageAddress::<Virtual>::from(0).checked_offset(-1), None);
+
+        let max_page_addr = Address::<Virtual>::new(usize::MAX).align_down_page();
+        assert_eq!(
+            PageAddress::<Virtual>::from(max_page_addr).checked_offset(1),
+            None
+        );
+
+        let zero = PageAddress::<Virtual>::from(0);
+        let three = PageAddress::<Virtual>::from(PageGranule::SIZE * 3);
+        assert_eq!(PageAddress::steps_between(&zero, &three), Some(3));
+    }
+
+    /// Sanity of [MemoryRegion] methods.
+    #[test]
+    fn memoryregion_type_method_sanity() {
+        let zero = PageAddress::<Virtual>::from(0);
+        let zero_region = MemoryRegion::new(zero, zero);
+        assert_eq!(zero_region.num_pages(), 0);
+        assert_eq!(zero_region.size(), 0);
+
+        let one = PageAddress::<Virtual>::from(PageGranule::SIZE);
+        let one_region = MemoryRegion::new(zero, one);
+        assert_eq!(one_region.num_pages(), 1);
+        assert_eq!(one_region.size(), PageGranule::SIZE);
+
+        let three = PageAddress::<Virtual>::from(PageGranule::SIZE * 3);
+        let mut three_region = MemoryRegion::new(zero, three);
+        assert!(three_region.contains(zero.into_inner()));
+        assert!(!three_region.contains(three.into_inner()));
+        assert!(three_region.overlaps(&one_region));
+
+        let allocation = three_region
+            .take_first_n_pages(NonZeroUsize::new(2).unwrap())
+            .unwrap();
+        assert_eq!(allocation.num_pages(), 2);
+        assert_eq!(three_region.num_pages(), 1);
+
+        for (count, i) in allocation.into_iter().enumerate() {
+            assert_eq!(i.into_inner().as_usize(), count * PageGranule::SIZE);
+        }
+    }
+
+    fn region(start: usize, end_exclusive: usize) -> MemoryRegion<Virtual> {
+        MemoryRegion::new(
+            PageAddress::from(start * PageGranule::SIZE),
+            PageAddress::from(end_exclusive * PageGranule::SIZE),
+        )
+    }
+
+    /// Offsets and steps must not wrap around at either end of the address space.
+    #[test]
+    fn pageaddress_offset_limits() {
+        let max = PageAddress::<Virtual>::MAX;
+        let zero = PageAddress::<Virtual>::from(0);
+
+        assert_eq!(max.checked_offset(1), None);
+        assert_eq!(max.checked_offset(isize::MAX), None);
+        assert_eq!(zero.checked_offset(isize::MIN), None);
+        assert_eq!(max.checked_offset(isize::MIN), None);
+
+        let num_pages = usize::MAX >> PageGranule::SHIFT;
+        assert_eq!(PageAddress::steps_between(&zero, &max), Some(num_pages));
+        assert_eq!(PageAddress::steps_between(&max, &zero), None);
+        assert_eq!(PageAddress::forward_checked(zero, num_pages), Some(max));
+        assert_eq!(PageAddress::forward_checked(zero, usize::MAX), None);
+        assert_eq!(PageAddress::backward_checked(max, num_pages), Some(zero));
+        assert_eq!(PageAddress::backward_checked(max, usize::MAX), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Input usize not page aligned")]
+    fn pageaddress_from_unaligned_usize_panics() {
+        let _ = PageAddress::<Virtual>::from(PageGranule::SIZE + 1);
+    }
+
+    /// Compare overlap and containment of all small regions against the page-wise definition.
+    #[test]
+    fn memoryregion_overlaps_exhaustive() {
+        const PAGES: usize = 6;
+
+        for (a_start, a_end) in (0..=PAGES).flat_map(|s| (s..=PAGES).map(move |e| (s, e))) {
+            let a = region(a_start, a_end);
+
+            for page in 0..=PAGES {
+                let addr = Address::new(page * PageGranule::SIZE + 1);
+                assert_eq!(a.contains(addr), (a_start..a_end).contains(&page));
+            }
+
+            for (b_start, b_end) in (0..=PAGES).flat_map(|s| (s..=PAGES).map(move |e| (s, e))) {
+                let b = region(b_start, b_end);
+                let shared_page = (a_start..a_end).any(|page| (b_start..b_end).contains(&page));
+
+                assert_eq!(a.overlaps(&b), shared_page);
+                assert_eq!(a.overlaps(&b), b.overlaps(&a));
+            }
+        }
+    }
+
+    /// Taking pages must split the region without losing or duplicating any page.
+    #[test]
+    fn memoryregion_take_first_n_pages_exhaustive() {
+        for num_pages in 0..8 {
+            for count in 1..10 {
+                let mut pool = region(3, 3 + num_pages);
+                let result = pool.take_first_n_pages(NonZeroUsize::new(count).unwrap());
+
+                if count > num_pages {
+                    assert_eq!(result, Err("Not enough free pages"));
+                    assert_eq!(pool, region(3, 3 + num_pages));
+                } else {
+                    let allocation = result.unwrap();
+                    assert_eq!(allocation, region(3, 3 + count));
+                    assert_eq!(pool, region(3 + count, 3 + num_pages));
+                    assert!(!allocation.overlaps(&pool));
+                }
+            }
+        }
+
+        let mut top = MemoryRegion::new(PageAddress::<Virtual>::MAX, PageAddress::MAX);
+        assert_eq!(
+            top.take_first_n_pages(NonZeroUsize::new(1).unwrap()),
+            Err("Overflow while calculating left_end_exclusive")
+        );
+    }
+}
@@ -0,0 +1,358 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Lock-free fixed-capacity ring buffers.
+//!
+//! Meant for handing data from IRQ context to regular kernel code, for example received
+//! characters. Neither side ever takes a lock, so producers running in IRQ context can not
+//! deadlock against the code they interrupted.
+//!
+//! Indices increase monotonically and wrap around on overflow. The slot of an index is the index
+//! modulo the capacity, which must therefore be a power of two.
+//!
+//! # Resources
+//!
+//!   - <https://www.1024cores.net/home/lock-free-algorithms/queues/bounded-mpmc-queue>
+
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A ring buffer for a single producer and a single consumer.
+pub struct SpscRingBuffer<T, const N: usize> {
+    /// Index of the next slot to read. Only written by the consumer.
+    head: AtomicUsize,
+
+    /// Index of the next slot to write. Only written by the producer.
+    tail: AtomicUsize,
+
+    slots: UnsafeCell<MaybeUninit<[T; N]>>,
+}
+
+/// A ring buffer for multiple producers and a single consumer.
+///
+/// Producers claim a slot by advancing the tail index, and then publish their value through the
+/// sequence number of the slot. The sequence number encodes in which lap around the buffer a slot
+/// was last written and read, so the consumer never reads a slot that is not yet published.
+pub struct MpscRingBuffer<T, const N: usize> {
+    /// Index of the next slot to read. Only written by the consumer.
+    head: AtomicUsize,
+
+    /// Index of the next slot to claim.
+    tail: AtomicUsize,
+
+    /// The per-slot sequence numbers, relative to the slot's position in the buffer.
+    sequence: [AtomicUsize; N],
+
+    slots: UnsafeCell<MaybeUninit<[T; N]>>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Pointer to the slot of the given index.
+fn slot_ptr<T, const N: usize>(slots: &UnsafeCell<MaybeUninit<[T; N]>>, index: usize) -> *mut T {
+    unsafe { (slots.get() as *mut T).add(index % N) }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+unsafe impl<T, const N: usize> Send for SpscRingBuffer<T, N> where T: Send {}
+unsafe impl<T, const N: usize> Sync for SpscRingBuffer<T, N> where T: Send {}
+
+impl<T: Copy, const N: usize> SpscRingBuffer<T, N> {
+    /// Create an instance.
+    pub const fn new() -> Self {
+        assert!(N.is_power_of_two());
+
+        Self {
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            slots: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Append a value. Hands the value back if the buffer is full.
+    ///
+    /// # Safety
+    ///
+    /// - Must not be called concurrently with itself.
+    pub unsafe fn push(&self, value: T) -> Result<(), T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+
+        // Pairs with the release store in pop(), so the slot is no longer read.
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == N {
+            return Err(value);
+        }
+
+        slot_ptr(&self.slots, tail).write(value);
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Remove the oldest value.
+    ///
+    /// # Safety
+    ///
+    /// - Must not be called concurrently with itself.
+    pub unsafe fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+
+        // Pairs with the release store in push(), so the slot is fully written.
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let value = slot_ptr(&self.slots, head).read();
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Returns whether the buffer is empty. Might be outdated already when returning.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Relaxed) == self.tail.load(Ordering::Relaxed)
+    }
+}
+
+unsafe impl<T, const N: usize> Send for MpscRingBuffer<T, N> where T: Send {}
+unsafe impl<T, const N: usize> Sync for MpscRingBuffer<T, N> where T: Send {}
+
+impl<T: Copy, const N: usize> MpscRingBuffer<T, N> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const SEQUENCE_INIT: AtomicUsize = AtomicUsize::new(0);
+
+    /// Create an instance.
+    pub const fn new() -> Self {
+        assert!(N.is_power_of_two());
+
+        Self {
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            sequence: [Self::SEQUENCE_INIT; N],
+            slots: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Append a value. Hands the value back if the buffer is full.
+    ///
+    /// Can be called concurrently from any number of cores and contexts.
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+
+        loop {
+            // The start index of the lap that `tail` belongs to.
+            let lap = tail - (tail % N);
+            let sequence = self.sequence[tail % N].load(Ordering::Acquire);
+
+            match sequence.wrapping_sub(lap) as isize {
+                // The slot was read in the previous lap and is free. Try to claim it.
+                0 => {
+                    match self.tail.compare_exchange_weak(
+                        tail,
+                        tail.wrapping_add(1),
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => break,
+                        Err(x) => tail = x,
+                    }
+                }
+                // The slot still holds an unread value from the previous lap.
+                x if x < 0 => return Err(value),
+                // Another producer claimed the slot in the meantime.
+                _ => tail = self.tail.load(Ordering::Relaxed),
+            }
+        }
+
+        unsafe { slot_ptr(&self.slots, tail).write(value) };
+
+        // Publish the value to the consumer.
+        let lap = tail - (tail % N);
+        self.sequence[tail % N].store(lap.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Remove the oldest value.
+    ///
+    /// # Safety
+    ///
+    /// - Must not be called concurrently with itself.
+    pub unsafe fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        let lap = head - (head % N);
+
+        if self.sequence[head % N].load(Ordering::Acquire) != lap.wrapping_add(1) {
+            return None;
+        }
+
+        let value = slot_ptr(&self.slots, head).read();
+
+        // Hand the slot to the producers for the next lap.
+        self.sequence[head % N].store(lap.wrapping_add(N), Ordering::Release);
+        self.head.store(head.wrapping_add(1), Ordering::Relaxed);
+
+        Some(value)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    const NUM_VALUES: u32 = 100_000;
+
+    /// Values must come out in order, and a full buffer must reject new values.
+    #[test]
+    fn spsc_ring_buffer_sanity() {
+        let rb: SpscRingBuffer<u8, 4> = SpscRingBuffer::new();
+
+        unsafe {
+            for lap in 0..3 {
+                for i in 0..4 {
+                    assert!(rb.push(lap * 4 + i).is_ok());
+                }
+                assert_eq!(rb.push(0xff), Err(0xff));
+
+                for i in 0..4 {
+                    assert_eq!(rb.pop(), Some(lap * 4 + i));
+                }
+                assert!(rb.pop().is_none());
+            }
+        }
+
+        assert!(rb.is_empty());
+    }
+
+    /// Same as above, for the multi-producer variant.
+    #[test]
+    fn mpsc_ring_buffer_sanity() {
+        let rb: MpscRingBuffer<u8, 4> = MpscRingBuffer::new();
+
+        for lap in 0..3 {
+            for i in 0..4 {
+                assert!(rb.push(lap * 4 + i).is_ok());
+            }
+            assert_eq!(rb.push(0xff), Err(0xff));
+
+            for i in 0..4 {
+                assert_eq!(unsafe { rb.pop() }, Some(lap * 4 + i));
+            }
+            assert!(unsafe { rb.pop() }.is_none());
+        }
+    }
+
+    /// Indices must keep working when they wrap around.
+    #[test]
+    fn ring_buffer_index_wrap_around() {
+        let spsc: SpscRingBuffer<usize, 4> = SpscRingBuffer::new();
+        let mpsc: MpscRingBuffer<usize, 4> = MpscRingBuffer::new();
+
+        let start = usize::MAX - 5;
+        spsc.head.store(start, Ordering::Relaxed);
+        spsc.tail.store(start, Ordering::Relaxed);
+        mpsc.head.store(start, Ordering::Relaxed);
+        mpsc.tail.store(start, Ordering::Relaxed);
+        for i in 0..4 {
+            // Mark the slots as free in the lap that the respective index belongs to.
+            let index = start.wrapping_add(i);
+            mpsc.sequence[index % 4].store(index - (index % 4), Ordering::Relaxed);
+        }
+
+        for i in 0..16 {
+            unsafe {
+                assert!(spsc.push(i).is_ok());
+                assert_eq!(spsc.pop(), Some(i));
+            }
+            assert!(mpsc.push(i).is_ok());
+            assert_eq!(unsafe { mpsc.pop() }, Some(i));
+        }
+    }
+
+    /// Hand values from one thread to another.
+    #[test]
+    fn spsc_ring_buffer_threaded() {
+        let rb: Arc<SpscRingBuffer<u32, 64>> = Arc::new(SpscRingBuffer::new());
+
+        let producer = {
+            let rb = Arc::clone(&rb);
+            thread::spawn(move || {
+                for i in 0..NUM_VALUES {
+                    while unsafe { rb.push(i) }.is_err() {
+                        thread::yield_now();
+                    }
+                }
+            })
+        };
+
+        let mut expected = 0;
+        while expected < NUM_VALUES {
+            match unsafe { rb.pop() } {
+                Some(x) => {
+                    assert_eq!(x, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        producer.join().unwrap();
+        assert!(rb.is_empty());
+    }
+
+    /// Every value of every producer must arrive exactly once, and in order per producer.
+    #[test]
+    fn mpsc_ring_buffer_threaded() {
+        const NUM_PRODUCERS: u32 = 4;
+
+        let rb: Arc<MpscRingBuffer<(u32, u32), 64>> = Arc::new(MpscRingBuffer::new());
+
+        let producers: Vec<_> = (0..NUM_PRODUCERS)
+            .map(|producer| {
+                let rb = Arc::clone(&rb);
+                thread::spawn(move || {
+                    for i in 0..NUM_VALUES {
+                        while rb.push((producer, i)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut next = [0; NUM_PRODUCERS as usize];
+        let mut received = 0;
+        while received < NUM_PRODUCERS * NUM_VALUES {
+            match unsafe { rb.pop() } {
+                Some((producer, i)) => {
+                    assert_eq!(next[producer as usize], i);
+                    next[producer as usize] += 1;
+                    received += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert!(unsafe { rb.pop() }.is_none());
+    }
+}
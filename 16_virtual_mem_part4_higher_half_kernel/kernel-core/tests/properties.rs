@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Property-based tests for the address and page math.
+//!
+//! The unit tests next to the code check small inputs exhaustively. These tests cover the whole
+//! `usize` range, where overflows happen.
+
+use core::num::NonZeroUsize;
+use kernel_core::{
+    common,
+    memory::{
+        mmu::{MemoryRegion, PageAddress, PageGranule},
+        Address, Virtual,
+    },
+};
+use proptest::prelude::*;
+
+/// Any page address.
+fn page_addr() -> impl Strategy<Value = PageAddress<Virtual>> {
+    (0..=(usize::MAX >> PageGranule::SHIFT))
+        .prop_map(|page| PageAddress::from(page << PageGranule::SHIFT))
+}
+
+/// Any region, including empty ones.
+fn region() -> impl Strategy<Value = MemoryRegion<Virtual>> {
+    (page_addr(), page_addr()).prop_map(|(a, b)| {
+        if a <= b {
+            MemoryRegion::new(a, b)
+        } else {
+            MemoryRegion::new(b, a)
+        }
+    })
+}
+
+/// A small region somewhere in the address space, so that page-wise reference checks stay fast.
+fn small_region() -> impl Strategy<Value = MemoryRegion<Virtual>> {
+    (page_addr(), 0..16_isize).prop_filter_map("end overflows", |(start, num_pages)| {
+        Some(MemoryRegion::new(start, start.checked_offset(num_pages)?))
+    })
+}
+
+fn alignment() -> impl Strategy<Value = usize> {
+    (0..usize::BITS).prop_map(|shift| 1 << shift)
+}
+
+proptest! {
+    #[test]
+    fn align_down_is_largest_aligned_value_below(value: usize, alignment in alignment()) {
+        let down = common::align_down(value, alignment);
+
+        prop_assert!(common::is_aligned(down, alignment));
+        prop_assert!(down <= value);
+        prop_assert!(value - down < alignment);
+    }
+
+    #[test]
+    fn align_up_is_smallest_aligned_value_above(value: usize, alignment in alignment()) {
+        match value.checked_add(alignment - 1) {
+            // Would panic.
+            None => prop_assume!(false),
+            Some(_) => {
+                let up = common::align_up(value, alignment);
+
+                prop_assert!(common::is_aligned(up, alignment));
+                prop_assert!(up >= value);
+                prop_assert!(up - value < alignment);
+            }
+        }
+    }
+
+    #[test]
+    fn page_rounding_brackets_the_address(value in 0..=(usize::MAX - PageGranule::MASK)) {
+        let addr = Address::<Virtual>::new(value);
+        let down = addr.align_down_page();
+        let up = addr.align_up_page();
+
+        prop_assert!(down.is_page_aligned() && up.is_page_aligned());
+        prop_assert_eq!(down.as_usize() + addr.offset_into_page(), value);
+        prop_assert_eq!(up == addr, addr.is_page_aligned());
+        prop_assert!(up.as_usize() - down.as_usize() <= PageGranule::SIZE);
+    }
+
+    #[test]
+    fn checked_offset_roundtrips(start in page_addr(), count: isize) {
+        match start.checked_offset(count) {
+            Some(moved) => {
+                let delta = count.unsigned_abs() << PageGranule::SHIFT;
+                let expected = if count >= 0 {
+                    start.into_inner().as_usize() + delta
+                } else {
+                    start.into_inner().as_usize() - delta
+                };
+
+                prop_assert_eq!(moved.into_inner().as_usize(), expected);
+                prop_assert_eq!(moved.checked_offset(-count), Some(start));
+            }
+            None => {
+                // Only allowed if the result is outside of the address space.
+                let delta = (count.unsigned_abs() as u128) << PageGranule::SHIFT;
+                let start = start.into_inner().as_usize() as u128;
+
+                if count >= 0 {
+                    prop_assert!(start + delta > usize::MAX as u128);
+                } else {
+                    prop_assert!(delta > start);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn region_size_matches_num_pages(region in region()) {
+        prop_assert_eq!(region.size(), region.num_pages() << PageGranule::SHIFT);
+        prop_assert_eq!(region.into_iter().size_hint().0, region.num_pages());
+    }
+
+    #[test]
+    fn overlap_is_symmetric_and_matches_shared_pages(a in small_region(), b in small_region()) {
+        let shared_page = a.into_iter().any(|page| b.contains(page.into_inner()));
+
+        prop_assert_eq!(a.overlaps(&b), shared_page);
+        prop_assert_eq!(a.overlaps(&b), b.overlaps(&a));
+    }
+
+    #[test]
+    fn take_first_n_pages_splits_the_region(region in region(), count in 1..usize::MAX) {
+        let mut remaining = region;
+        let count = NonZeroUsize::new(count).unwrap();
+
+        match remaining.take_first_n_pages(count) {
+            Ok(allocation) => {
+                prop_assert_eq!(allocation.num_pages(), count.get());
+                prop_assert_eq!(allocation.start_page_addr(), region.start_page_addr());
+                prop_assert_eq!(
+                    allocation.end_exclusive_page_addr(),
+                    remaining.start_page_addr()
+                );
+                prop_assert_eq!(
+                    remaining.end_exclusive_page_addr(),
+                    region.end_exclusive_page_addr()
+                );
+                prop_assert!(!allocation.overlaps(&remaining));
+            }
+            Err(_) => {
+                prop_assert!(count.get() > region.num_pages());
+                prop_assert_eq!(remaining, region);
+            }
+        }
+    }
+}
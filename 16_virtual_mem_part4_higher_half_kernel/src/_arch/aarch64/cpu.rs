@@ -11,7 +11,7 @@
 //!
 //! crate::cpu::arch_cpu
 
-use cortex_a::asm;
+use cortex_a::{asm, asm::barrier};
 
 //--------------------------------------------------------------------------------------------------
 // Public Code
@@ -19,6 +19,32 @@ use cortex_a::asm;
 
 pub use asm::nop;
 
+/// Sleep until an event is signaled, for example with [send_event()].
+///
+/// Might also return spuriously, so callers must recheck their wakeup condition.
+#[inline(always)]
+pub fn wait_for_event() {
+    asm::wfe()
+}
+
+/// Wake up all cores waiting in [wait_for_event()].
+///
+/// Prior memory writes are visible to the woken cores.
+#[inline(always)]
+pub fn send_event() {
+    unsafe { barrier::dsb(barrier::ISH) };
+    asm::sev()
+}
+
+/// Sleep until an interrupt is pending.
+///
+/// Also wakes up for interrupts that are masked, without taking them.
+#[inline(always)]
+pub fn wait_for_interrupt() {
+    unsafe { barrier::dsb(barrier::SY) };
+    asm::wfi()
+}
+
 /// Pause execution on the core.
 #[inline(always)]
 pub fn wait_forever() -> ! {
@@ -11,27 +11,106 @@
 //!
 //! crate::cpu::boot::arch_boot
 
-use crate::{memory, memory::Address};
-use core::arch::global_asm;
+use crate::{
+    config, cpu, earlycon, memory,
+    memory::{Address, Physical},
+};
+use core::{
+    arch::global_asm,
+    sync::atomic::{AtomicU64, Ordering},
+};
 use cortex_a::{asm, registers::*};
-use tock_registers::interfaces::Writeable;
+use tock_registers::interfaces::{Readable, Writeable};
 
 // Assembly counterpart to this file.
 global_asm!(include_str!("boot.s"));
 
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The reserved-one bits of CPTR_EL2, which is not covered by the register definitions. All trap
+/// bits are zero.
+const CPTR_EL2_RES1: u64 = 0x33ff;
+
+/// The CurrentEL value of EL3.
+const EL3: u64 = 3;
+
+/// SCR_EL3 bits. The register is not covered by the register definitions.
+mod scr_el3 {
+    /// EL2 and EL1 are in the Non-secure state.
+    pub const NS: u64 = 1 << 0;
+
+    /// Reserved-one bits.
+    pub const RES1: u64 = 0b11 << 4;
+
+    /// Make SMC undefined. Nothing in EL3 would answer it once the kernel left.
+    pub const SMD: u64 = 1 << 7;
+
+    /// EL2 and EL1 execute in AArch64.
+    pub const RW: u64 = 1 << 10;
+}
+
+/// The saved program status for the exception return from EL3: All interrupts masked, and EL1
+/// using SP_EL0 as a stack pointer. The same value that `prepare_el2_to_el1_transition()` puts
+/// into SPSR_EL2.
+const SPSR_EL3_TO_EL1T: u64 = (0b1111 << 6) | 0b0100;
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+/// The value of x0 at kernel entry. Written by `_start()`.
+///
+/// The Raspberry's firmware puts the physical address of the device tree blob there. Other boot
+/// paths, like the chainloader, might leave arbitrary values.
+#[no_mangle]
+static BOOT_DTB_PHYS_ADDR: AtomicU64 = AtomicU64::new(0);
+
+/// The system counter value at kernel entry. Written by `_start()`.
+#[no_mangle]
+static BOOT_ENTRY_TICKS: AtomicU64 = AtomicU64::new(0);
+
 //--------------------------------------------------------------------------------------------------
 // Private Code
 //--------------------------------------------------------------------------------------------------
 
+/// Prepares the transition from EL3 to EL1, skipping EL2.
+///
+/// EL1 ends up in the Non-secure state, where alternative firmware that stays resident in EL3
+/// would put it, too. EL2 is not left unconfigured: `prepare_el2_to_el1_transition()` still runs
+/// afterwards, because the EL2 registers control the Non-secure EL1 the same way as when entered
+/// in EL2.
+///
+/// # Safety
+///
+/// - Must be executed in EL3.
+/// - The `bss` section is not initialized yet. The code must not use or reference it in any way.
+#[inline(always)]
+unsafe fn prepare_el3_to_el1_transition(virt_kernel_init_addr: u64) {
+    use core::arch::asm;
+
+    let scr = scr_el3::NS | scr_el3::RES1 | scr_el3::SMD | scr_el3::RW;
+    asm!("msr scr_el3, {}", in(reg) scr, options(nomem, nostack));
+
+    // Do not trap FP/SIMD, trace or system register accesses to EL3.
+    asm!("msr cptr_el3, xzr", options(nomem, nostack));
+
+    asm!("msr spsr_el3, {}", in(reg) SPSR_EL3_TO_EL1T, options(nomem, nostack));
+    asm!("msr elr_el3, {}", in(reg) virt_kernel_init_addr, options(nomem, nostack));
+}
+
 /// Prepares the transition from EL2 to EL1.
 ///
+/// When entered in EL3, this configures EL2 on the way, and the exception return bypasses it.
+///
 /// # Safety
 ///
 /// - The `bss` section is not initialized yet. The code must not use or reference it in any way.
 /// - The HW state of EL1 must be prepared in a sound way.
 #[inline(always)]
 unsafe fn prepare_el2_to_el1_transition(
-    virt_boot_core_stack_end_exclusive_addr: u64,
+    virt_stack_end_exclusive_addr: u64,
     virt_kernel_init_addr: u64,
 ) {
     // Enable timer counter registers for EL1.
@@ -40,27 +119,37 @@ unsafe fn prepare_el2_to_el1_transition(
     // No offset for reading the counters.
     CNTVOFF_EL2.set(0);
 
+    // Per-core data is not available until the kernel set it up.
+    TPIDR_EL1.set(0);
+
+    // Do not trap FP/SIMD instructions to EL2. The kernel decides about trapping them to EL1.
+    core::arch::asm!("msr cptr_el2, {}", in(reg) CPTR_EL2_RES1, options(nomem, nostack));
+
     // Set EL1 execution state to AArch64.
     HCR_EL2.write(HCR_EL2::RW::EL1IsAarch64);
 
+    // Return addresses are signed from the first function in EL1 on, so the key must be set now.
+    cpu::pac::boot_enable();
+
     // Set up a simulated exception return.
     //
-    // First, fake a saved program status where all interrupts were masked and SP_EL1 was used as a
-    // stack pointer.
+    // First, fake a saved program status where all interrupts were masked and SP_EL0 was used as a
+    // stack pointer. SP_EL1 is reserved for the exception stacks, which are set up by
+    // `exception::handling_init()`.
     SPSR_EL2.write(
         SPSR_EL2::D::Masked
             + SPSR_EL2::A::Masked
             + SPSR_EL2::I::Masked
             + SPSR_EL2::F::Masked
-            + SPSR_EL2::M::EL1h,
+            + SPSR_EL2::M::EL1t,
     );
 
     // Second, let the link register point to kernel_init().
     ELR_EL2.set(virt_kernel_init_addr);
 
-    // Set up SP_EL1 (stack pointer), which will be used by EL1 once we "return" to it. Since there
+    // Set up SP_EL0 (stack pointer), which will be used by EL1 once we "return" to it. Since there
     // are no plans to ever return to EL2, just re-use the same stack.
-    SP_EL1.set(virt_boot_core_stack_end_exclusive_addr);
+    SP_EL0.set(virt_stack_end_exclusive_addr);
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -69,27 +158,60 @@ unsafe fn prepare_el2_to_el1_transition(
 
 /// The Rust entry of the `kernel` binary.
 ///
-/// The function is called from the assembly `_start` function.
+/// The function is called from the assembly `_start` function on the boot core, and from
+/// `_start_secondary` on the secondary cores.
 ///
 /// # Safety
 ///
-/// - Exception return from EL2 must must continue execution in EL1 with `kernel_init()`.
+/// - Exception return from EL2 or EL3 must continue execution in EL1 with `kernel_init()` or
+///   `kernel_init_secondary()`, respectively.
 #[no_mangle]
 pub unsafe extern "C" fn _start_rust(
     phys_kernel_tables_base_addr: u64,
-    virt_boot_core_stack_end_exclusive_addr: u64,
+    virt_stack_end_exclusive_addr: u64,
     virt_kernel_init_addr: u64,
 ) -> ! {
-    prepare_el2_to_el1_transition(
-        virt_boot_core_stack_end_exclusive_addr,
-        virt_kernel_init_addr,
-    );
+    // Any lock taken later would execute LSE atomics. Only plain strings can be printed this early.
+    if config::LSE && !cpu::features::features().lse {
+        earlycon::write_str_phys("\nKernel built with LSE=y, but the core lacks LSE atomics\n");
+        cpu::wait_forever()
+    }
+
+    // First, because `cpu::pac::boot_enable()` adds to the SCR_EL3 value written here.
+    if CurrentEL.read(CurrentEL::EL) == EL3 {
+        prepare_el3_to_el1_transition(virt_kernel_init_addr);
+    }
+    prepare_el2_to_el1_transition(virt_stack_end_exclusive_addr, virt_kernel_init_addr);
 
     // Turn on the MMU for EL1.
     let addr = Address::new(phys_kernel_tables_base_addr as usize);
-    memory::mmu::enable_mmu_and_caching(addr).unwrap();
+    if let Err(x) = memory::mmu::enable_mmu_and_caching(addr) {
+        // Still running from physical addresses, so only plain strings can be printed.
+        earlycon::write_str_phys("\nError enabling the MMU: ");
+        earlycon::write_str_phys(match x {
+            memory::mmu::MMUEnableError::AlreadyEnabled => "MMU is already enabled",
+            memory::mmu::MMUEnableError::Other(x) => x,
+        });
+        earlycon::write_str_phys("\n");
+        cpu::wait_forever()
+    }
 
-    // Use `eret` to "return" to EL1. Since virtual memory will already be enabled, this results in
-    // execution of kernel_init() in EL1 from its _virtual address_.
+    // Use `eret` to "return" to EL1, from EL2 or EL3. Since virtual memory will already be enabled,
+    // this results in execution of kernel_init() in EL1 from its _virtual address_.
     asm::eret()
 }
+
+/// The physical address of the device tree blob as handed over by the firmware, if any.
+///
+/// The address is not validated beyond being non-zero.
+pub fn boot_dtb_phys_addr() -> Option<Address<Physical>> {
+    match BOOT_DTB_PHYS_ADDR.load(Ordering::Relaxed) {
+        0 => None,
+        addr => Some(Address::new(addr as usize)),
+    }
+}
+
+/// The system counter value at the time the boot core entered the kernel.
+pub fn boot_entry_ticks() -> u64 {
+    BOOT_ENTRY_TICKS.load(Ordering::Relaxed)
+}
@@ -32,6 +32,8 @@
 
 .equ _EL2, 0x8
 .equ _core_id_mask, 0b11
+.equ _num_cores, 4
+.equ _secondary_core_stack_size, 0x10000
 
 //--------------------------------------------------------------------------------------------------
 // Public Code
@@ -42,10 +44,19 @@
 // fn _start()
 //------------------------------------------------------------------------------
 _start:
-	// Only proceed if the core executes in EL2. Park it otherwise.
+	// Preserve the device tree address that the firmware hands over in x0.
+	mov	x19, x0
+
+	// Take the timestamp of kernel entry.
+	mrs	x20, CNTPCT_EL0
+
+	// Only proceed if the core executes in EL2 or EL3. Park it otherwise.
+	//
+	// The Raspberry's firmware enters in EL2. Alternative firmware and some QEMU configurations
+	// enter in EL3. _start_rust() drops to EL1 from either one.
 	mrs	x0, CurrentEL
 	cmp	x0, _EL2
-	b.ne	.L_parking_loop
+	b.lo	.L_parking_loop
 
 	// Only proceed on the boot core. Park it otherwise.
 	mrs	x1, MPIDR_EL1
@@ -68,6 +79,12 @@ _start:
 
 	// Prepare the jump to Rust code.
 .L_prepare_rust:
+	// Now that the BSS is zeroed, store the device tree address and the entry timestamp.
+	ADR_REL	x0, BOOT_DTB_PHYS_ADDR
+	str	x19, [x0]
+	ADR_REL	x0, BOOT_ENTRY_TICKS
+	str	x20, [x0]
+
 	// Load the base address of the kernel's translation tables.
 	ldr	x0, PHYS_KERNEL_TABLES_BASE_ADDR // provided by bsp/__board_name__/memory/mmu.rs
 
@@ -81,8 +98,8 @@ _start:
 	// Since _start() is the first function that runs after the firmware has loaded the kernel
 	// into memory, retrieving this symbol PC-relative returns the "physical" address.
 	//
-	// Setting the stack pointer to this value ensures that anything that still runs in EL2,
-	// until the kernel returns to EL1 with the MMU enabled, works as well. After the return to
+	// Setting the stack pointer to this value ensures that anything that still runs in EL2 or
+	// EL3, until the kernel returns to EL1 with the MMU enabled, works as well. After the return to
 	// EL1, the virtual address of the stack retrieved above will be used.
 	ADR_REL	x4, __boot_core_stack_end_exclusive
 	mov	sp, x4
@@ -98,3 +115,50 @@ _start:
 .size	_start, . - _start
 .type	_start, function
 .global	_start
+
+//------------------------------------------------------------------------------
+// fn _start_secondary()
+//------------------------------------------------------------------------------
+_start_secondary:
+	// Only proceed if the core executes in EL2 or EL3. Park it otherwise.
+	mrs	x0, CurrentEL
+	cmp	x0, _EL2
+	b.lo	.L_parking_loop
+
+	// Calculate the offset of the core's stack end: (core_id + 1) * stack_size.
+	mrs	x1, MPIDR_EL1
+	and	x1, x1, _core_id_mask
+	add	x1, x1, #1
+	mov	x2, _secondary_core_stack_size
+	mul	x3, x1, x2
+
+	// Load the base address of the kernel's translation tables. They are shared by all cores.
+	ldr	x0, PHYS_KERNEL_TABLES_BASE_ADDR // provided by bsp/__board_name__/memory/mmu.rs
+
+	// Same as for the boot core: Virtual addresses for after the MMU is on...
+	ADR_ABS	x1, __secondary_core_stacks_start
+	add	x1, x1, x3
+	ADR_ABS	x2, kernel_init_secondary
+
+	// ...and the physical address of the stack for the time until then.
+	ADR_REL	x4, __secondary_core_stacks_start
+	add	x4, x4, x3
+	mov	sp, x4
+
+	// Jump to Rust code. x0, x1 and x2 hold the function arguments provided to _start_rust().
+	b	_start_rust
+
+.size	_start_secondary, . - _start_secondary
+.type	_start_secondary, function
+.global	_start_secondary
+
+//--------------------------------------------------------------------------------------------------
+// Secondary core stacks
+//--------------------------------------------------------------------------------------------------
+.section .bss.secondary_core_stacks, "aw", %nobits
+
+// Indexed by core id for simplicity, so the slot of the boot core stays unused. Being part of .bss,
+// the stacks are zeroed by the boot core before any secondary core is released.
+.balign 16
+__secondary_core_stacks_start:
+	.space	_secondary_core_stack_size * _num_cores
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural cache topology and maintenance.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::cpu::cache::arch_cache
+
+use crate::{
+    cpu::cache::{Cache, CacheType, MAX_CACHES},
+    exception,
+};
+use core::arch::asm;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The number of cache levels that CLIDR_EL1 can describe.
+const MAX_LEVELS: usize = 7;
+
+/// CLIDR_EL1 fields.
+mod clidr {
+    pub const CTYPE_BITS: u32 = 3;
+    pub const CTYPE_MASK: u64 = 0b111;
+
+    pub const CTYPE_NONE: u64 = 0b000;
+    pub const CTYPE_INSTRUCTION: u64 = 0b001;
+    pub const CTYPE_DATA: u64 = 0b010;
+    pub const CTYPE_SEPARATE: u64 = 0b011;
+    pub const CTYPE_UNIFIED: u64 = 0b100;
+}
+
+/// CSSELR_EL1 fields.
+mod csselr {
+    pub const LEVEL_SHIFT: u32 = 1;
+
+    /// Select the instruction cache of a level with separate caches.
+    pub const IND: u64 = 1;
+}
+
+/// CCSIDR_EL1 fields, without FEAT_CCIDX.
+mod ccsidr {
+    pub const LINE_SIZE_MASK: u64 = 0b111;
+    pub const ASSOCIATIVITY_SHIFT: u32 = 3;
+    pub const ASSOCIATIVITY_MASK: u64 = 0x3ff;
+    pub const NUM_SETS_SHIFT: u32 = 13;
+    pub const NUM_SETS_MASK: u64 = 0x7fff;
+}
+
+/// CTR_EL0 fields.
+mod ctr {
+    pub const IMINLINE_SHIFT: u32 = 0;
+    pub const DMINLINE_SHIFT: u32 = 16;
+    pub const MINLINE_MASK: u64 = 0xf;
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn read_ctr() -> u64 {
+    let ctr: u64;
+    unsafe { asm!("mrs {}, ctr_el0", out(reg) ctr, options(nomem, nostack)) };
+
+    ctr
+}
+
+/// Describe the cache of `level`, counting from 1, that CSSELR_EL1 selects with `ind`.
+fn describe(level: usize, ind: u64, kind: CacheType) -> Cache {
+    let csselr = ((level as u64 - 1) << csselr::LEVEL_SHIFT) | ind;
+
+    // CSSELR_EL1 is shared by everything running on the core.
+    let ccsidr = exception::asynchronous::exec_with_irq_masked(|| {
+        let ccsidr: u64;
+        unsafe {
+            asm!(
+                "msr csselr_el1, {}",
+                "isb",
+                "mrs {}, ccsidr_el1",
+                in(reg) csselr,
+                out(reg) ccsidr,
+                options(nomem, nostack)
+            )
+        };
+
+        ccsidr
+    });
+
+    Cache {
+        level: level as u8,
+        kind,
+        line_size: 16 << (ccsidr & ccsidr::LINE_SIZE_MASK),
+        ways: ((ccsidr >> ccsidr::ASSOCIATIVITY_SHIFT) & ccsidr::ASSOCIATIVITY_MASK) as usize + 1,
+        sets: ((ccsidr >> ccsidr::NUM_SETS_SHIFT) & ccsidr::NUM_SETS_MASK) as usize + 1,
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Describe the caches of the executing core, innermost first.
+pub fn read_caches() -> [Option<Cache>; MAX_CACHES] {
+    let clidr: u64;
+    unsafe { asm!("mrs {}, clidr_el1", out(reg) clidr, options(nomem, nostack)) };
+
+    let mut caches = [None; MAX_CACHES];
+    let mut slots = caches.iter_mut();
+
+    for level in 1..=MAX_LEVELS {
+        let ctype = (clidr >> ((level as u32 - 1) * clidr::CTYPE_BITS)) & clidr::CTYPE_MASK;
+
+        let entries: &[(u64, CacheType)] = match ctype {
+            clidr::CTYPE_NONE => break,
+            clidr::CTYPE_INSTRUCTION => &[(csselr::IND, CacheType::Instruction)],
+            clidr::CTYPE_DATA => &[(0, CacheType::Data)],
+            clidr::CTYPE_SEPARATE => &[(0, CacheType::Data), (csselr::IND, CacheType::Instruction)],
+            clidr::CTYPE_UNIFIED => &[(0, CacheType::Unified)],
+            _ => continue,
+        };
+
+        for &(ind, kind) in entries {
+            match slots.next() {
+                Some(slot) => *slot = Some(describe(level, ind, kind)),
+                None => return caches,
+            }
+        }
+    }
+
+    caches
+}
+
+/// The smallest data cache line size of all caches, in bytes.
+pub fn dcache_line_size() -> usize {
+    4 << ((read_ctr() >> ctr::DMINLINE_SHIFT) & ctr::MINLINE_MASK)
+}
+
+/// The smallest instruction cache line size of all caches, in bytes.
+pub fn icache_line_size() -> usize {
+    4 << ((read_ctr() >> ctr::IMINLINE_SHIFT) & ctr::MINLINE_MASK)
+}
+
+/// Write dirty data cache lines of the range back to the point of coherency.
+///
+/// # Safety
+///
+/// - The range must be mapped.
+pub unsafe fn clean_dcache_range(addr: usize, len: usize) {
+    let line = dcache_line_size();
+
+    let mut a = addr & !(line - 1);
+    while a < addr + len {
+        asm!("dc cvac, {}", in(reg) a, options(nostack));
+        a += line;
+    }
+    asm!("dsb sy", options(nostack));
+}
+
+/// Write dirty data cache lines of the range back to the point of coherency and discard them.
+///
+/// # Safety
+///
+/// - The range must be mapped.
+pub unsafe fn clean_invalidate_dcache_range(addr: usize, len: usize) {
+    let line = dcache_line_size();
+
+    let mut a = addr & !(line - 1);
+    while a < addr + len {
+        asm!("dc civac, {}", in(reg) a, options(nostack));
+        a += line;
+    }
+    asm!("dsb sy", options(nostack));
+}
+
+/// Make code that was written through a data alias visible to instruction fetches on all cores.
+///
+/// # Safety
+///
+/// - Both ranges must be mapped.
+pub unsafe fn sync_code(alias_addr: usize, code_addr: usize, len: usize) {
+    let dline = dcache_line_size();
+    let iline = icache_line_size();
+
+    // Clean the new code to the point of unification, where the instruction fetches pick it up.
+    let mut addr = alias_addr & !(dline - 1);
+    while addr < alias_addr + len {
+        asm!("dc cvau, {}", in(reg) addr, options(nostack));
+        addr += dline;
+    }
+    asm!("dsb ish", options(nostack));
+
+    // Then discard stale copies from the instruction caches.
+    let mut addr = code_addr & !(iline - 1);
+    while addr < code_addr + len {
+        asm!("ic ivau, {}", in(reg) addr, options(nostack));
+        addr += iline;
+    }
+    asm!("dsb ish", "isb", options(nostack));
+}
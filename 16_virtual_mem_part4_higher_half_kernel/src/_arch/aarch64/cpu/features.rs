@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural CPU identification.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::cpu::features::arch_features
+
+use crate::cpu::features::{Features, PmuVersion};
+use core::arch::asm;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Read an ID register. Not all of them are covered by the register definitions.
+macro_rules! read_id_reg {
+    ($name:literal) => {{
+        let value: u64;
+        unsafe { asm!(concat!("mrs {}, ", $name), out(reg) value, options(nomem, nostack)) };
+        value
+    }};
+}
+
+/// The 4-bit field at bit `shift` of an ID register.
+fn field(reg: u64, shift: u32) -> u8 {
+    ((reg >> shift) & 0b1111) as u8
+}
+
+/// MIDR_EL1 fields.
+mod midr {
+    pub const IMPLEMENTER_SHIFT: u32 = 24;
+    pub const VARIANT_SHIFT: u32 = 20;
+    pub const PART_NUM_SHIFT: u32 = 4;
+    pub const PART_NUM_MASK: u64 = 0xfff;
+    pub const REVISION_SHIFT: u32 = 0;
+}
+
+/// ID_AA64MMFR0_EL1 fields.
+mod mmfr0 {
+    pub const PARANGE_SHIFT: u32 = 0;
+    pub const TGRAN16_SHIFT: u32 = 20;
+    pub const TGRAN64_SHIFT: u32 = 24;
+    pub const TGRAN4_SHIFT: u32 = 28;
+}
+
+/// ID_AA64MMFR1_EL1 fields.
+mod mmfr1 {
+    pub const PAN_SHIFT: u32 = 20;
+}
+
+/// ID_AA64MMFR2_EL1 fields.
+mod mmfr2 {
+    pub const E0PD_SHIFT: u32 = 60;
+}
+
+/// ID_AA64ISAR0_EL1 fields.
+mod isar0 {
+    pub const ATOMIC_SHIFT: u32 = 20;
+
+    /// LDADD, CAS, SWP and friends are implemented.
+    pub const ATOMIC_LSE: u8 = 0b0010;
+}
+
+/// ID_AA64ISAR1_EL1 fields.
+mod isar1 {
+    /// Address authentication with the architected algorithm.
+    pub const APA_SHIFT: u32 = 4;
+
+    /// Address authentication with an implementation defined algorithm.
+    pub const API_SHIFT: u32 = 8;
+}
+
+/// ID_AA64PFR0_EL1 fields.
+mod pfr0 {
+    pub const CSV2_SHIFT: u32 = 56;
+    pub const CSV3_SHIFT: u32 = 60;
+}
+
+/// ID_AA64PFR1_EL1 fields.
+mod pfr1 {
+    pub const BT_SHIFT: u32 = 0;
+    pub const SSBS_SHIFT: u32 = 4;
+    pub const MTE_SHIFT: u32 = 8;
+
+    /// The tag-checking instructions and tag storage are implemented, not only the instructions.
+    pub const MTE_FULL: u8 = 0b0010;
+}
+
+/// ID_AA64DFR0_EL1 fields.
+mod dfr0 {
+    pub const PMUVER_SHIFT: u32 = 8;
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn pa_bits(parange: u8) -> u8 {
+    match parange {
+        0b0000 => 32,
+        0b0001 => 36,
+        0b0010 => 40,
+        0b0011 => 42,
+        0b0100 => 44,
+        0b0101 => 48,
+        _ => 52,
+    }
+}
+
+fn pmu_version(pmuver: u8) -> PmuVersion {
+    match pmuver {
+        0b0000 => PmuVersion::None,
+        0b1111 => PmuVersion::ImplementationDefined,
+        0b0001 => PmuVersion::V3,
+        0b0100 => PmuVersion::V3p1,
+        x => PmuVersion::Later(x),
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Decode the ID registers of the executing core.
+pub fn read() -> Features {
+    let midr = read_id_reg!("midr_el1");
+    let mmfr0 = read_id_reg!("id_aa64mmfr0_el1");
+    let mmfr1 = read_id_reg!("id_aa64mmfr1_el1");
+    let mmfr2 = read_id_reg!("id_aa64mmfr2_el1");
+    let isar0 = read_id_reg!("id_aa64isar0_el1");
+    let isar1 = read_id_reg!("id_aa64isar1_el1");
+    let pfr0 = read_id_reg!("id_aa64pfr0_el1");
+    let pfr1 = read_id_reg!("id_aa64pfr1_el1");
+    let dfr0 = read_id_reg!("id_aa64dfr0_el1");
+
+    Features {
+        implementer: (midr >> midr::IMPLEMENTER_SHIFT) as u8,
+        part_num: ((midr >> midr::PART_NUM_SHIFT) & midr::PART_NUM_MASK) as u16,
+        variant: field(midr, midr::VARIANT_SHIFT),
+        revision: field(midr, midr::REVISION_SHIFT),
+
+        pa_bits: pa_bits(field(mmfr0, mmfr0::PARANGE_SHIFT)),
+        granule_4k: field(mmfr0, mmfr0::TGRAN4_SHIFT) != 0b1111,
+        granule_16k: field(mmfr0, mmfr0::TGRAN16_SHIFT) != 0b0000,
+        granule_64k: field(mmfr0, mmfr0::TGRAN64_SHIFT) != 0b1111,
+
+        pan: field(mmfr1, mmfr1::PAN_SHIFT) != 0,
+        e0pd: field(mmfr2, mmfr2::E0PD_SHIFT) != 0,
+        lse: field(isar0, isar0::ATOMIC_SHIFT) >= isar0::ATOMIC_LSE,
+        pac: field(isar1, isar1::APA_SHIFT) != 0 || field(isar1, isar1::API_SHIFT) != 0,
+        bti: field(pfr1, pfr1::BT_SHIFT) != 0,
+        mte: field(pfr1, pfr1::MTE_SHIFT) >= pfr1::MTE_FULL,
+        pmu: pmu_version(field(dfr0, dfr0::PMUVER_SHIFT)),
+
+        csv2: field(pfr0, pfr0::CSV2_SHIFT) != 0,
+        csv3: field(pfr0, pfr0::CSV3_SHIFT) != 0,
+        ssbs: field(pfr1, pfr1::SSBS_SHIFT) != 0,
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural FP/SIMD register state.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::cpu::fpsimd::arch_fpsimd
+
+use core::arch::{asm, global_asm};
+
+// Assembly counterpart to this file.
+global_asm!(include_str!("fpsimd.s"));
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// CPACR_EL1 bits. The register is not covered by the register definitions.
+mod cpacr {
+    /// FP/SIMD access control.
+    pub const FPEN_MASK: u64 = 0b11 << 20;
+
+    /// FP/SIMD instructions at EL0 and EL1 do not trap.
+    pub const FPEN_TRAP_NOTHING: u64 = 0b11 << 20;
+}
+
+extern "C" {
+    fn __fpsimd_save(state: *mut State);
+    fn __fpsimd_restore(state: *const State);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The FP/SIMD registers. The layout is used by `fpsimd.s`.
+#[derive(Copy, Clone)]
+#[repr(C, align(16))]
+pub struct State {
+    /// V0-V31, low half first.
+    v: [u64; 64],
+
+    fpcr: u64,
+    fpsr: u64,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn set_fpen(value: u64) {
+    unsafe {
+        let mut cpacr: u64;
+        asm!("mrs {}, cpacr_el1", out(reg) cpacr, options(nomem, nostack));
+
+        cpacr = (cpacr & !cpacr::FPEN_MASK) | value;
+        asm!(
+            "msr cpacr_el1, {}",
+            "isb",
+            in(reg) cpacr,
+            options(nomem, nostack)
+        );
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl State {
+    /// Create an instance with all registers zeroed.
+    pub const fn new() -> Self {
+        Self {
+            v: [0; 64],
+            fpcr: 0,
+            fpsr: 0,
+        }
+    }
+}
+
+/// Let FP/SIMD instructions of the executing core trap.
+pub fn disable_access() {
+    set_fpen(0);
+}
+
+/// Let FP/SIMD instructions of the executing core execute.
+pub fn enable_access() {
+    set_fpen(cpacr::FPEN_TRAP_NOTHING);
+}
+
+/// Save the FP/SIMD registers of the executing core to `state`.
+///
+/// # Safety
+///
+/// - FP/SIMD access must be enabled.
+pub unsafe fn save(state: *mut State) {
+    __fpsimd_save(state)
+}
+
+/// Load the FP/SIMD registers of the executing core from `state`.
+///
+/// # Safety
+///
+/// - FP/SIMD access must be enabled.
+pub unsafe fn restore(state: *const State) {
+    __fpsimd_restore(state)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+/// Write the low 64 bits of V0. Traps if FP/SIMD access is disabled.
+#[cfg(test)]
+pub fn write_v0(value: u64) {
+    unsafe {
+        asm!(
+            ".arch_extension fp",
+            "fmov d0, {}",
+            in(reg) value,
+            options(nomem, nostack)
+        )
+    };
+}
+
+/// Read the low 64 bits of V0. Traps if FP/SIMD access is disabled.
+#[cfg(test)]
+pub fn read_v0() -> u64 {
+    let value: u64;
+    unsafe {
+        asm!(
+            ".arch_extension fp",
+            "fmov {}, d0",
+            out(reg) value,
+            options(nomem, nostack)
+        )
+    };
+
+    value
+}
@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural pointer authentication.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::cpu::pac::arch_pac
+
+use crate::cpu;
+use core::arch::asm;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// HCR_EL2 bits. They are not covered by the register definitions.
+mod hcr_el2 {
+    /// Do not trap pointer authentication instructions of EL1 and EL0 to EL2.
+    pub const API: u64 = 1 << 41;
+
+    /// Do not trap accesses to the key registers to EL2.
+    pub const APK: u64 = 1 << 40;
+}
+
+/// SCR_EL3 bits. They are not covered by the register definitions.
+mod scr_el3 {
+    /// Do not trap pointer authentication instructions of lower ELs to EL3.
+    pub const API: u64 = 1 << 17;
+
+    /// Do not trap accesses to the key registers to EL3.
+    pub const APK: u64 = 1 << 16;
+}
+
+/// SCTLR_EL1 bits. They are not covered by the register definitions.
+mod sctlr_el1 {
+    /// Enable pointer authentication of instruction addresses with key A.
+    pub const ENIA: u64 = 1 << 31;
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// The SplitMix64 finalizer, which spreads the few changing bits of the seed over the whole key.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    x ^ (x >> 31)
+}
+
+fn read_sctlr_el1() -> u64 {
+    let sctlr: u64;
+    unsafe { asm!("mrs {}, sctlr_el1", out(reg) sctlr, options(nomem, nostack)) };
+
+    sctlr
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Generate a key for the executing core and enable pointer authentication for EL1.
+///
+/// # Safety
+///
+/// - Must be executed in EL2 or EL3, before the transition to EL1.
+/// - The `bss` section is not initialized yet. The code must not use or reference it in any way.
+#[inline(always)]
+pub unsafe fn boot_enable() {
+    if !cpu::features::features().pac {
+        return;
+    }
+
+    // The counter value differs between boots and cores. This is not a strong key, but one that an
+    // attacker cannot predict from the kernel binary.
+    let seed: u64;
+    let mpidr: u64;
+    asm!("mrs {}, cntpct_el0", out(reg) seed, options(nomem, nostack));
+    asm!("mrs {}, mpidr_el1", out(reg) mpidr, options(nomem, nostack));
+
+    let key_lo = mix(seed ^ mpidr);
+    let key_hi = mix(key_lo ^ seed.rotate_left(32));
+
+    // APIAKeyLo_EL1 and APIAKeyHi_EL1, by encoding, because they need Armv8.3 in the assembler.
+    asm!(
+        "msr s3_0_c2_c1_0, {}",
+        "msr s3_0_c2_c1_1, {}",
+        in(reg) key_lo,
+        in(reg) key_hi,
+        options(nomem, nostack)
+    );
+
+    let mut hcr: u64;
+    asm!("mrs {}, hcr_el2", out(reg) hcr, options(nomem, nostack));
+    hcr |= hcr_el2::API | hcr_el2::APK;
+    asm!("msr hcr_el2, {}", in(reg) hcr, options(nomem, nostack));
+
+    // When entered in EL3, the instructions would trap there as well.
+    let current_el: u64;
+    asm!("mrs {}, CurrentEL", out(reg) current_el, options(nomem, nostack));
+    if current_el >> 2 == 3 {
+        let mut scr: u64;
+        asm!("mrs {}, scr_el3", out(reg) scr, options(nomem, nostack));
+        scr |= scr_el3::API | scr_el3::APK;
+        asm!("msr scr_el3, {}", in(reg) scr, options(nomem, nostack));
+    }
+
+    let sctlr = read_sctlr_el1() | sctlr_el1::ENIA;
+    asm!("msr sctlr_el1, {}", "isb", in(reg) sctlr, options(nomem, nostack));
+}
+
+/// Whether return addresses are signed on the executing core.
+pub fn is_enabled() -> bool {
+    read_sctlr_el1() & sctlr_el1::ENIA != 0
+}
+
+/// Remove the authentication code from a signed return address.
+///
+/// Unsigned addresses are returned unchanged.
+#[inline(always)]
+pub fn strip(addr: usize) -> usize {
+    let stripped: usize;
+
+    // XPACLRI, which is in the hint space and therefore also executes on cores without FEAT_PAuth.
+    unsafe {
+        asm!(
+            "hint #7",
+            inout("x30") addr => stripped,
+            options(nomem, nostack)
+        )
+    };
+
+    stripped
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+/// Sign `addr` with key A and `modifier`.
+#[cfg(test)]
+pub fn sign(addr: usize, modifier: usize) -> usize {
+    let signed: usize;
+
+    // PACIA1716
+    unsafe {
+        asm!(
+            "hint #8",
+            inout("x17") addr => signed,
+            in("x16") modifier,
+            options(nomem, nostack)
+        )
+    };
+
+    signed
+}
+
+/// Authenticate `addr` with key A and `modifier`.
+#[cfg(test)]
+pub fn authenticate(addr: usize, modifier: usize) -> usize {
+    let authenticated: usize;
+
+    // AUTIA1716
+    unsafe {
+        asm!(
+            "hint #12",
+            inout("x17") addr => authenticated,
+            in("x16") modifier,
+            options(nomem, nostack)
+        )
+    };
+
+    authenticated
+}
@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural per-core data.
+//!
+//! The offset from a per-core variable's template to the executing core's copy of it is kept in
+//! `TPIDR_EL1`, which is otherwise unused by the kernel.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::cpu::percpu::arch_percpu
+
+use cortex_a::registers::*;
+use tock_registers::interfaces::{Readable, Writeable};
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Return the executing core's per-core data offset.
+#[inline(always)]
+pub fn local_offset() -> usize {
+    TPIDR_EL1.get() as usize
+}
+
+/// Set the executing core's per-core data offset.
+///
+/// # Safety
+///
+/// - The offset must point to the executing core's copy of the per-core data.
+#[inline(always)]
+pub unsafe fn set_local_offset(offset: usize) {
+    TPIDR_EL1.set(offset as u64);
+}
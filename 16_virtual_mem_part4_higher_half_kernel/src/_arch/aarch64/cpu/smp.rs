@@ -11,9 +11,20 @@
 //!
 //! crate::cpu::smp::arch_smp
 
-use cortex_a::registers::*;
+use crate::memory::{self, Address, Physical, Virtual};
+use core::cell::UnsafeCell;
+use cortex_a::{asm, asm::barrier, registers::*};
 use tock_registers::interfaces::Readable;
 
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+// Symbol from boot.s.
+extern "Rust" {
+    static _start_secondary: UnsafeCell<()>;
+}
+
 //--------------------------------------------------------------------------------------------------
 // Public Code
 //--------------------------------------------------------------------------------------------------
@@ -28,3 +39,29 @@ where
 
     T::from((MPIDR_EL1.get() & CORE_MASK) as u8)
 }
+
+/// The physical address at which secondary cores must start execution.
+///
+/// Secondary cores are released while their MMU is still off, so they need the physical address of
+/// `_start_secondary()`.
+pub fn phys_secondary_entry_addr() -> Result<Address<Physical>, &'static str> {
+    let virt_addr = Address::<Virtual>::new(unsafe { _start_secondary.get() as usize });
+
+    memory::mmu::try_kernel_virt_addr_to_phys_addr(virt_addr)
+}
+
+/// Release a secondary core that waits on a spin-table entry.
+///
+/// # Safety
+///
+/// - `release_addr` must be a mapped, non-cacheable alias of the core's spin-table entry.
+pub unsafe fn spin_table_release(release_addr: Address<Virtual>, entry_addr: Address<Physical>) {
+    core::ptr::write_volatile(
+        release_addr.as_usize() as *mut u64,
+        entry_addr.as_usize() as u64,
+    );
+
+    // Make sure the write is visible before waking up the cores waiting in `wfe`.
+    barrier::dsb(barrier::SY);
+    asm::sev();
+}
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural crash dump support.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::debug::crashdump::arch_crashdump
+
+use crate::cpu;
+use core::arch::asm;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The number of the frame pointer, x29, in the register numbering of the GDB stub.
+pub const FRAME_POINTER_REGISTER: usize = 29;
+
+/// The number of the stack pointer in the register numbering of the GDB stub.
+pub const STACK_POINTER_REGISTER: usize = 31;
+
+/// The size of an AArch64 frame record: the caller's frame pointer, followed by the return
+/// address.
+pub const FRAME_RECORD_SIZE: usize = 16;
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// The stack pointer and frame pointer of the caller.
+#[inline(always)]
+pub fn current_frame() -> (usize, usize) {
+    let sp: usize;
+    let fp: usize;
+
+    unsafe {
+        asm!(
+            "mov {}, sp",
+            "mov {}, x29",
+            out(reg) sp,
+            out(reg) fp,
+            options(nomem, nostack)
+        )
+    };
+
+    (sp, fp)
+}
+
+/// Read the frame record at `fp`.
+///
+/// Returns the caller's frame pointer and the return address into the caller, without its pointer
+/// authentication code.
+///
+/// # Safety
+///
+/// - [FRAME_RECORD_SIZE] bytes at `fp` must be mapped and readable.
+pub unsafe fn read_frame_record(fp: usize) -> (usize, usize) {
+    let record = fp as *const usize;
+
+    (
+        core::ptr::read_volatile(record),
+        cpu::pac::strip(core::ptr::read_volatile(record.add(1))),
+    )
+}
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural GDB stub support.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::debug::gdbstub::arch_gdbstub
+
+use core::arch::asm;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The instruction that the stub writes for GDB's software breakpoints, `brk #0`.
+pub const BREAKPOINT_INSTRUCTION: u32 = 0xd420_0000;
+
+/// The instruction that [breakpoint()] executes, `brk #1`.
+///
+/// It differs from [BREAKPOINT_INSTRUCTION], so that the stub can step over it on its own.
+pub const COMPILED_BREAKPOINT_INSTRUCTION: u32 = 0xd420_0020;
+
+/// The size of an instruction in bytes.
+pub const INSTRUCTION_SIZE: usize = 4;
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Stop in the GDB stub.
+#[inline(always)]
+pub fn breakpoint() {
+    unsafe { asm!("brk #1", options(nostack)) };
+}
@@ -11,8 +11,16 @@
 //!
 //! crate::exception::arch_exception
 
-use crate::{bsp, exception};
-use core::{arch::global_asm, cell::UnsafeCell, fmt};
+use crate::{
+    bsp, cpu,
+    debug::{self, gdbstub::StopReason},
+    exception, per_cpu, time, trace,
+};
+use core::{
+    arch::{asm, global_asm},
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use cortex_a::{asm::barrier, registers::*};
 use tock_registers::{
     interfaces::{Readable, Writeable},
@@ -48,6 +56,31 @@ struct ExceptionContext {
 
     // Exception syndrome register.
     esr_el1: EsrEL1,
+
+    /// The system counter when the exception was taken.
+    entry_ticks: u64,
+
+    /// Keeps the stack 16 byte aligned.
+    _reserved: u64,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+per_cpu! {
+    /// Address of the context saved by the IRQ that is being handled. Zero outside of IRQ handling.
+    static INTERRUPTED_CONTEXT: AtomicUsize = AtomicUsize::new(0);
+}
+
+per_cpu! {
+    /// Address of the context saved by the exception that is about to panic. Zero before.
+    static FAULT_CONTEXT: AtomicUsize = AtomicUsize::new(0);
+}
+
+per_cpu! {
+    /// The SPSR_EL1 debug and IRQ mask bits of the code that is being single-stepped.
+    static STEP_SAVED_MASKS: AtomicUsize = AtomicUsize::new(0);
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -56,6 +89,15 @@ struct ExceptionContext {
 
 /// Prints verbose information about the exception and then panics.
 fn default_exception_handler(exc: &ExceptionContext) {
+    if exc.fault_address_valid() {
+        trace::record(trace::Event::PageFault, FAR_EL1.get());
+    }
+
+    // For the crash dump.
+    FAULT_CONTEXT
+        .local()
+        .store(exc as *const ExceptionContext as usize, Ordering::Relaxed);
+
     panic!(
         "\n\nCPU Exception!\n\
         {}",
@@ -65,25 +107,69 @@ fn default_exception_handler(exc: &ExceptionContext) {
 
 //------------------------------------------------------------------------------
 // Current, EL0
+//
+// Kernel code runs with SP_EL0 selected, so these vectors are taken for exceptions interrupting it.
 //------------------------------------------------------------------------------
 
 #[no_mangle]
-unsafe extern "C" fn current_el0_synchronous(_e: &mut ExceptionContext) {
-    panic!("Should not be here. Use of SP_EL0 in EL1 is not supported.")
+unsafe extern "C" fn current_el0_synchronous(e: &mut ExceptionContext) {
+    use ESR_EL1::EC::Value::*;
+
+    // First use of FP/SIMD in the executing context.
+    if let Some(TrappedFP) = e.exception_class() {
+        return cpu::fpsimd::handle_trap();
+    }
+
+    // See `null_svc()`. The return address already points past the `svc` instruction.
+    if let Some(SVC64) = e.exception_class() {
+        return;
+    }
+
+    if debug::gdbstub::is_enabled() {
+        match e.exception_class() {
+            Some(Brk64) => return debug::gdbstub::handle_stop(e, StopReason::Breakpoint),
+            Some(SoftwareStepCurrentEL) => return debug::gdbstub::handle_stop(e, StopReason::Step),
+            _ => (),
+        }
+    }
+
+    default_exception_handler(e);
 }
 
 #[no_mangle]
-unsafe extern "C" fn current_el0_irq(_e: &mut ExceptionContext) {
-    panic!("Should not be here. Use of SP_EL0 in EL1 is not supported.")
+unsafe extern "C" fn current_el0_irq(e: &mut ExceptionContext) {
+    use exception::asynchronous::interface::IRQManager;
+
+    crate::time::profile_scope!("IRQ handling");
+
+    trace::record(trace::Event::IrqEntry, e.elr_el1);
+
+    let token = &exception::asynchronous::IRQContext::new();
+    exception::asynchronous::account_local_irq(token, e.elr_el1 as usize);
+
+    let context = INTERRUPTED_CONTEXT.local();
+    context.store(e as *const ExceptionContext as usize, Ordering::Relaxed);
+    bsp::exception::asynchronous::irq_manager().handle_pending_irqs(token);
+    context.store(0, Ordering::Relaxed);
+
+    // GDB asked through the console to stop the interrupted code.
+    if debug::gdbstub::take_break_request() {
+        debug::gdbstub::handle_stop(e, StopReason::Interrupt);
+    }
+
+    trace::record(trace::Event::IrqExit, 0);
 }
 
 #[no_mangle]
-unsafe extern "C" fn current_el0_serror(_e: &mut ExceptionContext) {
-    panic!("Should not be here. Use of SP_EL0 in EL1 is not supported.")
+unsafe extern "C" fn current_el0_serror(e: &mut ExceptionContext) {
+    default_exception_handler(e);
 }
 
 //------------------------------------------------------------------------------
 // Current, ELx
+//
+// SP_EL1 is only selected while running on the exception stack, so these vectors are taken for
+// exceptions during exception handling.
 //------------------------------------------------------------------------------
 
 #[no_mangle]
@@ -93,10 +179,7 @@ unsafe extern "C" fn current_elx_synchronous(e: &mut ExceptionContext) {
 
 #[no_mangle]
 unsafe extern "C" fn current_elx_irq(_e: &mut ExceptionContext) {
-    use exception::asynchronous::interface::IRQManager;
-
-    let token = &exception::asynchronous::IRQContext::new();
-    bsp::exception::asynchronous::irq_manager().handle_pending_irqs(token);
+    panic!("Should not be here. IRQs are masked during exception handling.")
 }
 
 #[no_mangle]
@@ -142,6 +225,116 @@ unsafe extern "C" fn lower_aarch32_serror(e: &mut ExceptionContext) {
     default_exception_handler(e);
 }
 
+//------------------------------------------------------------------------------
+// Crash-only
+//
+// All vectors of the crash-only table. Unlike the default handler, this does not rely on per-core
+// data or tracing, which might not be set up when the table is in use.
+//------------------------------------------------------------------------------
+
+#[no_mangle]
+unsafe extern "C" fn crash_only_exception(e: &mut ExceptionContext) {
+    panic!(
+        "\n\nCPU Exception, taken through the crash-only vector table!\n\
+        {}",
+        e
+    );
+}
+
+//------------------------------------------------------------------------------
+// Debugging
+//------------------------------------------------------------------------------
+
+/// MDSCR_EL1 bits.
+mod mdscr {
+    /// Software step enable.
+    pub const SS: u64 = 1 << 0;
+
+    /// Debug exceptions enable at the current EL.
+    pub const KDE: u64 = 1 << 13;
+}
+
+/// SPSR_EL1 bits that are not covered by the register definitions.
+mod spsr {
+    /// Software step. Set to step the instruction that the exception returns to.
+    pub const SS: u64 = 1 << 21;
+
+    /// The debug and IRQ masks.
+    pub const D: u64 = 1 << 9;
+    pub const I: u64 = 1 << 7;
+}
+
+/// The registers of GDB's AArch64 `g` packet: x0 to x30, sp, pc and cpsr.
+const GDB_NUM_REGISTERS: usize = 34;
+
+impl debug::gdbstub::interface::StoppedCore for ExceptionContext {
+    fn num_registers(&self) -> usize {
+        GDB_NUM_REGISTERS
+    }
+
+    fn register(&self, n: usize) -> Option<(u64, usize)> {
+        match n {
+            0..=29 => Some((self.gpr[n], 8)),
+            30 => Some((self.lr, 8)),
+            // The interrupted kernel code runs on SP_EL0, which the exception entry leaves alone.
+            31 => Some((SP_EL0.get(), 8)),
+            32 => Some((self.elr_el1, 8)),
+            33 => Some((self.spsr_el1.0.get(), 4)),
+            _ => None,
+        }
+    }
+
+    fn set_register(&mut self, n: usize, value: u64) -> Result<(), &'static str> {
+        match n {
+            0..=29 => self.gpr[n] = value,
+            30 => self.lr = value,
+            31 => SP_EL0.set(value),
+            32 => self.elr_el1 = value,
+            33 => self.spsr_el1.0.set(value),
+            _ => return Err("Invalid register number"),
+        }
+
+        Ok(())
+    }
+
+    fn pc(&self) -> usize {
+        self.elr_el1 as usize
+    }
+
+    fn set_pc(&mut self, pc: usize) {
+        self.elr_el1 = pc as u64;
+    }
+
+    fn set_single_step(&mut self, enable: bool) {
+        let saved = STEP_SAVED_MASKS.local();
+        let mut spsr = self.spsr_el1.0.get();
+        let mut mdscr: u64;
+
+        unsafe { asm!("mrs {}, mdscr_el1", out(reg) mdscr, options(nomem, nostack)) };
+
+        if enable {
+            // Step with debug exceptions unmasked, as required for them to be taken, but with IRQs
+            // masked, so that the step does not land in the IRQ handler.
+            saved.store((spsr & (spsr::D | spsr::I)) as usize, Ordering::Relaxed);
+            spsr = (spsr & !spsr::D) | spsr::I | spsr::SS;
+            mdscr |= mdscr::SS | mdscr::KDE;
+
+            // The OS lock blocks debug exceptions. It is set on cold reset.
+            unsafe { asm!("msr oslar_el1, xzr", options(nomem, nostack)) };
+        } else if mdscr & mdscr::SS != 0 {
+            spsr = (spsr & !(spsr::D | spsr::I | spsr::SS)) | saved.load(Ordering::Relaxed) as u64;
+            mdscr &= !(mdscr::SS | mdscr::KDE);
+        }
+
+        self.spsr_el1.0.set(spsr);
+
+        unsafe {
+            asm!("msr mdscr_el1, {}", in(reg) mdscr, options(nomem, nostack));
+            barrier::isb(barrier::SY);
+        }
+    }
+}
+
 //------------------------------------------------------------------------------
 // Misc
 //------------------------------------------------------------------------------
@@ -184,6 +377,48 @@ impl EsrEL1 {
     fn exception_class(&self) -> Option<ESR_EL1::EC::Value> {
         self.0.read_as_enum(ESR_EL1::EC)
     }
+
+    /// The fault status code (IFSC or DFSC) of instruction and data aborts.
+    fn fault_status_code(&self) -> Option<u64> {
+        use ESR_EL1::EC::Value::*;
+
+        match self.exception_class() {
+            Some(
+                InstrAbortLowerEL | InstrAbortCurrentEL | DataAbortLowerEL | DataAbortCurrentEL,
+            ) => Some(self.0.read(ESR_EL1::ISS) & 0x3f),
+            _ => None,
+        }
+    }
+
+    /// Whether a data abort was caused by a write.
+    fn is_write_abort(&self) -> Option<bool> {
+        use ESR_EL1::EC::Value::*;
+
+        match self.exception_class() {
+            Some(DataAbortLowerEL | DataAbortCurrentEL) => {
+                Some(self.0.read(ESR_EL1::ISS) & (1 << 6) != 0)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Human readable fault status code, and the translation table level that caused the fault.
+fn fault_status_code_translation(fsc: u64) -> (&'static str, Option<u64>) {
+    let level = fsc & 0b11;
+
+    match fsc >> 2 {
+        0b0000 => ("Address size fault", Some(level)),
+        0b0001 => ("Translation fault", Some(level)),
+        0b0010 => ("Access flag fault", Some(level)),
+        0b0011 => ("Permission fault", Some(level)),
+        _ => match fsc {
+            0b01_0000 => ("Synchronous External abort", None),
+            0b10_0001 => ("Alignment fault", None),
+            0b11_0000 => ("TLB conflict abort", None),
+            _ => ("N/A", None),
+        },
+    }
 }
 
 /// Human readable ESR_EL1.
@@ -198,13 +433,32 @@ impl fmt::Display for EsrEL1 {
 
         // Exception class.
         let ec_translation = match self.exception_class() {
+            Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => "Instruction Abort, current EL",
             Some(ESR_EL1::EC::Value::DataAbortCurrentEL) => "Data Abort, current EL",
             _ => "N/A",
         };
         writeln!(f, " - {}", ec_translation)?;
 
         // Raw print of instruction specific syndrome.
-        write!(f, "      Instr Specific Syndrome (ISS): {:#x}", self.0.read(ESR_EL1::ISS))
+        write!(f, "      Instr Specific Syndrome (ISS): {:#x}", self.0.read(ESR_EL1::ISS))?;
+
+        // Decoded syndrome of aborts.
+        if let Some(fsc) = self.fault_status_code() {
+            let (fsc_translation, level) = fault_status_code_translation(fsc);
+
+            write!(f, "\n            Fault Status Code (FSC): {:#x} - {}", fsc, fsc_translation)?;
+            if let Some(level) = level {
+                write!(f, ", level {}", level)?;
+            }
+        }
+
+        if let Some(is_write) = self.is_write_abort() {
+            write!(f, "\n            Write not Read    (WnR): {}",
+                if is_write { "Write" } else { "Read" }
+            )?;
+        }
+
+        Ok(())
     }
 }
 
@@ -277,22 +531,99 @@ pub fn current_privilege_level() -> (PrivilegeLevel, &'static str) {
     }
 }
 
-/// Init exception handling by setting the exception vector base address register.
+/// Init exception handling by setting up the executing core's exception stack and per-core data,
+/// and installing the full exception vector table.
+///
+/// The handlers keep their state in per-core data, so it is set up here instead of leaving it to
+/// every caller.
 ///
 /// # Safety
 ///
 /// - Changes the HW state of the executing core.
-/// - The vector table and the symbol `__exception_vector_table_start` from the linker script must
-///   adhere to the alignment and size constraints demanded by the ARMv8-A Architecture Reference
-///   Manual.
+/// - Must be called while SP_EL0 is selected as the stack pointer.
+/// - Must be called exactly once per core, by the boot core before any other core.
 pub unsafe fn handling_init() {
-    // Provided by exception.S.
-    extern "Rust" {
-        static __exception_vector_start: UnsafeCell<()>;
+    // SP_EL1 can not be written directly from EL1. Briefly select it to set it up.
+    let stack_end = bsp::memory::virt_exception_stack_end_exclusive_addr(cpu::smp::core_id());
+    asm!(
+        "msr SPSel, #1",
+        "mov sp, {}",
+        "msr SPSel, #0",
+        in(reg) stack_end.as_usize(),
+        options(nostack)
+    );
+
+    if cpu::smp::core_id::<u64>() == bsp::cpu::BOOT_CORE_ID {
+        cpu::percpu::init();
+    } else {
+        cpu::percpu::init_secondary_core();
+    }
+
+    exception::vector::install(exception::vector::VectorTable::full());
+}
+
+/// Take a supervisor call and return from it right away.
+///
+/// The kernel has no system calls. This measures what the exception entry and exit cost.
+#[inline(always)]
+pub fn null_svc() {
+    unsafe { asm!("svc #0", options(nostack)) };
+}
+
+/// The time at which the vector stub entered the IRQ that is being handled.
+///
+/// Returns `None` outside of IRQ handling.
+pub fn irq_entry_time() -> Option<time::Instant> {
+    let addr = INTERRUPTED_CONTEXT.local().load(Ordering::Relaxed);
+
+    if addr == 0 {
+        return None;
+    }
+
+    // The context lives on the exception stack until the IRQ handler returns.
+    let context = unsafe { &*(addr as *const ExceptionContext) };
+
+    // The entry code reads the physical counter.
+    Some(time::Instant::from_ticks(time::physical_to_counter_ticks(
+        context.entry_ticks,
+    )))
+}
+
+/// Call `f` with the register state of the code that the IRQ being handled interrupted.
+///
+/// `f` receives `None` outside of IRQ handling.
+pub fn with_interrupted_context<R>(f: impl FnOnce(Option<&dyn fmt::Display>) -> R) -> R {
+    let addr = INTERRUPTED_CONTEXT.local().load(Ordering::Relaxed);
+
+    if addr == 0 {
+        return f(None);
+    }
+
+    // The context lives on the exception stack until the IRQ handler returns.
+    let context = unsafe { &*(addr as *const ExceptionContext) };
+
+    f(Some(context))
+}
+
+/// Call `f` with the register state of the code that the executing core was running when it took
+/// the exception that is being handled.
+///
+/// This is the faulting code if the exception is about to panic, and else the code that the IRQ
+/// being handled interrupted. `f` receives `None` outside of exception handling.
+pub fn with_exception_context<R>(
+    f: impl FnOnce(Option<&dyn debug::gdbstub::interface::StoppedCore>) -> R,
+) -> R {
+    let mut addr = FAULT_CONTEXT.local().load(Ordering::Relaxed);
+    if addr == 0 {
+        addr = INTERRUPTED_CONTEXT.local().load(Ordering::Relaxed);
+    }
+
+    if addr == 0 {
+        return f(None);
     }
 
-    VBAR_EL1.set(__exception_vector_start.get() as u64);
+    // Both contexts live on the exception stack until their handler returns.
+    let context = unsafe { &*(addr as *const ExceptionContext) };
 
-    // Force VBAR update to complete before next instruction.
-    barrier::isb(barrier::SY);
+    f(Some(context))
 }
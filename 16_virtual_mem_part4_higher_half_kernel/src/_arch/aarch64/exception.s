@@ -10,7 +10,7 @@
 /// the context as the first parameter to '\handler'.
 .macro CALL_WITH_CONTEXT handler
 	// Make room on the stack for the exception context.
-	sub	sp,  sp,  #16 * 17
+	sub	sp,  sp,  #16 * 18
 
 	// Store all general purpose registers on the stack.
 	stp	x0,  x1,  [sp, #16 * 0]
@@ -38,6 +38,10 @@
 	stp	lr,  x1,  [sp, #16 * 15]
 	stp	x2,  x3,  [sp, #16 * 16]
 
+	// Timestamp the exception entry, for measuring the latency of IRQs.
+	mrs	x4,  CNTPCT_EL0
+	str	x4,  [sp, #16 * 17]
+
 	// x0 is the first argument for the function called through `\handler`.
 	mov	x0,  sp
 
@@ -142,9 +146,55 @@ __exception_restore_context:
 	ldp	x26, x27, [sp, #16 * 13]
 	ldp	x28, x29, [sp, #16 * 14]
 
-	add	sp,  sp,  #16 * 17
+	add	sp,  sp,  #16 * 18
 
 	eret
 
 .size	__exception_restore_context, . - __exception_restore_context
 .type	__exception_restore_context, function
+
+//------------------------------------------------------------------------------
+// The crash-only exception vector table.
+//
+// Every entry, including IRQs, ends up in the same handler, which only reports the exception and
+// panics. In its own section, so that .org counts from its start.
+//------------------------------------------------------------------------------
+.section .text.crash_vectors, "ax", %progbits
+
+.align 11
+
+__crash_vector_start:
+
+.org 0x000
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x080
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x100
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x180
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x200
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x280
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x300
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x380
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x400
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x480
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x500
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x580
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x600
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x680
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x700
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x780
+	CALL_WITH_CONTEXT crash_only_exception
+.org 0x800
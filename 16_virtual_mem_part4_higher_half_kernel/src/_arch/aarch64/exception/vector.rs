@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural exception vector tables.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::exception::vector::arch_vector
+
+use core::cell::UnsafeCell;
+use cortex_a::{asm::barrier, registers::*};
+use tock_registers::interfaces::{Readable, Writeable};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+// Provided by exception.s.
+extern "Rust" {
+    static __exception_vector_start: UnsafeCell<()>;
+    static __crash_vector_start: UnsafeCell<()>;
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// VBAR_EL1 ignores the low 11 bits, so tables must be aligned to 2 KiB.
+pub const TABLE_ALIGN: usize = 2048;
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// The address of the full vector table.
+pub fn full_table_addr() -> usize {
+    unsafe { __exception_vector_start.get() as usize }
+}
+
+/// The address of the crash-only vector table.
+pub fn crash_table_addr() -> usize {
+    unsafe { __crash_vector_start.get() as usize }
+}
+
+/// The address of the executing core's vector table.
+pub fn vector_base() -> usize {
+    VBAR_EL1.get() as usize
+}
+
+/// Point the executing core to the vector table at `addr`.
+///
+/// # Safety
+///
+/// - `addr` must be the start of a valid vector table.
+pub unsafe fn set_vector_base(addr: usize) {
+    VBAR_EL1.set(addr as u64);
+
+    // Force VBAR update to complete before next instruction.
+    barrier::isb(barrier::SY);
+}
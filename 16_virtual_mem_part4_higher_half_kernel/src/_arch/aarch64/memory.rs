@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural bulk memory operations.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::memory::arch_memory
+
+use core::arch::{asm, global_asm};
+
+// Assembly counterpart to this file.
+global_asm!(include_str!("memory.s"));
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// DCZID_EL0 bits. The register is not covered by the register definitions.
+mod dczid {
+    /// DC ZVA is prohibited.
+    pub const DZP: u64 = 1 << 4;
+
+    /// Log2 of the block size in words.
+    pub const BS_MASK: u64 = 0b1111;
+}
+
+extern "C" {
+    fn __kmemcpy(dst: *mut u8, src: *const u8, len: usize);
+    fn __kmemmove(dst: *mut u8, src: *const u8, len: usize);
+    fn __kmemset(dst: *mut u8, value: u8, len: usize);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// The size of the blocks that DC ZVA zeroes, if it is permitted.
+fn zva_block_size() -> Option<usize> {
+    let dczid: u64;
+    unsafe { asm!("mrs {}, dczid_el0", out(reg) dczid, options(nomem, nostack)) };
+
+    if dczid & dczid::DZP != 0 {
+        return None;
+    }
+
+    Some(4 << (dczid & dczid::BS_MASK))
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Copy `len` bytes from `src` to `dst`. The ranges must not overlap.
+///
+/// Copies 64 bytes per iteration, which is considerably faster than the generic `memcpy` for
+/// bulk copies, like image loading or framebuffer updates.
+///
+/// # Safety
+///
+/// - `src` must be valid for reads and `dst` must be valid for writes of `len` bytes.
+/// - Both ranges must be normal memory, because the accesses are not aligned.
+pub unsafe fn kmemcpy(dst: *mut u8, src: *const u8, len: usize) {
+    __kmemcpy(dst, src, len)
+}
+
+/// Copy `len` bytes from `src` to `dst`. The ranges may overlap.
+///
+/// # Safety
+///
+/// - See [kmemcpy()].
+pub unsafe fn kmemmove(dst: *mut u8, src: *const u8, len: usize) {
+    __kmemmove(dst, src, len)
+}
+
+/// Set `len` bytes at `dst` to `value`.
+///
+/// Large zeroings use `DC ZVA`, which zeroes whole cache lines without reading them first.
+///
+/// # Safety
+///
+/// - `dst` must be valid for writes of `len` bytes.
+/// - The range must be normal memory, because the accesses are not aligned, and `DC ZVA` faults on
+///   device memory.
+pub unsafe fn kmemset(dst: *mut u8, value: u8, len: usize) {
+    let block = match zva_block_size() {
+        Some(block) if value == 0 && len >= 2 * block => block,
+        _ => return __kmemset(dst, value, len),
+    };
+
+    // Zero the unaligned head and tail conventionally, and the blocks in between with DC ZVA.
+    let start = dst as usize;
+    let end = start + len;
+    let zva_start = (start + block - 1) & !(block - 1);
+    let zva_end = end & !(block - 1);
+
+    __kmemset(dst, 0, zva_start - start);
+
+    let mut addr = zva_start;
+    while addr < zva_end {
+        asm!("dc zva, {}", in(reg) addr, options(nostack));
+        addr += block;
+    }
+
+    __kmemset(zva_end as *mut u8, 0, end - zva_end);
+}
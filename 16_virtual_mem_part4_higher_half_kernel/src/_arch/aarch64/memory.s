@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+// Not `.text`, whose start is reserved for the exception vector table.
+.section .text.kmem
+
+// The FP/SIMD registers belong to the executing FP/SIMD context, so only general purpose registers
+// are used. Blocks of 64 bytes are loaded completely before they are stored, which makes the
+// forward copy safe for overlapping ranges with dst < src, and the backward copy for dst > src.
+//
+// Unaligned accesses are fine, since the functions are only used on normal memory.
+
+//------------------------------------------------------------------------------
+// fn __kmemcpy(dst: *mut u8, src: *const u8, len: usize)
+//------------------------------------------------------------------------------
+__kmemcpy:
+	cmp	x2,  #64
+	b.lo	2f
+
+	// Copy blocks of 64 bytes.
+1:	ldp	x4,  x5,  [x1, #16 * 0]
+	ldp	x6,  x7,  [x1, #16 * 1]
+	ldp	x8,  x9,  [x1, #16 * 2]
+	ldp	x10, x11, [x1, #16 * 3]
+	add	x1,  x1,  #64
+	stp	x4,  x5,  [x0, #16 * 0]
+	stp	x6,  x7,  [x0, #16 * 1]
+	stp	x8,  x9,  [x0, #16 * 2]
+	stp	x10, x11, [x0, #16 * 3]
+	add	x0,  x0,  #64
+	sub	x2,  x2,  #64
+	cmp	x2,  #64
+	b.hs	1b
+
+	// Copy the remaining words, then the remaining bytes.
+2:	cmp	x2,  #8
+	b.lo	4f
+3:	ldr	x4,  [x1], #8
+	str	x4,  [x0], #8
+	sub	x2,  x2,  #8
+	cmp	x2,  #8
+	b.hs	3b
+
+4:	cbz	x2,  6f
+5:	ldrb	w4,  [x1], #1
+	strb	w4,  [x0], #1
+	subs	x2,  x2,  #1
+	b.ne	5b
+
+6:	ret
+
+.size	__kmemcpy, . - __kmemcpy
+.type	__kmemcpy, function
+.global	__kmemcpy
+
+//------------------------------------------------------------------------------
+// fn __kmemmove(dst: *mut u8, src: *const u8, len: usize)
+//------------------------------------------------------------------------------
+__kmemmove:
+	// Copy forward unless dst lies within the source.
+	sub	x3,  x0,  x1
+	cmp	x3,  x2
+	b.hs	__kmemcpy
+
+	// Copy backward, starting at the end.
+	add	x1,  x1,  x2
+	add	x0,  x0,  x2
+	cmp	x2,  #64
+	b.lo	2f
+
+1:	ldp	x4,  x5,  [x1, #-16 * 1]
+	ldp	x6,  x7,  [x1, #-16 * 2]
+	ldp	x8,  x9,  [x1, #-16 * 3]
+	ldp	x10, x11, [x1, #-16 * 4]
+	sub	x1,  x1,  #64
+	stp	x4,  x5,  [x0, #-16 * 1]
+	stp	x6,  x7,  [x0, #-16 * 2]
+	stp	x8,  x9,  [x0, #-16 * 3]
+	stp	x10, x11, [x0, #-16 * 4]
+	sub	x0,  x0,  #64
+	sub	x2,  x2,  #64
+	cmp	x2,  #64
+	b.hs	1b
+
+2:	cmp	x2,  #8
+	b.lo	4f
+3:	ldr	x4,  [x1, #-8]!
+	str	x4,  [x0, #-8]!
+	sub	x2,  x2,  #8
+	cmp	x2,  #8
+	b.hs	3b
+
+4:	cbz	x2,  6f
+5:	ldrb	w4,  [x1, #-1]!
+	strb	w4,  [x0, #-1]!
+	subs	x2,  x2,  #1
+	b.ne	5b
+
+6:	ret
+
+.size	__kmemmove, . - __kmemmove
+.type	__kmemmove, function
+.global	__kmemmove
+
+//------------------------------------------------------------------------------
+// fn __kmemset(dst: *mut u8, value: u8, len: usize)
+//------------------------------------------------------------------------------
+__kmemset:
+	// Replicate the byte into all bytes of a word.
+	and	x1,  x1,  #0xff
+	mov	x4,  #0x0101010101010101
+	mul	x4,  x4,  x1
+
+	cmp	x2,  #64
+	b.lo	2f
+
+1:	stp	x4,  x4,  [x0, #16 * 0]
+	stp	x4,  x4,  [x0, #16 * 1]
+	stp	x4,  x4,  [x0, #16 * 2]
+	stp	x4,  x4,  [x0, #16 * 3]
+	add	x0,  x0,  #64
+	sub	x2,  x2,  #64
+	cmp	x2,  #64
+	b.hs	1b
+
+2:	cmp	x2,  #8
+	b.lo	4f
+3:	str	x4,  [x0], #8
+	sub	x2,  x2,  #8
+	cmp	x2,  #8
+	b.hs	3b
+
+4:	cbz	x2,  6f
+5:	strb	w4,  [x0], #1
+	subs	x2,  x2,  #1
+	b.ne	5b
+
+6:	ret
+
+.size	__kmemset, . - __kmemset
+.type	__kmemset, function
+.global	__kmemset
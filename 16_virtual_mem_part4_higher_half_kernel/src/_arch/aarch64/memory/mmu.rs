@@ -14,10 +14,10 @@
 //! crate::memory::mmu::arch_mmu
 
 use crate::{
-    bsp, memory,
+    bsp, cpu, memory,
     memory::{mmu::TranslationGranule, Address, Physical},
 };
-use core::intrinsics::unlikely;
+use core::{arch::asm, intrinsics::unlikely};
 use cortex_a::{asm::barrier, registers::*};
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 
@@ -28,6 +28,9 @@ use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 /// Memory Management Unit type.
 struct MemoryManagementUnit;
 
+/// TCR_EL1.E0PD1: EL0 accesses to the TTBR1 region fault without a walk.
+const TCR_EL1_E0PD1: u64 = 1 << 56;
+
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
 //--------------------------------------------------------------------------------------------------
@@ -96,6 +99,13 @@ impl MemoryManagementUnit {
                 + TCR_EL1::T1SZ.val(t1sz)
                 + TCR_EL1::EPD0::DisableTTBR0Walks,
         );
+
+        // Where implemented, let EL0 accesses to the kernel half fault right away, so that their
+        // timing can not tell which kernel addresses are mapped. There is no EL0 code yet, but the
+        // bit costs nothing. Not covered by the register definitions.
+        if cpu::features::features().e0pd {
+            TCR_EL1.set(TCR_EL1.get() | TCR_EL1_E0PD1);
+        }
     }
 }
 
@@ -108,6 +118,45 @@ pub fn mmu() -> &'static impl memory::mmu::interface::MMU {
     &MMU
 }
 
+/// Invalidate the TLBs and instruction caches of all cores in the Inner Shareable domain.
+///
+/// The maintenance instructions are broadcast in hardware, so the other cores do not need to take
+/// part. The function returns after the invalidation completed everywhere.
+#[inline(always)]
+pub fn broadcast_tlb_and_icache_invalidation() {
+    unsafe {
+        // Make the new table entries visible to the table walkers first.
+        #[rustfmt::skip]
+        asm!(
+            "dsb ishst",
+            "tlbi vmalle1is",
+            "ic ialluis",
+            "dsb ish",
+            "isb",
+            options(nostack)
+        );
+    }
+}
+
+/// Invalidate the executing core's TLB entries for the page that contains `virt_addr`.
+#[inline(always)]
+pub fn local_tlb_invalidate_page(virt_addr: usize) {
+    // The operand holds bits [55:12] of the address.
+    let operand = (virt_addr >> 12) & ((1 << 44) - 1);
+
+    unsafe {
+        #[rustfmt::skip]
+        asm!(
+            "dsb nshst",
+            "tlbi vaae1, {}",
+            "dsb nsh",
+            "isb",
+            in(reg) operand,
+            options(nostack)
+        );
+    }
+}
+
 //------------------------------------------------------------------------------
 // OS Interface Code
 //------------------------------------------------------------------------------
@@ -135,6 +184,14 @@ impl memory::mmu::interface::MMU for MemoryManagementUnit {
         // Set the "Translation Table Base Register".
         TTBR1_EL1.set_baddr(phys_tables_base_addr.as_usize() as u64);
 
+        // The kernel never maps anything in the lower half. Not even the boot code needs an
+        // identity mapping, because it runs in EL2 or EL3 with their MMU off, and enters EL1 at a
+        // virtual address. Walks of TTBR0 are disabled below, so every lower half address faults.
+        //
+        // Clear whatever the firmware left in TTBR0 nonetheless, so that no stale tables can come
+        // back to life if the walks are ever enabled.
+        TTBR0_EL1.set(0);
+
         self.configure_translation_control();
 
         // Switch the MMU on.
@@ -26,7 +26,7 @@ use crate::{
 };
 use core::convert;
 use tock_registers::{
-    interfaces::{Readable, Writeable},
+    interfaces::{ReadWriteable, Readable, Writeable},
     register_bitfields,
     registers::InMemoryRegister,
 };
@@ -68,6 +68,12 @@ register_bitfields! {u64,
             True = 1
         ],
 
+        /// Guarded page, for branch target identification.
+        GP       OFFSET(50) NUMBITS(1) [
+            False = 0,
+            True = 1
+        ],
+
         /// Physical address of the next table descriptor (lvl2) or the page descriptor (lvl3).
         OUTPUT_ADDR_64KiB OFFSET(16) NUMBITS(32) [], // [47:16]
 
@@ -291,6 +297,14 @@ impl PageDescriptor {
     fn try_attributes(&self) -> Result<AttributeFields, &'static str> {
         InMemoryRegister::<u64, STAGE1_PAGE_DESCRIPTOR::Register>::new(self.value).try_into()
     }
+
+    /// Set the guarded page bit.
+    fn set_guarded(&mut self) {
+        let val = InMemoryRegister::<u64, STAGE1_PAGE_DESCRIPTOR::Register>::new(self.value);
+        val.modify(STAGE1_PAGE_DESCRIPTOR::GP::True);
+
+        self.value = val.get();
+    }
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -373,6 +387,17 @@ impl<const NUM_TABLES: usize, const START_FROM_TOP: bool>
         Ok(desc)
     }
 
+    /// Returns the mutable PageDescriptor corresponding to the supplied page address.
+    #[inline(always)]
+    fn page_descriptor_from_page_addr_mut(
+        &mut self,
+        virt_page_addr: PageAddress<Virtual>,
+    ) -> Result<&mut PageDescriptor, &'static str> {
+        let (lvl2_index, lvl3_index) = self.lvl2_lvl3_index_from_page_addr(virt_page_addr)?;
+
+        Ok(&mut self.lvl3[lvl2_index][lvl3_index])
+    }
+
     /// Sets the PageDescriptor corresponding to the supplied page address.
     ///
     /// Doesn't allow overriding an already valid page.
@@ -449,6 +474,27 @@ impl<const NUM_TABLES: usize, const START_FROM_TOP: bool>
         Ok(())
     }
 
+    unsafe fn set_guarded(
+        &mut self,
+        virt_region: &MemoryRegion<Virtual>,
+    ) -> Result<(), &'static str> {
+        for virt_page_addr in virt_region.into_iter() {
+            let desc = self.page_descriptor_from_page_addr_mut(virt_page_addr)?;
+
+            if !desc.is_valid() {
+                return Err("Page marked invalid");
+            }
+
+            if desc.try_attributes()?.execute_never {
+                return Err("Page is not executable");
+            }
+
+            desc.set_guarded();
+        }
+
+        Ok(())
+    }
+
     fn try_virt_page_addr_to_phys_page_addr(
         &self,
         virt_page_addr: PageAddress<Virtual>,
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural Performance Monitors Extension.
+//!
+//! Event counters are accessed indirectly, by selecting them through PMSELR_EL0 first. Callers must
+//! therefore ensure that the selection is not changed concurrently on the executing core, for
+//! example by an IRQ handler.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::pmu::arch_pmu
+
+use core::arch::asm;
+use cortex_a::asm::barrier;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// PMCR_EL0 bits.
+mod pmcr {
+    /// Enable all counters.
+    pub const E: u64 = 1 << 0;
+
+    /// Reset all event counters.
+    pub const P: u64 = 1 << 1;
+
+    /// Reset the cycle counter.
+    pub const C: u64 = 1 << 2;
+
+    /// Let the cycle counter overflow at 64 bits instead of 32 bits.
+    pub const LC: u64 = 1 << 6;
+
+    /// The number of event counters.
+    pub const N_SHIFT: u64 = 11;
+    pub const N_MASK: u64 = 0x1f;
+}
+
+/// The bit that stands for the cycle counter in the counter enable and overflow registers.
+const CYCLE_COUNTER_BIT: u32 = 1 << 31;
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+#[inline(always)]
+fn read_pmcr() -> u64 {
+    let val;
+    unsafe { asm!("mrs {}, pmcr_el0", out(reg) val, options(nomem, nostack)) };
+
+    val
+}
+
+#[inline(always)]
+fn select(index: usize) {
+    unsafe { asm!("msr pmselr_el0, {}", in(reg) index as u64, options(nomem, nostack)) };
+    unsafe { barrier::isb(barrier::SY) };
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Enable and reset the executing core's counters, including the cycle counter.
+///
+/// Counting is done at EL1 and EL0.
+pub fn init() {
+    unsafe {
+        asm!("msr pmccfiltr_el0, xzr", options(nomem, nostack));
+        asm!(
+            "msr pmcr_el0, {}",
+            in(reg) read_pmcr() | pmcr::E | pmcr::P | pmcr::C | pmcr::LC,
+            options(nomem, nostack)
+        );
+        asm!(
+            "msr pmcntenset_el0, {}",
+            in(reg) u64::from(CYCLE_COUNTER_BIT),
+            options(nomem, nostack)
+        );
+    }
+    unsafe { barrier::isb(barrier::SY) };
+}
+
+/// The number of event counters implemented by the executing core.
+pub fn num_event_counters() -> usize {
+    ((read_pmcr() >> pmcr::N_SHIFT) & pmcr::N_MASK) as usize
+}
+
+/// Read the cycle counter.
+#[inline(always)]
+pub fn cycle_counter() -> u64 {
+    let val;
+    unsafe { asm!("mrs {}, pmccntr_el0", out(reg) val, options(nomem, nostack)) };
+
+    val
+}
+
+/// Set the event counted by an event counter.
+pub fn set_event_type(index: usize, event: u16) {
+    select(index);
+
+    // The filter bits are left zero, which counts at EL1 and EL0.
+    unsafe { asm!("msr pmxevtyper_el0, {}", in(reg) u64::from(event), options(nomem, nostack)) };
+}
+
+/// Read an event counter.
+pub fn read_event_counter(index: usize) -> u32 {
+    let val: u64;
+
+    select(index);
+    unsafe { asm!("mrs {}, pmxevcntr_el0", out(reg) val, options(nomem, nostack)) };
+
+    val as u32
+}
+
+/// Write an event counter.
+pub fn write_event_counter(index: usize, val: u32) {
+    select(index);
+    unsafe { asm!("msr pmxevcntr_el0, {}", in(reg) u64::from(val), options(nomem, nostack)) };
+}
+
+/// Start an event counter and enable its overflow interrupt.
+pub fn enable_event_counter(index: usize) {
+    let bit = 1_u64 << index;
+
+    unsafe {
+        asm!("msr pmintenset_el1, {}", in(reg) bit, options(nomem, nostack));
+        asm!("msr pmcntenset_el0, {}", in(reg) bit, options(nomem, nostack));
+    }
+    unsafe { barrier::isb(barrier::SY) };
+}
+
+/// Stop an event counter and disable its overflow interrupt.
+pub fn disable_event_counter(index: usize) {
+    let bit = 1_u64 << index;
+
+    unsafe {
+        asm!("msr pmcntenclr_el0, {}", in(reg) bit, options(nomem, nostack));
+        asm!("msr pmintenclr_el1, {}", in(reg) bit, options(nomem, nostack));
+    }
+    unsafe { barrier::isb(barrier::SY) };
+}
+
+/// The event counters with a pending overflow, as a bitmask. The cycle counter is excluded.
+pub fn pending_overflows() -> u32 {
+    let val: u64;
+    unsafe { asm!("mrs {}, pmovsset_el0", out(reg) val, options(nomem, nostack)) };
+
+    val as u32 & !CYCLE_COUNTER_BIT
+}
+
+/// Clear the given pending overflows.
+pub fn clear_overflows(mask: u32) {
+    unsafe { asm!("msr pmovsclr_el0, {}", in(reg) u64::from(mask), options(nomem, nostack)) };
+    unsafe { barrier::isb(barrier::SY) };
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Architectural synchronization primitives.
+//!
+//! # Orientation
+//!
+//! Since arch modules are imported into generic modules using the path attribute, the path of this
+//! file is:
+//!
+//! crate::synchronization::arch_synchronization
+
+use core::{arch::asm, sync::atomic::AtomicU16};
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Wait until `value` equals `expected`, with acquire semantics.
+///
+/// Instead of hammering the interconnect, the core sleeps in `WFE` between reads. The exclusive
+/// load arms the core's exclusive monitor for the value's address. A store from another core
+/// clears the monitor, which in turn generates the event that wakes up the waiting core.
+#[inline(always)]
+pub fn wait_until_equal(value: &AtomicU16, expected: u16) {
+    unsafe {
+        #[rustfmt::skip]
+        asm!(
+            "sevl",
+            "1:",
+            "wfe",
+            "ldaxrh {tmp:w}, [{addr}]",
+            "cmp {tmp:w}, {expected:w}",
+            "b.ne 1b",
+            addr = in(reg) value as *const AtomicU16,
+            expected = in(reg) expected as u32,
+            tmp = out(reg) _,
+            options(nostack)
+        );
+    }
+}
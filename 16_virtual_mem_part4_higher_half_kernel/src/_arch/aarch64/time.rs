@@ -12,7 +12,11 @@
 //! crate::time::arch_time
 
 use crate::{time, warn};
-use core::time::Duration;
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 use cortex_a::{asm::barrier, registers::*};
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 
@@ -31,6 +35,9 @@ struct GenericTimer;
 
 static TIME_MANAGER: GenericTimer = GenericTimer;
 
+/// The physical counter minus the virtual one, or zero if the time base is the physical counter.
+static VIRTUAL_OFFSET: AtomicU64 = AtomicU64::new(0);
+
 //--------------------------------------------------------------------------------------------------
 // Private Code
 //--------------------------------------------------------------------------------------------------
@@ -42,31 +49,149 @@ impl GenericTimer {
         unsafe { barrier::isb(barrier::SY) };
         CNTPCT_EL0.get()
     }
+
+    #[inline(always)]
+    fn read_cntvct(&self) -> u64 {
+        unsafe { barrier::isb(barrier::SY) };
+        CNTVCT_EL0.get()
+    }
+
+    /// Read the counter that the alarms compare against.
+    #[inline(always)]
+    fn read_counter(&self) -> u64 {
+        if VIRTUAL_OFFSET.load(Ordering::Relaxed) == 0 {
+            self.read_cntpct()
+        } else {
+            self.read_cntvct()
+        }
+    }
+}
+
+/// The frequency of the system counter in Hz.
+///
+/// Only the lower 32 bits of CNTFRQ_EL0 are defined.
+fn counter_frequency() -> u64 {
+    CNTFRQ_EL0.get() & u64::from(u32::MAX)
 }
 
 //--------------------------------------------------------------------------------------------------
 // Public Code
 //--------------------------------------------------------------------------------------------------
 
+/// Select the time base.
+///
+/// The alarms use the virtual timer, so instants must be measured by the virtual counter that it
+/// compares against. The boot code zeroes CNTVOFF_EL2, which makes both counters equal. But an EL2
+/// that the kernel does not control, like a hypervisor, can keep an offset. Then the time base
+/// switches to the virtual counter, which holds for all cores, because they share one EL2.
+///
+/// Until this ran, the time base is the physical counter.
+///
+/// # Safety
+///
+/// - Must be called once on the boot core, before the first [time::Instant] is taken.
+pub unsafe fn init() {
+    let before = TIME_MANAGER.read_cntvct();
+    let pct = TIME_MANAGER.read_cntpct();
+    let after = TIME_MANAGER.read_cntvct();
+
+    // With no offset, the physical counter lies between the two reads of the virtual one.
+    if (before..=after).contains(&pct) {
+        return;
+    }
+
+    VIRTUAL_OFFSET.store(pct.wrapping_sub(before), Ordering::Relaxed);
+}
+
+/// Whether the time base is the virtual counter.
+pub fn uses_virtual_counter() -> bool {
+    VIRTUAL_OFFSET.load(Ordering::Relaxed) != 0
+}
+
+/// Convert a value of the physical counter into one of the time base.
+///
+/// Meant for timestamps taken before [init()], like the one of kernel entry.
+pub fn physical_to_counter_ticks(ticks: u64) -> u64 {
+    ticks.wrapping_sub(VIRTUAL_OFFSET.load(Ordering::Relaxed))
+}
+
 /// Return a reference to the time manager.
 pub fn time_manager() -> &'static impl time::interface::TimeManager {
     &TIME_MANAGER
 }
 
+/// The current value of the time base.
+#[inline(always)]
+pub fn counter_ticks() -> u64 {
+    TIME_MANAGER.read_counter()
+}
+
+/// Convert system counter ticks into a duration, rounding down.
+///
+/// Whole seconds and the remainder are converted separately. Since the frequency fits into 32 bits,
+/// none of the intermediate products can overflow.
+pub fn ticks_to_duration(ticks: u64) -> Duration {
+    let frq = counter_frequency();
+    let subsec_ticks = ticks % frq;
+
+    Duration::new(ticks / frq, ((subsec_ticks * NS_PER_S) / frq) as u32)
+}
+
+/// Convert a duration into system counter ticks, rounding down.
+///
+/// Returns `None` if the result does not fit into the counter.
+pub fn duration_to_ticks(duration: Duration) -> Option<u64> {
+    let frq = counter_frequency();
+    let subsec_ticks = (u64::from(duration.subsec_nanos()) * frq) / NS_PER_S;
+
+    duration
+        .as_secs()
+        .checked_mul(frq)?
+        .checked_add(subsec_ticks)
+}
+
+/// Arm the virtual timer to raise its IRQ after `duration`.
+///
+/// The physical timer is left to [time::interface::TimeManager::spin_for], so that spinning does
+/// not disturb a pending alarm.
+pub fn set_alarm(duration: Duration) -> Result<(), &'static str> {
+    // The upper 32 bits of CNTV_TVAL_EL0 are reserved.
+    let tval = duration_to_ticks(duration)
+        .filter(|&tval| tval <= u32::MAX.into())
+        .ok_or("Alarm duration bigger than architecturally supported")?;
+
+    CNTV_TVAL_EL0.set(tval);
+    CNTV_CTL_EL0.write(CNTV_CTL_EL0::ENABLE::SET + CNTV_CTL_EL0::IMASK::CLEAR);
+
+    Ok(())
+}
+
+/// Arm the virtual timer to raise its IRQ once the system counter reaches `ticks`.
+///
+/// The virtual timer compares against the virtual counter, which [init()] made the time base if it
+/// differs from the physical one. An expired deadline raises the IRQ right away.
+pub fn set_alarm_at(ticks: u64) {
+    // `cortex-a` does not provide CNTV_CVAL_EL0.
+    unsafe { asm!("msr cntv_cval_el0, {}", in(reg) ticks, options(nomem, nostack)) };
+    CNTV_CTL_EL0.write(CNTV_CTL_EL0::ENABLE::SET + CNTV_CTL_EL0::IMASK::CLEAR);
+}
+
+/// Disarm the virtual timer. This also deasserts its IRQ, which is level-sensitive.
+pub fn cancel_alarm() {
+    CNTV_CTL_EL0.write(CNTV_CTL_EL0::ENABLE::CLEAR);
+}
+
 //------------------------------------------------------------------------------
 // OS Interface Code
 //------------------------------------------------------------------------------
 
 impl time::interface::TimeManager for GenericTimer {
     fn resolution(&self) -> Duration {
-        Duration::from_nanos(NS_PER_S / (CNTFRQ_EL0.get() as u64))
+        Duration::from_nanos(NS_PER_S / counter_frequency())
     }
 
     fn uptime(&self) -> Duration {
-        let current_count: u64 = self.read_cntpct() * NS_PER_S;
-        let frq: u64 = CNTFRQ_EL0.get() as u64;
-
-        Duration::from_nanos(current_count / frq)
+        ticks_to_duration(self.read_counter())
     }
 
     fn spin_for(&self, duration: Duration) {
@@ -76,15 +201,13 @@ impl time::interface::TimeManager for GenericTimer {
         }
 
         // Calculate the register compare value.
-        let frq = CNTFRQ_EL0.get();
-        let x = match frq.checked_mul(duration.as_nanos() as u64) {
+        let tval = match duration_to_ticks(duration) {
             None => {
                 warn!("Spin duration too long, skipping");
                 return;
             }
             Some(val) => val,
         };
-        let tval = x / NS_PER_S;
 
         // Check if it is within supported bounds.
         let warn: Option<&str> = if tval == 0 {
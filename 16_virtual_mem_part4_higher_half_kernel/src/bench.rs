@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Memory benchmarks.
+//!
+//! What caches and the MMU buy, in numbers:
+//!
+//! - [bandwidth()]: Read, write and zeroing throughput over a buffer that is larger than the last
+//!   level cache. Zeroing uses `DC ZVA` where permitted, see [memory::kmemset()].
+//! - [latency()]: The cost of a load in a random pointer chase through the same buffer, which
+//!   neither the caches nor the prefetcher can hide.
+//! - [tlb_miss()]: The cost of a load whose translation was dropped from the TLB, compared to one
+//!   that hits.
+//! - [lock()]: The cost of taking and releasing an uncontended spinlock, which differs between
+//!   builds with and without `LSE=y`.
+//!
+//! [run()] prints all of them. Latencies are in CPU cycles, so the executing core's PMU must be
+//! initialized.
+//!
+//! The `12_bench` integration test runs the benchmarks in QEMU. With `ICOUNT=y`, QEMU derives time
+//! from the number of executed instructions instead of the host clock, which makes the numbers
+//! comparable from run to run. They only reflect the hardware when running on it, though.
+
+use crate::{
+    config, info,
+    memory::{self, Address},
+    pmu,
+    synchronization::{interface::Mutex, SpinLock},
+    time,
+};
+use core::{
+    cell::UnsafeCell,
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Larger than the L2 cache of both the RPi3 and the RPi4.
+const BUFFER_SIZE: usize = 2 * 1024 * 1024;
+const BUFFER_WORDS: usize = BUFFER_SIZE / 8;
+
+/// The pointer chase visits one word per cache line.
+const WORDS_PER_LINE: usize = 64 / 8;
+
+/// How often the bandwidth benchmarks go over the buffer.
+const BANDWIDTH_ITERATIONS: usize = 16;
+
+/// How many loads the pointer chase and the TLB benchmark measure.
+const LATENCY_LOADS: u64 = 4 * (BUFFER_SIZE / 64) as u64;
+const TLB_LOADS: u64 = 1000;
+
+/// How often the lock benchmark takes the lock.
+const LOCK_ITERATIONS: u64 = 100_000;
+
+/// Aligned, so that every line of the pointer chase is a cache line.
+#[repr(align(64))]
+struct Buffer(UnsafeCell<[u64; BUFFER_WORDS]>);
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The result of [bandwidth()].
+#[derive(Copy, Clone, Debug)]
+pub struct Bandwidth {
+    /// Read throughput in MiB/s.
+    pub read: u64,
+
+    /// Write throughput in MiB/s.
+    pub write: u64,
+
+    /// Zeroing throughput in MiB/s.
+    pub zero: u64,
+}
+
+/// The result of [tlb_miss()].
+#[derive(Copy, Clone, Debug)]
+pub struct TlbMiss {
+    /// Cycles per load whose translation is in the TLB.
+    pub hit: u64,
+
+    /// Cycles per load whose translation must be walked.
+    pub miss: u64,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static BUFFER: Buffer = Buffer(UnsafeCell::new([0; BUFFER_WORDS]));
+
+/// Whether a benchmark is using the buffer.
+static BUSY: AtomicBool = AtomicBool::new(false);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Only accessed by the benchmark that set [BUSY].
+unsafe impl Sync for Buffer {}
+
+fn with_buffer<T>(f: impl FnOnce(&mut [u64]) -> T) -> Result<T, &'static str> {
+    if BUSY.swap(true, Ordering::Acquire) {
+        return Err("Another benchmark is running");
+    }
+
+    let ret = f(unsafe { &mut *BUFFER.0.get() });
+    BUSY.store(false, Ordering::Release);
+
+    Ok(ret)
+}
+
+fn mib_per_s(bytes: usize, elapsed: Duration) -> u64 {
+    ((bytes as u128 * 1_000_000_000) / (elapsed.as_nanos().max(1) * 1024 * 1024)) as u64
+}
+
+/// A fixed xorshift sequence, so that every run chases the same pointers.
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+
+    *state
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Measure the read, write and zeroing throughput.
+pub fn bandwidth() -> Result<Bandwidth, &'static str> {
+    with_buffer(|buf| {
+        let dst = buf.as_mut_ptr() as *mut u8;
+        let set = |value| {
+            let start = time::Instant::now();
+            for _ in 0..BANDWIDTH_ITERATIONS {
+                unsafe { memory::kmemset(dst, value, BUFFER_SIZE) };
+            }
+
+            mib_per_s(BUFFER_SIZE * BANDWIDTH_ITERATIONS, start.elapsed())
+        };
+        let write = set(0xa5);
+        let zero = set(0);
+
+        let start = time::Instant::now();
+        for _ in 0..BANDWIDTH_ITERATIONS {
+            for word in buf.iter() {
+                unsafe { ptr::read_volatile(word) };
+            }
+        }
+        let read = mib_per_s(BUFFER_SIZE * BANDWIDTH_ITERATIONS, start.elapsed());
+
+        Bandwidth { read, write, zero }
+    })
+}
+
+/// Measure the average cycles per load of a random pointer chase.
+///
+/// Each load yields the address of the next one, so the loads can not overlap.
+pub fn latency() -> Result<u64, &'static str> {
+    with_buffer(|buf| {
+        let num_lines = BUFFER_WORDS / WORDS_PER_LINE;
+
+        // Link all lines into a single random cycle with Sattolo's algorithm. Line `i` holds the
+        // index of the line that follows it.
+        for i in 0..num_lines {
+            buf[i * WORDS_PER_LINE] = i as u64;
+        }
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for i in (1..num_lines).rev() {
+            let j = (xorshift(&mut state) % i as u64) as usize;
+            buf.swap(i * WORDS_PER_LINE, j * WORDS_PER_LINE);
+        }
+
+        let mut line = 0;
+        let start = pmu::cycle_counter();
+        for _ in 0..LATENCY_LOADS {
+            line = unsafe { ptr::read_volatile(&buf[line * WORDS_PER_LINE]) } as usize;
+        }
+
+        (pmu::cycle_counter() - start) / LATENCY_LOADS
+    })
+}
+
+/// Measure the cycles of a cached load, with and without a TLB miss.
+///
+/// Both numbers include reading the cycle counter. Their difference is the cost of the table walk.
+pub fn tlb_miss() -> Result<TlbMiss, &'static str> {
+    with_buffer(|buf| {
+        let word = &buf[0] as *const u64;
+        let (mut hit, mut miss) = (0, 0);
+
+        for _ in 0..TLB_LOADS {
+            // Bring the line into the cache and the translation into the TLB.
+            unsafe { ptr::read_volatile(word) };
+
+            let start = pmu::cycle_counter();
+            unsafe { ptr::read_volatile(word) };
+            hit += pmu::cycle_counter() - start;
+
+            memory::mmu::local_tlb_invalidate(Address::new(word as usize));
+
+            let start = pmu::cycle_counter();
+            unsafe { ptr::read_volatile(word) };
+            miss += pmu::cycle_counter() - start;
+        }
+
+        TlbMiss {
+            hit: hit / TLB_LOADS,
+            miss: miss / TLB_LOADS,
+        }
+    })
+}
+
+/// Measure the cycles to take and release an uncontended [SpinLock].
+pub fn lock() -> u64 {
+    let lock = SpinLock::new(0_u64);
+
+    let start = pmu::cycle_counter();
+    for _ in 0..LOCK_ITERATIONS {
+        lock.lock(|x| *x += 1);
+    }
+
+    (pmu::cycle_counter() - start) / LOCK_ITERATIONS
+}
+
+/// Run all benchmarks and print the results.
+pub fn run() -> Result<(), &'static str> {
+    info!("Benchmarks:");
+
+    let bandwidth = bandwidth()?;
+    info!(
+        "      Bandwidth: {} MiB/s read, {} MiB/s write, {} MiB/s zeroing",
+        bandwidth.read, bandwidth.write, bandwidth.zero
+    );
+
+    info!("      Latency: {} cycles per load", latency()?);
+
+    let tlb = tlb_miss()?;
+    info!(
+        "      TLB: {} cycles per load on a hit, {} on a miss",
+        tlb.hit, tlb.miss
+    );
+
+    info!(
+        "      Lock: {} cycles per lock and unlock, with {}",
+        lock(),
+        if config::LSE {
+            "LSE atomics"
+        } else {
+            "exclusive load/store loops"
+        }
+    );
+
+    Ok(())
+}
@@ -6,6 +6,8 @@
 
 mod device_driver;
 
+pub use device_driver::PL011UartRequest;
+
 #[cfg(any(feature = "bsp_rpi3", feature = "bsp_rpi4"))]
 mod raspberrypi;
 
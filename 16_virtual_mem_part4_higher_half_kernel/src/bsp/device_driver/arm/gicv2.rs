@@ -79,8 +79,14 @@
 mod gicc;
 mod gicd;
 
-use crate::{bsp, cpu, driver, exception, memory, synchronization, synchronization::InitStateLock};
+use crate::{
+    bsp, cpu, driver,
+    error::{ErrorKind, KernelError, ResultExt},
+    exception, memory,
+    synchronization::rcu::Rcu,
+};
 use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_a::asm::barrier;
 
 //--------------------------------------------------------------------------------------------------
 // Private Definitions
@@ -109,8 +115,8 @@ pub struct GICv2 {
     /// Have the MMIO regions been remapped yet?
     is_mmio_remapped: AtomicBool,
 
-    /// Stores registered IRQ handlers. Writable only during kernel init. RO afterwards.
-    handler_table: InitStateLock<HandlerTable>,
+    /// Stores registered IRQ handlers. Lookups from IRQ context never take a lock.
+    handler_table: Rcu<HandlerTable>,
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -121,6 +127,9 @@ impl GICv2 {
     const MAX_IRQ_NUMBER: usize = 300; // Normally 1019, but keep it lower to save some space.
     const NUM_IRQS: usize = Self::MAX_IRQ_NUMBER + 1;
 
+    /// The SGI used for inter-processor interrupts.
+    const IPI_SGI: IRQNumber = IRQNumber::new(0);
+
     /// Create an instance.
     ///
     /// # Safety
@@ -136,7 +145,7 @@ impl GICv2 {
             gicd: gicd::GICD::new(gicd_mmio_descriptor.start_addr().as_usize()),
             gicc: gicc::GICC::new(gicc_mmio_descriptor.start_addr().as_usize()),
             is_mmio_remapped: AtomicBool::new(false),
-            handler_table: InitStateLock::new([None; Self::NUM_IRQS]),
+            handler_table: Rcu::new([None; Self::NUM_IRQS]),
         }
     }
 }
@@ -144,22 +153,22 @@ impl GICv2 {
 //------------------------------------------------------------------------------
 // OS Interface Code
 //------------------------------------------------------------------------------
-use synchronization::interface::ReadWriteEx;
-
 impl driver::interface::DeviceDriver for GICv2 {
     fn compatible(&self) -> &'static str {
         "GICv2 (ARM Generic Interrupt Controller v2)"
     }
 
-    unsafe fn init(&self) -> Result<(), &'static str> {
+    unsafe fn init(&self) -> Result<(), KernelError> {
         let remapped = self.is_mmio_remapped.load(Ordering::Relaxed);
         if !remapped {
             // GICD
-            let mut virt_addr = memory::mmu::kernel_map_mmio("GICD", &self.gicd_mmio_descriptor)?;
+            let mut virt_addr = memory::mmu::kernel_map_mmio("GICD", &self.gicd_mmio_descriptor)
+                .context("Mapping the GICD MMIO")?;
             self.gicd.set_mmio(virt_addr.as_usize());
 
             // GICC
-            virt_addr = memory::mmu::kernel_map_mmio("GICC", &self.gicc_mmio_descriptor)?;
+            virt_addr = memory::mmu::kernel_map_mmio("GICC", &self.gicc_mmio_descriptor)
+                .context("Mapping the GICC MMIO")?;
             self.gicc.set_mmio(virt_addr.as_usize());
 
             // Conclude remapping.
@@ -170,6 +179,15 @@ impl driver::interface::DeviceDriver for GICv2 {
             self.gicd.boot_core_init();
         }
 
+        self.gicd.enable(Self::IPI_SGI);
+        self.gicc.priority_accept_all();
+        self.gicc.enable();
+
+        Ok(())
+    }
+
+    unsafe fn init_secondary_core(&self) -> Result<(), KernelError> {
+        self.gicd.enable(Self::IPI_SGI);
         self.gicc.priority_accept_all();
         self.gicc.enable();
 
@@ -184,12 +202,15 @@ impl exception::asynchronous::interface::IRQManager for GICv2 {
         &self,
         irq_number: Self::IRQNumberType,
         descriptor: exception::asynchronous::IRQDescriptor,
-    ) -> Result<(), &'static str> {
-        self.handler_table.write(|table| {
+    ) -> Result<(), KernelError> {
+        self.handler_table.update(|table| {
             let irq_number = irq_number.get();
 
             if table[irq_number].is_some() {
-                return Err("IRQ handler already registered");
+                return Err(KernelError::new(
+                    ErrorKind::AlreadyInUse,
+                    "IRQ handler already registered",
+                ));
             }
 
             table[irq_number] = Some(descriptor);
@@ -206,15 +227,23 @@ impl exception::asynchronous::interface::IRQManager for GICv2 {
         &'irq_context self,
         ic: &exception::asynchronous::IRQContext<'irq_context>,
     ) {
-        // Extract the highest priority pending IRQ number from the Interrupt Acknowledge Register
+        // Acknowledge the highest priority pending IRQ through the Interrupt Acknowledge Register
         // (IAR).
-        let irq_number = self.gicc.pending_irq_number(ic);
+        let irq = self.gicc.pending_irq(ic);
+        let irq_number = irq.number();
 
         // Guard against spurious interrupts.
         if irq_number > GICv2::MAX_IRQ_NUMBER {
             return;
         }
 
+        if irq_number == Self::IPI_SGI.get() {
+            cpu::smp::handle_ipi(ic);
+            self.gicc.mark_comleted(irq, ic);
+
+            return;
+        }
+
         // Call the IRQ handler. Panic if there is none.
         self.handler_table.read(|table| {
             match table[irq_number] {
@@ -227,7 +256,31 @@ impl exception::asynchronous::interface::IRQManager for GICv2 {
         });
 
         // Signal completion of handling.
-        self.gicc.mark_comleted(irq_number as u32, ic);
+        self.gicc.mark_comleted(irq, ic);
+    }
+
+    fn set_affinity(
+        &self,
+        irq_number: Self::IRQNumberType,
+        core_mask: usize,
+    ) -> Result<(), &'static str> {
+        if core_mask == 0 || core_mask >= (1 << bsp::cpu::NUM_CORES) {
+            return Err("Invalid core mask");
+        }
+
+        // The GIC's CPU interface numbers match the core IDs on the supported boards.
+        self.gicd.set_targets(irq_number, core_mask as u8)
+    }
+
+    fn affinity(&self, irq_number: Self::IRQNumberType) -> Result<usize, &'static str> {
+        self.gicd.targets(irq_number).map(usize::from)
+    }
+
+    fn send_ipi_to_others(&self) {
+        // Make prior memory writes visible to the receiving cores before they get interrupted.
+        unsafe { barrier::dsb(barrier::ISHST) };
+
+        self.gicd.send_sgi_to_others(Self::IPI_SGI);
     }
 
     fn print_handler(&self) {
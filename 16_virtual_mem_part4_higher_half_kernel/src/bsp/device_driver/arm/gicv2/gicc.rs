@@ -5,12 +5,16 @@
 //! GICC Driver - GIC CPU interface.
 
 use crate::{
-    bsp::device_driver::common::MMIODerefWrapper, exception, synchronization::InitStateLock,
+    bsp::device_driver::common::{
+        registers::{ReadWrite, WriteOnly},
+        MMIODerefWrapper,
+    },
+    exception,
+    synchronization::InitStateLock,
 };
 use tock_registers::{
     interfaces::{Readable, Writeable},
     register_bitfields, register_structs,
-    registers::ReadWrite,
 };
 
 //--------------------------------------------------------------------------------------------------
@@ -32,12 +36,8 @@ register_bitfields! {
 
     /// Interrupt Acknowledge Register
     IAR [
+        CPUID OFFSET(10) NUMBITS(3) [],
         InterruptID OFFSET(0) NUMBITS(10) []
-    ],
-
-    /// End of Interrupt Register
-    EOIR [
-        EOIINTID OFFSET(0) NUMBITS(10) []
     ]
 }
 
@@ -48,7 +48,7 @@ register_structs! {
         (0x004 => PMR: ReadWrite<u32, PMR::Register>),
         (0x008 => _reserved1),
         (0x00C => IAR: ReadWrite<u32, IAR::Register>),
-        (0x010 => EOIR: ReadWrite<u32, EOIR::Register>),
+        (0x010 => EOIR: WriteOnly<u32>),
         (0x014  => @END),
     }
 }
@@ -65,11 +65,25 @@ pub struct GICC {
     registers: InitStateLock<Registers>,
 }
 
+/// An IRQ that was acknowledged through the Interrupt Acknowledge Register.
+///
+/// Keeps the complete register value. For SGIs, it also identifies the requesting core, and must be
+/// echoed back unmodified when completing the IRQ.
+#[derive(Copy, Clone)]
+pub struct AcknowledgedIRQ(u32);
+
 //--------------------------------------------------------------------------------------------------
 // Public Code
 //--------------------------------------------------------------------------------------------------
 use crate::synchronization::interface::ReadWriteEx;
 
+impl AcknowledgedIRQ {
+    /// The number of the acknowledged IRQ.
+    pub fn number(self) -> usize {
+        IAR::InterruptID.read(self.0) as usize
+    }
+}
+
 impl GICC {
     /// Create an instance.
     ///
@@ -116,7 +130,7 @@ impl GICC {
         });
     }
 
-    /// Extract the number of the highest-priority pending IRQ.
+    /// Acknowledge the highest-priority pending IRQ.
     ///
     /// Can only be called from IRQ context, which is ensured by taking an `IRQContext` token.
     ///
@@ -125,19 +139,18 @@ impl GICC {
     /// - GICC MMIO registers are banked per CPU core. It is therefore safe to have `&self` instead
     ///   of `&mut self`.
     #[allow(clippy::trivially_copy_pass_by_ref)]
-    pub fn pending_irq_number<'irq_context>(
+    pub fn pending_irq<'irq_context>(
         &self,
         _ic: &exception::asynchronous::IRQContext<'irq_context>,
-    ) -> usize {
-        self.registers
-            .read(|regs| regs.IAR.read(IAR::InterruptID) as usize)
+    ) -> AcknowledgedIRQ {
+        self.registers.read(|regs| AcknowledgedIRQ(regs.IAR.get()))
     }
 
     /// Complete handling of the currently active IRQ.
     ///
     /// Can only be called from IRQ context, which is ensured by taking an `IRQContext` token.
     ///
-    /// To be called after `pending_irq_number()`.
+    /// To be called after `pending_irq()`.
     ///
     /// # Safety
     ///
@@ -146,11 +159,11 @@ impl GICC {
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn mark_comleted<'irq_context>(
         &self,
-        irq_number: u32,
+        irq: AcknowledgedIRQ,
         _ic: &exception::asynchronous::IRQContext<'irq_context>,
     ) {
         self.registers.read(|regs| {
-            regs.EOIR.write(EOIR::EOIINTID.val(irq_number));
+            regs.EOIR.set(irq.0);
         });
     }
 }
@@ -6,16 +6,19 @@
 //!
 //! # Glossary
 //!   - SPI - Shared Peripheral Interrupt.
+//!   - SGI - Software Generated Interrupt.
 
 use crate::{
-    bsp::device_driver::common::MMIODerefWrapper,
+    bsp::device_driver::common::{
+        registers::{ReadOnly, ReadWrite, WriteOnly},
+        MMIODerefWrapper,
+    },
     state, synchronization,
-    synchronization::{IRQSafeNullLock, InitStateLock},
+    synchronization::{IRQSafeSpinLock, InitStateLock},
 };
 use tock_registers::{
     interfaces::{Readable, Writeable},
     register_bitfields, register_structs,
-    registers::{ReadOnly, ReadWrite},
 };
 
 //--------------------------------------------------------------------------------------------------
@@ -41,6 +44,19 @@ register_bitfields! {
         Offset2 OFFSET(16) NUMBITS(8) [],
         Offset1 OFFSET(8)  NUMBITS(8) [],
         Offset0 OFFSET(0)  NUMBITS(8) []
+    ],
+
+    /// Software Generated Interrupt Register
+    SGIR [
+        TargetListFilter OFFSET(24) NUMBITS(2) [
+            TargetList = 0b00,
+            AllOtherCores = 0b01,
+            OnlySelf = 0b10
+        ],
+
+        CPUTargetList OFFSET(16) NUMBITS(8) [],
+
+        SGIINTID OFFSET(0) NUMBITS(4) []
     ]
 }
 
@@ -53,7 +69,9 @@ register_structs! {
         (0x104 => ISENABLER: [ReadWrite<u32>; 31]),
         (0x108 => _reserved2),
         (0x820 => ITARGETSR: [ReadWrite<u32, ITARGETSR::Register>; 248]),
-        (0x824 => @END),
+        (0x824 => _reserved3),
+        (0xF00 => SGIR: WriteOnly<u32, SGIR::Register>),
+        (0xF04 => @END),
     }
 }
 
@@ -81,7 +99,7 @@ type BankedRegisters = MMIODerefWrapper<BankedRegisterBlock>;
 /// Representation of the GIC Distributor.
 pub struct GICD {
     /// Access to shared registers is guarded with a lock.
-    shared_registers: IRQSafeNullLock<SharedRegisters>,
+    shared_registers: IRQSafeSpinLock<SharedRegisters>,
 
     /// Access to banked registers is unguarded.
     banked_registers: InitStateLock<BankedRegisters>,
@@ -132,7 +150,7 @@ impl GICD {
     /// - The user must ensure to provide a correct MMIO start address.
     pub const unsafe fn new(mmio_start_addr: usize) -> Self {
         Self {
-            shared_registers: IRQSafeNullLock::new(SharedRegisters::new(mmio_start_addr)),
+            shared_registers: IRQSafeSpinLock::new(SharedRegisters::new(mmio_start_addr)),
             banked_registers: InitStateLock::new(BankedRegisters::new(mmio_start_addr)),
         }
     }
@@ -206,4 +224,66 @@ impl GICD {
             }
         }
     }
+
+    /// Route an SPI to the CPU interfaces in `target_mask`.
+    pub fn set_targets(
+        &self,
+        irq_num: super::IRQNumber,
+        target_mask: u8,
+    ) -> Result<(), &'static str> {
+        let irq_num = irq_num.get();
+
+        if irq_num < 32 {
+            return Err("Only SPIs can be routed");
+        }
+
+        // Each ITARGETS register has four entries of one byte each. The first 32 IRQs are private,
+        // so they are not included in `shared_registers`.
+        let index = (irq_num - 32) >> 2;
+        let shift = (irq_num % 4) * 8;
+
+        self.shared_registers.lock(|regs| {
+            let target_reg = regs
+                .implemented_itargets_slice()
+                .get(index)
+                .ok_or("IRQ not implemented by the GIC")?;
+
+            let val = target_reg.get() & !(0xff << shift);
+            target_reg.set(val | (u32::from(target_mask) << shift));
+
+            Ok(())
+        })
+    }
+
+    /// Return the CPU interfaces that an SPI is routed to.
+    pub fn targets(&self, irq_num: super::IRQNumber) -> Result<u8, &'static str> {
+        let irq_num = irq_num.get();
+
+        if irq_num < 32 {
+            return Err("Only SPIs can be routed");
+        }
+
+        let index = (irq_num - 32) >> 2;
+        let shift = (irq_num % 4) * 8;
+
+        self.shared_registers.lock(|regs| {
+            let target_reg = regs
+                .implemented_itargets_slice()
+                .get(index)
+                .ok_or("IRQ not implemented by the GIC")?;
+
+            Ok((target_reg.get() >> shift) as u8)
+        })
+    }
+
+    /// Raise an SGI on all cores except the executing one.
+    pub fn send_sgi_to_others(&self, irq_num: super::IRQNumber) {
+        let irq_num = irq_num.get();
+        assert!(irq_num < 16, "Not an SGI");
+
+        self.shared_registers.lock(|regs| {
+            regs.SGIR
+                .write(SGIR::TargetListFilter::AllOtherCores + SGIR::SGIINTID.val(irq_num as u32));
+        });
+    }
 }
@@ -7,9 +7,21 @@
 mod bcm2xxx_gpio;
 #[cfg(feature = "bsp_rpi3")]
 mod bcm2xxx_interrupt_controller;
+mod bcm2xxx_mailbox;
 mod bcm2xxx_pl011_uart;
+mod bcm2xxx_pwm_ws2812;
+#[cfg(feature = "bsp_rpi3")]
+mod bcm2xxx_rng;
+#[cfg(feature = "bsp_rpi4")]
+mod bcm2xxx_rng200;
 
 pub use bcm2xxx_gpio::*;
 #[cfg(feature = "bsp_rpi3")]
 pub use bcm2xxx_interrupt_controller::*;
+pub use bcm2xxx_mailbox::*;
 pub use bcm2xxx_pl011_uart::*;
+pub use bcm2xxx_pwm_ws2812::*;
+#[cfg(feature = "bsp_rpi3")]
+pub use bcm2xxx_rng::*;
+#[cfg(feature = "bsp_rpi4")]
+pub use bcm2xxx_rng200::*;
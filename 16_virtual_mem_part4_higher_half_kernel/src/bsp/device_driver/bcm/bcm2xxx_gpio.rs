@@ -5,14 +5,16 @@
 //! GPIO Driver.
 
 use crate::{
-    bsp::device_driver::common::MMIODerefWrapper, driver, memory, synchronization,
-    synchronization::IRQSafeNullLock,
+    bsp::device_driver::common::{registers::ReadWrite, MMIODerefWrapper},
+    driver,
+    error::{KernelError, ResultExt},
+    memory, synchronization,
+    synchronization::IRQSafeSpinLock,
 };
 use core::sync::atomic::{AtomicUsize, Ordering};
 use tock_registers::{
     interfaces::{ReadWriteable, Writeable},
     register_bitfields, register_structs,
-    registers::ReadWrite,
 };
 
 //--------------------------------------------------------------------------------------------------
@@ -29,6 +31,13 @@ register_bitfields! {
 
     /// GPIO Function Select 1
     GPFSEL1 [
+        /// Pin 18
+        FSEL18 OFFSET(24) NUMBITS(3) [
+            Input = 0b000,
+            Output = 0b001,
+            AltFunc5 = 0b010  // PWM0 channel 1
+        ],
+
         /// Pin 15
         FSEL15 OFFSET(15) NUMBITS(3) [
             Input = 0b000,
@@ -124,7 +133,7 @@ pub use GPIOInner as PanicGPIO;
 pub struct GPIO {
     mmio_descriptor: memory::mmu::MMIODescriptor,
     virt_mmio_start_addr: AtomicUsize,
-    inner: IRQSafeNullLock<GPIOInner>,
+    inner: IRQSafeSpinLock<GPIOInner>,
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -203,6 +212,11 @@ impl GPIOInner {
         #[cfg(feature = "bsp_rpi4")]
         self.disable_pud_14_15_bcm2711();
     }
+
+    /// Map channel 1 of PWM0 to pin 18.
+    pub fn map_pwm0(&mut self) {
+        self.registers.GPFSEL1.modify(GPFSEL1::FSEL18::AltFunc5);
+    }
 }
 
 impl GPIO {
@@ -215,7 +229,7 @@ impl GPIO {
         Self {
             mmio_descriptor,
             virt_mmio_start_addr: AtomicUsize::new(0),
-            inner: IRQSafeNullLock::new(GPIOInner::new(mmio_descriptor.start_addr().as_usize())),
+            inner: IRQSafeSpinLock::new(GPIOInner::new(mmio_descriptor.start_addr().as_usize())),
         }
     }
 
@@ -223,6 +237,11 @@ impl GPIO {
     pub fn map_pl011_uart(&self) {
         self.inner.lock(|inner| inner.map_pl011_uart())
     }
+
+    /// Concurrency safe version of `GPIOInner.map_pwm0()`
+    pub fn map_pwm0(&self) {
+        self.inner.lock(|inner| inner.map_pwm0())
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -235,8 +254,9 @@ impl driver::interface::DeviceDriver for GPIO {
         "BCM GPIO"
     }
 
-    unsafe fn init(&self) -> Result<(), &'static str> {
-        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)?;
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+            .context("Mapping the GPIO MMIO")?;
 
         self.inner
             .lock(|inner| inner.init(Some(virt_addr.as_usize())))?;
@@ -4,9 +4,10 @@
 
 //! Interrupt Controller Driver.
 
+mod local_ic;
 mod peripheral_ic;
 
-use crate::{driver, exception, memory};
+use crate::{cpu, driver, error::KernelError, exception, memory};
 
 //--------------------------------------------------------------------------------------------------
 // Private Definitions
@@ -35,6 +36,7 @@ pub enum IRQNumber {
 
 /// Representation of the Interrupt Controller.
 pub struct InterruptController {
+    local: local_ic::LocalIC,
     periph: peripheral_ic::PeripheralIC,
 }
 
@@ -80,10 +82,11 @@ impl InterruptController {
     ///
     /// - The user must ensure to provide correct MMIO descriptors.
     pub const unsafe fn new(
-        _local_mmio_descriptor: memory::mmu::MMIODescriptor,
+        local_mmio_descriptor: memory::mmu::MMIODescriptor,
         periph_mmio_descriptor: memory::mmu::MMIODescriptor,
     ) -> Self {
         Self {
+            local: local_ic::LocalIC::new(local_mmio_descriptor),
             periph: peripheral_ic::PeripheralIC::new(periph_mmio_descriptor),
         }
     }
@@ -98,9 +101,14 @@ impl driver::interface::DeviceDriver for InterruptController {
         "BCM Interrupt Controller"
     }
 
-    unsafe fn init(&self) -> Result<(), &'static str> {
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        self.local.init()?;
         self.periph.init()
     }
+
+    unsafe fn init_secondary_core(&self) -> Result<(), KernelError> {
+        self.local.init_secondary_core()
+    }
 }
 
 impl exception::asynchronous::interface::IRQManager for InterruptController {
@@ -110,7 +118,7 @@ impl exception::asynchronous::interface::IRQManager for InterruptController {
         &self,
         irq: Self::IRQNumberType,
         descriptor: exception::asynchronous::IRQDescriptor,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), KernelError> {
         match irq {
             IRQNumber::Local(_) => unimplemented!("Local IRQ controller not implemented."),
             IRQNumber::Peripheral(pirq) => self.periph.register_handler(pirq, descriptor),
@@ -128,8 +136,40 @@ impl exception::asynchronous::interface::IRQManager for InterruptController {
         &'irq_context self,
         ic: &exception::asynchronous::IRQContext<'irq_context>,
     ) {
-        // It can only be a peripheral IRQ pending because enable() does not support local IRQs yet.
-        self.periph.handle_pending_irqs(ic)
+        // Apart from the IPI, the PMU and virtual timer IRQs and the peripheral IRQs routed to this
+        // core, no local IRQs can be pending because enable() does not support them yet.
+        for irq_number in self.local.pending_irqs(ic) {
+            match irq_number {
+                x if x == local_ic::LocalIC::MAILBOX0_IRQ.get() => {
+                    self.local.acknowledge_ipi(ic);
+                    cpu::smp::handle_ipi(ic);
+                }
+                x if x == local_ic::LocalIC::GPU_IRQ.get() => self.periph.handle_pending_irqs(ic),
+                x if x == local_ic::LocalIC::PMU_IRQ.get() => crate::pmu::handle_overflow_irq(ic),
+                x if x == local_ic::LocalIC::VIRTUAL_TIMER_IRQ.get() => {
+                    crate::time::alarm::handle_alarm_irq(ic)
+                }
+                x => panic!("No handler registered for local IRQ {}", x),
+            }
+        }
+    }
+
+    fn set_affinity(&self, irq: Self::IRQNumberType, core_mask: usize) -> Result<(), &'static str> {
+        match irq {
+            IRQNumber::Local(_) => Err("Local IRQs are private to a core"),
+            IRQNumber::Peripheral(pirq) => self.periph.set_affinity(pirq, core_mask),
+        }
+    }
+
+    fn affinity(&self, irq: Self::IRQNumberType) -> Result<usize, &'static str> {
+        match irq {
+            IRQNumber::Local(_) => Err("Local IRQs are private to a core"),
+            IRQNumber::Peripheral(pirq) => self.periph.affinity(pirq),
+        }
+    }
+
+    fn send_ipi_to_others(&self) {
+        self.local.send_ipi_to_others();
     }
 
     fn print_handler(&self) {
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Local Interrupt Controller Driver.
+//!
+//! The ARM control block of the BCM2837 has one set of interrupt source, mailbox and timer
+//! registers per core. Each core only ever touches the registers indexed with its own core ID,
+//! except for the mailbox set registers, which exist exactly to signal other cores. Writing to a
+//! mailbox set register only sets the written bits. Hence, no locking is needed for register
+//! access.
+//!
+//! The kernel uses mailbox 0 of each core for inter-processor interrupts. The PMU and virtual timer
+//! IRQs of each core are routed to the core itself.
+
+use super::{LocalIRQ, PendingIRQs};
+use crate::{
+    bsp,
+    bsp::device_driver::common::{
+        registers::{ReadOnly, ReadWrite, WriteOnly},
+        MMIODerefWrapper,
+    },
+    cpu, driver,
+    error::{KernelError, ResultExt},
+    exception, memory, synchronization,
+    synchronization::InitStateLock,
+};
+use cortex_a::asm::barrier;
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields, register_structs,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+register_bitfields! {
+    u32,
+
+    /// Core Timers Interrupt Control
+    CORE_TIMER_IRQ_CONTROL [
+        VirtualTimerIRQ OFFSET(3) NUMBITS(1) []
+    ],
+
+    /// Core Mailboxes Interrupt Control
+    CORE_MAILBOX_IRQ_CONTROL [
+        Mailbox0IRQ OFFSET(0) NUMBITS(1) []
+    ]
+}
+
+register_structs! {
+    #[allow(non_snake_case)]
+    RegisterBlock {
+        (0x00 => _reserved1),
+        (0x10 => PMU_IRQ_ROUTING_SET: WriteOnly<u32>),
+        (0x14 => _reserved2),
+        (0x40 => CORE_TIMER_IRQ_CONTROL: [ReadWrite<u32, CORE_TIMER_IRQ_CONTROL::Register>; 4]),
+        (0x50 => CORE_MAILBOX_IRQ_CONTROL: [ReadWrite<u32, CORE_MAILBOX_IRQ_CONTROL::Register>; 4]),
+        (0x60 => CORE_IRQ_SOURCE: [ReadOnly<u32>; 4]),
+        (0x70 => _reserved3),
+        (0x80 => CORE_MAILBOX_WRITE_SET: [WriteOnly<u32>; 16]),
+        (0xC0 => CORE_MAILBOX_READ_WRITE_CLEAR: [ReadWrite<u32>; 16]),
+        (0x100 => @END),
+    }
+}
+
+/// Abstraction for the associated MMIO registers.
+type Registers = MMIODerefWrapper<RegisterBlock>;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Representation of the local interrupt controller.
+pub struct LocalIC {
+    mmio_descriptor: memory::mmu::MMIODescriptor,
+
+    /// Register access is unguarded, see the module documentation.
+    registers: InitStateLock<Registers>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// The index of a core's mailbox 0 in the mailbox register arrays.
+fn mailbox0_index(core_id: usize) -> usize {
+    core_id * 4
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+use synchronization::interface::ReadWriteEx;
+
+impl LocalIC {
+    /// The local IRQ signaling an expired virtual timer.
+    pub const VIRTUAL_TIMER_IRQ: LocalIRQ = LocalIRQ::new(3);
+
+    /// The local IRQ signaling a write to mailbox 0.
+    pub const MAILBOX0_IRQ: LocalIRQ = LocalIRQ::new(4);
+
+    /// The local IRQ signaling a pending peripheral IRQ.
+    pub const GPU_IRQ: LocalIRQ = LocalIRQ::new(8);
+
+    /// The local IRQ signaling a PMU counter overflow.
+    pub const PMU_IRQ: LocalIRQ = LocalIRQ::new(9);
+
+    /// Create an instance.
+    ///
+    /// # Safety
+    ///
+    /// - The user must ensure to provide correct MMIO descriptors.
+    pub const unsafe fn new(mmio_descriptor: memory::mmu::MMIODescriptor) -> Self {
+        let addr = mmio_descriptor.start_addr().as_usize();
+
+        Self {
+            mmio_descriptor,
+            registers: InitStateLock::new(Registers::new(addr)),
+        }
+    }
+
+    /// Enable mailbox 0, PMU and virtual timer IRQs for the executing core.
+    fn enable_local_irqs(&self) {
+        let core_id: usize = cpu::smp::core_id();
+
+        self.registers.read(|regs| {
+            regs.CORE_TIMER_IRQ_CONTROL[core_id]
+                .write(CORE_TIMER_IRQ_CONTROL::VirtualTimerIRQ::SET);
+            regs.CORE_MAILBOX_IRQ_CONTROL[core_id]
+                .write(CORE_MAILBOX_IRQ_CONTROL::Mailbox0IRQ::SET);
+
+            // Writing a 1 only sets the corresponding bit, so this does not affect other cores.
+            regs.PMU_IRQ_ROUTING_SET.set(1 << core_id);
+        });
+    }
+
+    /// Query the list of the executing core's pending local IRQs.
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(super) fn pending_irqs<'irq_context>(
+        &self,
+        _ic: &exception::asynchronous::IRQContext<'irq_context>,
+    ) -> PendingIRQs {
+        let core_id: usize = cpu::smp::core_id();
+
+        self.registers
+            .read(|regs| PendingIRQs::new(u64::from(regs.CORE_IRQ_SOURCE[core_id].get())))
+    }
+
+    /// Acknowledge an inter-processor interrupt on the executing core.
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn acknowledge_ipi<'irq_context>(
+        &self,
+        _ic: &exception::asynchronous::IRQContext<'irq_context>,
+    ) {
+        let index = mailbox0_index(cpu::smp::core_id());
+
+        self.registers.read(|regs| {
+            let mailbox = &regs.CORE_MAILBOX_READ_WRITE_CLEAR[index];
+
+            // Writing a 1 clears the corresponding bit.
+            mailbox.set(mailbox.get());
+        });
+    }
+
+    /// Raise an inter-processor interrupt on all cores except the executing one.
+    pub fn send_ipi_to_others(&self) {
+        let self_id: usize = cpu::smp::core_id();
+
+        // Make prior memory writes visible to the receiving cores before they get interrupted.
+        unsafe { barrier::dsb(barrier::ISHST) };
+
+        self.registers.read(|regs| {
+            for i in (0..bsp::cpu::NUM_CORES).filter(|&i| i != self_id) {
+                regs.CORE_MAILBOX_WRITE_SET[mailbox0_index(i)].set(1);
+            }
+        });
+    }
+}
+
+//------------------------------------------------------------------------------
+// OS Interface Code
+//------------------------------------------------------------------------------
+
+impl driver::interface::DeviceDriver for LocalIC {
+    fn compatible(&self) -> &'static str {
+        "BCM Local Interrupt Controller"
+    }
+
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+            .context("Mapping the local interrupt controller MMIO")?
+            .as_usize();
+
+        self.registers
+            .write(|regs| *regs = Registers::new(virt_addr));
+
+        self.enable_local_irqs();
+
+        Ok(())
+    }
+
+    unsafe fn init_secondary_core(&self) -> Result<(), KernelError> {
+        self.enable_local_irqs();
+
+        Ok(())
+    }
+}
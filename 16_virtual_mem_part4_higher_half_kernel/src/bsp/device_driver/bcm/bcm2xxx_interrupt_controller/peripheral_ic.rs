@@ -6,14 +6,18 @@
 
 use super::{InterruptController, PendingIRQs, PeripheralIRQ};
 use crate::{
-    bsp::device_driver::common::MMIODerefWrapper,
-    driver, exception, memory, synchronization,
-    synchronization::{IRQSafeNullLock, InitStateLock},
+    bsp::device_driver::common::{
+        registers::{ReadOnly, WriteOnly},
+        MMIODerefWrapper,
+    },
+    driver,
+    error::{ErrorKind, KernelError, ResultExt},
+    exception, memory, synchronization,
+    synchronization::{rcu::Rcu, IRQSafeSpinLock, InitStateLock},
 };
 use tock_registers::{
     interfaces::{Readable, Writeable},
     register_structs,
-    registers::{ReadOnly, WriteOnly},
 };
 
 //--------------------------------------------------------------------------------------------------
@@ -58,13 +62,13 @@ pub struct PeripheralIC {
     mmio_descriptor: memory::mmu::MMIODescriptor,
 
     /// Access to write registers is guarded with a lock.
-    wo_registers: IRQSafeNullLock<WriteOnlyRegisters>,
+    wo_registers: IRQSafeSpinLock<WriteOnlyRegisters>,
 
     /// Register read access is unguarded.
     ro_registers: InitStateLock<ReadOnlyRegisters>,
 
-    /// Stores registered IRQ handlers. Writable only during kernel init. RO afterwards.
-    handler_table: InitStateLock<HandlerTable>,
+    /// Stores registered IRQ handlers. Lookups from IRQ context never take a lock.
+    handler_table: Rcu<HandlerTable>,
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -82,9 +86,9 @@ impl PeripheralIC {
 
         Self {
             mmio_descriptor,
-            wo_registers: IRQSafeNullLock::new(WriteOnlyRegisters::new(addr)),
+            wo_registers: IRQSafeSpinLock::new(WriteOnlyRegisters::new(addr)),
             ro_registers: InitStateLock::new(ReadOnlyRegisters::new(addr)),
-            handler_table: InitStateLock::new([None; InterruptController::NUM_PERIPHERAL_IRQS]),
+            handler_table: Rcu::new([None; InterruptController::NUM_PERIPHERAL_IRQS]),
         }
     }
 
@@ -109,9 +113,10 @@ impl driver::interface::DeviceDriver for PeripheralIC {
         "BCM Peripheral Interrupt Controller"
     }
 
-    unsafe fn init(&self) -> Result<(), &'static str> {
-        let virt_addr =
-            memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)?.as_usize();
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+            .context("Mapping the peripheral interrupt controller MMIO")?
+            .as_usize();
 
         self.wo_registers
             .lock(|regs| *regs = WriteOnlyRegisters::new(virt_addr));
@@ -129,12 +134,15 @@ impl exception::asynchronous::interface::IRQManager for PeripheralIC {
         &self,
         irq: Self::IRQNumberType,
         descriptor: exception::asynchronous::IRQDescriptor,
-    ) -> Result<(), &'static str> {
-        self.handler_table.write(|table| {
+    ) -> Result<(), KernelError> {
+        self.handler_table.update(|table| {
             let irq_number = irq.get();
 
             if table[irq_number].is_some() {
-                return Err("IRQ handler already registered");
+                return Err(KernelError::new(
+                    ErrorKind::AlreadyInUse,
+                    "IRQ handler already registered",
+                ));
             }
 
             table[irq_number] = Some(descriptor);
@@ -176,6 +184,23 @@ impl exception::asynchronous::interface::IRQManager for PeripheralIC {
         })
     }
 
+    fn set_affinity(
+        &self,
+        _irq: Self::IRQNumberType,
+        _core_mask: usize,
+    ) -> Result<(), &'static str> {
+        Err("Peripheral IRQs can not be routed individually")
+    }
+
+    fn affinity(&self, _irq: Self::IRQNumberType) -> Result<usize, &'static str> {
+        Err("Peripheral IRQs can not be routed individually")
+    }
+
+    fn send_ipi_to_others(&self) {
+        // IPIs are raised through the local IRQ controller. The top-level controller routes them
+        // there, so there is nothing to do here.
+    }
+
     fn print_handler(&self) {
         use crate::info;
 
@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! VideoCore mailbox driver.
+//!
+//! The firmware that runs on the VideoCore answers queries about the board through the property
+//! channel of the mailbox. A query is a buffer in DRAM that holds one or more tags. The ARM side
+//! sends the buffer's address, and the firmware overwrites the tags' values with its response.
+//!
+//! The buffer is part of the driver instance, and thus in cacheable memory. It is cleaned before
+//! sending and invalidated after the response, since the VideoCore does not snoop the ARM's caches.
+//!
+//! # Resources
+//!
+//! - <https://github.com/raspberrypi/firmware/wiki/Mailboxes>
+//! - <https://github.com/raspberrypi/firmware/wiki/Mailbox-property-interface>
+
+use crate::{
+    bsp::device_driver::common::{
+        registers::{ReadOnly, WriteOnly},
+        MMIODerefWrapper,
+    },
+    cpu, driver,
+    error::{ErrorKind, KernelError, ResultExt},
+    memory::{self, Address},
+    synchronization,
+    synchronization::IRQSafeSpinLock,
+    time,
+};
+use core::time::Duration;
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields, register_structs,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+register_bitfields! {
+    u32,
+
+    /// Status Register.
+    STATUS [
+        /// No space left to write.
+        FULL OFFSET(31) NUMBITS(1) [],
+
+        /// Nothing to read.
+        EMPTY OFFSET(30) NUMBITS(1) []
+    ]
+}
+
+register_structs! {
+    #[allow(non_snake_case)]
+    RegisterBlock {
+        (0x00 => READ: ReadOnly<u32>),
+        (0x04 => _reserved1),
+        (0x18 => READ_STATUS: ReadOnly<u32, STATUS::Register>),
+        (0x1c => _reserved2),
+        (0x20 => WRITE: WriteOnly<u32>),
+        (0x24 => _reserved3),
+        (0x38 => WRITE_STATUS: ReadOnly<u32, STATUS::Register>),
+        (0x3c => _reserved4),
+        (0x40 => @END),
+    }
+}
+
+/// Abstraction for the associated MMIO registers.
+type Registers = MMIODerefWrapper<RegisterBlock>;
+
+/// The channel on which the firmware answers property tags.
+const CHANNEL_PROPERTY: u32 = 8;
+
+/// The number of words in the property buffer.
+const BUFFER_WORDS: usize = 36;
+
+/// Words of the buffer that are not a tag's value: the buffer header, the tag header and the end
+/// tag.
+const OVERHEAD_WORDS: usize = 6;
+
+const CODE_REQUEST: u32 = 0;
+const CODE_RESPONSE_SUCCESS: u32 = 0x8000_0000;
+const TAG_RESPONSE: u32 = 0x8000_0000;
+const TAG_END: u32 = 0;
+
+/// How long to wait for the firmware.
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The firmware only takes buffers that are aligned to 16 bytes, because the low four bits of the
+/// address carry the channel.
+#[repr(C, align(16))]
+struct PropertyBuffer([u32; BUFFER_WORDS]);
+
+struct MailboxInner {
+    registers: Registers,
+    buffer: PropertyBuffer,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Property tags.
+pub mod tag {
+    /// The firmware revision, as a Unix timestamp of its build. 1 word.
+    pub const GET_FIRMWARE_REVISION: u32 = 0x0000_0001;
+
+    /// The board revision code. 1 word.
+    pub const GET_BOARD_REVISION: u32 = 0x0001_0002;
+
+    /// The MAC address of the on-board network interface, in network byte order. 6 bytes.
+    pub const GET_BOARD_MAC_ADDRESS: u32 = 0x0001_0003;
+
+    /// The board serial number. 2 words, low word first.
+    pub const GET_BOARD_SERIAL: u32 = 0x0001_0004;
+
+    /// Base address and size of the ARM's share of the memory. 2 words.
+    pub const GET_ARM_MEMORY: u32 = 0x0001_0005;
+
+    /// Base address and size of the VideoCore's share of the memory. 2 words.
+    pub const GET_VC_MEMORY: u32 = 0x0001_0006;
+
+    /// The temperature of the SoC in thousandths of a degree Celsius. 2 words: the sensor ID,
+    /// which must be 0, and the temperature.
+    pub const GET_TEMPERATURE: u32 = 0x0003_0006;
+
+    /// Undervoltage and throttling conditions. 1 word.
+    pub const GET_THROTTLED: u32 = 0x0003_0046;
+}
+
+/// Representation of the mailbox.
+pub struct Mailbox {
+    mmio_descriptor: memory::mmu::MMIODescriptor,
+    inner: IRQSafeSpinLock<MailboxInner>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl MailboxInner {
+    const unsafe fn new(mmio_start_addr: usize) -> Self {
+        Self {
+            registers: Registers::new(mmio_start_addr),
+            buffer: PropertyBuffer([0; BUFFER_WORDS]),
+        }
+    }
+
+    unsafe fn init(&mut self, new_mmio_start_addr: usize) {
+        self.registers = Registers::new(new_mmio_start_addr);
+    }
+
+    /// The address of the buffer as seen by the VideoCore.
+    fn buffer_bus_addr(&self) -> Result<u32, KernelError> {
+        let virt_addr = Address::new(self.buffer.0.as_ptr() as usize);
+        let phys_addr = memory::mmu::try_kernel_virt_addr_to_phys_addr(virt_addr)
+            .context("Translating the mailbox buffer address")?;
+
+        match u32::try_from(phys_addr.as_usize()) {
+            Ok(x) => Ok(x),
+            Err(_) => Err(KernelError::new(
+                ErrorKind::NotSupported,
+                "Mailbox buffer above 4 GiB",
+            )),
+        }
+    }
+
+    fn call(&mut self, channel: u32) -> Result<(), KernelError> {
+        let bus_addr = self.buffer_bus_addr()?;
+        let buffer_addr = self.buffer.0.as_ptr() as usize;
+        let buffer_len = core::mem::size_of::<PropertyBuffer>();
+
+        unsafe { cpu::cache::clean_dcache_range(buffer_addr, buffer_len) };
+
+        time::wait_for(TIMEOUT, || {
+            !self.registers.WRITE_STATUS.is_set(STATUS::FULL)
+        })
+        .map_err(|_| KernelError::new(ErrorKind::Other, "Mailbox full"))?;
+        self.registers.WRITE.set(bus_addr | channel);
+
+        loop {
+            time::wait_for(TIMEOUT, || {
+                !self.registers.READ_STATUS.is_set(STATUS::EMPTY)
+            })
+            .map_err(|_| KernelError::new(ErrorKind::Other, "Mailbox timed out"))?;
+
+            // Responses to other channels are not for us. Drop them.
+            if self.registers.READ.get() == bus_addr | channel {
+                break;
+            }
+        }
+
+        unsafe { cpu::cache::clean_invalidate_dcache_range(buffer_addr, buffer_len) };
+
+        Ok(())
+    }
+
+    fn property(&mut self, tag: u32, value: &mut [u32]) -> Result<usize, KernelError> {
+        if value.len() > BUFFER_WORDS - OVERHEAD_WORDS {
+            return Err(KernelError::new(
+                ErrorKind::InvalidArgument,
+                "Property value too large",
+            ));
+        }
+
+        let words = OVERHEAD_WORDS + value.len();
+        let buffer = &mut self.buffer.0;
+        buffer[0] = (words * 4) as u32;
+        buffer[1] = CODE_REQUEST;
+        buffer[2] = tag;
+        buffer[3] = (value.len() * 4) as u32;
+        buffer[4] = 0;
+        buffer[5..words - 1].copy_from_slice(value);
+        buffer[words - 1] = TAG_END;
+
+        self.call(CHANNEL_PROPERTY)?;
+
+        let buffer = &self.buffer.0;
+        if buffer[1] != CODE_RESPONSE_SUCCESS {
+            return Err(KernelError::new(ErrorKind::Other, "Mailbox request failed"));
+        }
+        if buffer[4] & TAG_RESPONSE == 0 {
+            return Err(KernelError::new(
+                ErrorKind::NotSupported,
+                "Property tag not supported",
+            ));
+        }
+
+        value.copy_from_slice(&buffer[5..words - 1]);
+
+        Ok((buffer[4] & !TAG_RESPONSE) as usize)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Mailbox {
+    /// Create an instance.
+    ///
+    /// # Safety
+    ///
+    /// - The user must ensure to provide correct MMIO descriptors.
+    pub const unsafe fn new(mmio_descriptor: memory::mmu::MMIODescriptor) -> Self {
+        Self {
+            mmio_descriptor,
+            inner: IRQSafeSpinLock::new(MailboxInner::new(mmio_descriptor.start_addr().as_usize())),
+        }
+    }
+
+    /// Query the property `tag`.
+    ///
+    /// `value` holds the request on entry, and the response on success. It must be large enough
+    /// for both. Returns the length of the response in bytes, which can be larger than `value` if
+    /// the firmware had more to say.
+    pub fn property(&self, tag: u32, value: &mut [u32]) -> Result<usize, KernelError> {
+        self.inner.lock(|inner| inner.property(tag, value))
+    }
+}
+
+//------------------------------------------------------------------------------
+// OS Interface Code
+//------------------------------------------------------------------------------
+use synchronization::interface::Mutex;
+
+impl driver::interface::DeviceDriver for Mailbox {
+    fn compatible(&self) -> &'static str {
+        "BCM Mailbox"
+    }
+
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+            .context("Mapping the mailbox MMIO")?;
+
+        self.inner.lock(|inner| inner.init(virt_addr.as_usize()));
+
+        Ok(())
+    }
+}
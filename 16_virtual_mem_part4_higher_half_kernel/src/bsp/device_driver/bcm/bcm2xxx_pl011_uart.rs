@@ -10,17 +10,25 @@
 //! - <https://developer.arm.com/documentation/ddi0183/latest>
 
 use crate::{
-    bsp, bsp::device_driver::common::MMIODerefWrapper, console, cpu, driver, exception, memory,
-    synchronization, synchronization::IRQSafeNullLock,
+    bsp,
+    bsp::device_driver::common::{
+        registers::{ReadOnly, ReadWrite, WriteOnly},
+        MMIODerefWrapper,
+    },
+    console, cpu, debug, driver,
+    error::{ErrorKind, KernelError, ResultExt},
+    exception, memory, status, synchronization,
+    synchronization::IRQSafeSpinLock,
+    trace,
 };
 use core::{
+    any::Any,
     fmt,
     sync::atomic::{AtomicUsize, Ordering},
 };
 use tock_registers::{
     interfaces::{Readable, Writeable},
     register_bitfields, register_structs,
-    registers::{ReadOnly, ReadWrite, WriteOnly},
 };
 
 //--------------------------------------------------------------------------------------------------
@@ -213,6 +221,12 @@ register_structs! {
 /// Abstraction for the associated MMIO registers.
 type Registers = MMIODerefWrapper<RegisterBlock>;
 
+/// The UART reference clock, as set in `config.txt`.
+const UART_CLOCK_HZ: u32 = 48_000_000;
+
+/// The baud rate that `init()` sets.
+const DEFAULT_BAUD_RATE: u32 = 921_600;
+
 #[derive(PartialEq)]
 enum BlockingMode {
     Blocking,
@@ -225,10 +239,21 @@ enum BlockingMode {
 
 pub struct PL011UartInner {
     registers: Registers,
+    baud_rate: u32,
     chars_written: usize,
     chars_read: usize,
 }
 
+/// Control requests of the PL011 UART.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PL011UartRequest {
+    /// Flush, then switch to the given baud rate.
+    SetBaudRate(u32),
+
+    /// Return the current baud rate in the field.
+    GetBaudRate(u32),
+}
+
 // Export the inner struct so that BSPs can use it for the panic handler.
 pub use PL011UartInner as PanicUart;
 
@@ -236,10 +261,32 @@ pub use PL011UartInner as PanicUart;
 pub struct PL011Uart {
     mmio_descriptor: memory::mmu::MMIODescriptor,
     virt_mmio_start_addr: AtomicUsize,
-    inner: IRQSafeNullLock<PL011UartInner>,
+    inner: IRQSafeSpinLock<PL011UartInner>,
     irq_number: bsp::device_driver::IRQNumber,
 }
 
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// The `IBRD` and `FBRD` values for `baud_rate`. See [PL011UartInner::init()] for the calculation.
+fn baud_rate_divisor(baud_rate: u32) -> Option<(u32, u32)> {
+    if baud_rate == 0 {
+        return None;
+    }
+
+    // The divisor in 1/64ths, rounded to nearest.
+    let brd = (4 * u64::from(UART_CLOCK_HZ) + u64::from(baud_rate) / 2) / u64::from(baud_rate);
+    let (int, frac) = (brd >> 6, brd & 0x3f);
+
+    // IBRD is 16 bits wide. Its maximum is only valid with FBRD zero.
+    if int == 0 || int > 0xffff || (int == 0xffff && frac != 0) {
+        return None;
+    }
+
+    Some((int as u32, frac as u32))
+}
+
 //--------------------------------------------------------------------------------------------------
 // Public Code
 //--------------------------------------------------------------------------------------------------
@@ -253,6 +300,7 @@ impl PL011UartInner {
     pub const unsafe fn new(mmio_start_addr: usize) -> Self {
         Self {
             registers: Registers::new(mmio_start_addr),
+            baud_rate: DEFAULT_BAUD_RATE,
             chars_written: 0,
             chars_read: 0,
         }
@@ -307,8 +355,9 @@ impl PL011UartInner {
         // contents of IBRD or FBRD, a LCR_H write must always be performed at the end.
         //
         // Set the baud rate, 8N1 and FIFO enabled.
-        self.registers.IBRD.write(IBRD::BAUD_DIVINT.val(3));
-        self.registers.FBRD.write(FBRD::BAUD_DIVFRAC.val(16));
+        let (int, frac) = baud_rate_divisor(DEFAULT_BAUD_RATE).ok_or("Unsupported baud rate")?;
+        self.registers.IBRD.write(IBRD::BAUD_DIVINT.val(int));
+        self.registers.FBRD.write(FBRD::BAUD_DIVFRAC.val(frac));
         self.registers
             .LCR_H
             .write(LCR_H::WLEN::EightBit + LCR_H::FEN::FifosEnabled);
@@ -325,6 +374,33 @@ impl PL011UartInner {
         self.registers
             .CR
             .write(CR::UARTEN::Enabled + CR::TXE::Enabled + CR::RXE::Enabled);
+        self.baud_rate = DEFAULT_BAUD_RATE;
+
+        Ok(())
+    }
+
+    /// Switch to a different baud rate, keeping 8N1.
+    ///
+    /// Pending output is sent with the old rate first. Input that arrives during the switch is
+    /// lost.
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), KernelError> {
+        let (int, frac) = baud_rate_divisor(baud_rate)
+            .ok_or_else(|| KernelError::new(ErrorKind::InvalidArgument, "Unsupported baud rate"))?;
+
+        self.flush();
+        self.registers.CR.set(0);
+
+        // As in init(), the divisor only takes effect with the LCR_H write.
+        self.registers.IBRD.write(IBRD::BAUD_DIVINT.val(int));
+        self.registers.FBRD.write(FBRD::BAUD_DIVFRAC.val(frac));
+        self.registers
+            .LCR_H
+            .write(LCR_H::WLEN::EightBit + LCR_H::FEN::FifosEnabled);
+
+        self.registers
+            .CR
+            .write(CR::UARTEN::Enabled + CR::TXE::Enabled + CR::RXE::Enabled);
+        self.baud_rate = baud_rate;
 
         Ok(())
     }
@@ -413,7 +489,7 @@ impl PL011Uart {
         Self {
             mmio_descriptor,
             virt_mmio_start_addr: AtomicUsize::new(0),
-            inner: IRQSafeNullLock::new(PL011UartInner::new(
+            inner: IRQSafeSpinLock::new(PL011UartInner::new(
                 mmio_descriptor.start_addr().as_usize(),
             )),
             irq_number,
@@ -431,8 +507,9 @@ impl driver::interface::DeviceDriver for PL011Uart {
         "BCM PL011 UART"
     }
 
-    unsafe fn init(&self) -> Result<(), &'static str> {
-        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)?;
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+            .context("Mapping the UART MMIO")?;
 
         self.inner
             .lock(|inner| inner.init(Some(virt_addr.as_usize())))?;
@@ -443,7 +520,7 @@ impl driver::interface::DeviceDriver for PL011Uart {
         Ok(())
     }
 
-    fn register_and_enable_irq_handler(&'static self) -> Result<(), &'static str> {
+    fn register_and_enable_irq_handler(&'static self) -> Result<(), KernelError> {
         use bsp::exception::asynchronous::irq_manager;
         use exception::asynchronous::{interface::IRQManager, IRQDescriptor};
 
@@ -452,7 +529,9 @@ impl driver::interface::DeviceDriver for PL011Uart {
             handler: self,
         };
 
-        irq_manager().register_handler(self.irq_number, descriptor)?;
+        irq_manager()
+            .register_handler(self.irq_number, descriptor)
+            .context("Registering the UART IRQ handler")?;
         irq_manager().enable(self.irq_number);
 
         Ok(())
@@ -467,6 +546,20 @@ impl driver::interface::DeviceDriver for PL011Uart {
 
         Some(addr)
     }
+
+    fn control(&self, request: &mut dyn Any) -> Result<(), KernelError> {
+        let request = request.downcast_mut::<PL011UartRequest>().ok_or_else(|| {
+            KernelError::new(ErrorKind::NotSupported, "Control request not supported")
+        })?;
+
+        self.inner.lock(|inner| match request {
+            PL011UartRequest::SetBaudRate(baud_rate) => inner.set_baud_rate(*baud_rate),
+            PL011UartRequest::GetBaudRate(baud_rate) => {
+                *baud_rate = inner.baud_rate;
+                Ok(())
+            }
+        })
+    }
 }
 
 impl console::interface::Write for PL011Uart {
@@ -514,9 +607,42 @@ impl console::interface::Statistics for PL011Uart {
     }
 }
 
+impl driver::chardev::interface::CharDevice for PL011Uart {
+    fn read(&self, buf: &mut [u8]) -> Result<usize, KernelError> {
+        self.inner.lock(|inner| {
+            let mut blocking_mode = BlockingMode::Blocking;
+
+            for (i, byte) in buf.iter_mut().enumerate() {
+                match inner.read_char_converting(blocking_mode) {
+                    None => return Ok(i),
+                    Some(c) => *byte = c as u8,
+                }
+
+                blocking_mode = BlockingMode::NonBlocking;
+            }
+
+            Ok(buf.len())
+        })
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, KernelError> {
+        // Bytes map to the chars U+0000 to U+00FF, which write_char() puts on the wire unchanged.
+        self.inner
+            .lock(|inner| buf.iter().for_each(|&b| inner.write_char(b as char)));
+
+        Ok(buf.len())
+    }
+
+    fn flush(&self) -> Result<(), KernelError> {
+        self.inner.lock(|inner| inner.flush());
+
+        Ok(())
+    }
+}
+
 impl exception::asynchronous::interface::IRQHandler for PL011Uart {
     fn handle(&self) -> Result<(), &'static str> {
-        self.inner.lock(|inner| {
+        let request = self.inner.lock(|inner| {
             let pending = inner.registers.MIS.extract();
 
             // Clear all pending IRQs.
@@ -526,11 +652,58 @@ impl exception::asynchronous::interface::IRQHandler for PL011Uart {
             if pending.matches_any(MIS::RXMIS::SET + MIS::RTMIS::SET) {
                 // Echo any received characters.
                 while let Some(c) = inner.read_char_converting(BlockingMode::NonBlocking) {
+                    if c == console::RELOAD_REQUEST
+                        || c == console::STATUS_REQUEST
+                        || c == console::TRACE_REQUEST
+                    {
+                        return Some(c);
+                    }
+
+                    // Leave GDB's input to the GDB stub, which takes over after this handler.
+                    if debug::gdbstub::console_input(c) {
+                        break;
+                    }
+
                     inner.write_char(c)
                 }
             }
+
+            None
         });
 
+        // Served outside of the lock, so that they can print.
+        match request {
+            Some(console::RELOAD_REQUEST) => {
+                crate::info!("Reload requested, rebooting");
+                cpu::reboot();
+            }
+            Some(console::STATUS_REQUEST) => status::print(),
+            Some(console::TRACE_REQUEST) => trace::dump_and_restart(),
+            _ => (),
+        }
+
         Ok(())
     }
 }
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check the divisor of the default baud rate against the calculation in `init()`, and that
+    /// out-of-range rates are rejected.
+    #[kernel_test]
+    fn baud_rate_divisor_works() {
+        assert_eq!(baud_rate_divisor(DEFAULT_BAUD_RATE), Some((3, 16)));
+        assert_eq!(baud_rate_divisor(115_200), Some((26, 3)));
+
+        assert_eq!(baud_rate_divisor(0), None);
+        assert_eq!(baud_rate_divisor(40), None);
+        assert_eq!(baud_rate_divisor(10_000_000), None);
+    }
+}
@@ -0,0 +1,416 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! WS2812 LED strip driver on top of the BCM PWM.
+//!
+//! WS2812 LEDs read a single data line, on which every bit is a 1.25 µs period whose high time
+//! tells a zero from a one. After the last bit, the line must stay low for long enough that the
+//! LEDs latch the colors.
+//!
+//! Channel 1 of PWM0 runs in serializer mode, which shifts the words of its FIFO out onto pin 18,
+//! most significant bit first. At 2.4 MHz, three symbols make up one period: `100` is a zero bit
+//! and `110` a one bit. The PWM clock is derived from the crystal oscillator, so it is independent
+//! of the core and VPU clocks that the firmware changes.
+//!
+//! The FIFO only holds a few words, and running dry in the middle of a frame latches the LEDs
+//! early. The driver feeds it from the CPU with IRQs masked, which limits the strip length, see
+//! [MAX_PIXELS].
+//!
+//! # Resources
+//!
+//! - <https://cdn-shop.adafruit.com/datasheets/WS2812B.pdf>
+//! - <https://datasheets.raspberrypi.com/bcm2711/bcm2711-peripherals.pdf>, chapters 5.4 and 8
+
+use crate::{
+    bsp::device_driver::common::{
+        registers::{ReadWrite, WriteOnly},
+        MMIODerefWrapper,
+    },
+    driver::{self, led::Rgb},
+    error::{ErrorKind, KernelError, ResultExt},
+    memory, synchronization,
+    synchronization::IRQSafeSpinLock,
+    time,
+};
+use core::time::Duration;
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    register_bitfields, register_structs,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+register_bitfields! {
+    u32,
+
+    /// PWM Control Register.
+    CTL [
+        /// Clear the FIFO.
+        CLRF1 OFFSET(6) NUMBITS(1) [],
+
+        /// Take the data from the FIFO instead of DAT1.
+        USEF1 OFFSET(5) NUMBITS(1) [],
+
+        /// Serializer mode.
+        MODE1 OFFSET(1) NUMBITS(1) [
+            Pwm = 0,
+            Serializer = 1
+        ],
+
+        /// Enable channel 1.
+        PWEN1 OFFSET(0) NUMBITS(1) []
+    ],
+
+    /// PWM Status Register. Error bits are cleared by writing one.
+    STA [
+        /// Bus error.
+        BERR OFFSET(8) NUMBITS(1) [],
+
+        /// Channel 1 ran out of data.
+        GAPO1 OFFSET(4) NUMBITS(1) [],
+
+        /// FIFO read error.
+        RERR1 OFFSET(3) NUMBITS(1) [],
+
+        /// FIFO write error.
+        WERR1 OFFSET(2) NUMBITS(1) [],
+
+        /// FIFO empty.
+        EMPT1 OFFSET(1) NUMBITS(1) [],
+
+        /// FIFO full.
+        FULL1 OFFSET(0) NUMBITS(1) []
+    ]
+}
+
+register_bitfields! {
+    u32,
+
+    /// Clock Manager PWM Clock Control.
+    CM_PWMCTL [
+        /// Must be written with every access.
+        PASSWD OFFSET(24) NUMBITS(8) [
+            Val = 0x5a
+        ],
+
+        /// The clock generator is running.
+        BUSY OFFSET(7) NUMBITS(1) [],
+
+        /// Enable the clock generator.
+        ENAB OFFSET(4) NUMBITS(1) [],
+
+        /// Clock source.
+        SRC OFFSET(0) NUMBITS(4) [
+            Oscillator = 1
+        ]
+    ],
+
+    /// Clock Manager PWM Clock Divisor.
+    CM_PWMDIV [
+        /// Must be written with every access.
+        PASSWD OFFSET(24) NUMBITS(8) [
+            Val = 0x5a
+        ],
+
+        /// Integer part of the divisor.
+        DIVI OFFSET(12) NUMBITS(12) [],
+
+        /// Fractional part of the divisor, in 1/4096ths.
+        DIVF OFFSET(0) NUMBITS(12) []
+    ]
+}
+
+register_structs! {
+    #[allow(non_snake_case)]
+    PwmRegisterBlock {
+        (0x00 => CTL: ReadWrite<u32, CTL::Register>),
+        (0x04 => STA: ReadWrite<u32, STA::Register>),
+        (0x08 => _reserved1),
+        (0x10 => RNG1: ReadWrite<u32>),
+        (0x14 => _reserved2),
+        (0x18 => FIF1: WriteOnly<u32>),
+        (0x1c => _reserved3),
+        (0x28 => @END),
+    }
+}
+
+register_structs! {
+    #[allow(non_snake_case)]
+    ClockRegisterBlock {
+        (0x00 => CTL: ReadWrite<u32, CM_PWMCTL::Register>),
+        (0x04 => DIV: ReadWrite<u32, CM_PWMDIV::Register>),
+        (0x08 => @END),
+    }
+}
+
+/// Abstraction for the associated MMIO registers.
+type PwmRegisters = MMIODerefWrapper<PwmRegisterBlock>;
+type ClockRegisters = MMIODerefWrapper<ClockRegisterBlock>;
+
+/// The divisor from the oscillator to 2.4 MHz, as integer part and 1/4096ths.
+#[cfg(feature = "bsp_rpi3")]
+const CLOCK_DIVISOR: (u32, u32) = (8, 0); // 19.2 MHz.
+#[cfg(feature = "bsp_rpi4")]
+const CLOCK_DIVISOR: (u32, u32) = (22, 2048); // 54 MHz.
+
+/// The symbols that encode a zero and a one bit, three symbols each.
+const SYMBOLS_ZERO: u64 = 0b100;
+const SYMBOLS_ONE: u64 = 0b110;
+const SYMBOLS_PER_BIT: u32 = 3;
+
+/// Zero words that keep the line low after a frame, 320 µs at 2.4 MHz. Newer WS2812 revisions
+/// need 280 µs to latch.
+const NUM_LATCH_WORDS: usize = 24;
+
+/// How long to wait for the clock generator and the FIFO.
+const TIMEOUT: Duration = Duration::from_millis(10);
+
+/// The maximum number of pixels per frame. A frame keeps IRQs masked for 30 µs per pixel.
+const MAX_PIXELS: usize = 256;
+
+struct WS2812Inner {
+    pwm: PwmRegisters,
+    clock: ClockRegisters,
+    is_configured: bool,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Representation of the WS2812 LED strip.
+pub struct WS2812 {
+    pwm_mmio_descriptor: memory::mmu::MMIODescriptor,
+    clock_mmio_descriptor: memory::mmu::MMIODescriptor,
+    inner: IRQSafeSpinLock<WS2812Inner>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Encode `pixels` into serializer words, and hand them to `emit` in order.
+///
+/// The strip expects green, red and blue, most significant bit first. The last word is padded with
+/// zero symbols, which keep the line low.
+fn encode(
+    pixels: &[Rgb],
+    mut emit: impl FnMut(u32) -> Result<(), KernelError>,
+) -> Result<(), KernelError> {
+    let mut symbols: u64 = 0;
+    let mut num_symbols = 0;
+
+    for pixel in pixels {
+        for byte in [pixel.g, pixel.r, pixel.b] {
+            for bit in (0..8).rev() {
+                let bit_symbols = if (byte >> bit) & 1 != 0 {
+                    SYMBOLS_ONE
+                } else {
+                    SYMBOLS_ZERO
+                };
+                symbols = (symbols << SYMBOLS_PER_BIT) | bit_symbols;
+                num_symbols += SYMBOLS_PER_BIT;
+
+                if num_symbols >= 32 {
+                    num_symbols -= 32;
+                    emit((symbols >> num_symbols) as u32)?;
+                }
+            }
+        }
+    }
+
+    if num_symbols != 0 {
+        emit((symbols << (32 - num_symbols)) as u32)?;
+    }
+
+    Ok(())
+}
+
+fn timed_out() -> KernelError {
+    KernelError::new(ErrorKind::Other, "WS2812 timed out")
+}
+
+impl WS2812Inner {
+    const unsafe fn new(pwm_mmio_start_addr: usize, clock_mmio_start_addr: usize) -> Self {
+        Self {
+            pwm: PwmRegisters::new(pwm_mmio_start_addr),
+            clock: ClockRegisters::new(clock_mmio_start_addr),
+            is_configured: false,
+        }
+    }
+
+    unsafe fn init(&mut self, new_pwm_mmio_start_addr: usize, new_clock_mmio_start_addr: usize) {
+        self.pwm = PwmRegisters::new(new_pwm_mmio_start_addr);
+        self.clock = ClockRegisters::new(new_clock_mmio_start_addr);
+    }
+
+    /// Run the PWM clock at 2.4 MHz and put channel 1 into serializer mode.
+    ///
+    /// Done on first use instead of at boot, so that a missing or emulated PWM does not fail the
+    /// kernel's bring-up.
+    fn configure(&mut self) -> Result<(), KernelError> {
+        self.pwm.CTL.set(0);
+
+        // The divisor must only be changed while the clock generator is stopped.
+        self.clock
+            .CTL
+            .write(CM_PWMCTL::PASSWD::Val + CM_PWMCTL::SRC::Oscillator);
+        time::wait_for(TIMEOUT, || !self.clock.CTL.is_set(CM_PWMCTL::BUSY))
+            .map_err(|_| timed_out())?;
+
+        let (divi, divf) = CLOCK_DIVISOR;
+        self.clock
+            .DIV
+            .write(CM_PWMDIV::PASSWD::Val + CM_PWMDIV::DIVI.val(divi) + CM_PWMDIV::DIVF.val(divf));
+        self.clock
+            .CTL
+            .write(CM_PWMCTL::PASSWD::Val + CM_PWMCTL::SRC::Oscillator + CM_PWMCTL::ENAB::SET);
+
+        self.pwm.RNG1.set(32);
+        self.pwm.CTL.write(CTL::CLRF1::SET);
+        self.pwm
+            .CTL
+            .write(CTL::PWEN1::SET + CTL::MODE1::Serializer + CTL::USEF1::SET);
+
+        self.is_configured = true;
+
+        Ok(())
+    }
+
+    fn push_word(&mut self, word: u32) -> Result<(), KernelError> {
+        time::wait_for(TIMEOUT, || !self.pwm.STA.is_set(STA::FULL1)).map_err(|_| timed_out())?;
+        self.pwm.FIF1.set(word);
+
+        Ok(())
+    }
+
+    fn set_pixels(&mut self, pixels: &[Rgb]) -> Result<(), KernelError> {
+        if pixels.len() > MAX_PIXELS {
+            return Err(KernelError::new(
+                ErrorKind::InvalidArgument,
+                "Too many pixels",
+            ));
+        }
+
+        if !self.is_configured {
+            self.configure().context("Configuring the PWM")?;
+        }
+
+        self.pwm
+            .STA
+            .write(STA::BERR::SET + STA::GAPO1::SET + STA::RERR1::SET + STA::WERR1::SET);
+
+        encode(pixels, |word| self.push_word(word))?;
+        for _ in 0..NUM_LATCH_WORDS {
+            self.push_word(0)?;
+        }
+
+        // The LEDs latched once the latch words are out.
+        time::wait_for(TIMEOUT, || self.pwm.STA.is_set(STA::EMPT1)).map_err(|_| timed_out())?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl WS2812 {
+    /// Create an instance.
+    ///
+    /// # Safety
+    ///
+    /// - The user must ensure to provide correct MMIO descriptors.
+    pub const unsafe fn new(
+        pwm_mmio_descriptor: memory::mmu::MMIODescriptor,
+        clock_mmio_descriptor: memory::mmu::MMIODescriptor,
+    ) -> Self {
+        Self {
+            pwm_mmio_descriptor,
+            clock_mmio_descriptor,
+            inner: IRQSafeSpinLock::new(WS2812Inner::new(
+                pwm_mmio_descriptor.start_addr().as_usize(),
+                clock_mmio_descriptor.start_addr().as_usize(),
+            )),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// OS Interface Code
+//------------------------------------------------------------------------------
+use synchronization::interface::Mutex;
+
+impl driver::interface::DeviceDriver for WS2812 {
+    fn compatible(&self) -> &'static str {
+        "BCM PWM WS2812"
+    }
+
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let pwm_virt_addr =
+            memory::mmu::kernel_map_mmio(self.compatible(), &self.pwm_mmio_descriptor)
+                .context("Mapping the PWM MMIO")?;
+        let clock_virt_addr =
+            memory::mmu::kernel_map_mmio(self.compatible(), &self.clock_mmio_descriptor)
+                .context("Mapping the PWM clock MMIO")?;
+
+        self.inner
+            .lock(|inner| inner.init(pwm_virt_addr.as_usize(), clock_virt_addr.as_usize()));
+
+        Ok(())
+    }
+}
+
+impl driver::led::interface::LedStrip for WS2812 {
+    /// IRQs stay masked while the frame is sent, because the LEDs latch as soon as the FIFO runs
+    /// dry.
+    fn set_pixels(&self, pixels: &[Rgb]) -> Result<(), KernelError> {
+        self.inner.lock(|inner| inner.set_pixels(pixels))
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    fn encode_pixel(pixel: Rgb) -> [u32; 3] {
+        let mut words = [0; 3];
+        let mut num_words = 0;
+
+        encode(&[pixel], |word| {
+            words[num_words] = word;
+            num_words += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(num_words, 3);
+
+        words
+    }
+
+    /// A pixel takes 72 symbols, which end in a padded third word.
+    #[kernel_test]
+    fn ws2812_encoding_works() {
+        assert_eq!(
+            encode_pixel(Rgb::new(0, 0, 0)),
+            [0x9249_2492, 0x4924_9249, 0x2400_0000]
+        );
+        assert_eq!(
+            encode_pixel(Rgb::new(0xff, 0xff, 0xff)),
+            [0xdb6d_b6db, 0x6db6_db6d, 0xb600_0000]
+        );
+
+        // Green is sent first: its top bit is the first symbol triple.
+        assert_eq!(encode_pixel(Rgb::new(0, 0x80, 0))[0] >> 29, 0b110);
+        assert_eq!(encode_pixel(Rgb::new(0x80, 0, 0))[0] >> 29, 0b100);
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! BCM2835 hardware random number generator driver.
+//!
+//! Found in the BCM2837 of the Raspberry Pi 3.
+//!
+//! # Resources
+//!
+//! - <https://github.com/torvalds/linux/blob/master/drivers/char/hw_random/bcm2835-rng.c>
+
+use crate::{
+    bsp::device_driver::common::{
+        registers::{ReadOnly, ReadWrite},
+        MMIODerefWrapper,
+    },
+    driver,
+    error::{ErrorKind, KernelError, ResultExt},
+    memory, synchronization,
+    synchronization::IRQSafeSpinLock,
+    time,
+};
+use core::time::Duration;
+use tock_registers::{
+    interfaces::{ReadWriteable, Readable, Writeable},
+    register_bitfields, register_structs,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+register_bitfields! {
+    u32,
+
+    /// Control Register.
+    RNG_CTRL [
+        /// Random bit generator enable.
+        RBGEN OFFSET(0) NUMBITS(1) []
+    ],
+
+    /// Status Register.
+    RNG_STATUS [
+        /// The number of words in the FIFO.
+        VAL OFFSET(24) NUMBITS(8) [],
+
+        /// The number of initial bits that are thrown away.
+        WARM_CNT OFFSET(0) NUMBITS(20) []
+    ],
+
+    /// Interrupt Mask Register.
+    RNG_INT_MASK [
+        /// Mask the interrupt.
+        INT_OFF OFFSET(0) NUMBITS(1) []
+    ]
+}
+
+register_structs! {
+    #[allow(non_snake_case)]
+    RegisterBlock {
+        (0x00 => CTRL: ReadWrite<u32, RNG_CTRL::Register>),
+        (0x04 => STATUS: ReadWrite<u32, RNG_STATUS::Register>),
+        (0x08 => DATA: ReadOnly<u32>),
+        (0x0c => _reserved1),
+        (0x10 => INT_MASK: ReadWrite<u32, RNG_INT_MASK::Register>),
+        (0x14 => @END),
+    }
+}
+
+/// Abstraction for the associated MMIO registers.
+type Registers = MMIODerefWrapper<RegisterBlock>;
+
+/// The number of initial bits to throw away, like the Linux driver does.
+const WARMUP_COUNT: u32 = 0x40000;
+
+/// How long to wait for the FIFO to fill.
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+struct RNGInner {
+    registers: Registers,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Representation of the RNG.
+pub struct RNG {
+    mmio_descriptor: memory::mmu::MMIODescriptor,
+    inner: IRQSafeSpinLock<RNGInner>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl RNGInner {
+    const unsafe fn new(mmio_start_addr: usize) -> Self {
+        Self {
+            registers: Registers::new(mmio_start_addr),
+        }
+    }
+
+    unsafe fn init(&mut self, new_mmio_start_addr: usize) {
+        self.registers = Registers::new(new_mmio_start_addr);
+
+        self.registers
+            .STATUS
+            .write(RNG_STATUS::WARM_CNT.val(WARMUP_COUNT));
+        self.registers.INT_MASK.modify(RNG_INT_MASK::INT_OFF::SET);
+        self.registers.CTRL.modify(RNG_CTRL::RBGEN::SET);
+    }
+
+    fn num_words_available(&self) -> usize {
+        self.registers.STATUS.read(RNG_STATUS::VAL) as usize
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, KernelError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        time::wait_for(TIMEOUT, || self.num_words_available() != 0)
+            .map_err(|_| KernelError::new(ErrorKind::Other, "RNG timed out"))?;
+
+        let mut read = 0;
+        for chunk in buf.chunks_mut(4).take(self.num_words_available()) {
+            let word = self.registers.DATA.get().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+            read += chunk.len();
+        }
+
+        Ok(read)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl RNG {
+    /// Create an instance.
+    ///
+    /// # Safety
+    ///
+    /// - The user must ensure to provide correct MMIO descriptors.
+    pub const unsafe fn new(mmio_descriptor: memory::mmu::MMIODescriptor) -> Self {
+        Self {
+            mmio_descriptor,
+            inner: IRQSafeSpinLock::new(RNGInner::new(mmio_descriptor.start_addr().as_usize())),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// OS Interface Code
+//------------------------------------------------------------------------------
+use synchronization::interface::Mutex;
+
+impl driver::interface::DeviceDriver for RNG {
+    fn compatible(&self) -> &'static str {
+        "BCM2835 RNG"
+    }
+
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+            .context("Mapping the RNG MMIO")?;
+
+        self.inner.lock(|inner| inner.init(virt_addr.as_usize()));
+
+        Ok(())
+    }
+}
+
+impl driver::chardev::interface::CharDevice for RNG {
+    /// Fails if the FIFO stays empty, which happens while the generator warms up, or if there is no
+    /// generator at all, like in some emulators.
+    fn read(&self, buf: &mut [u8]) -> Result<usize, KernelError> {
+        self.inner.lock(|inner| inner.read(buf))
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, KernelError> {
+        Err(KernelError::new(
+            ErrorKind::NotSupported,
+            "RNG is read-only",
+        ))
+    }
+}
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! RNG200 hardware random number generator driver.
+//!
+//! Found in the BCM2711 of the Raspberry Pi 4.
+//!
+//! # Resources
+//!
+//! - <https://github.com/torvalds/linux/blob/master/drivers/char/hw_random/iproc-rng200.c>
+
+use crate::{
+    bsp::device_driver::common::{
+        registers::{ReadOnly, ReadWrite},
+        MMIODerefWrapper,
+    },
+    driver,
+    error::{ErrorKind, KernelError, ResultExt},
+    memory, synchronization,
+    synchronization::IRQSafeSpinLock,
+    time,
+};
+use core::time::Duration;
+use tock_registers::{
+    interfaces::{ReadWriteable, Readable, Writeable},
+    register_bitfields, register_structs,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+register_bitfields! {
+    u32,
+
+    /// Control Register.
+    RNG_CTRL [
+        /// Random bit generator enable.
+        RBGEN OFFSET(0) NUMBITS(13) [
+            Disabled = 0,
+            Enabled = 1
+        ]
+    ],
+
+    /// Soft Reset Registers.
+    SOFT_RESET [
+        /// Hold the block in reset.
+        RESET OFFSET(0) NUMBITS(1) []
+    ],
+
+    /// FIFO Count Register.
+    RNG_FIFO_COUNT [
+        /// The number of words in the FIFO.
+        COUNT OFFSET(0) NUMBITS(8) []
+    ]
+}
+
+register_structs! {
+    #[allow(non_snake_case)]
+    RegisterBlock {
+        (0x00 => CTRL: ReadWrite<u32, RNG_CTRL::Register>),
+        (0x04 => RNG_SOFT_RESET: ReadWrite<u32, SOFT_RESET::Register>),
+        (0x08 => RBG_SOFT_RESET: ReadWrite<u32, SOFT_RESET::Register>),
+        (0x0c => _reserved1),
+        (0x18 => INT_STATUS: ReadWrite<u32>),
+        (0x1c => _reserved2),
+        (0x20 => FIFO_DATA: ReadOnly<u32>),
+        (0x24 => FIFO_COUNT: ReadOnly<u32, RNG_FIFO_COUNT::Register>),
+        (0x28 => @END),
+    }
+}
+
+/// Abstraction for the associated MMIO registers.
+type Registers = MMIODerefWrapper<RegisterBlock>;
+
+/// How long to wait for the FIFO to fill.
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+struct RNGInner {
+    registers: Registers,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Representation of the RNG.
+pub struct RNG {
+    mmio_descriptor: memory::mmu::MMIODescriptor,
+    inner: IRQSafeSpinLock<RNGInner>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl RNGInner {
+    const unsafe fn new(mmio_start_addr: usize) -> Self {
+        Self {
+            registers: Registers::new(mmio_start_addr),
+        }
+    }
+
+    unsafe fn init(&mut self, new_mmio_start_addr: usize) {
+        self.registers = Registers::new(new_mmio_start_addr);
+
+        // Reset both the generator and the FIFO, like the Linux driver does when it recovers from
+        // errors.
+        self.registers.CTRL.modify(RNG_CTRL::RBGEN::Disabled);
+        self.registers.INT_STATUS.set(u32::MAX);
+
+        self.registers.RBG_SOFT_RESET.modify(SOFT_RESET::RESET::SET);
+        self.registers.RNG_SOFT_RESET.modify(SOFT_RESET::RESET::SET);
+        self.registers
+            .RNG_SOFT_RESET
+            .modify(SOFT_RESET::RESET::CLEAR);
+        self.registers
+            .RBG_SOFT_RESET
+            .modify(SOFT_RESET::RESET::CLEAR);
+
+        self.registers.CTRL.modify(RNG_CTRL::RBGEN::Enabled);
+    }
+
+    fn num_words_available(&self) -> usize {
+        self.registers.FIFO_COUNT.read(RNG_FIFO_COUNT::COUNT) as usize
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, KernelError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        time::wait_for(TIMEOUT, || self.num_words_available() != 0)
+            .map_err(|_| KernelError::new(ErrorKind::Other, "RNG timed out"))?;
+
+        let mut read = 0;
+        for chunk in buf.chunks_mut(4).take(self.num_words_available()) {
+            let word = self.registers.FIFO_DATA.get().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+            read += chunk.len();
+        }
+
+        Ok(read)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl RNG {
+    /// Create an instance.
+    ///
+    /// # Safety
+    ///
+    /// - The user must ensure to provide correct MMIO descriptors.
+    pub const unsafe fn new(mmio_descriptor: memory::mmu::MMIODescriptor) -> Self {
+        Self {
+            mmio_descriptor,
+            inner: IRQSafeSpinLock::new(RNGInner::new(mmio_descriptor.start_addr().as_usize())),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// OS Interface Code
+//------------------------------------------------------------------------------
+use synchronization::interface::Mutex;
+
+impl driver::interface::DeviceDriver for RNG {
+    fn compatible(&self) -> &'static str {
+        "BCM2711 RNG200"
+    }
+
+    unsafe fn init(&self) -> Result<(), KernelError> {
+        let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+            .context("Mapping the RNG MMIO")?;
+
+        self.inner.lock(|inner| inner.init(virt_addr.as_usize()));
+
+        Ok(())
+    }
+}
+
+impl driver::chardev::interface::CharDevice for RNG {
+    /// Fails if the FIFO stays empty, which happens while the generator warms up, or if there is no
+    /// generator at all, like in some emulators.
+    fn read(&self, buf: &mut [u8]) -> Result<usize, KernelError> {
+        self.inner.lock(|inner| inner.read(buf))
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, KernelError> {
+        Err(KernelError::new(
+            ErrorKind::NotSupported,
+            "RNG is read-only",
+        ))
+    }
+}
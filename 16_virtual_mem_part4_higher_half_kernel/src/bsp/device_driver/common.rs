@@ -4,12 +4,28 @@
 
 //! Common device driver code.
 
+#[cfg(feature = "mmio_trace")]
+mod mmio_trace;
+
 use core::{marker::PhantomData, ops};
 
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
 //--------------------------------------------------------------------------------------------------
 
+/// Register types for the drivers' `register_structs!`.
+///
+/// With the `mmio_trace` feature, every access is recorded in the trace buffer as
+/// [crate::trace::Event::MmioRead] or [crate::trace::Event::MmioWrite]. Otherwise, these are
+/// tock-registers' own types.
+pub mod registers {
+    #[cfg(feature = "mmio_trace")]
+    pub use super::mmio_trace::{ReadOnly, ReadWrite, WriteOnly};
+
+    #[cfg(not(feature = "mmio_trace"))]
+    pub use tock_registers::registers::{ReadOnly, ReadWrite, WriteOnly};
+}
+
 pub struct MMIODerefWrapper<T> {
     start_addr: usize,
     phantom: PhantomData<fn() -> T>,
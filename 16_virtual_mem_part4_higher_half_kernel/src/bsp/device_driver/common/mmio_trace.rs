@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Traced MMIO register types.
+//!
+//! Drop-in replacements for tock-registers' register types, which record each access in the trace
+//! buffer. The argument of a record holds the value in its upper 32 bits, and the lower 32 bits of
+//! the register's virtual address in its lower ones. The mapping records tell which driver the
+//! address belongs to, and the offset into its MMIO.
+//!
+//! Builds with this feature enable tracing at boot, so that the accesses of driver init are
+//! recorded, too.
+
+use crate::trace;
+use tock_registers::{
+    interfaces::{Readable, Writeable},
+    registers, RegisterLongName, UIntLike,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A traced read-only register.
+#[repr(transparent)]
+pub struct ReadOnly<T: UIntLike, R: RegisterLongName = ()>(registers::ReadOnly<T, R>);
+
+/// A traced write-only register.
+#[repr(transparent)]
+pub struct WriteOnly<T: UIntLike, R: RegisterLongName = ()>(registers::WriteOnly<T, R>);
+
+/// A traced read-write register.
+#[repr(transparent)]
+pub struct ReadWrite<T: UIntLike, R: RegisterLongName = ()>(registers::ReadWrite<T, R>);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn record<T: Into<u64>>(event: trace::Event, register: *const (), value: T) {
+    let addr = register as usize as u64 & 0xFFFF_FFFF;
+
+    trace::record(event, (value.into() << 32) | addr);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl<T: UIntLike + Into<u64>, R: RegisterLongName> Readable for ReadOnly<T, R> {
+    type T = T;
+    type R = R;
+
+    #[inline]
+    fn get(&self) -> T {
+        let value = self.0.get();
+        record(trace::Event::MmioRead, self as *const _ as *const (), value);
+
+        value
+    }
+}
+
+impl<T: UIntLike + Into<u64>, R: RegisterLongName> Writeable for WriteOnly<T, R> {
+    type T = T;
+    type R = R;
+
+    #[inline]
+    fn set(&self, value: T) {
+        record(
+            trace::Event::MmioWrite,
+            self as *const _ as *const (),
+            value,
+        );
+        self.0.set(value);
+    }
+}
+
+impl<T: UIntLike + Into<u64>, R: RegisterLongName> Readable for ReadWrite<T, R> {
+    type T = T;
+    type R = R;
+
+    #[inline]
+    fn get(&self) -> T {
+        let value = self.0.get();
+        record(trace::Event::MmioRead, self as *const _ as *const (), value);
+
+        value
+    }
+}
+
+impl<T: UIntLike + Into<u64>, R: RegisterLongName> Writeable for ReadWrite<T, R> {
+    type T = T;
+    type R = R;
+
+    #[inline]
+    fn set(&self, value: T) {
+        record(
+            trace::Event::MmioWrite,
+            self as *const _ as *const (),
+            value,
+        );
+        self.0.set(value);
+    }
+}
@@ -4,6 +4,8 @@
 
 //! Top-level BSP file for the Raspberry Pi 3 and 4.
 
+pub mod board;
+pub mod cmdline;
 pub mod console;
 pub mod cpu;
 pub mod driver;
@@ -11,7 +13,10 @@ pub mod exception;
 pub mod memory;
 
 use super::device_driver;
-use crate::memory::mmu::MMIODescriptor;
+use crate::{
+    console::interface::Statistics, driver::interface::DeviceDriver, memory::mmu::MMIODescriptor,
+    register_char_device, register_device_driver, register_introspection_node,
+};
 use memory::map::mmio;
 
 //--------------------------------------------------------------------------------------------------
@@ -20,6 +25,7 @@ use memory::map::mmio;
 
 static GPIO: device_driver::GPIO =
     unsafe { device_driver::GPIO::new(MMIODescriptor::new(mmio::GPIO_START, mmio::GPIO_SIZE)) };
+register_device_driver!(early_print GPIO);
 
 static PL011_UART: device_driver::PL011Uart = unsafe {
     device_driver::PL011Uart::new(
@@ -27,6 +33,24 @@ static PL011_UART: device_driver::PL011Uart = unsafe {
         exception::asynchronous::irq_map::PL011_UART,
     )
 };
+register_device_driver!(early_print PL011_UART);
+register_char_device!(204, 64, "ttyAMA0", PL011_UART);
+register_char_device!(5, 1, "console", PL011_UART);
+register_introspection_node!("drivers/pl011/baud", |f| {
+    let mut request = device_driver::PL011UartRequest::GetBaudRate(0);
+    match (PL011_UART.control(&mut request), request) {
+        (Ok(()), device_driver::PL011UartRequest::GetBaudRate(baud_rate)) => {
+            write!(f, "{}", baud_rate)
+        }
+        _ => f.write_str("unknown"),
+    }
+});
+register_introspection_node!("drivers/pl011/chars_written", |f| {
+    write!(f, "{}", PL011_UART.chars_written())
+});
+register_introspection_node!("drivers/pl011/chars_read", |f| {
+    write!(f, "{}", PL011_UART.chars_read())
+});
 
 #[cfg(feature = "bsp_rpi3")]
 static INTERRUPT_CONTROLLER: device_driver::InterruptController = unsafe {
@@ -44,19 +68,36 @@ static INTERRUPT_CONTROLLER: device_driver::GICv2 = unsafe {
     )
 };
 
+register_device_driver!(INTERRUPT_CONTROLLER);
+
+static RNG: device_driver::RNG =
+    unsafe { device_driver::RNG::new(MMIODescriptor::new(mmio::RNG_START, mmio::RNG_SIZE)) };
+register_device_driver!(RNG);
+register_char_device!(10, 183, "hwrng", RNG);
+
+static MAILBOX: device_driver::Mailbox = unsafe {
+    device_driver::Mailbox::new(MMIODescriptor::new(mmio::MAILBOX_START, mmio::MAILBOX_SIZE))
+};
+register_device_driver!(MAILBOX);
+
+static LED_STRIP: device_driver::WS2812 = unsafe {
+    device_driver::WS2812::new(
+        MMIODescriptor::new(mmio::PWM0_START, mmio::PWM0_SIZE),
+        MMIODescriptor::new(mmio::CM_PWM_START, mmio::CM_PWM_SIZE),
+    )
+};
+register_device_driver!(LED_STRIP);
+
 //--------------------------------------------------------------------------------------------------
 // Public Code
 //--------------------------------------------------------------------------------------------------
 
 /// Board identification.
 pub fn board_name() -> &'static str {
-    #[cfg(feature = "bsp_rpi3")]
-    {
-        "Raspberry Pi 3"
-    }
+    crate::config::BOARD.name()
+}
 
-    #[cfg(feature = "bsp_rpi4")]
-    {
-        "Raspberry Pi 4"
-    }
+/// The WS2812 LED strip whose data line is connected to pin 18.
+pub fn led_strip() -> &'static impl crate::driver::led::interface::LedStrip {
+    &LED_STRIP
 }
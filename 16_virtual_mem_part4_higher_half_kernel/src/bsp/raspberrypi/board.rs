@@ -0,0 +1,393 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! BSP board information.
+//!
+//! The firmware knows the exact board that it runs on. [print_info()] asks it through the mailbox,
+//! so that the boot log shows the board model and revision, the serial number and MAC address, the
+//! firmware revision and how the memory is split between the ARM and the VideoCore.
+//!
+//! The model and the memory size are decoded from the revision code, as documented in
+//! <https://www.raspberrypi.com/documentation/computers/raspberry-pi.html#raspberry-pi-revision-codes>.
+//!
+//! [start_monitor()] polls the firmware for undervoltage and throttling, and warns when either
+//! starts. Mysterious slowdowns and crashes of real boards are usually caused by a weak power
+//! supply, which the firmware detects as undervoltage.
+
+use super::{device_driver::tag, MAILBOX};
+use crate::{
+    error::{KernelError, ResultExt},
+    event::{self, Event},
+    info, time, warn,
+};
+use core::{
+    fmt,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Set in revision codes that use the bitfield layout. Older codes are plain serial numbers.
+const REVISION_NEW_STYLE: u32 = 1 << 23;
+
+/// How often the monitor polls the firmware.
+const MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The SoC temperature in thousandths of a degree Celsius, for adding to the monitor's messages.
+struct Temperature(Option<u32>);
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A board revision code, as reported by the firmware.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Revision(u32);
+
+/// Undervoltage and throttling conditions, as reported by the firmware.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Throttled(u32);
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+/// The conditions that were active at the monitor's previous poll.
+static ACTIVE_CONDITIONS: AtomicU32 = AtomicU32::new(0);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Query a property that takes no arguments and answers with `N` words.
+fn query<const N: usize>(tag: u32) -> Option<[u32; N]> {
+    let mut value = [0; N];
+
+    MAILBOX.property(tag, &mut value).ok().map(|_| value)
+}
+
+fn query_throttled() -> Result<Throttled, KernelError> {
+    let mut value = [0];
+    MAILBOX.property(tag::GET_THROTTLED, &mut value)?;
+
+    Ok(Throttled::new(value[0]))
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => write!(f, "unknown"),
+            Some(x) => write!(f, "{}.{} C", x / 1000, (x % 1000) / 100),
+        }
+    }
+}
+
+fn temperature() -> Temperature {
+    // The request carries the sensor ID, which is 0 for the SoC.
+    Temperature(query::<2>(tag::GET_TEMPERATURE).map(|[_, x]| x))
+}
+
+fn monitor() {
+    let active = match query_throttled() {
+        Err(x) => {
+            warn!("Board monitor: {}", x);
+            return;
+        }
+        Ok(x) => x.active(),
+    };
+    let previous = Throttled::new(ACTIVE_CONDITIONS.swap(active.0, Ordering::Relaxed));
+
+    let started = active.difference(previous);
+    if !started.is_empty() {
+        warn!("Board: {}, SoC temperature {}", started, temperature());
+    }
+    if started.is_undervoltage() {
+        event::publish(Event::Undervoltage);
+    }
+    if started.is_throttled() {
+        event::publish(Event::Throttled);
+    }
+
+    let stopped = previous.difference(active);
+    if !stopped.is_empty() {
+        info!("Board: No longer {}", stopped);
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Revision {
+    /// Create an instance.
+    pub const fn new(code: u32) -> Self {
+        Self(code)
+    }
+
+    /// The raw revision code.
+    pub const fn code(&self) -> u32 {
+        self.0
+    }
+
+    fn field(&self, shift: u32, bits: u32) -> Option<u32> {
+        if self.0 & REVISION_NEW_STYLE == 0 {
+            return None;
+        }
+
+        Some((self.0 >> shift) & ((1 << bits) - 1))
+    }
+
+    /// The board model.
+    pub fn model(&self) -> Option<&'static str> {
+        let model = match self.field(4, 8)? {
+            0x04 => "Raspberry Pi 2 Model B",
+            0x08 => "Raspberry Pi 3 Model B",
+            0x0a => "Raspberry Pi Compute Module 3",
+            0x0d => "Raspberry Pi 3 Model B+",
+            0x0e => "Raspberry Pi 3 Model A+",
+            0x10 => "Raspberry Pi Compute Module 3+",
+            0x11 => "Raspberry Pi 4 Model B",
+            0x12 => "Raspberry Pi Zero 2 W",
+            0x13 => "Raspberry Pi 400",
+            0x14 => "Raspberry Pi Compute Module 4",
+            _ => return None,
+        };
+
+        Some(model)
+    }
+
+    /// The board's minor revision, as in `1.<minor>`.
+    pub fn minor_revision(&self) -> Option<u32> {
+        self.field(0, 4)
+    }
+
+    /// The system on chip.
+    pub fn processor(&self) -> Option<&'static str> {
+        let processor = match self.field(12, 4)? {
+            0 => "BCM2835",
+            1 => "BCM2836",
+            2 => "BCM2837",
+            3 => "BCM2711",
+            _ => return None,
+        };
+
+        Some(processor)
+    }
+
+    /// The size of the board's memory in bytes.
+    pub fn memory_size(&self) -> Option<usize> {
+        Some((256 * 1024 * 1024) << self.field(20, 3)?)
+    }
+}
+
+impl fmt::Display for Revision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (model, minor, processor, memory_size) = match (
+            self.model(),
+            self.minor_revision(),
+            self.processor(),
+            self.memory_size(),
+        ) {
+            (Some(a), Some(b), Some(c), Some(d)) => (a, b, c, d),
+            _ => return write!(f, "Unknown board, revision code {:#010x}", self.0),
+        };
+
+        write!(
+            f,
+            "{} Rev 1.{} ({}, {} MiB)",
+            model,
+            minor,
+            processor,
+            memory_size / (1024 * 1024)
+        )
+    }
+}
+
+impl Throttled {
+    const UNDERVOLTAGE: u32 = 1 << 0;
+    const FREQUENCY_CAPPED: u32 = 1 << 1;
+    const THROTTLED: u32 = 1 << 2;
+    const SOFT_TEMPERATURE_LIMIT: u32 = 1 << 3;
+    const ALL: u32 = 0xf;
+
+    /// The firmware reports the conditions that occurred since power-on at this offset.
+    const OCCURRED_SHIFT: u32 = 16;
+
+    /// Create an instance from the firmware's report.
+    pub const fn new(value: u32) -> Self {
+        Self(value)
+    }
+
+    /// The conditions that are active now.
+    pub const fn active(&self) -> Self {
+        Self(self.0 & Self::ALL)
+    }
+
+    /// The conditions that occurred since power-on.
+    pub const fn occurred(&self) -> Self {
+        Self((self.0 >> Self::OCCURRED_SHIFT) & Self::ALL)
+    }
+
+    /// The conditions of `self` that are not in `other`.
+    pub const fn difference(&self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Whether no condition is set.
+    pub const fn is_empty(&self) -> bool {
+        self.0 & Self::ALL == 0
+    }
+
+    /// Whether undervoltage is set.
+    pub const fn is_undervoltage(&self) -> bool {
+        self.0 & Self::UNDERVOLTAGE != 0
+    }
+
+    /// Whether any condition that reduces the performance is set.
+    pub const fn is_throttled(&self) -> bool {
+        self.0 & (Self::FREQUENCY_CAPPED | Self::THROTTLED | Self::SOFT_TEMPERATURE_LIMIT) != 0
+    }
+}
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [(u32, &str); 4] = [
+            (Throttled::UNDERVOLTAGE, "undervoltage"),
+            (Throttled::FREQUENCY_CAPPED, "ARM frequency capped"),
+            (Throttled::THROTTLED, "throttled"),
+            (Throttled::SOFT_TEMPERATURE_LIMIT, "soft temperature limit"),
+        ];
+
+        let mut names = NAMES.iter().filter(|(bit, _)| self.0 & bit != 0);
+
+        match names.next() {
+            None => return write!(f, "none"),
+            Some((_, name)) => write!(f, "{}", name)?,
+        }
+        for (_, name) in names {
+            write!(f, ", {}", name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Print what the firmware reports about the board.
+///
+/// Falls back to the configured board name if the mailbox does not answer.
+pub fn print_info() {
+    let revision = match query::<1>(tag::GET_BOARD_REVISION) {
+        None => {
+            info!("Booting on: {}", super::board_name());
+            return;
+        }
+        Some([x]) => Revision::new(x),
+    };
+
+    info!("Booting on: {}", revision);
+    info!("      Revision code: {:#010x}", revision.code());
+
+    if let Some([lo, hi]) = query::<2>(tag::GET_BOARD_SERIAL) {
+        info!(
+            "      Serial number: {:016x}",
+            (u64::from(hi) << 32) | u64::from(lo)
+        );
+    }
+
+    if let Some([lo, hi]) = query::<2>(tag::GET_BOARD_MAC_ADDRESS) {
+        let lo = lo.to_le_bytes();
+        let hi = hi.to_le_bytes();
+        info!(
+            "      MAC address: {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            lo[0], lo[1], lo[2], lo[3], hi[0], hi[1]
+        );
+    }
+
+    if let Some([x]) = query::<1>(tag::GET_FIRMWARE_REVISION) {
+        info!("      Firmware revision: {}", x);
+    }
+
+    if let Some([base, size]) = query::<2>(tag::GET_ARM_MEMORY) {
+        info!(
+            "      ARM memory: {:#010x}, {} MiB",
+            base,
+            size / (1024 * 1024)
+        );
+    }
+
+    if let Some([base, size]) = query::<2>(tag::GET_VC_MEMORY) {
+        info!(
+            "      VideoCore memory: {:#010x}, {} MiB",
+            base,
+            size / (1024 * 1024)
+        );
+    }
+}
+
+/// Poll the firmware for undervoltage and throttling as a periodic task.
+///
+/// Warns right away about conditions that occurred since power-on, and later whenever a condition
+/// starts.
+pub fn start_monitor() -> Result<(), KernelError> {
+    let occurred = query_throttled()
+        .context("Querying the throttling state")?
+        .occurred();
+    if !occurred.is_empty() {
+        warn!("Board: Since power-on: {}", occurred);
+    }
+
+    monitor();
+    time::periodic::spawn("Board monitor", MONITOR_INTERVAL, monitor)?;
+
+    Ok(())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Decode revision codes of a Raspberry Pi 3 and 4, and reject an old-style code.
+    #[kernel_test]
+    fn revision_decoding_works() {
+        let rpi3 = Revision::new(0x00a0_2082);
+        assert_eq!(rpi3.model(), Some("Raspberry Pi 3 Model B"));
+        assert_eq!(rpi3.minor_revision(), Some(2));
+        assert_eq!(rpi3.processor(), Some("BCM2837"));
+        assert_eq!(rpi3.memory_size(), Some(1024 * 1024 * 1024));
+
+        let rpi4 = Revision::new(0x00c0_3111);
+        assert_eq!(rpi4.model(), Some("Raspberry Pi 4 Model B"));
+        assert_eq!(rpi4.minor_revision(), Some(1));
+        assert_eq!(rpi4.processor(), Some("BCM2711"));
+        assert_eq!(rpi4.memory_size(), Some(4 * 1024 * 1024 * 1024));
+
+        assert_eq!(Revision::new(0x0000_000e).model(), None);
+    }
+
+    /// Split a throttling report into active and past conditions.
+    #[kernel_test]
+    fn throttled_decoding_works() {
+        // Undervoltage now. Throttled in the past, but not anymore.
+        let report = Throttled::new(0x0005_0001);
+
+        assert_eq!(report.active(), Throttled::new(Throttled::UNDERVOLTAGE));
+        assert_eq!(
+            report.occurred(),
+            Throttled::new(Throttled::UNDERVOLTAGE | Throttled::THROTTLED)
+        );
+        assert_eq!(
+            report.occurred().difference(report.active()),
+            Throttled::new(Throttled::THROTTLED)
+        );
+        assert!(Throttled::new(0).is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! BSP kernel command line.
+//!
+//! The Raspberry's firmware passes the `cmdline.txt` of the boot partition in the
+//! `/chosen/bootargs` property of the device tree. The command line is a whitespace separated list
+//! of `key=value` parameters and plain `key` flags, for example:
+//!
+//! ```text
+//! quiet smp=n console=serial0,921600
+//! ```
+//!
+//! The device tree is only read once during init, and the command line is copied into the kernel,
+//! so that lookups do not depend on the device tree staying mapped or intact.
+
+use crate::{
+    cpu,
+    error::{ErrorKind, KernelError, ResultExt},
+    memory,
+    memory::{mmu::MMIODescriptor, Address, Virtual},
+    synchronization::{interface::ReadWriteEx, InitStateLock},
+};
+use core::str::FromStr;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The maximum length of the command line. Longer ones are truncated.
+const MAX_LEN: usize = 1024;
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_HEADER_SIZE: usize = 40;
+
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A parsed kernel command line.
+pub struct Cmdline {
+    buf: [u8; MAX_LEN],
+    len: usize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static KERNEL_CMDLINE: InitStateLock<Cmdline> = InitStateLock::new(Cmdline::new());
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Round up to the 4 byte alignment of device tree tokens.
+const fn align4(x: usize) -> usize {
+    (x + 3) & !3
+}
+
+/// A flattened device tree that is accessed byte by byte.
+///
+/// The blob is mapped as device memory, which does not allow unaligned accesses. Reading single
+/// bytes avoids the wider accesses that the compiler might emit for slice operations.
+struct Fdt<F: Fn(usize) -> u8> {
+    read_u8: F,
+    size: usize,
+}
+
+impl<F: Fn(usize) -> u8> Fdt<F> {
+    fn read_u32(&self, offset: usize) -> Option<u32> {
+        if offset + 4 > self.size {
+            return None;
+        }
+
+        let bytes = [0, 1, 2, 3].map(|i| (self.read_u8)(offset + i));
+
+        Some(u32::from_be_bytes(bytes))
+    }
+
+    /// Compare the NUL-terminated string at `offset` with `s`.
+    fn str_eq(&self, offset: usize, s: &str) -> bool {
+        offset + s.len() < self.size
+            && s.bytes()
+                .enumerate()
+                .all(|(i, c)| (self.read_u8)(offset + i) == c)
+            && (self.read_u8)(offset + s.len()) == 0
+    }
+
+    /// The length of the NUL-terminated string at `offset`, excluding the terminator.
+    fn str_len(&self, offset: usize) -> usize {
+        (offset..self.size)
+            .position(|i| (self.read_u8)(i) == 0)
+            .unwrap_or(self.size - offset)
+    }
+
+    /// Find the value of property `prop` in the top-level node `node`.
+    ///
+    /// Returns the offset and length of the value.
+    fn find_property(&self, node: &str, prop: &str) -> Option<(usize, usize)> {
+        if self.read_u32(0)? != FDT_MAGIC {
+            return None;
+        }
+
+        let off_struct = self.read_u32(8)? as usize;
+        let off_strings = self.read_u32(12)? as usize;
+
+        let mut offset = off_struct;
+        let mut depth = 0;
+        let mut in_node = false;
+
+        loop {
+            let token = self.read_u32(offset)?;
+            offset += 4;
+
+            match token {
+                FDT_BEGIN_NODE => {
+                    depth += 1;
+                    in_node = depth == 2 && self.str_eq(offset, node);
+                    offset = align4(offset + self.str_len(offset) + 1);
+                }
+                FDT_END_NODE => {
+                    if in_node {
+                        return None;
+                    }
+
+                    depth -= 1;
+                }
+                FDT_PROP => {
+                    let len = self.read_u32(offset)? as usize;
+                    let name_offset = self.read_u32(offset + 4)? as usize;
+                    let value_offset = offset + 8;
+
+                    if in_node && self.str_eq(off_strings + name_offset, prop) {
+                        return (value_offset + len <= self.size).then_some((value_offset, len));
+                    }
+
+                    offset = align4(value_offset + len);
+                }
+                FDT_NOP => (),
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Read `/chosen/bootargs` from the device tree that the firmware handed over.
+///
+/// # Safety
+///
+/// - The device tree address must either be invalid or point to a device tree.
+unsafe fn copy_bootargs_from_dtb(cmdline: &mut Cmdline) -> Result<(), KernelError> {
+    let dtb_addr = match cpu::boot_dtb_phys_addr() {
+        // Not every boot path provides a device tree.
+        None => return Ok(()),
+        Some(addr) => addr,
+    };
+
+    if dtb_addr.as_usize() % 8 != 0 {
+        return Err(KernelError::new(
+            ErrorKind::InvalidArgument,
+            "Device tree address is misaligned",
+        ));
+    }
+
+    let map =
+        |size| memory::mmu::kernel_map_mmio("Device Tree", &MMIODescriptor::new(dtb_addr, size));
+    let read_u8_at = |base: Address<Virtual>| {
+        move |offset: usize| core::ptr::read_volatile((base.as_usize() + offset) as *const u8)
+    };
+
+    // Map the header first to learn the blob's size.
+    let header = Fdt {
+        read_u8: read_u8_at(map(FDT_HEADER_SIZE).context("Mapping the device tree header")?),
+        size: FDT_HEADER_SIZE,
+    };
+    if header.read_u32(0) != Some(FDT_MAGIC) {
+        // Whatever x0 held at boot, it was not a device tree.
+        return Ok(());
+    }
+    let size = header.read_u32(4).ok_or("Malformed device tree header")? as usize;
+
+    let fdt = Fdt {
+        read_u8: read_u8_at(map(size).context("Mapping the device tree")?),
+        size,
+    };
+
+    if let Some((offset, len)) = fdt.find_property("chosen", "bootargs") {
+        // The value is NUL-terminated.
+        cmdline.set(
+            (offset..offset + len)
+                .map(&fdt.read_u8)
+                .take_while(|&c| c != 0),
+        );
+    }
+
+    Ok(())
+}
+
+impl Cmdline {
+    const fn new() -> Self {
+        Self {
+            buf: [0; MAX_LEN],
+            len: 0,
+        }
+    }
+
+    fn set(&mut self, bytes: impl Iterator<Item = u8>) {
+        self.len = 0;
+
+        for (dst, src) in self.buf.iter_mut().zip(bytes) {
+            *dst = src;
+            self.len += 1;
+        }
+    }
+
+    /// All parameters as key and optional value.
+    fn params(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.as_str()
+            .split_whitespace()
+            .map(|param| match param.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (param, None),
+            })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Cmdline {
+    /// The whole command line. Empty if it is not valid UTF-8.
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+
+    /// The value of parameter `key`. If `key` is given more than once, the last one counts.
+    ///
+    /// Flags without a value are reported as an empty value.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.params()
+            .filter(|&(k, _)| k == key)
+            .last()
+            .map(|(_, value)| value.unwrap_or(""))
+    }
+
+    /// Whether flag `key` is set.
+    ///
+    /// A plain `key` sets it, and so do `key=y`, `key=1` and `key=on`.
+    pub fn flag(&self, key: &str) -> bool {
+        matches!(self.value(key), Some("" | "y" | "1" | "on"))
+    }
+
+    /// The value of parameter `key`, parsed into a `T`.
+    ///
+    /// Returns `None` if the parameter is missing or does not parse.
+    pub fn parse<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.value(key).and_then(|value| value.parse().ok())
+    }
+}
+
+/// Return a reference to the kernel command line.
+pub fn cmdline() -> &'static InitStateLock<Cmdline> {
+    &KERNEL_CMDLINE
+}
+
+/// Read the command line from the device tree.
+///
+/// Without a device tree, the command line stays empty.
+///
+/// # Safety
+///
+/// - Must be called during kernel init, after the MMU's post-enable init.
+pub unsafe fn init() -> Result<(), KernelError> {
+    KERNEL_CMDLINE.write(|cmdline| copy_bootargs_from_dtb(cmdline))
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check key/value lookups, including flags and repeated keys.
+    #[kernel_test]
+    fn cmdline_lookups_work() {
+        let mut cmdline = Cmdline::new();
+        cmdline.set(
+            b"quiet smp=n cores=2 cores=4 console=serial0,921600"
+                .iter()
+                .copied(),
+        );
+
+        assert!(cmdline.flag("quiet"));
+        assert!(!cmdline.flag("smp"));
+        assert!(!cmdline.flag("missing"));
+        assert_eq!(cmdline.parse::<usize>("cores"), Some(4));
+        assert_eq!(cmdline.parse::<usize>("console"), None);
+        assert_eq!(cmdline.value("console"), Some("serial0,921600"));
+    }
+
+    /// Find a property in a minimal hand-made device tree.
+    #[kernel_test]
+    fn fdt_property_lookup_works() {
+        #[rustfmt::skip]
+        const BLOB: [u8; 104] = [
+            // Header: magic, totalsize, off_dt_struct, off_dt_strings, remaining fields unused.
+            0xd0, 0x0d, 0xfe, 0xed,  0, 0, 0, 104,  0, 0, 0, 40,  0, 0, 0, 92,
+            0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,  0, 0, 0, 0,
+            // Root node with an empty name.
+            0, 0, 0, 1,  0, 0, 0, 0,
+            // Node "chosen".
+            0, 0, 0, 1,  b'c', b'h', b'o', b's',  b'e', b'n', 0, 0,
+            // Property "bootargs" = "quiet\0".
+            0, 0, 0, 3,  0, 0, 0, 6,  0, 0, 0, 0,  b'q', b'u', b'i', b'e',  b't', 0, 0, 0,
+            // End of "chosen", end of root, end of structure.
+            0, 0, 0, 2,  0, 0, 0, 2,  0, 0, 0, 9,
+            // Strings.
+            b'b', b'o', b'o', b't',  b'a', b'r', b'g', b's',  0, 0, 0, 0,
+        ];
+
+        let fdt = Fdt {
+            read_u8: |offset: usize| BLOB[offset],
+            size: BLOB.len(),
+        };
+
+        assert_eq!(fdt.find_property("chosen", "bootargs"), Some((72, 6)));
+        assert_eq!(fdt.find_property("chosen", "stdout-path"), None);
+        assert_eq!(fdt.find_property("memory", "bootargs"), None);
+    }
+}
@@ -4,7 +4,8 @@
 
 //! BSP console facilities.
 
-use crate::{bsp::device_driver, console, cpu, driver};
+use super::memory::map::mmio;
+use crate::{bsp::device_driver, console, cpu, driver, memory::mmu::MMIODescriptor};
 use core::fmt;
 
 //--------------------------------------------------------------------------------------------------
@@ -25,14 +26,18 @@ use core::fmt;
 pub unsafe fn panic_console_out() -> impl fmt::Write {
     use driver::interface::DeviceDriver;
 
-    // If remapping of the driver's MMIO hasn't already happened, we won't be able to print. Just
-    // park the CPU core in this case.
-    let gpio_mmio_start_addr = match super::GPIO.virt_mmio_start_addr() {
-        None => cpu::wait_forever(),
+    // If remapping of the driver's MMIO hasn't already happened, we won't be able to print, unless
+    // the early console mapped the UART. It is used as the firmware left it. Otherwise, just park
+    // the CPU core.
+    let uart_mmio_start_addr = match super::PL011_UART.virt_mmio_start_addr() {
+        None => match crate::earlycon::virt_mmio_start_addr() {
+            None => cpu::wait_forever(),
+            Some(x) => return device_driver::PanicUart::new(x),
+        },
         Some(x) => x,
     };
 
-    let uart_mmio_start_addr = match super::PL011_UART.virt_mmio_start_addr() {
+    let gpio_mmio_start_addr = match super::GPIO.virt_mmio_start_addr() {
         None => cpu::wait_forever(),
         Some(x) => x,
     };
@@ -69,6 +74,24 @@ pub unsafe fn panic_console_out() -> impl fmt::Write {
     panic_uart
 }
 
+/// The MMIO of the UART that the early console writes to.
+pub fn early_console_mmio_descriptor() -> MMIODescriptor {
+    MMIODescriptor::new(mmio::PL011_UART_START, mmio::PL011_UART_SIZE)
+}
+
+/// A writer for the early console.
+///
+/// It does not configure the UART, and relies on the firmware having done so.
+///
+/// # Safety
+///
+/// - `mmio_start_addr` must be the start of the UART's MMIO. The physical address before the MMU is
+///   on, the mapped one after.
+/// - Use only during bring-up, before the console driver is up.
+pub unsafe fn early_console_out(mmio_start_addr: usize) -> impl fmt::Write {
+    device_driver::PanicUart::new(mmio_start_addr)
+}
+
 /// Return a reference to the console.
 pub fn console() -> &'static impl console::interface::All {
     &super::PL011_UART
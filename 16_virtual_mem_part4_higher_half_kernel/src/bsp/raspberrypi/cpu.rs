@@ -4,6 +4,30 @@
 
 //! BSP Processor code.
 
+use super::memory::map;
+use crate::{
+    cpu,
+    error::{KernelError, ResultExt},
+    memory::{self, mmu::MMIODescriptor, Address, Virtual},
+    synchronization::{interface::ReadWriteEx, InitStateLock},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Offsets of the power management watchdog registers.
+const PM_RSTC: usize = 0x1c;
+const PM_WDOG: usize = 0x24;
+
+/// Writes to the power management registers are ignored without the password.
+const PM_PASSWORD: u32 = 0x5a00_0000;
+const PM_RSTC_WRCFG_MASK: u32 = 0x30;
+const PM_RSTC_WRCFG_FULL_RESET: u32 = 0x20;
+
+/// The watchdog timeout in ticks of roughly 16 us.
+const PM_WDOG_RESET_TICKS: u32 = 10;
+
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
 //--------------------------------------------------------------------------------------------------
@@ -12,3 +36,90 @@
 #[no_mangle]
 #[link_section = ".text._start_arguments"]
 pub static BOOT_CORE_ID: u64 = 0;
+
+/// The number of processor cores.
+pub const NUM_CORES: usize = 4;
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+/// Virtual address of the remapped spin table. Populated during kernel init.
+static SPIN_TABLE_VIRT_START: InitStateLock<Option<Address<Virtual>>> = InitStateLock::new(None);
+
+/// Virtual address of the remapped power management registers. Populated during kernel init.
+static PM_VIRT_START: InitStateLock<Option<Address<Virtual>>> = InitStateLock::new(None);
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Map the firmware's spin table into the kernel's address space.
+///
+/// The spin table lives in the first page of DRAM, which is otherwise only reachable through the
+/// boot core's stack mapping. It is mapped as device memory, so that writes reach DRAM directly
+/// and are seen by the secondary cores, which are still running with their caches off.
+///
+/// # Safety
+///
+/// - Must only be called during kernel init.
+pub unsafe fn spin_table_init() -> Result<(), KernelError> {
+    let virt_addr = memory::mmu::kernel_map_mmio(
+        "Spin table",
+        &MMIODescriptor::new(map::SPIN_TABLE_START, map::SPIN_TABLE_SIZE),
+    )
+    .context("Mapping the spin table")?;
+
+    SPIN_TABLE_VIRT_START.write(|start| *start = Some(virt_addr));
+
+    Ok(())
+}
+
+/// Return the virtual address of a core's release address in the spin table.
+pub fn spin_table_release_addr(core_id: usize) -> Result<Address<Virtual>, &'static str> {
+    if core_id >= NUM_CORES {
+        return Err("Invalid core id");
+    }
+
+    SPIN_TABLE_VIRT_START
+        .read(|start| *start)
+        .map(|start| start + core_id * core::mem::size_of::<u64>())
+        .ok_or("Spin table not mapped")
+}
+
+/// Map the power management registers, which hold the watchdog that is used for resetting the
+/// board.
+///
+/// # Safety
+///
+/// - Must only be called during kernel init.
+pub unsafe fn reboot_init() -> Result<(), KernelError> {
+    let virt_addr = memory::mmu::kernel_map_mmio(
+        "PM Watchdog",
+        &MMIODescriptor::new(map::mmio::PM_START, map::mmio::PM_SIZE),
+    )
+    .context("Mapping the watchdog")?;
+
+    PM_VIRT_START.write(|start| *start = Some(virt_addr));
+
+    Ok(())
+}
+
+/// Reset the board by letting the watchdog expire almost immediately.
+///
+/// If the watchdog has not been mapped, the core is parked instead.
+pub fn reboot() -> ! {
+    if let Some(start) = PM_VIRT_START.read(|start| *start) {
+        let rstc = (start.as_usize() + PM_RSTC) as *mut u32;
+        let wdog = (start.as_usize() + PM_WDOG) as *mut u32;
+
+        unsafe {
+            core::ptr::write_volatile(wdog, PM_PASSWORD | PM_WDOG_RESET_TICKS);
+
+            let cfg = core::ptr::read_volatile(rstc) & !PM_RSTC_WRCFG_MASK;
+            core::ptr::write_volatile(rstc, PM_PASSWORD | cfg | PM_RSTC_WRCFG_FULL_RESET);
+        }
+    }
+
+    cpu::wait_forever()
+}
@@ -4,28 +4,33 @@
 
 //! BSP driver support.
 
-use crate::driver;
+use crate::driver::{self, DeviceDriverDescriptor, DEVICE_DRIVERS};
 
 //--------------------------------------------------------------------------------------------------
 // Private Definitions
 //--------------------------------------------------------------------------------------------------
 
 /// Device Driver Manager type.
-struct BSPDriverManager {
-    device_drivers: [&'static (dyn DeviceDriver + Sync); 3],
-}
+struct BSPDriverManager;
 
 //--------------------------------------------------------------------------------------------------
 // Global instances
 //--------------------------------------------------------------------------------------------------
 
-static BSP_DRIVER_MANAGER: BSPDriverManager = BSPDriverManager {
-    device_drivers: [
-        &super::GPIO,
-        &super::PL011_UART,
-        &super::INTERRUPT_CONTROLLER,
-    ],
-};
+static BSP_DRIVER_MANAGER: BSPDriverManager = BSPDriverManager;
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl BSPDriverManager {
+    /// The number of early-print drivers, which come first in the registered drivers.
+    fn num_early_print_device_drivers(&self) -> usize {
+        DEVICE_DRIVERS
+            .as_slice()
+            .partition_point(|descriptor| descriptor.early_print)
+    }
+}
 
 //--------------------------------------------------------------------------------------------------
 // Public Code
@@ -39,23 +44,25 @@ pub fn driver_manager() -> &'static impl driver::interface::DriverManager {
 //------------------------------------------------------------------------------
 // OS Interface Code
 //------------------------------------------------------------------------------
-use driver::interface::DeviceDriver;
 
 impl driver::interface::DriverManager for BSPDriverManager {
-    fn all_device_drivers(&self) -> &[&'static (dyn DeviceDriver + Sync)] {
-        &self.device_drivers[..]
+    fn all_device_drivers(&self) -> &[DeviceDriverDescriptor] {
+        DEVICE_DRIVERS.as_slice()
     }
 
-    fn early_print_device_drivers(&self) -> &[&'static (dyn DeviceDriver + Sync)] {
-        &self.device_drivers[0..=1]
+    fn early_print_device_drivers(&self) -> &[DeviceDriverDescriptor] {
+        &DEVICE_DRIVERS.as_slice()[..self.num_early_print_device_drivers()]
     }
 
-    fn non_early_print_device_drivers(&self) -> &[&'static (dyn DeviceDriver + Sync)] {
-        &self.device_drivers[2..]
+    fn non_early_print_device_drivers(&self) -> &[DeviceDriverDescriptor] {
+        &DEVICE_DRIVERS.as_slice()[self.num_early_print_device_drivers()..]
     }
 
     fn post_early_print_device_driver_init(&self) {
         // Configure PL011Uart's output pins.
         super::GPIO.map_pl011_uart();
+
+        // Configure the LED strip's data pin.
+        super::GPIO.map_pwm0();
     }
 }
@@ -4,7 +4,10 @@
 
 //! BSP asynchronous exception handling.
 
-use crate::{bsp, exception};
+use crate::{bsp, error::KernelError, exception};
+
+#[cfg(feature = "test_build")]
+use crate::{cpu, driver};
 
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
@@ -22,6 +25,17 @@ pub(in crate::bsp) mod irq_map {
     use super::bsp::device_driver::IRQNumber;
 
     pub const PL011_UART: IRQNumber = IRQNumber::new(153);
+
+    /// The virtual timer PPI, which is banked per core.
+    pub const VIRTUAL_TIMER: IRQNumber = IRQNumber::new(27);
+
+    /// The PMU overflow IRQs of cores 0 to 3.
+    pub const PMU: [IRQNumber; 4] = [
+        IRQNumber::new(48),
+        IRQNumber::new(49),
+        IRQNumber::new(50),
+        IRQNumber::new(51),
+    ];
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -34,3 +48,84 @@ pub fn irq_manager() -> &'static impl exception::asynchronous::interface::IRQMan
 > {
     &super::super::INTERRUPT_CONTROLLER
 }
+
+/// Minimal code needed to bring up IRQ handling in QEMU (for testing only).
+#[cfg(feature = "test_build")]
+pub fn qemu_bring_up_irqs() {
+    use driver::interface::DeviceDriver;
+
+    unsafe {
+        super::super::INTERRUPT_CONTROLLER
+            .init()
+            .unwrap_or_else(|_| cpu::qemu_exit_failure());
+    }
+}
+
+/// Route the console's UART IRQ to the cores in `core_mask`.
+///
+/// Input from the console is then handled there, for example to keep it off a core that runs
+/// latency-sensitive work.
+pub fn set_console_irq_affinity(core_mask: usize) -> Result<(), &'static str> {
+    use exception::asynchronous::interface::IRQManager;
+
+    irq_manager().set_affinity(irq_map::PL011_UART, core_mask)
+}
+
+/// Return the mask of the cores that the console's UART IRQ is routed to.
+pub fn console_irq_affinity() -> Result<usize, &'static str> {
+    use exception::asynchronous::interface::IRQManager;
+
+    irq_manager().affinity(irq_map::PL011_UART)
+}
+
+/// Register and enable the PMU overflow IRQ handler.
+#[cfg(feature = "bsp_rpi3")]
+pub fn register_and_enable_pmu_irq_handler(
+    _descriptor: exception::asynchronous::IRQDescriptor,
+) -> Result<(), KernelError> {
+    // The local interrupt controller routes and dispatches the PMU IRQ of each core by itself.
+    Ok(())
+}
+
+/// Register and enable the PMU overflow IRQ handler.
+///
+/// Each core has its own PMU IRQ, which is routed to that core only.
+#[cfg(feature = "bsp_rpi4")]
+pub fn register_and_enable_pmu_irq_handler(
+    descriptor: exception::asynchronous::IRQDescriptor,
+) -> Result<(), KernelError> {
+    use exception::asynchronous::interface::IRQManager;
+
+    for (core_id, &irq) in irq_map::PMU.iter().enumerate() {
+        irq_manager().register_handler(irq, descriptor)?;
+        irq_manager().set_affinity(irq, 1 << core_id)?;
+        irq_manager().enable(irq);
+    }
+
+    Ok(())
+}
+
+/// Register and enable the alarm IRQ handler.
+#[cfg(feature = "bsp_rpi3")]
+pub fn register_and_enable_alarm_irq_handler(
+    _descriptor: exception::asynchronous::IRQDescriptor,
+) -> Result<(), KernelError> {
+    // The local interrupt controller routes and dispatches the virtual timer IRQ of each core by
+    // itself.
+    Ok(())
+}
+
+/// Register and enable the alarm IRQ handler.
+///
+/// The virtual timer IRQ is a PPI. Enabling it only affects the executing core.
+#[cfg(feature = "bsp_rpi4")]
+pub fn register_and_enable_alarm_irq_handler(
+    descriptor: exception::asynchronous::IRQDescriptor,
+) -> Result<(), KernelError> {
+    use exception::asynchronous::interface::IRQManager;
+
+    irq_manager().register_handler(irq_map::VIRTUAL_TIMER, descriptor)?;
+    irq_manager().enable(irq_map::VIRTUAL_TIMER);
+
+    Ok(())
+}
@@ -24,10 +24,16 @@
 //! +---------------------------------------+
 //! |                                       | data_start == code_end_exclusive
 //! | .data                                 |
+//! | .percpu                               |
 //! | .bss                                  |
+//! | .percpu_blocks                        |
 //! |                                       |
 //! +---------------------------------------+
 //! |                                       | data_end_exclusive
+//! | Exception stacks                      |
+//! |                                       |
+//! +---------------------------------------+
+//! |                                       |
 //! |                                       |
 //!
 //!
@@ -45,7 +51,9 @@
 //! +---------------------------------------+
 //! |                                       | data_start == code_end_exclusive
 //! | .data                                 |
+//! | .percpu                               |
 //! | .bss                                  |
+//! | .percpu_blocks                        |
 //! |                                       |
 //! +---------------------------------------+
 //! |                                       |  mmio_remap_start == data_end_exclusive
@@ -63,11 +71,28 @@
 //! |                                       |                                | direction
 //! +---------------------------------------+
 //! |                                       | boot_core_stack_end_exclusive
+//! | Unmapped guard page                   |
+//! |                                       |
+//! +---------------------------------------+
+//! |                                       | exception_stacks_start
+//! | Exception stack of core 0             |
+//! |                                       |
+//! +---------------------------------------+
+//! |                                       |
+//! | Unmapped guard page                   |
+//! |                                       |
+//! +---------------------------------------+
+//! |                                       |
+//! | Exception stack of core 1             |
+//! |                                       |
+//! +---------------------------------------+
+//! |                                       |
+//! | ...                                   |
 //! |                                       |
 pub mod mmu;
 
 use crate::memory::{mmu::PageAddress, Address, Physical, Virtual};
-use core::cell::UnsafeCell;
+use core::{cell::UnsafeCell, ops::Range};
 
 //--------------------------------------------------------------------------------------------------
 // Private Definitions
@@ -81,11 +106,19 @@ extern "Rust" {
     static __data_start: UnsafeCell<()>;
     static __data_end_exclusive: UnsafeCell<()>;
 
+    static __percpu_start: UnsafeCell<()>;
+    static __percpu_end_exclusive: UnsafeCell<()>;
+
+    static __percpu_blocks_start: UnsafeCell<()>;
+    static __percpu_blocks_end_exclusive: UnsafeCell<()>;
+
     static __mmio_remap_start: UnsafeCell<()>;
     static __mmio_remap_end_exclusive: UnsafeCell<()>;
 
     static __boot_core_stack_start: UnsafeCell<()>;
     static __boot_core_stack_end_exclusive: UnsafeCell<()>;
+
+    static __exception_stacks_start: UnsafeCell<()>;
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -105,12 +138,27 @@ pub(super) mod map {
         pub const PERIPHERAL_IC_START: Address<Physical> = Address::new(0x3F00_B200);
         pub const PERIPHERAL_IC_SIZE:  usize             =              0x24;
 
+        pub const MAILBOX_START:       Address<Physical> = Address::new(0x3F00_B880);
+        pub const MAILBOX_SIZE:        usize             =              0x40;
+
+        pub const PM_START:            Address<Physical> = Address::new(0x3F10_0000);
+        pub const PM_SIZE:             usize             =              0x28;
+
+        pub const CM_PWM_START:        Address<Physical> = Address::new(0x3F10_10A0);
+        pub const CM_PWM_SIZE:         usize             =              0x8;
+
+        pub const RNG_START:           Address<Physical> = Address::new(0x3F10_4000);
+        pub const RNG_SIZE:            usize             =              0x14;
+
         pub const GPIO_START:          Address<Physical> = Address::new(0x3F20_0000);
         pub const GPIO_SIZE:           usize             =              0xA0;
 
         pub const PL011_UART_START:    Address<Physical> = Address::new(0x3F20_1000);
         pub const PL011_UART_SIZE:     usize             =              0x48;
 
+        pub const PWM0_START:          Address<Physical> = Address::new(0x3F20_C000);
+        pub const PWM0_SIZE:           usize             =              0x28;
+
         pub const LOCAL_IC_START:      Address<Physical> = Address::new(0x4000_0000);
         pub const LOCAL_IC_SIZE:       usize             =              0x100;
 
@@ -122,14 +170,29 @@ pub(super) mod map {
     pub mod mmio {
         use super::*;
 
+        pub const MAILBOX_START:    Address<Physical> = Address::new(0xFE00_B880);
+        pub const MAILBOX_SIZE:     usize             =              0x40;
+
+        pub const PM_START:         Address<Physical> = Address::new(0xFE10_0000);
+        pub const PM_SIZE:          usize             =              0x28;
+
+        pub const CM_PWM_START:     Address<Physical> = Address::new(0xFE10_10A0);
+        pub const CM_PWM_SIZE:      usize             =              0x8;
+
+        pub const RNG_START:        Address<Physical> = Address::new(0xFE10_4000);
+        pub const RNG_SIZE:         usize             =              0x28;
+
         pub const GPIO_START:       Address<Physical> = Address::new(0xFE20_0000);
         pub const GPIO_SIZE:        usize             =              0xA0;
 
         pub const PL011_UART_START: Address<Physical> = Address::new(0xFE20_1000);
         pub const PL011_UART_SIZE:  usize             =              0x48;
 
+        pub const PWM0_START:       Address<Physical> = Address::new(0xFE20_C000);
+        pub const PWM0_SIZE:        usize             =              0x28;
+
         pub const GICD_START:       Address<Physical> = Address::new(0xFF84_1000);
-        pub const GICD_SIZE:        usize             =              0x824;
+        pub const GICD_SIZE:        usize             =              0xF04;
 
         pub const GICC_START:       Address<Physical> = Address::new(0xFF84_2000);
         pub const GICC_SIZE:        usize             =              0x14;
@@ -137,6 +200,12 @@ pub(super) mod map {
         pub const END:              Address<Physical> = Address::new(0xFF85_0000);
     }
 
+    /// The spin table in which the firmware parks the secondary cores.
+    ///
+    /// Holds one 64 bit release address per core, starting with core 0.
+    pub const SPIN_TABLE_START: Address<Physical> = Address::new(0xD8);
+    pub const SPIN_TABLE_SIZE:  usize             =              0x20;
+
     pub const END: Address<Physical> = mmio::END;
 }
 
@@ -214,6 +283,24 @@ fn boot_core_stack_size() -> usize {
     }
 }
 
+/// Start page address of the given core's exception stack.
+///
+/// The stacks are one page each, and every stack is preceded by an unmapped guard page.
+#[inline(always)]
+fn virt_exception_stack_start(core_id: usize) -> PageAddress<Virtual> {
+    assert!(core_id < super::cpu::NUM_CORES);
+
+    let start = unsafe { __exception_stacks_start.get() as usize };
+
+    PageAddress::from(start + core_id * 2 * exception_stack_size())
+}
+
+/// Size of a core's exception stack.
+#[inline(always)]
+fn exception_stack_size() -> usize {
+    mmu::KernelGranule::SIZE
+}
+
 //--------------------------------------------------------------------------------------------------
 // Public Code
 //--------------------------------------------------------------------------------------------------
@@ -223,3 +310,59 @@ fn boot_core_stack_size() -> usize {
 pub fn phys_addr_space_end_exclusive_addr() -> PageAddress<Physical> {
     PageAddress::from(map::END)
 }
+
+/// Start address of the per-core data template.
+///
+/// # Safety
+///
+/// - Value is provided by the linker script and must be trusted as-is.
+#[inline(always)]
+pub fn virt_percpu_template_start() -> Address<Virtual> {
+    Address::new(unsafe { __percpu_start.get() as usize })
+}
+
+/// Size of the per-core data template.
+///
+/// # Safety
+///
+/// - Value is provided by the linker script and must be trusted as-is.
+#[inline(always)]
+pub fn percpu_template_size() -> usize {
+    unsafe { (__percpu_end_exclusive.get() as usize) - (__percpu_start.get() as usize) }
+}
+
+/// Start address of the per-core copies of the template.
+///
+/// # Safety
+///
+/// - Value is provided by the linker script and must be trusted as-is.
+#[inline(always)]
+pub fn virt_percpu_blocks_start() -> Address<Virtual> {
+    Address::new(unsafe { __percpu_blocks_start.get() as usize })
+}
+
+/// Size of the per-core copies of the template.
+///
+/// # Safety
+///
+/// - Value is provided by the linker script and must be trusted as-is.
+#[inline(always)]
+pub fn percpu_blocks_size() -> usize {
+    unsafe {
+        (__percpu_blocks_end_exclusive.get() as usize) - (__percpu_blocks_start.get() as usize)
+    }
+}
+
+/// Virtual address range of the kernel's code segment.
+#[inline(always)]
+pub fn virt_code_range() -> Range<Address<Virtual>> {
+    let start = virt_code_start().into_inner();
+
+    start..(start + code_size())
+}
+
+/// Exclusive end address of the given core's exception stack.
+#[inline(always)]
+pub fn virt_exception_stack_end_exclusive_addr(core_id: usize) -> Address<Virtual> {
+    virt_exception_stack_start(core_id).into_inner() + exception_stack_size()
+}
@@ -5,6 +5,7 @@
 //! BSP Memory Management Unit.
 
 use crate::{
+    bsp,
     memory::{
         mmu::{
             self as generic_mmu, AddressSpace, AssociatedTranslationTable, AttributeFields,
@@ -60,6 +61,10 @@ static PHYS_KERNEL_TABLES_BASE_ADDR: u64 = 0xCCCCAAAAFFFFEEEE;
 // Private Code
 //--------------------------------------------------------------------------------------------------
 
+// The page-based memory types are built for a fixed granule, see `kernel-core`.
+#[allow(clippy::assertions_on_constants)]
+const _: () = assert!(KernelGranule::SIZE == generic_mmu::PageGranule::SIZE);
+
 /// This is a hack for retrieving the value for the kernel's virtual address space size as a
 /// constant from a common place, since it is needed as a compile-time/link-time constant in both,
 /// the linker script and the Rust sources.
@@ -110,6 +115,16 @@ fn virt_boot_core_stack_region() -> MemoryRegion<Virtual> {
     MemoryRegion::new(start_page_addr, end_exclusive_page_addr)
 }
 
+/// The exception stack pages of the given core.
+fn virt_exception_stack_region(core_id: usize) -> MemoryRegion<Virtual> {
+    let num_pages = size_to_num_pages(super::exception_stack_size());
+
+    let start_page_addr = super::virt_exception_stack_start(core_id);
+    let end_exclusive_page_addr = start_page_addr.checked_offset(num_pages as isize).unwrap();
+
+    MemoryRegion::new(start_page_addr, end_exclusive_page_addr)
+}
+
 // There is no reason to expect the following conversions to fail, since they were generated offline
 // by the `translation table tool`. If it doesn't work, a panic due to the unwraps is justified.
 fn kernel_virt_to_phys_region(virt_region: MemoryRegion<Virtual>) -> MemoryRegion<Physical> {
@@ -176,4 +191,14 @@ pub fn kernel_add_mapping_records_for_precomputed() {
         &kernel_virt_to_phys_region(virt_boot_core_stack_region),
         &kernel_page_attributes(virt_boot_core_stack_region.start_page_addr()),
     );
+
+    for i in 0..bsp::cpu::NUM_CORES {
+        let virt_exception_stack_region = virt_exception_stack_region(i);
+        generic_mmu::kernel_add_mapping_record(
+            "Kernel exception stack",
+            &virt_exception_stack_region,
+            &kernel_virt_to_phys_region(virt_exception_stack_region),
+            &kernel_page_attributes(virt_exception_stack_region.start_page_addr()),
+        );
+    }
 }
@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Compile-time kernel configuration.
+//!
+//! The build selects the configuration with cargo features, which the Makefile derives from its
+//! variables, for example `BSP`, `LOG_LEVEL`, `LOCKDEP`, `BTI` and `LSE`. This module turns
+//! the features into typed constants, so that code can branch on a choice with a plain `if` or
+//! `match` instead of repeating `cfg` attributes.
+//!
+//! Feature combinations that cannot produce a working kernel are rejected at compile time.
+
+use crate::info;
+use core::fmt;
+
+//--------------------------------------------------------------------------------------------------
+// Compile-time Validation
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(not(any(feature = "bsp_rpi3", feature = "bsp_rpi4")))]
+compile_error!("No board selected. Enable exactly one of the `bsp_*` features.");
+
+#[cfg(all(feature = "bsp_rpi3", feature = "bsp_rpi4"))]
+compile_error!("More than one board selected. Enable exactly one of the `bsp_*` features.");
+
+#[cfg(all(feature = "bti", not(target_arch = "aarch64")))]
+compile_error!("The `bti` feature needs an AArch64 target.");
+
+#[cfg(all(feature = "lse", not(target_feature = "lse")))]
+compile_error!("The `lse` feature needs `-C target-feature=+lse`, which `LSE=y` adds.");
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The boards the kernel can be built for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Board {
+    /// Raspberry Pi 3.
+    RaspberryPi3,
+
+    /// Raspberry Pi 4.
+    RaspberryPi4,
+}
+
+/// The devices the console can be attached to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConsoleDevice {
+    /// The PL011 UART on GPIO pins 14 and 15.
+    Pl011Uart,
+}
+
+/// The time sources the kernel's timekeeping can use.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimerBackend {
+    /// The architectural timer of each core.
+    ArmGenericTimer,
+}
+
+/// The most verbose messages that are printed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Only warnings.
+    Warn,
+
+    /// Warnings and infos.
+    Info,
+}
+
+/// The board the kernel is built for.
+#[cfg(feature = "bsp_rpi3")]
+pub const BOARD: Board = Board::RaspberryPi3;
+
+/// The board the kernel is built for.
+#[cfg(feature = "bsp_rpi4")]
+pub const BOARD: Board = Board::RaspberryPi4;
+
+/// The console device.
+pub const CONSOLE: ConsoleDevice = ConsoleDevice::Pl011Uart;
+
+/// The time source.
+pub const TIMER: TimerBackend = TimerBackend::ArmGenericTimer;
+
+/// The most verbose messages that are printed.
+#[cfg(not(feature = "log_level_warn"))]
+pub const LOG_LEVEL: LogLevel = LogLevel::Info;
+
+/// The most verbose messages that are printed.
+#[cfg(feature = "log_level_warn")]
+pub const LOG_LEVEL: LogLevel = LogLevel::Warn;
+
+/// Whether this is a test kernel, which exits QEMU instead of halting.
+pub const TEST_BUILD: bool = cfg!(feature = "test_build");
+
+/// Whether lock dependency validation is enabled.
+pub const LOCKDEP: bool = cfg!(feature = "lockdep");
+
+/// Whether the kernel is built with branch target identification landing pads.
+pub const BTI: bool = cfg!(feature = "bti");
+
+/// Whether boot errors are printed through the UART before its driver is up.
+pub const EARLYCON: bool = cfg!(feature = "earlycon");
+
+/// Whether the drivers' MMIO register accesses are recorded in the trace buffer.
+pub const MMIO_TRACE: bool = cfg!(feature = "mmio_trace");
+
+/// Whether atomics use the Large System Extensions instructions.
+pub const LSE: bool = cfg!(feature = "lse");
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Board {
+    /// The human-readable name.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Board::RaspberryPi3 => "Raspberry Pi 3",
+            Board::RaspberryPi4 => "Raspberry Pi 4",
+        }
+    }
+}
+
+impl fmt::Display for ConsoleDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsoleDevice::Pl011Uart => write!(f, "PL011 UART"),
+        }
+    }
+}
+
+impl fmt::Display for TimerBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimerBackend::ArmGenericTimer => write!(f, "ARM generic timer"),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Warn => write!(f, "warn"),
+            LogLevel::Info => write!(f, "info"),
+        }
+    }
+}
+
+/// Print the build configuration.
+pub fn print() {
+    let on_off = |x| if x { "on" } else { "off" };
+
+    info!("      Board: {}", BOARD.name());
+    info!("      Console: {}", CONSOLE);
+    info!("      Timer: {}", TIMER);
+    info!("      Log level: {}", LOG_LEVEL);
+    info!("      Lockdep: {}", on_off(LOCKDEP));
+    info!("      BTI: {}", on_off(BTI));
+    info!("      Early console: {}", on_off(EARLYCON));
+    info!("      MMIO tracing: {}", on_off(MMIO_TRACE));
+    info!("      LSE atomics: {}", on_off(LSE));
+}
@@ -8,6 +8,18 @@
 // Public Definitions
 //--------------------------------------------------------------------------------------------------
 
+/// Receiving this character (`CTRL + R`) on the console reboots the board, see
+/// [crate::cpu::reboot].
+pub const RELOAD_REQUEST: char = '\u{12}';
+
+/// Receiving this character (`CTRL + T`) on the console prints the kernel's health report, see
+/// [crate::status::print].
+pub const STATUS_REQUEST: char = '\u{14}';
+
+/// Receiving this character (`CTRL + E`) on the console prints the trace records, see
+/// [crate::trace::dump_and_restart].
+pub const TRACE_REQUEST: char = '\u{5}';
+
 /// Console interfaces.
 pub mod interface {
     use core::fmt;
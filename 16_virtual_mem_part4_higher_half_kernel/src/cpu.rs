@@ -10,12 +10,39 @@ mod arch_cpu;
 
 mod boot;
 
+use crate::{bsp, console};
+
+pub mod cache;
+pub mod features;
+pub mod fpsimd;
+pub mod idle;
+pub mod mitigations;
+pub mod pac;
+pub mod percpu;
 pub mod smp;
 
 //--------------------------------------------------------------------------------------------------
 // Architectural Public Reexports
 //--------------------------------------------------------------------------------------------------
-pub use arch_cpu::{nop, wait_forever};
+pub use arch_cpu::{nop, send_event, wait_for_event, wait_for_interrupt, wait_forever};
+pub use boot::{boot_dtb_phys_addr, boot_entry_ticks};
+pub use smp::{offline, online};
 
 #[cfg(feature = "test_build")]
 pub use arch_cpu::{qemu_exit_failure, qemu_exit_success};
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Reset the board.
+///
+/// The firmware boots again whatever is installed on the SD card. With the chainloader installed,
+/// this requests a fresh kernel from the host, which saves a power cycle between iterations.
+pub fn reboot() -> ! {
+    use console::interface::Write;
+
+    // The reset is quick, so let pending output leave the board first.
+    bsp::console::console().flush();
+    bsp::cpu::reboot()
+}
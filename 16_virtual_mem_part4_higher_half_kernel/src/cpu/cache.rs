@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Cache topology and maintenance.
+//!
+//! [caches()] describes the caches of the executing core as the hardware reports them. The
+//! maintenance helpers work on address ranges, and step through them with the line sizes that the
+//! hardware reports, so that they are correct on every core type.
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/cpu/cache.rs"]
+mod arch_cache;
+
+use crate::info;
+use core::fmt;
+
+//--------------------------------------------------------------------------------------------------
+// Architectural Public Reexports
+//--------------------------------------------------------------------------------------------------
+pub use arch_cache::{
+    clean_dcache_range, clean_invalidate_dcache_range, dcache_line_size, icache_line_size,
+    sync_code,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The maximum number of caches that [caches()] describes.
+pub const MAX_CACHES: usize = 8;
+
+/// What a cache holds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CacheType {
+    /// Instructions only.
+    Instruction,
+
+    /// Data only.
+    Data,
+
+    /// Instructions and data.
+    Unified,
+}
+
+/// The description of a cache.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Cache {
+    /// The level, counting from 1 for the innermost caches.
+    pub level: u8,
+
+    /// What the cache holds.
+    pub kind: CacheType,
+
+    /// The line size in bytes.
+    pub line_size: usize,
+
+    /// The associativity.
+    pub ways: usize,
+
+    /// The number of sets.
+    pub sets: usize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Cache {
+    /// The size in bytes.
+    pub fn size(&self) -> usize {
+        self.line_size * self.ways * self.sets
+    }
+}
+
+impl fmt::Display for CacheType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheType::Instruction => write!(f, "instruction"),
+            CacheType::Data => write!(f, "data"),
+            CacheType::Unified => write!(f, "unified"),
+        }
+    }
+}
+
+/// Describe the caches of the executing core, innermost first.
+pub fn caches() -> impl Iterator<Item = Cache> {
+    arch_cache::read_caches().into_iter().flatten()
+}
+
+/// Print the caches of the executing core.
+pub fn print() {
+    for cache in caches() {
+        info!(
+            "      L{} {:<11} {:>5} KiB, {:>2}-way, {} byte lines",
+            cache.level,
+            cache.kind,
+            cache.size() / 1024,
+            cache.ways,
+            cache.line_size
+        );
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check that the reported caches are plausible.
+    #[kernel_test]
+    fn caches_are_described() {
+        let mut level = 1;
+
+        for cache in caches() {
+            assert!(cache.level >= level);
+            assert!(cache.line_size.is_power_of_two());
+            assert!(cache.line_size >= dcache_line_size().min(icache_line_size()));
+            level = cache.level;
+        }
+    }
+
+    /// Check that range maintenance covers unaligned ranges without faulting.
+    #[kernel_test]
+    fn dcache_range_maintenance_works() {
+        let mut buf = [0u8; 300];
+
+        buf[1] = 1;
+        unsafe {
+            clean_dcache_range(buf.as_ptr() as usize + 1, buf.len() - 2);
+            clean_invalidate_dcache_range(buf.as_ptr() as usize + 1, buf.len() - 2);
+        }
+
+        assert_eq!(unsafe { core::ptr::read_volatile(&buf[1]) }, 1);
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! CPU identification and features.
+//!
+//! [features()] decodes the identification registers of the executing core. Subsystems that can
+//! only use a feature when the hardware implements it check the report instead of reading the
+//! registers themselves.
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/cpu/features.rs"]
+mod arch_features;
+
+use crate::info;
+use core::fmt;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The version of the performance monitors extension.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PmuVersion {
+    /// Not implemented.
+    None,
+
+    /// PMUv3.
+    V3,
+
+    /// PMUv3 for Armv8.1.
+    V3p1,
+
+    /// A later version, with its raw ID register value.
+    Later(u8),
+
+    /// An implementation defined PMU, which is not PMUv3 compatible.
+    ImplementationDefined,
+}
+
+/// The identification and features of a core.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Features {
+    /// The implementer code, for example `0x41` for Arm.
+    pub implementer: u8,
+
+    /// The implementer-specific part number.
+    pub part_num: u16,
+
+    /// The major revision.
+    pub variant: u8,
+
+    /// The minor revision.
+    pub revision: u8,
+
+    /// The supported physical address width.
+    pub pa_bits: u8,
+
+    /// The 4 KiB translation granule is supported.
+    pub granule_4k: bool,
+
+    /// The 16 KiB translation granule is supported.
+    pub granule_16k: bool,
+
+    /// The 64 KiB translation granule is supported.
+    pub granule_64k: bool,
+
+    /// Privileged Access Never.
+    pub pan: bool,
+
+    /// EL0 accesses to the kernel's half of the address space fault in constant time, without a
+    /// translation table walk.
+    pub e0pd: bool,
+
+    /// Large System Extensions atomics.
+    pub lse: bool,
+
+    /// Pointer authentication of instruction addresses.
+    pub pac: bool,
+
+    /// Branch target identification.
+    pub bti: bool,
+
+    /// Memory tagging with tag checks.
+    pub mte: bool,
+
+    /// Performance monitors.
+    pub pmu: PmuVersion,
+
+    /// Branch targets trained in one context can not steer speculation in another.
+    pub csv2: bool,
+
+    /// Speculative loads can not return data that a permission fault denies.
+    pub csv3: bool,
+
+    /// The speculative store bypass can be disabled with `PSTATE.SSBS`.
+    pub ssbs: bool,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Features {
+    /// The name of the core type, if it is known.
+    pub fn core_name(&self) -> Option<&'static str> {
+        let name = match (self.implementer, self.part_num) {
+            (0x41, 0xd03) => "Cortex-A53",
+            (0x41, 0xd04) => "Cortex-A35",
+            (0x41, 0xd05) => "Cortex-A55",
+            (0x41, 0xd07) => "Cortex-A57",
+            (0x41, 0xd08) => "Cortex-A72",
+            (0x41, 0xd0b) => "Cortex-A76",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+}
+
+impl fmt::Display for PmuVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PmuVersion::None => write!(f, "none"),
+            PmuVersion::V3 => write!(f, "PMUv3"),
+            PmuVersion::V3p1 => write!(f, "PMUv3.1"),
+            PmuVersion::Later(x) => write!(f, "PMUv3 (ID {:#x})", x),
+            PmuVersion::ImplementationDefined => write!(f, "implementation defined"),
+        }
+    }
+}
+
+/// The features of the executing core.
+pub fn features() -> Features {
+    arch_features::read()
+}
+
+/// Print the features of the executing core.
+pub fn print() {
+    let f = features();
+
+    match f.core_name() {
+        Some(name) => info!("      Core: {} r{}p{}", name, f.variant, f.revision),
+        None => info!(
+            "      Core: implementer {:#04x}, part {:#05x}, r{}p{}",
+            f.implementer, f.part_num, f.variant, f.revision
+        ),
+    }
+
+    let yes_no = |x| if x { "yes" } else { "no" };
+    info!("      Physical address bits: {}", f.pa_bits);
+    info!(
+        "      Translation granules: 4 KiB: {}, 16 KiB: {}, 64 KiB: {}",
+        yes_no(f.granule_4k),
+        yes_no(f.granule_16k),
+        yes_no(f.granule_64k)
+    );
+    info!("      PAN: {}", yes_no(f.pan));
+    info!("      E0PD: {}", yes_no(f.e0pd));
+    info!("      LSE atomics: {}", yes_no(f.lse));
+    info!("      Pointer authentication: {}", yes_no(f.pac));
+    info!("      Branch target identification: {}", yes_no(f.bti));
+    info!("      Memory tagging: {}", yes_no(f.mte));
+    info!("      PMU: {}", f.pmu);
+    info!("      CSV2: {}", yes_no(f.csv2));
+    info!("      CSV3: {}", yes_no(f.csv3));
+    info!("      SSBS: {}", yes_no(f.ssbs));
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check that the report matches what the kernel already relies on.
+    #[kernel_test]
+    fn features_match_running_kernel() {
+        let f = features();
+
+        // The kernel runs with 64 KiB pages, which the MMU setup checks before enabling.
+        assert!(f.granule_64k);
+        assert!(f.pa_bits >= 32);
+        assert_eq!(f, features());
+    }
+}
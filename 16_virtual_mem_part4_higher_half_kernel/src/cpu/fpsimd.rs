@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! FP/SIMD register state.
+//!
+//! The kernel is built for a soft-float target and never touches the FP/SIMD registers itself. Code
+//! that does, like future user tasks or hand-written assembly, runs in a [Context] that holds its
+//! copy of the registers:
+//!
+//! - FP/SIMD access starts out disabled on every core, so that the first FP/SIMD instruction traps.
+//!   The trap handler loads the registers of the executing context, enables access and retries the
+//!   instruction.
+//! - [switch_to()] saves the registers only if the outgoing context used them since it was switched
+//!   in, and disables access again. Contexts that never use FP/SIMD neither pay for saving nor for
+//!   restoring.
+//!
+//! Because the registers are saved when switching out, a context can be switched in on any core.
+//! Each core starts out in its [initial_context()].
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/cpu/fpsimd.rs"]
+mod arch_fpsimd;
+
+use crate::{bsp, cpu, exception, per_cpu};
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The FP/SIMD registers of an execution context.
+pub struct Context {
+    state: UnsafeCell<arch_fpsimd::State>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[allow(clippy::declare_interior_mutable_const)]
+const CONTEXT_INIT: Context = Context::new();
+
+/// The contexts that the cores boot into.
+static INITIAL_CONTEXTS: [Context; bsp::cpu::NUM_CORES] = [CONTEXT_INIT; bsp::cpu::NUM_CORES];
+
+per_cpu! {
+    /// The address of the executing context. Zero for the core's initial context.
+    static CURRENT: AtomicUsize = AtomicUsize::new(0);
+}
+
+per_cpu! {
+    /// Whether the registers hold the state of the executing context, and access is enabled.
+    static LOADED: AtomicBool = AtomicBool::new(false);
+}
+
+/// The number of traps that loaded a context.
+static NUM_TRAPS: AtomicUsize = AtomicUsize::new(0);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// A context is only accessed by the core that executes it, with IRQs masked.
+unsafe impl Sync for Context {}
+
+fn current() -> &'static Context {
+    match CURRENT.local().load(Ordering::Relaxed) {
+        0 => initial_context(),
+        addr => unsafe { &*(addr as *const Context) },
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Context {
+    /// Create an instance with all registers zeroed.
+    pub const fn new() -> Self {
+        Self {
+            state: UnsafeCell::new(arch_fpsimd::State::new()),
+        }
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Let the first FP/SIMD instruction on the executing core trap.
+pub fn init() {
+    arch_fpsimd::disable_access();
+    LOADED.local().store(false, Ordering::Relaxed);
+}
+
+/// The context that the executing core booted into.
+pub fn initial_context() -> &'static Context {
+    &INITIAL_CONTEXTS[cpu::smp::core_id::<usize>()]
+}
+
+/// Make `next` the executing context.
+///
+/// # Safety
+///
+/// - `next` must stay valid until a later call switches away from it.
+/// - `next` must not be executing on another core.
+pub unsafe fn switch_to(next: &Context) {
+    exception::asynchronous::exec_with_irq_masked(|| {
+        if LOADED.local().swap(false, Ordering::Relaxed) {
+            arch_fpsimd::save(current().state.get());
+            arch_fpsimd::disable_access();
+        }
+
+        let addr = if core::ptr::eq(next, initial_context()) {
+            0
+        } else {
+            next as *const Context as usize
+        };
+        CURRENT.local().store(addr, Ordering::Relaxed);
+    })
+}
+
+/// Handle a trapped FP/SIMD instruction. It is retried once the handler returns.
+pub fn handle_trap() {
+    arch_fpsimd::enable_access();
+    unsafe { arch_fpsimd::restore(current().state.get()) };
+
+    LOADED.local().store(true, Ordering::Relaxed);
+    NUM_TRAPS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The number of FP/SIMD first-use traps on all cores since boot.
+pub fn num_traps() -> usize {
+    NUM_TRAPS.load(Ordering::Relaxed)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arch_fpsimd::{read_v0, write_v0};
+    use test_macros::kernel_test;
+
+    /// Check that the first use traps, and that switching keeps the contexts' registers apart.
+    #[kernel_test]
+    fn fpsimd_contexts_are_switched_lazily() {
+        let a = Context::new();
+        let b = Context::new();
+
+        unsafe { switch_to(&a) };
+        let traps = num_traps();
+        write_v0(1);
+        write_v0(2);
+        assert_eq!(num_traps(), traps + 1);
+
+        unsafe { switch_to(&b) };
+        assert_eq!(read_v0(), 0);
+        write_v0(3);
+
+        unsafe { switch_to(&a) };
+        assert_eq!(read_v0(), 2);
+
+        unsafe { switch_to(&b) };
+        assert_eq!(read_v0(), 3);
+        assert_eq!(num_traps(), traps + 4);
+
+        unsafe { switch_to(initial_context()) };
+    }
+}
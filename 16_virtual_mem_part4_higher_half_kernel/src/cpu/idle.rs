@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Idle loop.
+//!
+//! Cores that have nothing left to do enter [idle_loop()]. It sleeps with WFI, which keeps the
+//! core in a low-power state until an interrupt is pending.
+//!
+//! IRQs are masked while the core sleeps. A pending IRQ still ends WFI, but it is only taken after
+//! the idle loop accounted for the sleep. That way, the residency does not include the time spent
+//! in IRQ handlers, and no IRQ can slip in between deciding to sleep and sleeping.
+//!
+//! Each core counts how long it slept and how often it woke up. [print()] shows the numbers, as
+//! part of the health report of [crate::status].
+
+use crate::{bsp, cpu, exception, info, per_cpu, time};
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Idle statistics of a core.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IdleStats {
+    /// The total time spent sleeping in the idle loop.
+    pub residency: Duration,
+
+    /// The number of times that the core woke up from the idle loop.
+    pub wakeups: u64,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+per_cpu! {
+    /// The timer ticks that a core spent sleeping in the idle loop.
+    static IDLE_TICKS: AtomicU64 = AtomicU64::new(0);
+}
+
+per_cpu! {
+    /// The number of times that a core woke up from the idle loop.
+    static WAKEUPS: AtomicU64 = AtomicU64::new(0);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Sleep until the next interrupt, and account for it.
+///
+/// IRQs must be unmasked. They are handled before returning.
+pub fn idle() {
+    unsafe { exception::asynchronous::local_irq_mask() };
+
+    let start = time::Instant::now();
+    cpu::wait_for_interrupt();
+    let ticks = time::Instant::now().ticks_since(start);
+
+    IDLE_TICKS.local().fetch_add(ticks, Ordering::Relaxed);
+    WAKEUPS.local().fetch_add(1, Ordering::Relaxed);
+
+    // Takes the IRQ that ended the sleep.
+    unsafe { exception::asynchronous::local_irq_unmask() };
+}
+
+/// Run [idle()] forever.
+///
+/// IRQs must be unmasked.
+pub fn idle_loop() -> ! {
+    loop {
+        idle()
+    }
+}
+
+/// The idle statistics of the given core.
+pub fn stats(core_id: usize) -> IdleStats {
+    let ticks = IDLE_TICKS.remote(core_id).load(Ordering::Relaxed);
+
+    IdleStats {
+        residency: time::ticks_to_duration(ticks),
+        wakeups: WAKEUPS.remote(core_id).load(Ordering::Relaxed),
+    }
+}
+
+/// Print the idle statistics of all cores.
+///
+/// The share of time spent idle is relative to the uptime, which includes the boot.
+pub fn print() {
+    use time::interface::TimeManager;
+
+    let uptime = time::time_manager().uptime();
+
+    info!("Idle statistics:");
+    for core_id in 0..bsp::cpu::NUM_CORES {
+        let stats = stats(core_id);
+        let permille = (stats.residency.as_micros() * 1000)
+            .checked_div(uptime.as_micros())
+            .unwrap_or(0);
+
+        info!(
+            "      Core {}: {:>3}.{}% idle, {} wakeups{}",
+            core_id,
+            permille / 10,
+            permille % 10,
+            stats.wakeups,
+            if cpu::smp::is_online(core_id) {
+                ""
+            } else {
+                " (offline)"
+            }
+        );
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Sleeping until the next alarm must count as one wakeup and as residency.
+    ///
+    /// This replaces the test runner's timeout for the rest of the test.
+    #[kernel_test]
+    fn idle_is_accounted() {
+        let core_id = cpu::smp::core_id();
+        let before = stats(core_id);
+
+        time::alarm::set(Duration::from_millis(10), || ()).unwrap();
+        idle();
+
+        let after = stats(core_id);
+        assert!(after.wakeups > before.wakeups);
+        assert!(after.residency > before.residency);
+    }
+}
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Speculative execution vulnerabilities.
+//!
+//! [report()] tells whether the executing core is affected by the known Arm speculation
+//! vulnerabilities. The ID registers tell it for newer cores, and Arm's published list of affected
+//! cores for older ones, like the Cortex-A53 and Cortex-A72 of the Raspberry Pis.
+//!
+//! The mitigations all guard the boundary between user space and the kernel: barriers on kernel
+//! entry, predictor invalidation on context switch, or a firmware call that disables the
+//! speculative store bypass. The kernel does not run any EL0 code yet, so there is no boundary to
+//! guard, and none of them is applied.
+
+use crate::{cpu::features, info};
+use core::fmt;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Whether a core is affected by a vulnerability.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The ID registers report that the core is not affected.
+    NotAffectedArchitecturally,
+
+    /// The core is known not to be affected.
+    NotAffected,
+
+    /// The core is known to be affected.
+    Affected,
+
+    /// The core is neither known nor does it report its status.
+    Unknown,
+}
+
+/// The status of the executing core for each known vulnerability.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// Spectre variant 2, branch target injection.
+    pub spectre_v2: Status,
+
+    /// Spectre variant 3a, rogue system register read.
+    pub spectre_v3a: Status,
+
+    /// Spectre variant 4, speculative store bypass.
+    pub spectre_v4: Status,
+
+    /// Meltdown, rogue data cache load.
+    pub meltdown: Status,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Arm's list of affected cores, for the cores that the kernel knows.
+fn known_core_report(f: &features::Features) -> Option<Report> {
+    use Status::*;
+
+    let report = match f.core_name()? {
+        "Cortex-A35" | "Cortex-A53" | "Cortex-A55" => Report {
+            spectre_v2: NotAffected,
+            spectre_v3a: NotAffected,
+            spectre_v4: NotAffected,
+            meltdown: NotAffected,
+        },
+        "Cortex-A57" | "Cortex-A72" => Report {
+            spectre_v2: Affected,
+            spectre_v3a: Affected,
+            spectre_v4: Affected,
+            meltdown: NotAffected,
+        },
+        "Cortex-A76" => Report {
+            spectre_v2: Affected,
+            spectre_v3a: NotAffected,
+            spectre_v4: Affected,
+            meltdown: NotAffected,
+        },
+        _ => return None,
+    };
+
+    Some(report)
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Status::NotAffectedArchitecturally => "not affected (ID registers)",
+            Status::NotAffected => "not affected (known core)",
+            Status::Affected => "affected, not mitigated (no EL0 code)",
+            Status::Unknown => "unknown",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// The vulnerability status of the executing core.
+pub fn report() -> Report {
+    let f = features::features();
+    let mut report = known_core_report(&f).unwrap_or(Report {
+        spectre_v2: Status::Unknown,
+        spectre_v3a: Status::Unknown,
+        spectre_v4: Status::Unknown,
+        meltdown: Status::Unknown,
+    });
+
+    // What the core reports about itself takes precedence.
+    if f.csv2 {
+        report.spectre_v2 = Status::NotAffectedArchitecturally;
+    }
+    if f.csv3 {
+        report.meltdown = Status::NotAffectedArchitecturally;
+    }
+
+    report
+}
+
+/// Print the vulnerability status of the executing core.
+pub fn print() {
+    let report = report();
+
+    info!("      Spectre v2: {}", report.spectre_v2);
+    info!("      Spectre v3a: {}", report.spectre_v3a);
+    info!("      Spectre v4: {}", report.spectre_v4);
+    info!("      Meltdown: {}", report.meltdown);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// QEMU emulates the Raspberry Pi's known cores, so the report must not be unknown.
+    #[kernel_test]
+    fn report_knows_emulated_core() {
+        let report = report();
+
+        assert_ne!(report.spectre_v2, Status::Unknown);
+        assert_ne!(report.meltdown, Status::Unknown);
+    }
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Pointer authentication.
+//!
+//! The kernel is built with `-Z branch-protection=pac-ret`. Functions that save the link register
+//! sign it with a per-core key in their prologue, and authenticate it before returning. A return
+//! address that was overwritten on the stack fails authentication, and the return faults instead of
+//! jumping to the attacker's target.
+//!
+//! The signing instructions are in the hint space, so the same binary runs on cores without
+//! pointer authentication, like the Cortex-A53 of the Raspberry Pi 3 and the Cortex-A72 of the
+//! Raspberry Pi 4. There, they execute as no-ops.
+//!
+//! Code that reads return addresses from the stack, like the crash dump's backtrace, must [strip()]
+//! the authentication code before using them.
+//!
+//! Keys are per core. They must be set before the first signed function runs, which is why the
+//! boot code sets them up before the kernel enters EL1. Once there are tasks with their own stacks,
+//! their keys can be switched together with the stacks.
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/cpu/pac.rs"]
+mod arch_pac;
+
+//--------------------------------------------------------------------------------------------------
+// Architectural Public Reexports
+//--------------------------------------------------------------------------------------------------
+pub use arch_pac::{boot_enable, is_enabled, strip};
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arch_pac::{authenticate, sign};
+    use test_macros::kernel_test;
+
+    fn target() {}
+
+    /// Check that signed addresses authenticate and strip back to the original.
+    #[kernel_test]
+    fn signed_addresses_authenticate() {
+        let addr = target as usize;
+        let signed = sign(addr, 0x1234);
+
+        assert_eq!(authenticate(signed, 0x1234), addr);
+        assert_eq!(strip(signed), addr);
+
+        if !is_enabled() {
+            assert_eq!(signed, addr);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Per-core data.
+//!
+//! Variables declared with [per_cpu!](crate::per_cpu) are placed in the linker section `.percpu`.
+//! This section is never accessed directly. Instead, it serves as a template that the boot core
+//! copies once for every core during kernel init. Each core then stores the offset from the
+//! template to its copy in a core-private register, which the accessors add to a variable's
+//! template address.
+//!
+//! ```
+//! +-------------+          +-------------+-------------+-------------+-------------+
+//! |   .percpu   |   copy   |   core 0    |   core 1    |   core 2    |   core 3    |
+//! |  template   | -------> |             |             |             |             |
+//! +-------------+          +-------------+-------------+-------------+-------------+
+//! ```
+//!
+//! The kernel does not preempt or migrate execution between cores, so a reference obtained through
+//! [PerCpu::local()] keeps referring to the executing core's instance for as long as it lives.
+//! Debug builds assert this on every access, see [is_preemptible()].
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/cpu/percpu.rs"]
+mod arch_percpu;
+
+use crate::{bsp, cpu};
+use core::cell::UnsafeCell;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A variable with one instance per core.
+///
+/// Use [per_cpu!](crate::per_cpu) to declare instances.
+#[repr(transparent)]
+pub struct PerCpu<T> {
+    template: UnsafeCell<T>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// The offset from the template to the copy of the given core.
+fn offset_for(core_id: usize) -> usize {
+    let template_start = bsp::memory::virt_percpu_template_start().as_usize();
+    let block_start = bsp::memory::virt_percpu_blocks_start().as_usize()
+        + core_id * bsp::memory::percpu_template_size();
+
+    block_start - template_start
+}
+
+/// Point the executing core to its copy of the per-core data.
+unsafe fn set_up_local_offset() {
+    arch_percpu::set_local_offset(offset_for(cpu::smp::core_id()));
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Declare a per-core variable.
+///
+/// Every core's instance starts out with the given initial value.
+///
+/// ```
+/// per_cpu! {
+///     /// The number of IRQs handled by a core.
+///     static NUM_IRQS: AtomicUsize = AtomicUsize::new(0);
+/// }
+/// ```
+#[macro_export]
+macro_rules! per_cpu {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;) => {
+        $(#[$attr])*
+        #[link_section = ".percpu"]
+        $vis static $name: $crate::cpu::percpu::PerCpu<$ty> =
+            $crate::cpu::percpu::PerCpu::new($init);
+    };
+}
+
+unsafe impl<T> Sync for PerCpu<T> where T: Sync {}
+
+impl<T> PerCpu<T> {
+    /// Create an instance.
+    ///
+    /// Only use through [per_cpu!](crate::per_cpu), which places the instance in the `.percpu`
+    /// section.
+    #[doc(hidden)]
+    pub const fn new(value: T) -> Self {
+        Self {
+            template: UnsafeCell::new(value),
+        }
+    }
+
+    /// Return a reference to the executing core's instance.
+    ///
+    /// Must not be called while the executing code is preemptible.
+    pub fn local(&self) -> &T {
+        debug_assert!(
+            !is_preemptible(),
+            "Per-core data accessed while preemptible"
+        );
+
+        let offset = arch_percpu::local_offset();
+        assert!(offset != 0, "Per-core data not initialized on this core");
+
+        unsafe { &*((self.template.get() as usize + offset) as *const T) }
+    }
+
+    /// Return a reference to the instance of the given core.
+    pub fn remote(&self, core_id: usize) -> &T {
+        assert!(core_id < bsp::cpu::NUM_CORES, "Invalid core id");
+
+        unsafe { &*((self.template.get() as usize + offset_for(core_id)) as *const T) }
+    }
+}
+
+/// Whether code running on the executing core may be preempted and continue on another core.
+///
+/// The kernel has no scheduler, so execution never migrates. A preemptive scheduler must report
+/// here whether the executing code can be moved, so that per-core accesses from such code are
+/// caught.
+pub fn is_preemptible() -> bool {
+    false
+}
+
+/// Set up the per-core data of all cores and make it available on the boot core.
+///
+/// Called by [handling_init()](crate::exception::handling_init).
+///
+/// # Safety
+///
+/// - Must only be called once, by the boot core during kernel init, before any per-core variable is
+///   accessed.
+pub unsafe fn init() {
+    let template_start = bsp::memory::virt_percpu_template_start().as_usize();
+    let template_size = bsp::memory::percpu_template_size();
+
+    assert_eq!(
+        bsp::memory::percpu_blocks_size(),
+        template_size * bsp::cpu::NUM_CORES
+    );
+
+    for i in 0..bsp::cpu::NUM_CORES {
+        core::ptr::copy_nonoverlapping(
+            template_start as *const u8,
+            (template_start + offset_for(i)) as *mut u8,
+            template_size,
+        );
+    }
+
+    set_up_local_offset();
+}
+
+/// Make the per-core data available on the executing core.
+///
+/// Called by [handling_init()](crate::exception::handling_init).
+///
+/// # Safety
+///
+/// - The boot core must have called [init()] before.
+pub unsafe fn init_secondary_core() {
+    set_up_local_offset();
+}
@@ -3,12 +3,285 @@
 // Copyright (c) 2018-2022 Andre Richter <andre.o.richter@gmail.com>
 
 //! Symmetric multiprocessing.
+//!
+//! Besides bringing up the secondary cores, this module provides cross-core function calls and
+//! parking of secondary cores at runtime. Both are built on inter-processor interrupts (IPIs)
+//! raised through the platform's interrupt controller.
+//!
+//! A parked core sleeps in `WFE` from within the IPI that parked it, with IRQs masked. It does not
+//! count as online while parked, so it takes no part in cross-core function calls.
 
 #[cfg(target_arch = "aarch64")]
 #[path = "../_arch/aarch64/cpu/smp.rs"]
 mod arch_smp;
 
+use crate::{
+    bsp, cpu, driver,
+    error::KernelError,
+    exception, per_cpu, pmu, state,
+    synchronization::{interface::Mutex, SpinLock},
+    time,
+};
+use core::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
 //--------------------------------------------------------------------------------------------------
 // Architectural Public Reexports
 //--------------------------------------------------------------------------------------------------
 pub use arch_smp::core_id;
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+/// Bitmask of the cores that finished their bring-up and are not parked, including the boot core.
+static CORES_ONLINE: AtomicUsize = AtomicUsize::new(1 << bsp::cpu::BOOT_CORE_ID);
+
+/// Bitmask of the cores that have been released from the spin table.
+static CORES_STARTED: AtomicUsize = AtomicUsize::new(1 << bsp::cpu::BOOT_CORE_ID);
+
+/// Serializes cross-core function calls and changes to the set of online cores.
+static CALL_LOCK: SpinLock<()> = SpinLock::new(());
+
+/// The address of the function that is currently being called on the other cores.
+static CALL_FUNCTION: AtomicUsize = AtomicUsize::new(0);
+
+/// Bitmask of the cores that did not yet finish the current cross-core function call.
+static CALL_PENDING: AtomicUsize = AtomicUsize::new(0);
+
+per_cpu! {
+    /// Whether a core has been asked to park.
+    static PARK_REQUESTED: AtomicBool = AtomicBool::new(false);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// The bitmask for the given core.
+const fn core_mask(core_id: usize) -> usize {
+    1 << core_id
+}
+
+/// Sleep until unparked.
+///
+/// Runs in the IPI handler, so IRQs stay masked while the core is parked.
+fn park() {
+    let mask = core_mask(core_id());
+
+    CORES_ONLINE.fetch_and(!mask, Ordering::Release);
+
+    while PARK_REQUESTED.local().load(Ordering::Acquire) {
+        cpu::wait_for_event();
+    }
+
+    CORES_ONLINE.fetch_or(mask, Ordering::Release);
+}
+
+/// Check that the given core is a secondary core other than the executing one.
+fn check_hotplug_target(core_id: usize) -> Result<(), &'static str> {
+    if core_id >= bsp::cpu::NUM_CORES {
+        return Err("Invalid core id");
+    }
+
+    if core_id as u64 == bsp::cpu::BOOT_CORE_ID {
+        return Err("The boot core can not be parked");
+    }
+
+    if core_id == self::core_id::<usize>() {
+        return Err("The executing core can not park itself");
+    }
+
+    if CORES_STARTED.load(Ordering::Acquire) & core_mask(core_id) == 0 {
+        return Err("Core was never started");
+    }
+
+    Ok(())
+}
+
+/// Init code for the secondary cores.
+///
+/// Like `kernel_init()` for the boot core, this runs with virtual memory already enabled, using the
+/// same kernel translation tables.
+///
+/// # Safety
+///
+/// - Must only be entered through `_start_secondary()` after the boot core released the core.
+#[no_mangle]
+unsafe fn kernel_init_secondary() -> ! {
+    use driver::interface::DriverManager;
+
+    exception::handling_init();
+    cpu::fpsimd::init();
+    pmu::init();
+
+    for i in bsp::driver::driver_manager().all_device_drivers() {
+        if let Err(x) = i.driver.init_secondary_core() {
+            panic!(
+                "Error initializing driver on core {}: {}: {}",
+                core_id::<usize>(),
+                i.driver.compatible(),
+                x
+            );
+        }
+    }
+
+    exception::asynchronous::local_irq_unmask();
+
+    CORES_ONLINE.fetch_or(core_mask(core_id()), Ordering::Release);
+
+    cpu::idle::idle_loop()
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Prepare the bring-up of the secondary cores.
+///
+/// # Safety
+///
+/// - Must only be called during kernel init.
+pub unsafe fn init() -> Result<(), KernelError> {
+    bsp::cpu::spin_table_init()
+}
+
+/// Release the secondary cores and wait for them to come online.
+///
+/// Transitions the kernel into the `MultiCoreMain` state. Returns the number of cores online.
+pub fn start_secondary_cores() -> Result<usize, &'static str> {
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    let entry_addr = arch_smp::phys_secondary_entry_addr()?;
+
+    state::state_manager().transition_to_multi_core_main();
+
+    for i in (0..bsp::cpu::NUM_CORES).filter(|&i| i as u64 != bsp::cpu::BOOT_CORE_ID) {
+        let release_addr = bsp::cpu::spin_table_release_addr(i)?;
+
+        CORES_STARTED.fetch_or(core_mask(i), Ordering::Release);
+        unsafe { arch_smp::spin_table_release(release_addr, entry_addr) };
+    }
+
+    time::wait_for(TIMEOUT, || num_cores_online() == bsp::cpu::NUM_CORES)
+        .map_err(|_| "Timeout waiting for secondary cores")?;
+
+    Ok(num_cores_online())
+}
+
+/// The number of cores that are online.
+pub fn num_cores_online() -> usize {
+    CORES_ONLINE.load(Ordering::Acquire).count_ones() as usize
+}
+
+/// Returns whether the given core is online.
+pub fn is_online(core_id: usize) -> bool {
+    core_id < bsp::cpu::NUM_CORES && CORES_ONLINE.load(Ordering::Acquire) & core_mask(core_id) != 0
+}
+
+/// Park a secondary core.
+///
+/// Returns after the core stopped executing kernel code. Must be called with IRQs unmasked.
+pub fn offline(core_id: usize) -> Result<(), &'static str> {
+    use exception::asynchronous::interface::IRQManager;
+
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    check_hotplug_target(core_id)?;
+
+    CALL_LOCK.lock(|_| {
+        if !is_online(core_id) {
+            return Err("Core is not online");
+        }
+
+        PARK_REQUESTED
+            .remote(core_id)
+            .store(true, Ordering::Release);
+        bsp::exception::asynchronous::irq_manager().send_ipi_to_others();
+
+        time::wait_for(TIMEOUT, || !is_online(core_id))
+            .map_err(|_| "Timeout waiting for core to park")
+    })
+}
+
+/// Unpark a secondary core that was parked with [offline()].
+///
+/// Returns after the core is back online.
+pub fn online(core_id: usize) -> Result<(), &'static str> {
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    check_hotplug_target(core_id)?;
+
+    CALL_LOCK.lock(|_| {
+        if is_online(core_id) {
+            return Err("Core is already online");
+        }
+
+        PARK_REQUESTED
+            .remote(core_id)
+            .store(false, Ordering::Release);
+        cpu::send_event();
+
+        time::wait_for(TIMEOUT, || is_online(core_id))
+            .map_err(|_| "Timeout waiting for core to unpark")
+    })
+}
+
+/// Call a function on all online cores, including the executing one.
+///
+/// Returns after every core has finished executing `f`. On the other cores, `f` runs in IRQ
+/// context, so it must not block.
+///
+/// # Panics
+///
+/// - If called with IRQs masked while other cores are online. The executing core must be able to
+///   serve the call of another core while it waits for its own call to begin.
+pub fn call_on_each_cpu(f: fn()) {
+    use exception::asynchronous::interface::IRQManager;
+
+    if num_cores_online() == 1 {
+        f();
+        return;
+    }
+
+    assert!(
+        !exception::asynchronous::is_local_irq_masked(),
+        "call_on_each_cpu() called with IRQs masked"
+    );
+
+    CALL_LOCK.lock(|_| {
+        let others = CORES_ONLINE.load(Ordering::Acquire) & !core_mask(core_id());
+
+        CALL_FUNCTION.store(f as usize, Ordering::Relaxed);
+        CALL_PENDING.store(others, Ordering::Release);
+
+        bsp::exception::asynchronous::irq_manager().send_ipi_to_others();
+
+        f();
+
+        while CALL_PENDING.load(Ordering::Acquire) != 0 {
+            cpu::nop();
+        }
+    });
+}
+
+/// Handle an inter-processor interrupt.
+///
+/// Called by the interrupt controller driver.
+pub fn handle_ipi(_ic: &exception::asynchronous::IRQContext) {
+    let mask = core_mask(core_id());
+
+    // Pairs with the release store of `CALL_PENDING` in `call_on_each_cpu()`.
+    if CALL_PENDING.load(Ordering::Acquire) & mask != 0 {
+        let f: fn() = unsafe { core::mem::transmute(CALL_FUNCTION.load(Ordering::Relaxed)) };
+        f();
+
+        CALL_PENDING.fetch_and(!mask, Ordering::Release);
+    }
+
+    if PARK_REQUESTED.local().load(Ordering::Acquire) {
+        park();
+    }
+}
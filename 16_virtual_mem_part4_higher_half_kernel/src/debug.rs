@@ -0,0 +1,9 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Debugging support.
+
+pub mod crashdump;
+pub mod gdbstub;
+pub mod invariant;
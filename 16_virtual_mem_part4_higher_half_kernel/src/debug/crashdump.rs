@@ -0,0 +1,418 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Post-mortem crash dumps.
+//!
+//! On panic, the panic handler writes a crash dump to the console. It consists of text lines,
+//! framed by `CRASHDUMP-BEGIN` and `CRASHDUMP-END`, that each start with a tag:
+//!
+//! ```text
+//! CRASHDUMP-BEGIN <format version>
+//! CRASH-VERSION <kernel version>
+//! CRASH-CORE <core>
+//! CRASH-UPTIME <nanoseconds since power-on>
+//! CRASH-LOCATION <file>:<line>:<column>
+//! CRASH-MSG <line of the panic message>
+//! CRASH-ASSERT <expression>
+//! CRASH-ASSERT-VALUE <name> = <value>
+//! CRASH-REG <register number> <value>
+//! CRASH-FRAME <address>
+//! TRACE-BEGIN
+//! TRACE ...
+//! TRACE-END
+//! CRASH-LOG <line of console output>
+//! CRASH-MEM <address> <bytes>
+//! CRASHDUMP-END
+//! ```
+//!
+//! - The assertion lines are only present if a [crate::kassert!] failed.
+//! - Registers are numbered like in the GDB stub. They are the state of the faulting code if the
+//!   panic comes from an exception, or else that of the interrupted code if it comes from an IRQ
+//!   handler. Outside of exception handling, only the panic handler's own stack and frame pointer
+//!   are known.
+//! - The backtrace follows the chain of frame records, so the kernel is built with frame pointers.
+//! - The trace records are those of [crate::trace], in the format of [crate::trace::dump()].
+//! - The log is the tail of the console output, which is kept in a ring buffer.
+//! - The RAM window is given in hex with `crashdump.mem=<address>,<length>` on the kernel command
+//!   line. Without it, the bottom of the stack that was in use is dumped.
+//!
+//! `tools/crashdump` pretty-prints the dump from a captured log. Only the first panic writes a
+//! dump. There is no storage driver yet, so the console is the only sink.
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/debug/crashdump.rs"]
+mod arch_crashdump;
+
+use crate::{
+    bsp, cpu, debug, exception, memory,
+    memory::{
+        mmu::{PageAddress, PageGranule},
+        Address, Virtual,
+    },
+    time, trace,
+};
+use core::{
+    fmt,
+    panic::PanicInfo,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The version of the dump format. Bumped on incompatible changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// The size of the console output ring.
+const LOG_RING_SIZE: usize = 4096;
+
+/// The deepest backtrace that is written.
+const MAX_FRAMES: usize = 32;
+
+/// The size of the default RAM window, and the bytes per `CRASH-MEM` line.
+const DEFAULT_MEM_WINDOW_SIZE: usize = 512;
+const MEM_BYTES_PER_LINE: usize = 16;
+
+/// Upper bound for the RAM window, so that a typo does not dump for hours.
+const MAX_MEM_WINDOW_SIZE: usize = 64 * 1024;
+
+/// The tail of the console output.
+struct LogRing {
+    /// The number of bytes written since boot.
+    written: AtomicUsize,
+
+    buf: [AtomicU8; LOG_RING_SIZE],
+}
+
+/// Prefixes every line that is written through it with a tag.
+struct Tagged<'a> {
+    w: &'a mut dyn fmt::Write,
+    tag: &'static str,
+    line_start: bool,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[allow(clippy::declare_interior_mutable_const)]
+const LOG_RING_INIT: LogRing = {
+    const ZERO: AtomicU8 = AtomicU8::new(0);
+
+    LogRing {
+        written: AtomicUsize::new(0),
+        buf: [ZERO; LOG_RING_SIZE],
+    }
+};
+
+static LOG_RING: LogRing = LOG_RING_INIT;
+
+/// Set by the first panic.
+static WRITTEN: AtomicBool = AtomicBool::new(false);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl LogRing {
+    /// Concurrent writers reserve disjoint parts of the ring, so that their output is not torn
+    /// within the ring. It interleaves at most.
+    fn write_str(&self, s: &str) {
+        let start = self.written.fetch_add(s.len(), Ordering::Relaxed);
+
+        for (i, b) in s.bytes().enumerate() {
+            self.buf[(start + i) % LOG_RING_SIZE].store(b, Ordering::Relaxed);
+        }
+    }
+
+    /// The kept bytes in the order they were written.
+    ///
+    /// If the ring wrapped, the leading partial line is skipped.
+    fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        let written = self.written.load(Ordering::Relaxed);
+        let oldest = written.saturating_sub(LOG_RING_SIZE);
+
+        let bytes =
+            (oldest..written).map(move |i| self.buf[i % LOG_RING_SIZE].load(Ordering::Relaxed));
+        let mut in_partial_line = oldest != 0;
+
+        bytes.filter(move |&b| {
+            if in_partial_line {
+                in_partial_line = b != b'\n';
+                return false;
+            }
+
+            true
+        })
+    }
+}
+
+impl<'a> Tagged<'a> {
+    fn new(w: &'a mut dyn fmt::Write, tag: &'static str) -> Self {
+        Self {
+            w,
+            tag,
+            line_start: true,
+        }
+    }
+
+    /// Terminate an unfinished last line.
+    fn finish(self) -> fmt::Result {
+        if self.line_start {
+            return Ok(());
+        }
+
+        writeln!(self.w)
+    }
+}
+
+impl fmt::Write for Tagged<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.line_start {
+                write!(self.w, "{} ", self.tag)?;
+            }
+
+            self.w.write_str(line)?;
+            self.line_start = line.ends_with('\n');
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `len` bytes at `addr` are mapped in the kernel's address space.
+fn is_readable(addr: usize, len: usize) -> bool {
+    let end = match addr.checked_add(len) {
+        None => return false,
+        Some(x) => x,
+    };
+
+    // Addresses below the kernel's address space have no translation table entries.
+    if addr < usize::MAX - bsp::memory::mmu::KernelVirtAddrSpace::SIZE + 1 {
+        return false;
+    }
+
+    let mut page = Address::<Virtual>::new(addr).align_down_page().as_usize();
+    while page < end {
+        if memory::mmu::try_kernel_page_attributes(PageAddress::from(page)).is_err() {
+            return false;
+        }
+
+        page = match page.checked_add(PageGranule::SIZE) {
+            None => break,
+            Some(x) => x,
+        };
+    }
+
+    true
+}
+
+/// The RAM window from the command line.
+fn mem_window_from_cmdline() -> Option<(usize, usize)> {
+    use crate::synchronization::interface::ReadWriteEx;
+
+    let parse = |s: &str| usize::from_str_radix(s.trim_start_matches("0x"), 16).ok();
+
+    bsp::cmdline::cmdline().read(|cmdline| {
+        let (addr, len) = cmdline.value("crashdump.mem")?.split_once(',')?;
+
+        Some((parse(addr)?, parse(len)?))
+    })
+}
+
+fn write_assertion(w: &mut dyn fmt::Write) -> fmt::Result {
+    debug::invariant::with_failed_assertion(|assertion| {
+        let assertion = match assertion {
+            None => return Ok(()),
+            Some(x) => x,
+        };
+
+        writeln!(w, "CRASH-ASSERT {}", assertion.expr)?;
+        for (name, value) in assertion.values {
+            writeln!(w, "CRASH-ASSERT-VALUE {} = {:?}", name, value)?;
+        }
+
+        Ok(())
+    })
+}
+
+fn write_registers(w: &mut dyn fmt::Write) -> Result<(usize, usize), fmt::Error> {
+    use arch_crashdump::{FRAME_POINTER_REGISTER, STACK_POINTER_REGISTER};
+
+    exception::with_exception_context(|context| {
+        let context = match context {
+            None => return Ok(arch_crashdump::current_frame()),
+            Some(x) => x,
+        };
+
+        for n in 0..context.num_registers() {
+            if let Some((value, _)) = context.register(n) {
+                writeln!(w, "CRASH-REG {} {:#x}", n, value)?;
+            }
+        }
+
+        // The innermost frame is the faulting function itself, which has no frame record yet.
+        writeln!(w, "CRASH-FRAME {:#x}", context.pc())?;
+
+        let reg = |n| context.register(n).map_or(0, |(value, _)| value as usize);
+        Ok((reg(STACK_POINTER_REGISTER), reg(FRAME_POINTER_REGISTER)))
+    })
+}
+
+fn write_backtrace(w: &mut dyn fmt::Write, mut fp: usize) -> fmt::Result {
+    use arch_crashdump::FRAME_RECORD_SIZE;
+
+    for _ in 0..MAX_FRAMES {
+        if fp == 0 || fp % 8 != 0 || !is_readable(fp, FRAME_RECORD_SIZE) {
+            break;
+        }
+
+        let (caller_fp, return_addr) = unsafe { arch_crashdump::read_frame_record(fp) };
+        if return_addr == 0 {
+            break;
+        }
+        writeln!(w, "CRASH-FRAME {:#x}", return_addr)?;
+
+        // Stacks grow downwards, so the caller's frame lives above. Anything else is garbage.
+        if caller_fp <= fp {
+            break;
+        }
+        fp = caller_fp;
+    }
+
+    Ok(())
+}
+
+fn write_log(w: &mut dyn fmt::Write) -> fmt::Result {
+    let mut line = Tagged::new(w, "CRASH-LOG");
+
+    for b in LOG_RING.bytes() {
+        match b {
+            b'\r' => (),
+            b'\n' | b' '..=b'~' => fmt::Write::write_char(&mut line, b as char)?,
+            _ => fmt::Write::write_char(&mut line, '?')?,
+        }
+    }
+
+    line.finish()
+}
+
+fn write_mem(w: &mut dyn fmt::Write, addr: usize, len: usize) -> fmt::Result {
+    let len = len.min(MAX_MEM_WINDOW_SIZE);
+
+    if !is_readable(addr, len) {
+        return writeln!(w, "CRASH-MEM-UNMAPPED {:#x} {:#x}", addr, len);
+    }
+
+    for line in (addr..addr + len).step_by(MEM_BYTES_PER_LINE) {
+        write!(w, "CRASH-MEM {:#x} ", line)?;
+
+        for a in line..(line + MEM_BYTES_PER_LINE).min(addr + len) {
+            write!(w, "{:02x}", unsafe {
+                core::ptr::read_volatile(a as *const u8)
+            })?;
+        }
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Keep console output for the crash dump.
+#[doc(hidden)]
+pub fn log(args: fmt::Arguments) {
+    struct Writer;
+
+    impl fmt::Write for Writer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            LOG_RING.write_str(s);
+            Ok(())
+        }
+    }
+
+    fmt::Write::write_fmt(&mut Writer, args).ok();
+}
+
+/// Write the crash dump for `info` to `w`.
+///
+/// Does nothing after the first call.
+pub fn write(w: &mut dyn fmt::Write, info: &PanicInfo) -> fmt::Result {
+    use time::interface::TimeManager;
+
+    if WRITTEN.swap(true, Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    // Recording while the records are read would tear them.
+    trace::disable();
+
+    writeln!(w, "CRASHDUMP-BEGIN {}", FORMAT_VERSION)?;
+    writeln!(w, "CRASH-VERSION {}", crate::version())?;
+    writeln!(w, "CRASH-CORE {}", cpu::smp::core_id::<usize>())?;
+    writeln!(
+        w,
+        "CRASH-UPTIME {}",
+        time::time_manager().uptime().as_nanos()
+    )?;
+
+    if let Some(location) = info.location() {
+        writeln!(w, "CRASH-LOCATION {}", location)?;
+    }
+
+    if let Some(args) = info.message() {
+        let mut msg = Tagged::new(w, "CRASH-MSG");
+        fmt::Write::write_fmt(&mut msg, *args)?;
+        msg.finish()?;
+    }
+
+    write_assertion(w)?;
+
+    let (sp, fp) = write_registers(w)?;
+    write_backtrace(w, fp)?;
+
+    trace::dump_to(w).map_err(|_| fmt::Error)?;
+
+    write_log(w)?;
+
+    let (addr, len) = mem_window_from_cmdline()
+        .unwrap_or((sp & !(MEM_BYTES_PER_LINE - 1), DEFAULT_MEM_WINDOW_SIZE));
+    write_mem(w, addr, len)?;
+
+    writeln!(w, "CRASHDUMP-END")
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check that a wrapped ring keeps its tail, starting at a full line.
+    #[kernel_test]
+    fn crashdump_log_ring_keeps_tail() {
+        let ring = LOG_RING_INIT;
+
+        ring.write_str("first\n");
+        assert!(ring.bytes().eq(b"first\n".iter().copied()));
+
+        for _ in 0..LOG_RING_SIZE / 4 {
+            ring.write_str("abc\n");
+        }
+        ring.write_str("last\n");
+
+        // The oldest kept byte is in the middle of a line.
+        assert!(ring.bytes().take(4).eq(b"abc\n".iter().copied()));
+        assert_eq!(ring.bytes().filter(|&b| b == b'f').count(), 0);
+        assert_eq!(ring.bytes().filter(|&b| b == b'l').count(), 1);
+    }
+}
@@ -0,0 +1,734 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! GDB remote serial protocol stub.
+//!
+//! Lets GDB debug the kernel on real hardware through the console UART:
+//!
+//! ```console
+//! $ gdb-multiarch target/aarch64-unknown-none-softfloat/release/kernel
+//! (gdb) set serial baud 921600
+//! (gdb) target remote /dev/ttyUSB0
+//! ```
+//!
+//! The stub is enabled with the `gdb` flag on the kernel command line. It shares the console with
+//! the kernel log. While the kernel runs, the console's RX IRQ handler watches for the start of a
+//! GDB packet (`$`) and for GDB's interrupt request (`CTRL + C`). Either one stops the core that
+//! handled the IRQ. A stopped core talks to GDB by polling the console from its exception handler,
+//! until GDB lets it continue or step. Log lines that reach GDB in between packets are ignored by
+//! it.
+//!
+//! Supported are register and memory access, software breakpoints (`Z0`), continue and single
+//! step. The kernel's code pages are read-only, so breakpoints are written through a writable alias
+//! of the code, which is mapped during init. [breakpoint()] stops in the stub from code.
+//!
+//! Only the stopped core is halted. The other cores keep running. Placing breakpoints in the
+//! console driver or in the stub itself deadlocks the core.
+//!
+//! # Resources
+//!
+//! - <https://sourceware.org/gdb/onlinedocs/gdb/Remote-Protocol.html>
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/debug/gdbstub.rs"]
+mod arch_gdbstub;
+
+use crate::{
+    bsp, console, cpu,
+    error::KernelError,
+    memory,
+    memory::{
+        mmu::{AccessPermissions, PageAddress, PageGranule},
+        Address, Virtual,
+    },
+    synchronization::{interface::Mutex, IRQSafeSpinLock},
+};
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The maximum size of a packet's payload.
+const PACKET_SIZE: usize = 1024;
+
+/// The maximum number of software breakpoints.
+const MAX_BREAKPOINTS: usize = 16;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// GDB's signal numbers for stop replies.
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+/// A software breakpoint and the instruction it replaced.
+#[derive(Copy, Clone)]
+struct Breakpoint {
+    addr: usize,
+    original: u32,
+}
+
+/// A received or outgoing packet payload.
+struct Packet {
+    buf: [u8; PACKET_SIZE],
+    len: usize,
+}
+
+/// What the stopped core does once the session ends.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Resume {
+    Continue,
+    Step,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Why a core stopped.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// A breakpoint instruction was executed.
+    Breakpoint,
+
+    /// A single step completed.
+    Step,
+
+    /// GDB asked for the stop.
+    Interrupt,
+}
+
+/// GDB stub interfaces.
+pub mod interface {
+    /// The register state of a stopped core.
+    ///
+    /// Registers are numbered in the order of GDB's `g` packet.
+    pub trait StoppedCore {
+        /// The number of registers in the `g` packet.
+        fn num_registers(&self) -> usize;
+
+        /// Return the value of register `n` and its size in bytes.
+        fn register(&self, n: usize) -> Option<(u64, usize)>;
+
+        /// Overwrite register `n`.
+        fn set_register(&mut self, n: usize, value: u64) -> Result<(), &'static str>;
+
+        /// The address of the next instruction.
+        fn pc(&self) -> usize;
+
+        /// Set the address of the next instruction.
+        fn set_pc(&mut self, pc: usize);
+
+        /// When enabled, the core executes a single instruction after resuming and stops again
+        /// with [super::StopReason::Step].
+        fn set_single_step(&mut self, enable: bool);
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The virtual address of the writable code alias.
+static CODE_ALIAS: AtomicUsize = AtomicUsize::new(0);
+
+/// Set by the console when GDB wants to stop the kernel.
+static BREAK_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Set if the console IRQ handler consumed the start of a packet.
+static PACKET_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+static BREAKPOINTS: IRQSafeSpinLock<[Option<Breakpoint>; MAX_BREAKPOINTS]> =
+    IRQSafeSpinLock::new([None; MAX_BREAKPOINTS]);
+
+/// Serializes the sessions of cores that stop at the same time.
+static SESSION: IRQSafeSpinLock<()> = IRQSafeSpinLock::new(());
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn get_byte() -> u8 {
+    use console::interface::Read;
+
+    bsp::console::console().read_char() as u8
+}
+
+fn put_byte(b: u8) {
+    use console::interface::Write;
+
+    bsp::console::console().write_char(b as char)
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Parse a big-endian hex number, as used for addresses and lengths.
+fn parse_hex(s: &[u8]) -> Option<usize> {
+    if s.is_empty() || s.len() > 2 * core::mem::size_of::<usize>() {
+        return None;
+    }
+
+    s.iter()
+        .try_fold(0, |acc, &c| Some((acc << 4) | usize::from(hex_value(c)?)))
+}
+
+/// Parse `size` bytes of little-endian hex, as used for register values.
+fn parse_hex_le(s: &[u8], size: usize) -> Option<u64> {
+    if s.len() != 2 * size {
+        return None;
+    }
+
+    s.chunks(2).rev().try_fold(0, |acc, pair| {
+        let byte = (hex_value(pair[0])? << 4) | hex_value(pair[1])?;
+
+        Some((acc << 8) | u64::from(byte))
+    })
+}
+
+/// Split `s` at the first occurrence of `separator`.
+fn split_once(s: &[u8], separator: u8) -> Option<(&[u8], &[u8])> {
+    let i = s.iter().position(|&c| c == separator)?;
+
+    Some((&s[..i], &s[i + 1..]))
+}
+
+/// Parse the `addr,len` arguments of memory and breakpoint packets.
+fn parse_addr_len(s: &[u8]) -> Option<(usize, usize)> {
+    let (addr, len) = split_once(s, b',')?;
+
+    Some((parse_hex(addr)?, parse_hex(len)?))
+}
+
+impl Packet {
+    const fn new() -> Self {
+        Self {
+            buf: [0; PACKET_SIZE],
+            len: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Append a byte. Bytes beyond the packet size are dropped.
+    fn push(&mut self, b: u8) {
+        if self.len < PACKET_SIZE {
+            self.buf[self.len] = b;
+            self.len += 1;
+        }
+    }
+
+    fn push_hex_byte(&mut self, b: u8) {
+        self.push(HEX_DIGITS[usize::from(b >> 4)]);
+        self.push(HEX_DIGITS[usize::from(b & 0xf)]);
+    }
+
+    /// Append the lower `size` bytes of `value` in target byte order.
+    fn push_hex_le(&mut self, value: u64, size: usize) {
+        for b in value.to_le_bytes().iter().take(size) {
+            self.push_hex_byte(*b);
+        }
+    }
+
+    fn push_ok(&mut self) {
+        self.push_str("OK");
+    }
+
+    fn push_error(&mut self, errno: u8) {
+        self.push(b'E');
+        self.push_hex_byte(errno);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        for b in s.bytes() {
+            self.push(b);
+        }
+    }
+}
+
+impl fmt::Write for Packet {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+
+        Ok(())
+    }
+}
+
+/// Receive the next well-formed packet and acknowledge it.
+fn receive(packet: &mut Packet) {
+    loop {
+        // GDB's acknowledgements and anything else outside of packets are skipped.
+        while get_byte() != b'$' {}
+
+        packet.clear();
+        let mut checksum: u8 = 0;
+        let mut overflow = false;
+
+        loop {
+            let c = get_byte();
+            if c == b'#' {
+                break;
+            }
+
+            checksum = checksum.wrapping_add(c);
+            overflow |= packet.len == PACKET_SIZE;
+            packet.push(c);
+        }
+
+        let expected = (hex_value(get_byte()), hex_value(get_byte()));
+        match expected {
+            (Some(hi), Some(lo)) if !overflow && ((hi << 4) | lo) == checksum => {
+                put_byte(b'+');
+                return;
+            }
+            _ => put_byte(b'-'),
+        }
+    }
+}
+
+/// Send a packet and wait until GDB acknowledged it.
+fn send(packet: &Packet) {
+    loop {
+        put_byte(b'$');
+
+        let mut checksum: u8 = 0;
+        for &b in packet.as_bytes() {
+            put_byte(b);
+            checksum = checksum.wrapping_add(b);
+        }
+
+        put_byte(b'#');
+        put_byte(HEX_DIGITS[usize::from(checksum >> 4)]);
+        put_byte(HEX_DIGITS[usize::from(checksum & 0xf)]);
+
+        loop {
+            match get_byte() {
+                b'+' => return,
+                b'-' => break,
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Whether `addr` lies in the kernel's code.
+fn is_code(addr: usize, len: usize) -> bool {
+    let code = bsp::memory::virt_code_range();
+
+    addr >= code.start.as_usize()
+        && addr
+            .checked_add(len)
+            .map_or(false, |end| end <= code.end.as_usize())
+}
+
+/// Check that the given range is mapped, and writable if requested.
+fn check_mapped(addr: usize, len: usize, write: bool) -> Result<(), &'static str> {
+    let end = addr.checked_add(len).ok_or("Address overflow")?;
+
+    // Addresses below the kernel's address space have no translation table entries.
+    if addr < usize::MAX - bsp::memory::mmu::KernelVirtAddrSpace::SIZE + 1 {
+        return Err("Address outside of the kernel's address space");
+    }
+
+    let mut page = Address::<Virtual>::new(addr).align_down_page().as_usize();
+    while page < end {
+        let attr = memory::mmu::try_kernel_page_attributes(PageAddress::from(page))?;
+
+        if write && attr.acc_perms != AccessPermissions::ReadWrite {
+            return Err("Page is not writable");
+        }
+
+        page = match page.checked_add(PageGranule::SIZE) {
+            None => break,
+            Some(x) => x,
+        };
+    }
+
+    Ok(())
+}
+
+/// Write to the kernel's code through the writable alias.
+fn write_code(addr: usize, bytes: &[u8]) -> Result<(), &'static str> {
+    if !is_code(addr, bytes.len()) {
+        return Err("Not in the kernel's code");
+    }
+
+    let alias = CODE_ALIAS.load(Ordering::Relaxed)
+        + (addr - bsp::memory::virt_code_range().start.as_usize());
+
+    unsafe {
+        for (i, b) in bytes.iter().enumerate() {
+            core::ptr::write_volatile((alias + i) as *mut u8, *b);
+        }
+
+        cpu::cache::sync_code(alias, addr, bytes.len());
+    }
+
+    Ok(())
+}
+
+fn read_instruction(addr: usize) -> u32 {
+    unsafe { core::ptr::read_volatile(addr as *const u32) }
+}
+
+fn read_registers(core: &dyn interface::StoppedCore, reply: &mut Packet) {
+    for n in 0..core.num_registers() {
+        if let Some((value, size)) = core.register(n) {
+            reply.push_hex_le(value, size);
+        }
+    }
+}
+
+fn write_registers(core: &mut dyn interface::StoppedCore, mut data: &[u8]) -> Option<()> {
+    for n in 0..core.num_registers() {
+        let (_, size) = core.register(n)?;
+
+        // GDB may send fewer registers than the stub reports.
+        if data.len() < 2 * size {
+            break;
+        }
+
+        let value = parse_hex_le(&data[..2 * size], size)?;
+        core.set_register(n, value).ok()?;
+        data = &data[2 * size..];
+    }
+
+    Some(())
+}
+
+fn read_register(core: &dyn interface::StoppedCore, args: &[u8], reply: &mut Packet) -> Option<()> {
+    let (value, size) = core.register(parse_hex(args)?)?;
+    reply.push_hex_le(value, size);
+
+    Some(())
+}
+
+fn write_register(core: &mut dyn interface::StoppedCore, args: &[u8]) -> Option<()> {
+    let (n, value) = split_once(args, b'=')?;
+    let n = parse_hex(n)?;
+    let (_, size) = core.register(n)?;
+
+    core.set_register(n, parse_hex_le(value, size)?).ok()
+}
+
+fn read_memory(args: &[u8], reply: &mut Packet) -> Option<()> {
+    let (addr, len) = parse_addr_len(args)?;
+
+    if len > PACKET_SIZE / 2 || check_mapped(addr, len, false).is_err() {
+        return None;
+    }
+
+    for i in 0..len {
+        reply.push_hex_byte(unsafe { core::ptr::read_volatile((addr + i) as *const u8) });
+    }
+
+    Some(())
+}
+
+fn write_memory(args: &[u8]) -> Option<()> {
+    let (range, data) = split_once(args, b':')?;
+    let (addr, len) = parse_addr_len(range)?;
+
+    if data.len() != 2 * len || len > PACKET_SIZE / 2 {
+        return None;
+    }
+
+    let mut bytes = [0_u8; PACKET_SIZE / 2];
+    for (i, pair) in data.chunks(2).enumerate() {
+        bytes[i] = (hex_value(pair[0])? << 4) | hex_value(pair[1])?;
+    }
+    let bytes = &bytes[..len];
+
+    if is_code(addr, len) {
+        return write_code(addr, bytes).ok();
+    }
+
+    check_mapped(addr, len, true).ok()?;
+    for (i, b) in bytes.iter().enumerate() {
+        unsafe { core::ptr::write_volatile((addr + i) as *mut u8, *b) };
+    }
+
+    Some(())
+}
+
+fn insert_breakpoint(args: &[u8]) -> Option<()> {
+    let (addr, _kind) = parse_addr_len(args)?;
+
+    if addr % arch_gdbstub::INSTRUCTION_SIZE != 0 || !is_code(addr, arch_gdbstub::INSTRUCTION_SIZE)
+    {
+        return None;
+    }
+
+    BREAKPOINTS.lock(|breakpoints| {
+        if breakpoints.iter().flatten().any(|bp| bp.addr == addr) {
+            return Some(());
+        }
+
+        let slot = breakpoints.iter_mut().find(|bp| bp.is_none())?;
+        let original = read_instruction(addr);
+
+        write_code(addr, &arch_gdbstub::BREAKPOINT_INSTRUCTION.to_le_bytes()).ok()?;
+        *slot = Some(Breakpoint { addr, original });
+
+        Some(())
+    })
+}
+
+fn remove_breakpoint(args: &[u8]) -> Option<()> {
+    let (addr, _kind) = parse_addr_len(args)?;
+
+    BREAKPOINTS.lock(|breakpoints| {
+        let slot = breakpoints
+            .iter_mut()
+            .find(|bp| matches!(bp, Some(bp) if bp.addr == addr))?;
+
+        write_code(addr, &slot.unwrap().original.to_le_bytes()).ok()?;
+        *slot = None;
+
+        Some(())
+    })
+}
+
+/// Handle the optional resume address of the `c` and `s` packets.
+fn set_resume_addr(core: &mut dyn interface::StoppedCore, args: &[u8]) {
+    if let Some(addr) = parse_hex(args) {
+        core.set_pc(addr);
+    }
+}
+
+/// Handle a packet. Returns how to resume if the packet ends the session.
+fn handle_packet(
+    core: &mut dyn interface::StoppedCore,
+    cmd: &[u8],
+    signal: u8,
+    reply: &mut Packet,
+) -> Option<Resume> {
+    use fmt::Write;
+
+    // Replies for commands that either succeed or fail.
+    let status = |result: Option<()>, reply: &mut Packet| match result {
+        Some(()) => reply.push_ok(),
+        None => reply.push_error(0x0e),
+    };
+
+    let (&command, args) = match cmd.split_first() {
+        None => return None,
+        Some(x) => x,
+    };
+
+    match command {
+        b'?' => {
+            reply.push(b'S');
+            reply.push_hex_byte(signal);
+        }
+        b'g' => read_registers(core, reply),
+        b'G' => status(write_registers(core, args), reply),
+        b'p' => {
+            if read_register(core, args, reply).is_none() {
+                reply.push_error(0x0e);
+            }
+        }
+        b'P' => status(write_register(core, args), reply),
+        b'm' => {
+            if read_memory(args, reply).is_none() {
+                reply.push_error(0x0e);
+            }
+        }
+        b'M' => status(write_memory(args), reply),
+        b'Z' if args.starts_with(b"0,") => status(insert_breakpoint(&args[2..]), reply),
+        b'z' if args.starts_with(b"0,") => status(remove_breakpoint(&args[2..]), reply),
+        b'c' => {
+            set_resume_addr(core, args);
+            return Some(Resume::Continue);
+        }
+        b's' => {
+            set_resume_addr(core, args);
+            return Some(Resume::Step);
+        }
+        b'D' => {
+            reply.push_ok();
+            send(reply);
+            return Some(Resume::Continue);
+        }
+        b'k' => return Some(Resume::Continue),
+        b'H' => reply.push_ok(),
+        b'q' if args.starts_with(b"Supported") => {
+            let _ = write!(reply, "PacketSize={:x}", PACKET_SIZE);
+        }
+        b'q' if args == b"Attached" => reply.push(b'1'),
+        // An empty reply tells GDB that the command is not supported.
+        _ => (),
+    }
+
+    None
+}
+
+/// Talk to GDB until it resumes the core.
+fn session(core: &mut dyn interface::StoppedCore, reason: StopReason) {
+    let signal = match reason {
+        StopReason::Interrupt => SIGINT,
+        StopReason::Breakpoint | StopReason::Step => SIGTRAP,
+    };
+
+    // Step over compiled-in breakpoints, so that continuing does not stop at them again.
+    if reason == StopReason::Breakpoint
+        && read_instruction(core.pc()) == arch_gdbstub::COMPILED_BREAKPOINT_INSTRUCTION
+    {
+        core.set_pc(core.pc() + arch_gdbstub::INSTRUCTION_SIZE);
+    }
+
+    let mut packet = Packet::new();
+    let mut reply = Packet::new();
+
+    if PACKET_INTERRUPTED.swap(false, Ordering::Relaxed) {
+        // The console IRQ handler consumed the start of the packet that stopped the core. Drop the
+        // rest of it and let GDB send it again. GDB does not await a stop reply in this case.
+        while get_byte() != b'#' {}
+        get_byte();
+        get_byte();
+        put_byte(b'-');
+    } else {
+        reply.push(b'S');
+        reply.push_hex_byte(signal);
+        send(&reply);
+    }
+
+    let resume = loop {
+        receive(&mut packet);
+        reply.clear();
+
+        if let Some(resume) = handle_packet(core, packet.as_bytes(), signal, &mut reply) {
+            break resume;
+        }
+
+        send(&reply);
+    };
+
+    core.set_single_step(resume == Resume::Step);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Enable the stub if the kernel command line has the `gdb` flag.
+///
+/// # Safety
+///
+/// - Must only be called during kernel init, after the command line was read.
+pub unsafe fn init() -> Result<(), KernelError> {
+    use crate::synchronization::interface::ReadWriteEx;
+
+    if !bsp::cmdline::cmdline().read(|cmdline| cmdline.flag("gdb")) {
+        return Ok(());
+    }
+
+    let alias = memory::mmu::kernel_map_code_alias("GDB stub code alias")?;
+    CODE_ALIAS.store(alias.as_usize(), Ordering::Relaxed);
+
+    ENABLED.store(true, Ordering::Release);
+
+    Ok(())
+}
+
+/// Whether the stub is enabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// Stop in the stub, if it is enabled.
+///
+/// For example, to attach GDB at a specific point during boot.
+#[inline(always)]
+pub fn breakpoint() {
+    if is_enabled() {
+        arch_gdbstub::breakpoint()
+    }
+}
+
+/// Check a character that the console received while the kernel runs.
+///
+/// Returns `true` if the character belongs to GDB. The console must then leave any remaining input
+/// to the stub, which takes over once the IRQ handler finished.
+pub fn console_input(c: char) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+
+    match c {
+        '\u{3}' => (),
+        '$' => PACKET_INTERRUPTED.store(true, Ordering::Relaxed),
+        _ => return false,
+    }
+
+    BREAK_REQUESTED.store(true, Ordering::Relaxed);
+
+    true
+}
+
+/// Return whether the console asked for a stop, and clear the request.
+#[inline(always)]
+pub fn take_break_request() -> bool {
+    BREAK_REQUESTED.load(Ordering::Relaxed) && BREAK_REQUESTED.swap(false, Ordering::Relaxed)
+}
+
+/// Let GDB take control of a stopped core.
+///
+/// Called by the exception handler. Returns when GDB resumes the core.
+pub fn handle_stop(core: &mut dyn interface::StoppedCore, reason: StopReason) {
+    // A completed step always ends stepping, even if no stub session follows.
+    core.set_single_step(false);
+
+    SESSION.lock(|_| session(core, reason));
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Numbers, register values and packet arguments must be decoded as GDB encodes them.
+    #[kernel_test]
+    fn gdbstub_parses_arguments() {
+        assert_eq!(parse_hex(b"ffff0000"), Some(0xffff_0000));
+        assert_eq!(parse_hex(b""), None);
+        assert_eq!(parse_hex(b"12g"), None);
+
+        assert_eq!(parse_hex_le(b"78563412", 4), Some(0x1234_5678));
+        assert_eq!(parse_hex_le(b"7856", 4), None);
+
+        assert_eq!(parse_addr_len(b"80000,4"), Some((0x80000, 4)));
+        assert_eq!(parse_addr_len(b"80000"), None);
+    }
+
+    /// Register values must be sent in target byte order.
+    #[kernel_test]
+    fn gdbstub_encodes_registers_little_endian() {
+        let mut packet = Packet::new();
+
+        packet.push_hex_le(0x1234_5678, 4);
+        assert_eq!(packet.as_bytes(), b"78563412");
+    }
+}
@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Kernel assertions and invariant checks.
+//!
+//! [kassert!] works like `assert!`, and additionally names the values that matter. They are
+//! printed with the panic message, and the crash dump records the failed expression and the values
+//! in `CRASH-ASSERT` lines:
+//!
+//! ```ignore
+//! kassert!(used <= total, used, total);
+//! ```
+//!
+//! [kassert_debug!] is only checked in test builds and in builds with debug assertions.
+//!
+//! Invariant checks validate the consistency of a subsystem's state, for example, that the
+//! mapping record matches the translation tables. Subsystems [register()] them, and [check_all()]
+//! runs them. With `invariants=<milliseconds>` on the kernel command line, they also run as a
+//! periodic task, which panics on a violation.
+
+use crate::{
+    synchronization::{interface::Mutex, IRQSafeSpinLock},
+    time, warn,
+};
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+const MAX_INVARIANTS: usize = 16;
+
+#[derive(Copy, Clone)]
+struct Invariant {
+    name: &'static str,
+    check: fn() -> Result<(), &'static str>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A failed assertion.
+pub struct Assertion<'a> {
+    /// The asserted expression.
+    pub expr: &'static str,
+
+    /// The named values, as written at the assertion.
+    pub values: &'a [(&'static str, &'a dyn fmt::Debug)],
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static INVARIANTS: IRQSafeSpinLock<[Option<Invariant>; MAX_INVARIANTS]> =
+    IRQSafeSpinLock::new([None; MAX_INVARIANTS]);
+
+/// The address of the first failed assertion. It lives on the stack of the panicking core.
+static FAILED_ASSERTION: AtomicUsize = AtomicUsize::new(0);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn periodic_check() {
+    let violations = check_all();
+    if violations != 0 {
+        panic!("{} invariant(s) violated", violations);
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl fmt::Display for Assertion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Assertion failed: {}", self.expr)?;
+
+        for (name, value) in self.values {
+            write!(f, "\n      {} = {:?}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Panic if `$cond` is false, recording the values of the given expressions.
+#[macro_export]
+macro_rules! kassert {
+    ($cond:expr $(, $value:expr)* $(,)?) => {
+        if !$cond {
+            $crate::debug::invariant::assertion_failed(
+                stringify!($cond),
+                &[$((stringify!($value), &$value as &dyn core::fmt::Debug)),*],
+            );
+        }
+    };
+}
+
+/// Like [kassert!], but only checked in test builds and in builds with debug assertions.
+#[cfg(any(debug_assertions, feature = "test_build"))]
+#[macro_export]
+macro_rules! kassert_debug {
+    ($($arg:tt)*) => {
+        $crate::kassert!($($arg)*)
+    };
+}
+
+/// Like [kassert!], but only checked in test builds and in builds with debug assertions.
+#[cfg(not(any(debug_assertions, feature = "test_build")))]
+#[macro_export]
+macro_rules! kassert_debug {
+    ($($arg:tt)*) => {};
+}
+
+/// Record the failed assertion for the crash dump and panic. Used by [kassert!].
+#[cold]
+#[track_caller]
+#[doc(hidden)]
+pub fn assertion_failed(expr: &'static str, values: &[(&'static str, &dyn fmt::Debug)]) -> ! {
+    let assertion = Assertion { expr, values };
+
+    // The panic handler does not return, so the assertion stays valid while the dump is written.
+    FAILED_ASSERTION
+        .compare_exchange(
+            0,
+            &assertion as *const Assertion as usize,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        )
+        .ok();
+
+    panic!("{}", assertion)
+}
+
+/// Call `f` with the first assertion that failed.
+///
+/// `f` receives `None` if no assertion failed.
+pub fn with_failed_assertion<R>(f: impl FnOnce(Option<&Assertion>) -> R) -> R {
+    let addr = FAILED_ASSERTION.load(Ordering::Relaxed);
+
+    if addr == 0 {
+        return f(None);
+    }
+
+    f(Some(unsafe { &*(addr as *const Assertion) }))
+}
+
+/// Register the invariant check `check`.
+pub fn register(
+    name: &'static str,
+    check: fn() -> Result<(), &'static str>,
+) -> Result<(), &'static str> {
+    INVARIANTS.lock(|invariants| {
+        let slot = invariants
+            .iter_mut()
+            .find(|x| x.is_none())
+            .ok_or("Too many invariant checks")?;
+
+        *slot = Some(Invariant { name, check });
+
+        Ok(())
+    })
+}
+
+/// Remove the invariant check `name`.
+pub fn unregister(name: &'static str) {
+    INVARIANTS.lock(|invariants| {
+        for slot in invariants.iter_mut() {
+            if matches!(slot, Some(x) if x.name == name) {
+                *slot = None;
+            }
+        }
+    })
+}
+
+/// Run all invariant checks. Violations are reported as warnings.
+///
+/// Returns the number of violated invariants.
+pub fn check_all() -> usize {
+    // Checks run outside of the lock, so that they can take their subsystem's locks freely.
+    let invariants = INVARIANTS.lock(|invariants| *invariants);
+    let mut violations = 0;
+
+    for invariant in invariants.iter().flatten() {
+        if let Err(x) = (invariant.check)() {
+            warn!("Invariant violated: {}: {}", invariant.name, x);
+            violations += 1;
+        }
+    }
+
+    violations
+}
+
+/// Run all invariant checks every `interval` as a periodic task.
+pub fn check_periodically(interval: Duration) -> Result<(), &'static str> {
+    time::periodic::spawn("Invariant checks", interval, periodic_check)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    fn passing() -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn failing() -> Result<(), &'static str> {
+        Err("Test violation")
+    }
+
+    /// Check that only violated invariants are counted.
+    #[kernel_test]
+    fn invariant_violations_are_counted() {
+        register("invariant::passing", passing).unwrap();
+        register("invariant::failing", failing).unwrap();
+        assert_eq!(check_all(), 1);
+
+        unregister("invariant::failing");
+        assert_eq!(check_all(), 0);
+        unregister("invariant::passing");
+    }
+
+    /// Check that the mapping record invariant holds for the test kernel.
+    #[kernel_test]
+    fn kernel_mappings_match_record() {
+        assert_eq!(crate::memory::mmu::kernel_check_mappings(), Ok(()));
+    }
+
+    /// Check that passing assertions do not evaluate into a failure.
+    #[kernel_test]
+    fn kassert_passes() {
+        let (used, total) = (1, 2);
+
+        kassert!(used <= total, used, total);
+        kassert_debug!(used <= total);
+        kassert!(used < total,);
+    }
+}
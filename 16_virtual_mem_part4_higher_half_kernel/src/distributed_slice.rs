@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Slices whose elements are registered at their definition site.
+//!
+//! A slice is declared once with [distributed_slice!](crate::distributed_slice). Each element is
+//! then added with [distributed_slice_entry!](crate::distributed_slice_entry) next to the code it
+//! belongs to, instead of being listed in a central table that every addition has to touch.
+//!
+//! Every slice gets its own set of linker sections, which the linker script sorts by name and keeps
+//! contiguous in `.rodata`:
+//!
+//! ```
+//! .dslice.NAME.0      Zero-sized start marker.
+//! .dslice.NAME.1      Entries without an order, in link order.
+//! .dslice.NAME.1.x    Entries with order `x`, sorted by `x`.
+//! .dslice.NAME.2      Zero-sized end marker.
+//! ```
+//!
+//! Slice names are global: two slices with the same name end up as one.
+//!
+//! The linker only keeps the entries of object files that it links. The kernel is built with LTO,
+//! which puts all entries into the one object file that makes it.
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A slice whose elements are collected by the linker.
+///
+/// Use [distributed_slice!](crate::distributed_slice) to declare instances.
+pub struct DistributedSlice<T: 'static> {
+    start: &'static [T; 0],
+    end: &'static [T; 0],
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Declare a distributed slice.
+///
+/// ```
+/// distributed_slice! {
+///     /// The kernel's shell commands.
+///     pub static COMMANDS: [Command];
+/// }
+/// ```
+#[macro_export]
+macro_rules! distributed_slice {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: [$ty:ty];) => {
+        $(#[$attr])*
+        $vis static $name: $crate::distributed_slice::DistributedSlice<$ty> = {
+            #[used]
+            #[link_section = concat!(".dslice.", stringify!($name), ".0")]
+            static START: [$ty; 0] = [];
+
+            #[used]
+            #[link_section = concat!(".dslice.", stringify!($name), ".2")]
+            static END: [$ty; 0] = [];
+
+            unsafe { $crate::distributed_slice::DistributedSlice::new(&START, &END) }
+        };
+    };
+}
+
+/// Add an element to a distributed slice.
+///
+/// The slice must be in scope. An optional order, given in brackets, sorts the element among the
+/// slice's other ordered elements. Elements without an order come first.
+///
+/// ```
+/// distributed_slice_entry!(COMMANDS: Command = Command::new("uptime", uptime));
+/// distributed_slice_entry!(COMMANDS["0"]: Command = Command::new("help", help));
+/// ```
+#[macro_export]
+macro_rules! distributed_slice_entry {
+    ($slice:ident $([$order:literal])?: $ty:ty = $init:expr) => {
+        const _: () = {
+            #[used]
+            #[link_section = concat!(".dslice.", stringify!($slice), ".1" $(, ".", $order)?)]
+            static ENTRY: $ty = $init;
+
+            // Reject entries whose type does not match the slice.
+            fn _check() -> &'static $crate::distributed_slice::DistributedSlice<$ty> {
+                &$slice
+            }
+        };
+    };
+}
+
+unsafe impl<T> Sync for DistributedSlice<T> where T: Sync {}
+
+impl<T> DistributedSlice<T> {
+    /// Create an instance.
+    ///
+    /// Only use through [distributed_slice!](crate::distributed_slice).
+    ///
+    /// # Safety
+    ///
+    /// - `start` and `end` must be the markers of the same slice.
+    #[doc(hidden)]
+    pub const unsafe fn new(start: &'static [T; 0], end: &'static [T; 0]) -> Self {
+        Self { start, end }
+    }
+
+    /// Return the elements as a regular slice.
+    pub fn as_slice(&self) -> &'static [T] {
+        let size = core::mem::size_of::<T>();
+        let start = self.start.as_ptr() as usize;
+        let end = self.end.as_ptr() as usize;
+
+        assert!(size != 0, "Zero-sized distributed slice elements");
+        assert!(
+            end >= start && (end - start) % size == 0,
+            "Distributed slice not laid out by the linker script"
+        );
+
+        unsafe { core::slice::from_raw_parts(start as *const T, (end - start) / size) }
+    }
+
+    /// Return an iterator over the elements.
+    pub fn iter(&self) -> core::slice::Iter<'static, T> {
+        self.as_slice().iter()
+    }
+
+    /// Return the number of elements.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Whether the slice has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use test_macros::kernel_test;
+
+    distributed_slice! {
+        static TEST_SLICE: [u64];
+    }
+
+    distributed_slice_entry!(TEST_SLICE["1"]: u64 = 3);
+    distributed_slice_entry!(TEST_SLICE["0"]: u64 = 2);
+    distributed_slice_entry!(TEST_SLICE: u64 = 1);
+
+    /// Check that all entries are collected, and that unordered ones come first.
+    #[kernel_test]
+    fn distributed_slice_collects_entries_in_order() {
+        assert_eq!(TEST_SLICE.as_slice(), &[1, 2, 3]);
+    }
+}
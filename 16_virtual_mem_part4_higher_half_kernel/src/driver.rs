@@ -3,6 +3,29 @@
 // Copyright (c) 2018-2022 Andre Richter <andre.o.richter@gmail.com>
 
 //! Driver support.
+//!
+//! Device drivers are registered with [register_device_driver!](crate::register_device_driver)
+//! next to their instance, and collected in [DEVICE_DRIVERS].
+//!
+//! Runtime operations, like changing a UART's baud rate, go through
+//! [DeviceDriver::control()](interface::DeviceDriver::control). Each driver defines its own request
+//! type, for example [PL011UartRequest](crate::bsp::PL011UartRequest), and downcasts the requests
+//! it gets:
+//!
+//! ```
+//! let mut request = PL011UartRequest::SetBaudRate(115_200);
+//! driver_manager().control("BCM PL011 UART", &mut request)?;
+//! ```
+//!
+//! Devices that offer a plain byte stream or block storage also register with [chardev] or
+//! [blockdev], which give them a [DeviceId] and a name.
+
+pub mod blockdev;
+pub mod chardev;
+pub mod led;
+
+use crate::distributed_slice;
+use core::fmt;
 
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
@@ -10,6 +33,9 @@
 
 /// Driver interfaces.
 pub mod interface {
+    use crate::error::{ErrorKind, KernelError};
+    use core::any::Any;
+
     /// Device Driver functions.
     pub trait DeviceDriver {
         /// Return a compatibility string for identifying the driver.
@@ -20,7 +46,19 @@ pub mod interface {
         /// # Safety
         ///
         /// - During init, drivers might do stuff with system-wide impact.
-        unsafe fn init(&self) -> Result<(), &'static str> {
+        unsafe fn init(&self) -> Result<(), KernelError> {
+            Ok(())
+        }
+
+        /// Called by the kernel on each secondary core during its bring-up.
+        ///
+        /// Only needed for devices that have per-core state, for example the banked CPU interface
+        /// of an interrupt controller.
+        ///
+        /// # Safety
+        ///
+        /// - Must only be called after `init()` completed on the boot core.
+        unsafe fn init_secondary_core(&self) -> Result<(), KernelError> {
             Ok(())
         }
 
@@ -28,7 +66,7 @@ pub mod interface {
         ///
         /// Rust's type system will prevent a call to this function unless the calling instance
         /// itself has static lifetime.
-        fn register_and_enable_irq_handler(&'static self) -> Result<(), &'static str> {
+        fn register_and_enable_irq_handler(&'static self) -> Result<(), KernelError> {
             Ok(())
         }
 
@@ -39,24 +77,139 @@ pub mod interface {
         fn virt_mmio_start_addr(&self) -> Option<usize> {
             None
         }
+
+        /// Called by the kernel to run a driver-specific operation at runtime.
+        ///
+        /// `request` is an instance of the driver's request type. Requests that return data carry
+        /// fields for the driver to fill in. Requests of a different type fail with
+        /// [ErrorKind::NotSupported].
+        fn control(&self, _request: &mut dyn Any) -> Result<(), KernelError> {
+            Err(KernelError::new(
+                ErrorKind::NotSupported,
+                "Control request not supported",
+            ))
+        }
     }
 
     /// Device driver management functions.
     ///
     /// The `BSP` is supposed to supply one global instance.
     pub trait DriverManager {
-        /// Return a slice of references to all `BSP`-instantiated drivers.
-        fn all_device_drivers(&self) -> &[&'static (dyn DeviceDriver + Sync)];
+        /// Return all registered drivers.
+        fn all_device_drivers(&self) -> &[super::DeviceDriverDescriptor];
 
         /// Return only those drivers needed for the BSP's early printing functionality.
         ///
         /// For example, the default UART.
-        fn early_print_device_drivers(&self) -> &[&'static (dyn DeviceDriver + Sync)];
+        fn early_print_device_drivers(&self) -> &[super::DeviceDriverDescriptor];
 
         /// Return all drivers minus early-print drivers.
-        fn non_early_print_device_drivers(&self) -> &[&'static (dyn DeviceDriver + Sync)];
+        fn non_early_print_device_drivers(&self) -> &[super::DeviceDriverDescriptor];
 
         /// Initialization code that runs after the early print driver init.
         fn post_early_print_device_driver_init(&self);
+
+        /// Run a control request on the driver with compatibility string `compatible`.
+        fn control(&self, compatible: &str, request: &mut dyn Any) -> Result<(), KernelError> {
+            self.all_device_drivers()
+                .iter()
+                .find(|descriptor| descriptor.driver.compatible() == compatible)
+                .ok_or_else(|| KernelError::new(ErrorKind::InvalidArgument, "No such driver"))?
+                .driver
+                .control(request)
+        }
+    }
+}
+
+/// A device number, made of the major number of a device class and the minor number of a device
+/// within the class.
+///
+/// The numbers follow Linux where there is an equivalent, for example `5:1` for the console.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeviceId {
+    major: u16,
+    minor: u16,
+}
+
+/// A registered device driver.
+pub struct DeviceDriverDescriptor {
+    /// The driver instance.
+    pub driver: &'static (dyn interface::DeviceDriver + Sync),
+
+    /// Whether the driver is needed for the BSP's early printing functionality.
+    pub early_print: bool,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+distributed_slice! {
+    /// All registered device drivers. Early-print drivers come first, otherwise the order is
+    /// unspecified.
+    pub static DEVICE_DRIVERS: [DeviceDriverDescriptor];
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl DeviceId {
+    /// Create an instance.
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
     }
+
+    /// The major number.
+    pub const fn major(&self) -> u16 {
+        self.major
+    }
+
+    /// The minor number.
+    pub const fn minor(&self) -> u16 {
+        self.minor
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.major, self.minor)
+    }
+}
+
+/// Register a device driver instance.
+///
+/// Drivers that are needed for early printing, like the default UART, are marked with
+/// `early_print`.
+///
+/// ```
+/// register_device_driver!(early_print PL011_UART);
+/// register_device_driver!(INTERRUPT_CONTROLLER);
+/// ```
+#[macro_export]
+macro_rules! register_device_driver {
+    (early_print $driver:path) => {
+        const _: () = {
+            use $crate::driver::{DeviceDriverDescriptor, DEVICE_DRIVERS};
+
+            $crate::distributed_slice_entry!(
+                DEVICE_DRIVERS["0"]: DeviceDriverDescriptor = DeviceDriverDescriptor {
+                    driver: &$driver,
+                    early_print: true,
+                }
+            );
+        };
+    };
+    ($driver:path) => {
+        const _: () = {
+            use $crate::driver::{DeviceDriverDescriptor, DEVICE_DRIVERS};
+
+            $crate::distributed_slice_entry!(
+                DEVICE_DRIVERS["1"]: DeviceDriverDescriptor = DeviceDriverDescriptor {
+                    driver: &$driver,
+                    early_print: false,
+                }
+            );
+        };
+    };
 }
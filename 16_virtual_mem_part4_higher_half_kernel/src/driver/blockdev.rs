@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Block devices.
+//!
+//! Storage that is accessed in fixed-size blocks, like an SD card. Devices are registered with
+//! [register_block_device!](crate::register_block_device) and looked up by [DeviceId] or by name.
+
+use super::DeviceId;
+use crate::distributed_slice;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Block device interfaces.
+pub mod interface {
+    use crate::error::KernelError;
+
+    /// Block access functions.
+    ///
+    /// Buffers must hold a whole number of blocks.
+    pub trait BlockDevice {
+        /// The size of a block in bytes.
+        fn block_size(&self) -> usize;
+
+        /// The number of blocks.
+        fn num_blocks(&self) -> u64;
+
+        /// Read the blocks starting at block `start` into `buf`.
+        fn read_blocks(&self, start: u64, buf: &mut [u8]) -> Result<(), KernelError>;
+
+        /// Write `buf` to the blocks starting at block `start`.
+        fn write_blocks(&self, start: u64, buf: &[u8]) -> Result<(), KernelError>;
+
+        /// Block until written blocks reached the storage.
+        fn flush(&self) -> Result<(), KernelError> {
+            Ok(())
+        }
+    }
+}
+
+/// A registered block device.
+pub struct BlockDeviceDescriptor {
+    /// The device number.
+    pub id: DeviceId,
+
+    /// The device name, for example `mmcblk0`.
+    pub name: &'static str,
+
+    /// The device.
+    pub device: &'static (dyn interface::BlockDevice + Sync),
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+distributed_slice! {
+    /// All registered block devices, in unspecified order.
+    pub static BLOCK_DEVICES: [BlockDeviceDescriptor];
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Register a block device.
+///
+/// Device numbers must be unique among the block devices.
+///
+/// ```
+/// register_block_device!(179, 0, "mmcblk0", EMMC);
+/// ```
+#[macro_export]
+macro_rules! register_block_device {
+    ($major:expr, $minor:expr, $name:expr, $device:path) => {
+        const _: () = {
+            use $crate::driver::blockdev::{BlockDeviceDescriptor, BLOCK_DEVICES};
+
+            $crate::distributed_slice_entry!(
+                BLOCK_DEVICES: BlockDeviceDescriptor = BlockDeviceDescriptor {
+                    id: $crate::driver::DeviceId::new($major, $minor),
+                    name: $name,
+                    device: &$device,
+                }
+            );
+        };
+    };
+}
+
+/// Return the block device with number `id`.
+pub fn find(id: DeviceId) -> Option<&'static BlockDeviceDescriptor> {
+    BLOCK_DEVICES.iter().find(|descriptor| descriptor.id == id)
+}
+
+/// Return the block device called `name`.
+pub fn find_by_name(name: &str) -> Option<&'static BlockDeviceDescriptor> {
+    BLOCK_DEVICES
+        .iter()
+        .find(|descriptor| descriptor.name == name)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{ErrorKind, KernelError},
+        register_block_device,
+        synchronization::{interface::Mutex, IRQSafeSpinLock},
+    };
+    use test_macros::kernel_test;
+
+    const BLOCK_SIZE: usize = 512;
+    const NUM_BLOCKS: usize = 4;
+
+    struct RamDisk {
+        data: IRQSafeSpinLock<[u8; BLOCK_SIZE * NUM_BLOCKS]>,
+    }
+
+    impl RamDisk {
+        fn range(&self, start: u64, len: usize) -> Result<core::ops::Range<usize>, KernelError> {
+            let start = start as usize * BLOCK_SIZE;
+
+            if len % BLOCK_SIZE != 0 || start + len > BLOCK_SIZE * NUM_BLOCKS {
+                return Err(KernelError::new(ErrorKind::InvalidArgument, "Out of range"));
+            }
+
+            Ok(start..start + len)
+        }
+    }
+
+    impl interface::BlockDevice for RamDisk {
+        fn block_size(&self) -> usize {
+            BLOCK_SIZE
+        }
+
+        fn num_blocks(&self) -> u64 {
+            NUM_BLOCKS as u64
+        }
+
+        fn read_blocks(&self, start: u64, buf: &mut [u8]) -> Result<(), KernelError> {
+            let range = self.range(start, buf.len())?;
+            self.data.lock(|data| buf.copy_from_slice(&data[range]));
+
+            Ok(())
+        }
+
+        fn write_blocks(&self, start: u64, buf: &[u8]) -> Result<(), KernelError> {
+            let range = self.range(start, buf.len())?;
+            self.data.lock(|data| data[range].copy_from_slice(buf));
+
+            Ok(())
+        }
+    }
+
+    static RAM_DISK: RamDisk = RamDisk {
+        data: IRQSafeSpinLock::new([0; BLOCK_SIZE * NUM_BLOCKS]),
+    };
+
+    register_block_device!(1, 0, "ram0", RAM_DISK);
+
+    /// Check that a registered device is found, and that block access goes through to it.
+    #[kernel_test]
+    fn block_device_lookup_works() {
+        let ram0 = find_by_name("ram0").unwrap();
+        assert_eq!(ram0.id, DeviceId::new(1, 0));
+        assert!(core::ptr::eq(find(ram0.id).unwrap(), ram0));
+
+        let written = [0xa5; BLOCK_SIZE];
+        let mut read = [0; BLOCK_SIZE];
+        ram0.device.write_blocks(2, &written).unwrap();
+        ram0.device.read_blocks(2, &mut read).unwrap();
+        assert_eq!(read, written);
+
+        assert!(ram0.device.read_blocks(4, &mut read).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Character devices.
+//!
+//! Byte streams like UARTs or random number generators. Devices are registered with
+//! [register_char_device!](crate::register_char_device) and looked up by [DeviceId] or by name.
+
+use super::DeviceId;
+use crate::{distributed_slice, error::KernelError};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Character device interfaces.
+pub mod interface {
+    use crate::error::KernelError;
+
+    /// Byte stream functions.
+    pub trait CharDevice {
+        /// Read into `buf`.
+        ///
+        /// Blocks until at least one byte is available, then returns the number of bytes read.
+        fn read(&self, buf: &mut [u8]) -> Result<usize, KernelError>;
+
+        /// Write `buf`. Returns the number of bytes written.
+        fn write(&self, buf: &[u8]) -> Result<usize, KernelError>;
+
+        /// Block until the written bytes reached the device.
+        fn flush(&self) -> Result<(), KernelError> {
+            Ok(())
+        }
+    }
+}
+
+/// A registered character device.
+pub struct CharDeviceDescriptor {
+    /// The device number.
+    pub id: DeviceId,
+
+    /// The device name, for example `ttyAMA0`.
+    pub name: &'static str,
+
+    /// The device.
+    pub device: &'static (dyn interface::CharDevice + Sync),
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+distributed_slice! {
+    /// All registered character devices, in unspecified order.
+    pub static CHAR_DEVICES: [CharDeviceDescriptor];
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Register a character device.
+///
+/// Device numbers must be unique among the character devices.
+///
+/// ```
+/// register_char_device!(204, 64, "ttyAMA0", PL011_UART);
+/// ```
+#[macro_export]
+macro_rules! register_char_device {
+    ($major:expr, $minor:expr, $name:expr, $device:path) => {
+        const _: () = {
+            use $crate::driver::chardev::{CharDeviceDescriptor, CHAR_DEVICES};
+
+            $crate::distributed_slice_entry!(
+                CHAR_DEVICES: CharDeviceDescriptor = CharDeviceDescriptor {
+                    id: $crate::driver::DeviceId::new($major, $minor),
+                    name: $name,
+                    device: &$device,
+                }
+            );
+        };
+    };
+}
+
+/// Return the character device with number `id`.
+pub fn find(id: DeviceId) -> Option<&'static CharDeviceDescriptor> {
+    CHAR_DEVICES.iter().find(|descriptor| descriptor.id == id)
+}
+
+/// Return the character device called `name`.
+pub fn find_by_name(name: &str) -> Option<&'static CharDeviceDescriptor> {
+    CHAR_DEVICES
+        .iter()
+        .find(|descriptor| descriptor.name == name)
+}
+
+/// Write all of `buf` to `device`.
+pub fn write_all(
+    device: &(dyn interface::CharDevice + Sync),
+    mut buf: &[u8],
+) -> Result<(), KernelError> {
+    while !buf.is_empty() {
+        let written = device.write(buf)?;
+        buf = &buf[written..];
+    }
+
+    Ok(())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check that the BSP's console is registered, and that lookups by number and name agree.
+    #[kernel_test]
+    fn char_device_lookup_works() {
+        let console = find_by_name("console").unwrap();
+
+        assert_eq!(console.id, DeviceId::new(5, 1));
+        assert!(core::ptr::eq(find(console.id).unwrap(), console));
+        assert!(find_by_name("nonexistent").is_none());
+    }
+}
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! LED strips.
+//!
+//! Chains of individually addressable RGB LEDs, like WS2812 "NeoPixels". The first pixel goes to
+//! the LED closest to the data input.
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// LED strip interfaces.
+pub mod interface {
+    use super::Rgb;
+    use crate::error::KernelError;
+
+    /// LED strip functions.
+    pub trait LedStrip {
+        /// Show `pixels`. Returns once the LEDs latched them.
+        fn set_pixels(&self, pixels: &[Rgb]) -> Result<(), KernelError>;
+    }
+}
+
+/// The color of a pixel.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rgb {
+    /// Red.
+    pub r: u8,
+
+    /// Green.
+    pub g: u8,
+
+    /// Blue.
+    pub b: u8,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Rgb {
+    /// Create an instance.
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Early console.
+//!
+//! Until the console driver is up, `kernel_init()` can not print, so errors during bring-up used to
+//! park the core without a word. With the `earlycon` feature, they are written to the UART
+//! directly instead:
+//!
+//! - Before the MMU is on, [write_str_phys()] uses the UART's physical address. It only writes
+//!   plain strings, because formatting needs absolute addresses that are not valid yet.
+//! - After [init()] mapped the UART, [early_println!](crate::early_println) also formats.
+//!
+//! The early console does not configure the UART. It relies on the firmware having done so, which
+//! it does with `enable_uart=1` in `config.txt`. Once the console driver is up, the early console
+//! must not be used anymore, since it bypasses the driver's lock.
+//!
+//! Without the feature, all of this compiles to nothing.
+
+use crate::{bsp, config, memory};
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+/// The virtual start address of the UART's MMIO, or zero before [init()].
+static VIRT_MMIO_START_ADDR: AtomicUsize = AtomicUsize::new(0);
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Write `s` through the UART's physical address.
+///
+/// # Safety
+///
+/// - Only use before the MMU is on.
+pub unsafe fn write_str_phys(s: &str) {
+    use fmt::Write;
+
+    if !config::EARLYCON {
+        return;
+    }
+
+    let phys_mmio_start_addr = bsp::console::early_console_mmio_descriptor()
+        .start_addr()
+        .as_usize();
+    bsp::console::early_console_out(phys_mmio_start_addr)
+        .write_str(s)
+        .ok();
+}
+
+/// Map the UART, so that the early console can be used with the MMU on.
+///
+/// The console driver's own mapping reuses this one later. If mapping fails, the early console
+/// stays silent.
+///
+/// # Safety
+///
+/// - Same as [memory::mmu::kernel_map_mmio()].
+/// - Must be called after [memory::mmu::post_enable_init()].
+pub unsafe fn init() {
+    if !config::EARLYCON {
+        return;
+    }
+
+    let mmio_descriptor = bsp::console::early_console_mmio_descriptor();
+    if let Ok(virt_addr) = memory::mmu::kernel_map_mmio("Early console", &mmio_descriptor) {
+        VIRT_MMIO_START_ADDR.store(virt_addr.as_usize(), Ordering::Relaxed);
+    }
+}
+
+/// The virtual start address of the UART's MMIO, if [init()] mapped it.
+pub fn virt_mmio_start_addr() -> Option<usize> {
+    match VIRT_MMIO_START_ADDR.load(Ordering::Relaxed) {
+        0 => None,
+        addr => Some(addr),
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use fmt::Write;
+
+    if let Some(addr) = virt_mmio_start_addr() {
+        unsafe { bsp::console::early_console_out(addr) }
+            .write_fmt(args)
+            .ok();
+    }
+}
+
+/// Prints with a newline through the early console.
+///
+/// Only use during bring-up, before the console driver is up.
+#[macro_export]
+macro_rules! early_println {
+    ($($arg:tt)*) => ({
+        $crate::earlycon::_print(format_args_nl!($($arg)*));
+    })
+}
@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! ELF64 file parsing.
+//!
+//! Validates static `aarch64` ELF executables and describes their loadable segments in terms of the
+//! kernel's architecture agnostic [AttributeFields].
+//!
+//! The kernel does not have user address spaces or `EL0` tasks yet, so this module stops at
+//! describing _what_ has to be mapped. Actually mapping the segments and starting a program is left
+//! to a later stage.
+
+use crate::memory::{
+    mmu::{AccessPermissions, AttributeFields, MemAttributes},
+    Address, Virtual,
+};
+use core::ops::Range;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+const ET_EXEC: u16 = 2;
+const EM_AARCH64: u16 = 183;
+
+const ELF64_HEADER_SIZE: usize = 64;
+const ELF64_PHDR_SIZE: usize = 56;
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+
+const PF_X: u32 = 1 << 0;
+const PF_W: u32 = 1 << 1;
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, &'static str> {
+    data.get(offset..offset + 2)
+        .map(|x| u16::from_le_bytes(x.try_into().unwrap()))
+        .ok_or("ELF: Read out of bounds")
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, &'static str> {
+    data.get(offset..offset + 4)
+        .map(|x| u32::from_le_bytes(x.try_into().unwrap()))
+        .ok_or("ELF: Read out of bounds")
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, &'static str> {
+    data.get(offset..offset + 8)
+        .map(|x| u64::from_le_bytes(x.try_into().unwrap()))
+        .ok_or("ELF: Read out of bounds")
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A validated ELF64 executable.
+pub struct ElfFile<'a> {
+    data: &'a [u8],
+    entry: u64,
+    ph_offset: usize,
+    ph_entry_size: usize,
+    ph_count: usize,
+}
+
+/// Description of a `PT_LOAD` segment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadSegment {
+    virt_start_addr: Address<Virtual>,
+    mem_size: usize,
+    file_range: Range<usize>,
+    attributes: AttributeFields,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl<'a> ElfFile<'a> {
+    /// Validate the ELF header and the program header table.
+    ///
+    /// Only static, little-endian `aarch64` executables are accepted.
+    pub fn parse(data: &'a [u8]) -> Result<Self, &'static str> {
+        if data.len() < ELF64_HEADER_SIZE {
+            return Err("ELF: File too small");
+        }
+
+        if data[0..4] != ELF_MAGIC {
+            return Err("ELF: Bad magic");
+        }
+
+        if data[4] != ELFCLASS64 {
+            return Err("ELF: Not a 64 bit file");
+        }
+
+        if data[5] != ELFDATA2LSB {
+            return Err("ELF: Not little-endian");
+        }
+
+        if data[6] != EV_CURRENT {
+            return Err("ELF: Unknown version");
+        }
+
+        if read_u16(data, 16)? != ET_EXEC {
+            return Err("ELF: Not a static executable");
+        }
+
+        if read_u16(data, 18)? != EM_AARCH64 {
+            return Err("ELF: Not an aarch64 file");
+        }
+
+        let entry = read_u64(data, 24)?;
+        let ph_offset = read_u64(data, 32)? as usize;
+        let ph_entry_size = read_u16(data, 54)? as usize;
+        let ph_count = read_u16(data, 56)? as usize;
+
+        if ph_entry_size < ELF64_PHDR_SIZE {
+            return Err("ELF: Program header entry too small");
+        }
+
+        let ph_table_end = ph_entry_size
+            .checked_mul(ph_count)
+            .and_then(|x| x.checked_add(ph_offset))
+            .ok_or("ELF: Program header table overflow")?;
+        if ph_table_end > data.len() {
+            return Err("ELF: Program header table out of bounds");
+        }
+
+        let elf = Self {
+            data,
+            entry,
+            ph_offset,
+            ph_entry_size,
+            ph_count,
+        };
+
+        // Validate all segments upfront, so that later users can iterate without error handling.
+        let mut num_load_segments = 0;
+        for i in 0..ph_count {
+            if elf.segment(i)?.is_some() {
+                num_load_segments += 1;
+            }
+        }
+
+        if num_load_segments == 0 {
+            return Err("ELF: No loadable segments");
+        }
+
+        Ok(elf)
+    }
+
+    /// The program's entry point.
+    pub fn entry(&self) -> Address<Virtual> {
+        Address::new(self.entry as usize)
+    }
+
+    /// Iterate over the loadable segments.
+    pub fn load_segments(&self) -> impl Iterator<Item = LoadSegment> + '_ {
+        // Unwrap is fine, all segments have been validated in parse().
+        (0..self.ph_count).filter_map(|i| self.segment(i).unwrap())
+    }
+
+    /// The file contents that back a segment.
+    ///
+    /// The remaining `mem_size() - file_data().len()` bytes must be zero-filled by the loader.
+    pub fn file_data(&self, segment: &LoadSegment) -> &'a [u8] {
+        &self.data[segment.file_range.clone()]
+    }
+
+    fn segment(&self, index: usize) -> Result<Option<LoadSegment>, &'static str> {
+        let base = self.ph_offset + index * self.ph_entry_size;
+
+        match read_u32(self.data, base)? {
+            PT_LOAD => (),
+            PT_DYNAMIC | PT_INTERP => return Err("ELF: Dynamically linked files not supported"),
+            _ => return Ok(None),
+        }
+
+        let flags = read_u32(self.data, base + 4)?;
+        let file_offset = read_u64(self.data, base + 8)? as usize;
+        let virt_addr = read_u64(self.data, base + 16)? as usize;
+        let file_size = read_u64(self.data, base + 32)? as usize;
+        let mem_size = read_u64(self.data, base + 40)? as usize;
+
+        if file_size > mem_size {
+            return Err("ELF: Segment file size exceeds memory size");
+        }
+
+        let file_end = file_offset
+            .checked_add(file_size)
+            .ok_or("ELF: Segment file range overflow")?;
+        if file_end > self.data.len() {
+            return Err("ELF: Segment out of bounds");
+        }
+
+        if virt_addr.checked_add(mem_size).is_none() {
+            return Err("ELF: Segment address range overflow");
+        }
+
+        if (flags & PF_W != 0) && (flags & PF_X != 0) {
+            return Err("ELF: Writable and executable segments not supported");
+        }
+
+        let acc_perms = if flags & PF_W != 0 {
+            AccessPermissions::ReadWrite
+        } else {
+            AccessPermissions::ReadOnly
+        };
+
+        Ok(Some(LoadSegment {
+            virt_start_addr: Address::new(virt_addr),
+            mem_size,
+            file_range: file_offset..file_end,
+            attributes: AttributeFields {
+                mem_attributes: MemAttributes::CacheableDRAM,
+                acc_perms,
+                execute_never: flags & PF_X == 0,
+            },
+        }))
+    }
+}
+
+impl LoadSegment {
+    /// The virtual start address of the segment.
+    pub fn virt_start_addr(&self) -> Address<Virtual> {
+        self.virt_start_addr
+    }
+
+    /// The size of the segment in memory.
+    pub fn mem_size(&self) -> usize {
+        self.mem_size
+    }
+
+    /// The attributes the segment must be mapped with.
+    pub fn attributes(&self) -> AttributeFields {
+        self.attributes
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    const TEST_FILE_SIZE: usize = ELF64_HEADER_SIZE + 2 * ELF64_PHDR_SIZE;
+
+    /// Construct an executable with a text (R-X) and a bss (RW-) segment.
+    fn test_file() -> [u8; TEST_FILE_SIZE] {
+        let mut f = [0_u8; TEST_FILE_SIZE];
+
+        f[0..4].copy_from_slice(&ELF_MAGIC);
+        f[4] = ELFCLASS64;
+        f[5] = ELFDATA2LSB;
+        f[6] = EV_CURRENT;
+        f[16..18].copy_from_slice(&ET_EXEC.to_le_bytes());
+        f[18..20].copy_from_slice(&EM_AARCH64.to_le_bytes());
+        f[24..32].copy_from_slice(&0x1_0000_u64.to_le_bytes());
+        f[32..40].copy_from_slice(&(ELF64_HEADER_SIZE as u64).to_le_bytes());
+        f[54..56].copy_from_slice(&(ELF64_PHDR_SIZE as u16).to_le_bytes());
+        f[56..58].copy_from_slice(&2_u16.to_le_bytes());
+
+        let text = ELF64_HEADER_SIZE;
+        f[text..text + 4].copy_from_slice(&PT_LOAD.to_le_bytes());
+        f[text + 4..text + 8].copy_from_slice(&(PF_X | 0b100).to_le_bytes());
+        f[text + 16..text + 24].copy_from_slice(&0x1_0000_u64.to_le_bytes());
+        f[text + 32..text + 40].copy_from_slice(&(TEST_FILE_SIZE as u64).to_le_bytes());
+        f[text + 40..text + 48].copy_from_slice(&(TEST_FILE_SIZE as u64).to_le_bytes());
+
+        let bss = ELF64_HEADER_SIZE + ELF64_PHDR_SIZE;
+        f[bss..bss + 4].copy_from_slice(&PT_LOAD.to_le_bytes());
+        f[bss + 4..bss + 8].copy_from_slice(&(PF_W | 0b100).to_le_bytes());
+        f[bss + 16..bss + 24].copy_from_slice(&0x2_0000_u64.to_le_bytes());
+        f[bss + 40..bss + 48].copy_from_slice(&0x1000_u64.to_le_bytes());
+
+        f
+    }
+
+    /// Check that segments are described with the correct attributes.
+    #[kernel_test]
+    fn elf_load_segments_sanity() {
+        let file = test_file();
+        let elf = ElfFile::parse(&file).unwrap();
+
+        assert_eq!(elf.entry().as_usize(), 0x1_0000);
+
+        let mut segments = elf.load_segments();
+
+        let text = segments.next().unwrap();
+        assert_eq!(text.virt_start_addr().as_usize(), 0x1_0000);
+        assert_eq!(text.attributes().acc_perms, AccessPermissions::ReadOnly);
+        assert!(!text.attributes().execute_never);
+        assert_eq!(elf.file_data(&text).len(), TEST_FILE_SIZE);
+
+        let bss = segments.next().unwrap();
+        assert_eq!(bss.mem_size(), 0x1000);
+        assert_eq!(bss.attributes().acc_perms, AccessPermissions::ReadWrite);
+        assert!(bss.attributes().execute_never);
+        assert!(elf.file_data(&bss).is_empty());
+
+        assert!(segments.next().is_none());
+    }
+
+    /// Check that malformed files are rejected.
+    #[kernel_test]
+    fn elf_parse_rejects_invalid_files() {
+        let mut file = test_file();
+        file[18] = 62; // EM_X86_64
+        assert!(ElfFile::parse(&file).is_err());
+
+        let mut file = test_file();
+        let text_flags = ELF64_HEADER_SIZE + 4;
+        file[text_flags..text_flags + 4].copy_from_slice(&(PF_W | PF_X).to_le_bytes());
+        assert!(ElfFile::parse(&file).is_err());
+
+        let file = test_file();
+        assert!(ElfFile::parse(&file[..ELF64_HEADER_SIZE]).is_err());
+    }
+}
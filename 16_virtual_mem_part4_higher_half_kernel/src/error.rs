@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Kernel errors.
+//!
+//! A [KernelError] records what went wrong and where, plus the context that callers added on the
+//! way up:
+//!
+//! ```ignore
+//! let virt_addr = memory::mmu::kernel_map_mmio(self.compatible(), &self.mmio_descriptor)
+//!     .context("Mapping the UART MMIO")?;
+//! ```
+//!
+//! Printing an error shows the chain on one line, outermost context first. The alternate form,
+//! `{:#}`, prints one line per link together with its source location, which is what boot
+//! failures use.
+//!
+//! Code that still returns `&'static str` errors converts into [KernelError] with `?`.
+
+use core::{fmt, panic::Location};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The maximum number of context links that an error keeps.
+const MAX_CONTEXT: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Context {
+    message: &'static str,
+    location: &'static Location<'static>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The category of an error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// An argument is out of range or inconsistent.
+    InvalidArgument,
+
+    /// A fixed-size resource, like a table or an address range, is exhausted.
+    OutOfResources,
+
+    /// The target is already taken, for example a mapped page or a registered IRQ.
+    AlreadyInUse,
+
+    /// The hardware or the configuration does not support the operation.
+    NotSupported,
+
+    /// Anything else, including errors converted from `&'static str`.
+    Other,
+}
+
+/// An error with its source location and the context added by callers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KernelError {
+    kind: ErrorKind,
+    message: &'static str,
+    location: &'static Location<'static>,
+
+    /// Innermost first.
+    context: [Option<Context>; MAX_CONTEXT],
+
+    /// Context links that did not fit.
+    num_dropped: usize,
+}
+
+/// Adding context to errors.
+pub trait ResultExt<T> {
+    /// Convert the error into a [KernelError] and add `message` as context.
+    fn context(self, message: &'static str) -> Result<T, KernelError>;
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl KernelError {
+    /// Create an instance at the caller's location.
+    #[track_caller]
+    pub fn new(kind: ErrorKind, message: &'static str) -> Self {
+        Self {
+            kind,
+            message,
+            location: Location::caller(),
+            context: [None; MAX_CONTEXT],
+            num_dropped: 0,
+        }
+    }
+
+    /// The category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The message of the innermost error, without context.
+    pub fn message(&self) -> &'static str {
+        self.message
+    }
+
+    /// The location where the innermost error was created.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Add `message` as context at the caller's location.
+    ///
+    /// If the error already holds the maximum number of links, the new one is counted but dropped.
+    #[track_caller]
+    pub fn with_context(mut self, message: &'static str) -> Self {
+        let context = Context {
+            message,
+            location: Location::caller(),
+        };
+
+        match self.context.iter_mut().find(|x| x.is_none()) {
+            Some(slot) => *slot = Some(context),
+            None => self.num_dropped += 1,
+        }
+
+        self
+    }
+
+    fn context_outermost_first(&self) -> impl Iterator<Item = &Context> {
+        self.context.iter().rev().flatten()
+    }
+}
+
+impl From<&'static str> for KernelError {
+    #[track_caller]
+    fn from(message: &'static str) -> Self {
+        Self::new(ErrorKind::Other, message)
+    }
+}
+
+impl fmt::Display for KernelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            for c in self.context_outermost_first() {
+                write!(f, "{}\n      at {}\n", c.message, c.location)?;
+            }
+            if self.num_dropped != 0 {
+                writeln!(f, "({} more)", self.num_dropped)?;
+            }
+
+            return write!(
+                f,
+                "Caused by: {} ({:?})\n      at {}",
+                self.message, self.kind, self.location
+            );
+        }
+
+        for c in self.context_outermost_first() {
+            write!(f, "{}: ", c.message)?;
+        }
+        if self.num_dropped != 0 {
+            write!(f, "...: ")?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+impl<T, E: Into<KernelError>> ResultExt<T> for Result<T, E> {
+    #[track_caller]
+    fn context(self, message: &'static str) -> Result<T, KernelError> {
+        match self {
+            Ok(x) => Ok(x),
+            Err(e) => Err(e.into().with_context(message)),
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    struct Buffer {
+        data: [u8; 64],
+        len: usize,
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+
+            self.data
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+
+            Ok(())
+        }
+    }
+
+    fn failing() -> Result<(), &'static str> {
+        Err("Inner failure")
+    }
+
+    fn propagating() -> Result<(), KernelError> {
+        failing()?;
+
+        Ok(())
+    }
+
+    /// Check that context is printed outermost first, and that conversions keep the message.
+    #[kernel_test]
+    fn kernel_error_chains_context() {
+        let e = propagating()
+            .context("Middle")
+            .context("Outer")
+            .unwrap_err();
+
+        assert_eq!(e.kind(), ErrorKind::Other);
+        assert_eq!(e.message(), "Inner failure");
+
+        let mut buf = Buffer {
+            data: [0; 64],
+            len: 0,
+        };
+        fmt::write(&mut buf, format_args!("{}", e)).unwrap();
+        assert_eq!(&buf.data[..buf.len], b"Outer: Middle: Inner failure");
+    }
+
+    /// Check that links beyond the maximum are counted.
+    #[kernel_test]
+    fn kernel_error_drops_excess_context() {
+        let mut e = KernelError::new(ErrorKind::OutOfResources, "Full");
+        for _ in 0..MAX_CONTEXT + 2 {
+            e = e.with_context("Context");
+        }
+
+        assert_eq!(e.num_dropped, 2);
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Kernel event notifications.
+//!
+//! Subsystems [publish()] events without knowing who reacts to them, and subscribers register next
+//! to their own code:
+//!
+//! ```
+//! fn on_event(event: &Event) {
+//!     if let Event::Undervoltage = event {
+//!         // Dim the LEDs.
+//!     }
+//! }
+//!
+//! register_event_subscriber!("LED dimmer", on_event);
+//! ```
+//!
+//! Publishing only queues the event, so it is safe from any core and context, including IRQ
+//! handlers. A periodic task on the boot core, see [init()], delivers queued events to every
+//! subscriber in the order they were published. Subscribers therefore run in IRQ context, and
+//! should be short. Events that find the queue full are dropped and counted.
+
+use crate::{
+    distributed_slice, error::KernelError, register_introspection_node,
+    synchronization::ringbuffer::MpscRingBuffer, time,
+};
+use core::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The number of events that can wait for delivery.
+const QUEUE_SIZE: usize = 16;
+
+/// How often queued events are delivered.
+const DELIVERY_INTERVAL: Duration = Duration::from_millis(50);
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A driver failed after the kernel brought it up.
+    DriverFailed {
+        /// The driver's compatible string.
+        compatible: &'static str,
+
+        /// What went wrong.
+        error: KernelError,
+    },
+
+    /// The board's supply voltage dropped below its minimum.
+    Undervoltage,
+
+    /// The board reduced its performance to stay within its temperature or power limits.
+    Throttled,
+}
+
+/// A registered event subscriber.
+pub struct Subscriber {
+    /// Descriptive name.
+    pub name: &'static str,
+
+    /// Called with every delivered event.
+    pub notify: fn(&Event),
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+distributed_slice! {
+    /// All registered event subscribers, in unspecified order.
+    pub static SUBSCRIBERS: [Subscriber];
+}
+
+static QUEUE: MpscRingBuffer<Event, QUEUE_SIZE> = MpscRingBuffer::new();
+
+static NUM_PUBLISHED: AtomicUsize = AtomicUsize::new(0);
+static NUM_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+register_introspection_node!("events/published", |f| {
+    write!(f, "{}", NUM_PUBLISHED.load(Ordering::Relaxed))
+});
+register_introspection_node!("events/dropped", |f| {
+    write!(f, "{}", NUM_DROPPED.load(Ordering::Relaxed))
+});
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Hand all queued events to the subscribers.
+///
+/// Only called by the delivery task, which is the queue's single consumer.
+fn deliver() {
+    while let Some(event) = unsafe { QUEUE.pop() } {
+        for subscriber in SUBSCRIBERS.iter() {
+            (subscriber.notify)(&event);
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Register an event subscriber.
+///
+/// ```
+/// register_event_subscriber!("LED dimmer", on_event);
+/// ```
+#[macro_export]
+macro_rules! register_event_subscriber {
+    ($name:expr, $notify:expr) => {
+        const _: () = {
+            use $crate::event::{Subscriber, SUBSCRIBERS};
+
+            $crate::distributed_slice_entry!(
+                SUBSCRIBERS: Subscriber = Subscriber {
+                    name: $name,
+                    notify: $notify,
+                }
+            );
+        };
+    };
+}
+
+/// Queue `event` for delivery to all subscribers.
+///
+/// Can be called before [init()]. The event is then delivered once the delivery task runs.
+pub fn publish(event: Event) {
+    if QUEUE.push(event).is_err() {
+        NUM_DROPPED.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    NUM_PUBLISHED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Start delivering events.
+///
+/// Must be called on the boot core, see [time::periodic::spawn()].
+pub fn init() -> Result<(), &'static str> {
+    time::periodic::spawn("Event delivery", DELIVERY_INTERVAL, deliver)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use test_macros::kernel_test;
+
+    static NUM_NOTIFIED: AtomicUsize = AtomicUsize::new(0);
+
+    fn count_test_driver_failures(event: &Event) {
+        if let Event::DriverFailed {
+            compatible: "event::test",
+            ..
+        } = event
+        {
+            NUM_NOTIFIED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    register_event_subscriber!("event::test", count_test_driver_failures);
+
+    /// Published events must reach the subscribers once delivered, and a full queue must drop.
+    ///
+    /// The test kernel does not start the delivery task, so the test delivers itself.
+    #[kernel_test]
+    fn events_are_delivered_to_subscribers() {
+        let event = Event::DriverFailed {
+            compatible: "event::test",
+            error: KernelError::new(ErrorKind::Other, "Test"),
+        };
+
+        publish(event);
+        publish(event);
+        assert_eq!(NUM_NOTIFIED.load(Ordering::Relaxed), 0);
+
+        deliver();
+        assert_eq!(NUM_NOTIFIED.load(Ordering::Relaxed), 2);
+
+        let dropped = NUM_DROPPED.load(Ordering::Relaxed);
+        for _ in 0..QUEUE_SIZE + 1 {
+            publish(event);
+        }
+        assert_eq!(NUM_DROPPED.load(Ordering::Relaxed), dropped + 1);
+
+        deliver();
+        assert_eq!(NUM_NOTIFIED.load(Ordering::Relaxed), 2 + QUEUE_SIZE);
+    }
+}
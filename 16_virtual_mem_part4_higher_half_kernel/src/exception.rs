@@ -9,11 +9,15 @@
 mod arch_exception;
 
 pub mod asynchronous;
+pub mod vector;
 
 //--------------------------------------------------------------------------------------------------
 // Architectural Public Reexports
 //--------------------------------------------------------------------------------------------------
-pub use arch_exception::{current_privilege_level, handling_init};
+pub use arch_exception::{
+    current_privilege_level, handling_init, irq_entry_time, null_svc, with_exception_context,
+    with_interrupted_context,
+};
 
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
@@ -21,7 +25,7 @@ pub use arch_exception::{current_privilege_level, handling_init};
 
 /// Kernel privilege levels.
 #[allow(missing_docs)]
-#[derive(PartialEq)]
+#[derive(PartialEq, Eq)]
 pub enum PrivilegeLevel {
     User,
     Kernel,
@@ -8,7 +8,12 @@
 #[path = "../_arch/aarch64/exception/asynchronous.rs"]
 mod arch_asynchronous;
 
-use core::{fmt, marker::PhantomData};
+use crate::{bsp, per_cpu, register_introspection_node};
+use core::{
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 //--------------------------------------------------------------------------------------------------
 // Architectural Public Reexports
@@ -66,7 +71,7 @@ pub mod interface {
             &self,
             irq_number: Self::IRQNumberType,
             descriptor: super::IRQDescriptor,
-        ) -> Result<(), &'static str>;
+        ) -> Result<(), crate::error::KernelError>;
 
         /// Enable an interrupt in the controller.
         fn enable(&self, irq_number: Self::IRQNumberType);
@@ -84,6 +89,24 @@ pub mod interface {
             ic: &super::IRQContext<'irq_context>,
         );
 
+        /// Route an interrupt to the cores in `core_mask`, where bit `n` stands for core `n`.
+        ///
+        /// Only cores that are online should be targeted. A parked core does not take IRQs.
+        fn set_affinity(
+            &self,
+            irq_number: Self::IRQNumberType,
+            core_mask: usize,
+        ) -> Result<(), &'static str>;
+
+        /// Return the mask of the cores that an interrupt is routed to, as read back from the
+        /// interrupt controller.
+        fn affinity(&self, irq_number: Self::IRQNumberType) -> Result<usize, &'static str>;
+
+        /// Raise an inter-processor interrupt on all cores except the executing one.
+        ///
+        /// The receiving cores respond by calling [crate::cpu::smp::handle_ipi()].
+        fn send_ipi_to_others(&self);
+
         /// Print list of registered handlers.
         fn print_handler(&self);
     }
@@ -93,6 +116,31 @@ pub mod interface {
 #[derive(Copy, Clone)]
 pub struct IRQNumber<const MAX_INCLUSIVE: usize>(usize);
 
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+per_cpu! {
+    /// The number of IRQs taken by a core.
+    static NUM_IRQS_TAKEN: AtomicUsize = AtomicUsize::new(0);
+}
+
+per_cpu! {
+    /// The address at which the most recent IRQ interrupted a core.
+    static INTERRUPTED_PC: AtomicUsize = AtomicUsize::new(0);
+}
+
+register_introspection_node!("irq/taken", |f| {
+    for core_id in 0..bsp::cpu::NUM_CORES {
+        if core_id != 0 {
+            f.write_str(" ")?;
+        }
+        write!(f, "{}", num_irqs_taken(core_id))?;
+    }
+
+    Ok(())
+});
+
 //--------------------------------------------------------------------------------------------------
 // Public Code
 //--------------------------------------------------------------------------------------------------
@@ -104,7 +152,7 @@ impl<'irq_context> IRQContext<'irq_context> {
     ///
     /// - This must only be called when the current core is in an interrupt context and will not
     ///   live beyond the end of it. That is, creation is allowed in interrupt vector functions. For
-    ///   example, in the ARMv8-A case, in `extern "C" fn current_elx_irq()`.
+    ///   example, in the ARMv8-A case, in `extern "C" fn current_el0_irq()`.
     /// - Note that the lifetime `'irq_context` of the returned instance is unconstrained. User code
     ///   must not be able to influence the lifetime picked for this type, since that might cause it
     ///   to be inferred to `'static`.
@@ -119,7 +167,7 @@ impl<const MAX_INCLUSIVE: usize> IRQNumber<{ MAX_INCLUSIVE }> {
     pub const fn new(number: usize) -> Self {
         assert!(number <= MAX_INCLUSIVE);
 
-        Self { 0: number }
+        Self(number)
     }
 
     /// Return the wrapped number.
@@ -150,3 +198,27 @@ pub fn exec_with_irq_masked<T>(f: impl FnOnce() -> T) -> T {
 
     ret
 }
+
+/// Account for an IRQ taken by the executing core at `interrupted_pc`.
+///
+/// Called from the architectural IRQ vector.
+#[inline(always)]
+pub fn account_local_irq(_ic: &IRQContext, interrupted_pc: usize) {
+    NUM_IRQS_TAKEN.local().fetch_add(1, Ordering::Relaxed);
+    INTERRUPTED_PC
+        .local()
+        .store(interrupted_pc, Ordering::Relaxed);
+    crate::rand::add_irq_timing(interrupted_pc);
+}
+
+/// The address at which the IRQ that is being handled interrupted the executing core.
+///
+/// Only meaningful in IRQ context.
+pub fn interrupted_pc() -> usize {
+    INTERRUPTED_PC.local().load(Ordering::Relaxed)
+}
+
+/// The number of IRQs taken by the given core.
+pub fn num_irqs_taken(core_id: usize) -> usize {
+    NUM_IRQS_TAKEN.remote(core_id).load(Ordering::Relaxed)
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Exception vector tables.
+//!
+//! Each core has its own vector base register, so every core can use a different table. The kernel
+//! provides two:
+//!
+//! - [VectorTable::full()], which [handling_init()](crate::exception::handling_init) installs.
+//! - [VectorTable::crash_only()], which reports any exception, including IRQs, and panics. It does
+//!   not rely on per-core data, so it suits code that runs before that is set up. It still needs
+//!   the exception stack that `handling_init()` sets up.
+//!
+//! ```
+//! let previous = exception::vector::install(VectorTable::crash_only());
+//! // ...
+//! exception::vector::install(previous);
+//! ```
+
+#[cfg(target_arch = "aarch64")]
+#[path = "../_arch/aarch64/exception/vector.rs"]
+mod arch_vector;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// An exception vector table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VectorTable {
+    addr: usize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl VectorTable {
+    /// The kernel's full vector table.
+    pub fn full() -> Self {
+        Self {
+            addr: arch_vector::full_table_addr(),
+        }
+    }
+
+    /// The crash-only vector table.
+    pub fn crash_only() -> Self {
+        Self {
+            addr: arch_vector::crash_table_addr(),
+        }
+    }
+
+    /// Use the table at `addr`.
+    ///
+    /// # Safety
+    ///
+    /// - `addr` must be the start of a valid vector table for the architecture, which stays mapped
+    ///   and unchanged for as long as it is installed.
+    pub unsafe fn from_addr(addr: usize) -> Result<Self, &'static str> {
+        if addr % arch_vector::TABLE_ALIGN != 0 {
+            return Err("Vector table is misaligned");
+        }
+
+        Ok(Self { addr })
+    }
+
+    /// The address of the table.
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+}
+
+/// Install `table` on the executing core. Returns the previous table.
+///
+/// The other cores keep their tables. Use [crate::cpu::smp::call_on_each_cpu()] to install a table
+/// everywhere.
+pub fn install(table: VectorTable) -> VectorTable {
+    let previous = current();
+
+    unsafe { arch_vector::set_vector_base(table.addr) };
+
+    previous
+}
+
+/// The table of the executing core.
+pub fn current() -> VectorTable {
+    VectorTable {
+        addr: arch_vector::vector_base(),
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Switching tables must take effect, and restoring the previous one must bring back the full
+    /// table.
+    #[kernel_test]
+    fn vector_tables_can_be_switched() {
+        assert_eq!(current(), VectorTable::full());
+
+        let previous = install(VectorTable::crash_only());
+        assert_eq!(current(), VectorTable::crash_only());
+        assert_ne!(VectorTable::crash_only(), VectorTable::full());
+
+        install(previous);
+        assert_eq!(current(), VectorTable::full());
+
+        assert!(unsafe { VectorTable::from_addr(VectorTable::full().addr() + 0x80) }.is_err());
+    }
+}
@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Fault injection for tests.
+//!
+//! A failpoint is a named spot in an error-handling code path. Tests arm it by name, and the next
+//! time execution passes the spot, the surrounding function returns the error given at the call
+//! site instead of continuing:
+//!
+//! ```ignore
+//! failpoint!("mmu::mmio_va_alloc", Err("Failpoint: MMIO VA allocation"));
+//! ```
+//!
+//! Failpoints only exist in `test_build`s. In all other builds, [failpoint!] expands to nothing.
+
+#[cfg(feature = "test_build")]
+use crate::synchronization::{interface::Mutex, IRQSafeSpinLock};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(feature = "test_build")]
+const MAX_ARMED: usize = 8;
+
+#[cfg(feature = "test_build")]
+#[derive(Copy, Clone)]
+struct Armed {
+    name: &'static str,
+    trigger: Trigger,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// When an armed failpoint fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Pass `skip` times, then fail once and disarm.
+    Once {
+        /// The number of calls that pass before the failing one.
+        skip: usize,
+    },
+
+    /// Fail every time until disarmed.
+    Always,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(feature = "test_build")]
+static ARMED: IRQSafeSpinLock<[Option<Armed>; MAX_ARMED]> = IRQSafeSpinLock::new([None; MAX_ARMED]);
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Return early with `$ret` if the failpoint `$name` is armed and triggers.
+#[cfg(feature = "test_build")]
+#[macro_export]
+macro_rules! failpoint {
+    ($name:expr, $ret:expr) => {
+        if $crate::failpoint::should_fail($name) {
+            return $ret;
+        }
+    };
+}
+
+/// Return early with `$ret` if the failpoint `$name` is armed and triggers.
+#[cfg(not(feature = "test_build"))]
+#[macro_export]
+macro_rules! failpoint {
+    ($name:expr, $ret:expr) => {};
+}
+
+/// Arm the failpoint `name`. Replaces an earlier arming of the same failpoint.
+#[cfg(feature = "test_build")]
+pub fn arm(name: &'static str, trigger: Trigger) -> Result<(), &'static str> {
+    ARMED.lock(|armed| {
+        let slot = match armed
+            .iter()
+            .position(|x| matches!(x, Some(a) if a.name == name))
+        {
+            Some(i) => i,
+            None => armed
+                .iter()
+                .position(Option::is_none)
+                .ok_or("Too many armed failpoints")?,
+        };
+
+        armed[slot] = Some(Armed { name, trigger });
+
+        Ok(())
+    })
+}
+
+/// Disarm the failpoint `name`.
+#[cfg(feature = "test_build")]
+pub fn disarm(name: &'static str) {
+    ARMED.lock(|armed| {
+        for slot in armed.iter_mut() {
+            if matches!(slot, Some(a) if a.name == name) {
+                *slot = None;
+            }
+        }
+    })
+}
+
+/// Disarm all failpoints.
+#[cfg(feature = "test_build")]
+pub fn disarm_all() {
+    ARMED.lock(|armed| *armed = [None; MAX_ARMED])
+}
+
+/// Check whether the failpoint `name` triggers now. Used by [failpoint!].
+#[cfg(feature = "test_build")]
+#[doc(hidden)]
+pub fn should_fail(name: &'static str) -> bool {
+    ARMED.lock(|armed| {
+        let slot = match armed
+            .iter_mut()
+            .find(|x| matches!(x, Some(a) if a.name == name))
+        {
+            None => return false,
+            Some(slot) => slot,
+        };
+
+        match &mut slot.as_mut().unwrap().trigger {
+            Trigger::Always => true,
+            Trigger::Once { skip } if *skip > 0 => {
+                *skip -= 1;
+                false
+            }
+            Trigger::Once { .. } => {
+                *slot = None;
+                true
+            }
+        }
+    })
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    fn guarded() -> Result<(), &'static str> {
+        failpoint!("failpoint::test", Err("Failpoint"));
+
+        Ok(())
+    }
+
+    /// Skipping, single failures and disarming.
+    #[kernel_test]
+    fn failpoint_triggers() {
+        assert_eq!(guarded(), Ok(()));
+
+        arm("failpoint::test", Trigger::Once { skip: 1 }).unwrap();
+        assert_eq!(guarded(), Ok(()));
+        assert_eq!(guarded(), Err("Failpoint"));
+        assert_eq!(guarded(), Ok(()));
+
+        arm("failpoint::test", Trigger::Always).unwrap();
+        assert_eq!(guarded(), Err("Failpoint"));
+        assert_eq!(guarded(), Err("Failpoint"));
+
+        disarm("failpoint::test");
+        assert_eq!(guarded(), Ok(()));
+    }
+}
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Read-only introspection tree.
+//!
+//! Subsystems publish values as nodes with a slash-separated path, like `memory/mmio/pages_free`,
+//! next to the code that owns the value:
+//!
+//! ```
+//! register_introspection_node!("time/uptime_us", |f| {
+//!     write!(f, "{}", time::time_manager().uptime().as_micros())
+//! });
+//! ```
+//!
+//! A node computes its value each time it is read, so the tree never holds stale copies. The
+//! directories are implied by the paths. [print()] shows the nodes below a directory, and the
+//! kernel's health report, see [crate::status], shows the whole tree. [read()] writes a single
+//! value into any [fmt::Write], which a file system could build its files on.
+
+use crate::{
+    distributed_slice,
+    error::{ErrorKind, KernelError},
+    info,
+};
+use core::fmt;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A node of the introspection tree.
+pub struct Node {
+    /// The path, without a leading slash.
+    pub path: &'static str,
+
+    /// Formats the node's current value.
+    pub show: fn(&mut fmt::Formatter<'_>) -> fmt::Result,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+distributed_slice! {
+    /// All registered introspection nodes, in unspecified order.
+    pub static NODES: [Node];
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl Node {
+    /// Whether the node lies below directory `dir`. Every node lies below the empty directory.
+    fn is_below(&self, dir: &str) -> bool {
+        let dir = dir.trim_matches('/');
+
+        dir.is_empty()
+            || self
+                .path
+                .strip_prefix(dir)
+                .map_or(false, |rest| rest.starts_with('/'))
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.show)(f)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Register an introspection node.
+///
+/// Paths must be unique. `show` is a function or a closure that does not capture anything.
+///
+/// ```
+/// register_introspection_node!("memory/mmio/pages_free", |f| write!(f, "{}", free()));
+/// ```
+#[macro_export]
+macro_rules! register_introspection_node {
+    ($path:expr, $show:expr) => {
+        const _: () = {
+            use $crate::introspect::{Node, NODES};
+
+            $crate::distributed_slice_entry!(
+                NODES: Node = Node {
+                    path: $path,
+                    show: $show,
+                }
+            );
+        };
+    };
+}
+
+/// Return the node at `path`. A leading slash is ignored.
+pub fn find(path: &str) -> Option<&'static Node> {
+    let path = path.trim_start_matches('/');
+
+    NODES.iter().find(|node| node.path == path)
+}
+
+/// Return the nodes below directory `dir`, in unspecified order.
+pub fn nodes_below(dir: &str) -> impl Iterator<Item = &'static Node> + '_ {
+    NODES.iter().filter(move |node| node.is_below(dir))
+}
+
+/// Write the current value of the node at `path` into `w`.
+pub fn read(path: &str, w: &mut dyn fmt::Write) -> Result<(), KernelError> {
+    let node = find(path).ok_or_else(|| {
+        KernelError::new(ErrorKind::InvalidArgument, "No such introspection node")
+    })?;
+
+    write!(w, "{}", node)
+        .map_err(|_| KernelError::new(ErrorKind::OutOfResources, "Value does not fit"))
+}
+
+/// Print the path and the current value of every node below directory `dir`.
+pub fn print(dir: &str) {
+    for node in nodes_below(dir) {
+        info!("      /{}: {}", node.path, node);
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    struct Buffer {
+        data: [u8; 16],
+        len: usize,
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+
+            self.data
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+
+            Ok(())
+        }
+    }
+
+    register_introspection_node!("test/answer", |f| write!(f, "{}", 42));
+
+    /// Check that registered nodes are found and read, and that directories match whole names.
+    #[kernel_test]
+    fn introspection_nodes_are_read() {
+        let mut buf = Buffer {
+            data: [0; 16],
+            len: 0,
+        };
+        read("/test/answer", &mut buf).unwrap();
+        assert_eq!(&buf.data[..buf.len], b"42");
+
+        assert!(read("test/question", &mut buf).is_err());
+
+        assert_eq!(nodes_below("test").count(), 1);
+        assert_eq!(nodes_below("/test/").count(), 1);
+        assert_eq!(nodes_below("tes").count(), 0);
+        assert_eq!(nodes_below("").count(), NODES.len());
+    }
+}
@@ -109,8 +109,6 @@
 #![allow(clippy::upper_case_acronyms)]
 #![allow(incomplete_features)]
 #![feature(asm_const)]
-#![feature(const_fn_fn_ptr_basics)]
-#![feature(const_fn_trait_bound)]
 #![feature(core_intrinsics)]
 #![feature(format_args_nl)]
 #![feature(generic_const_exprs)]
@@ -118,6 +116,7 @@
 #![feature(panic_info_message)]
 #![feature(step_trait)]
 #![feature(trait_alias)]
+#![cfg_attr(feature = "lockdep", feature(const_caller_location))]
 #![no_std]
 // Testing
 #![cfg_attr(test, no_main)]
@@ -126,18 +125,33 @@
 #![test_runner(crate::test_runner)]
 
 mod panic_wait;
-mod synchronization;
 
+pub mod bench;
 pub mod bsp;
-pub mod common;
+pub mod config;
 pub mod console;
 pub mod cpu;
+pub mod debug;
+pub mod distributed_slice;
 pub mod driver;
+pub mod earlycon;
+pub mod elf;
+pub mod error;
+pub mod event;
 pub mod exception;
+pub mod failpoint;
+pub mod introspect;
 pub mod memory;
+pub mod pmu;
 pub mod print;
+pub mod rand;
 pub mod state;
+pub mod status;
+pub mod synchronization;
 pub mod time;
+pub mod trace;
+
+pub use kernel_core::common;
 
 //--------------------------------------------------------------------------------------------------
 // Public Code
@@ -156,7 +170,20 @@ pub fn version() -> &'static str {
 // Testing
 //--------------------------------------------------------------------------------------------------
 
+/// A test that runs longer than this is considered hung.
+const TEST_TIMEOUT: core::time::Duration = core::time::Duration::from_secs(5);
+
+/// Fail a hung test, showing where it was stuck.
+fn test_timed_out() {
+    exception::with_interrupted_context(|context| match context {
+        Some(context) => panic!("Test timed out after {:?}\n\n{}", TEST_TIMEOUT, context),
+        None => panic!("Test timed out after {:?}", TEST_TIMEOUT),
+    })
+}
+
 /// The default runner for unit tests.
+///
+/// Every test is guarded by an alarm. It only fires if the test binary brought up IRQ handling.
 pub fn test_runner(tests: &[&test_types::UnitTest]) {
     // This line will be printed as the test header.
     println!("Running {} tests", tests.len());
@@ -164,9 +191,15 @@ pub fn test_runner(tests: &[&test_types::UnitTest]) {
     for (i, test) in tests.iter().enumerate() {
         print!("{:>3}. {:.<58}", i + 1, test.name);
 
+        if let Err(x) = time::alarm::set(TEST_TIMEOUT, test_timed_out) {
+            panic!("Arming the test timeout failed: {}", x);
+        }
+
         // Run the actual test.
         (test.test_func)();
 
+        time::alarm::cancel();
+
         // Failed tests call panic!(). Execution reaches here only if the test has passed.
         println!("[ok]")
     }
@@ -176,10 +209,17 @@ pub fn test_runner(tests: &[&test_types::UnitTest]) {
 #[cfg(test)]
 #[no_mangle]
 unsafe fn kernel_init() -> ! {
+    time::init();
     exception::handling_init();
+    cpu::fpsimd::init();
     memory::mmu::post_enable_init();
     bsp::console::qemu_bring_up_console();
 
+    // The test timeouts need the alarm IRQ.
+    bsp::exception::asynchronous::qemu_bring_up_irqs();
+    time::alarm::register_and_enable_irq_handler().unwrap_or_else(|_| cpu::qemu_exit_failure());
+    exception::asynchronous::local_irq_unmask();
+
     test_main();
 
     cpu::qemu_exit_success()
@@ -11,7 +11,11 @@
 #![no_main]
 #![no_std]
 
-use libkernel::{bsp, cpu, driver, exception, info, memory, state, time, warn};
+use core::time::Duration;
+use libkernel::{
+    bsp, config, cpu, debug, driver, early_println, earlycon, event, exception, info, memory, pmu,
+    rand, state, synchronization, time, trace, warn,
+};
 
 /// Early init code.
 ///
@@ -24,9 +28,26 @@ use libkernel::{bsp, cpu, driver, exception, info, memory, state, time, warn};
 #[no_mangle]
 unsafe fn kernel_init() -> ! {
     use driver::interface::DriverManager;
+    use synchronization::interface::ReadWriteEx;
+    use time::interface::TimeManager;
+
+    time::init();
+    time::boot::record(time::boot::Milestone::MmuOn);
+
+    // Record the MMIO accesses of driver init, too.
+    if config::MMIO_TRACE {
+        trace::enable();
+    }
 
     exception::handling_init();
-    memory::mmu::post_enable_init();
+    cpu::fpsimd::init();
+
+    {
+        time::profile_scope!("MMU post-enable init");
+        memory::mmu::post_enable_init();
+    }
+
+    earlycon::init();
 
     // Add the mapping records for the precomputed entries first, so that they appear on the top of
     // the list.
@@ -37,29 +58,100 @@ unsafe fn kernel_init() -> ! {
         .early_print_device_drivers()
         .iter()
     {
-        // Any encountered errors cannot be printed yet, obviously, so just safely park the CPU.
-        i.init().unwrap_or_else(|_| cpu::wait_forever());
+        // Encountered errors can only be printed by the early console, if it is enabled. Safely
+        // park the CPU afterwards.
+        i.driver.init().unwrap_or_else(|x| {
+            early_println!("Error loading driver: {}: {:#}", i.driver.compatible(), x);
+            cpu::wait_forever()
+        });
     }
     bsp::driver::driver_manager().post_early_print_device_driver_init();
     // Printing available from here on.
+    time::boot::record(time::boot::Milestone::ConsoleUp);
+
+    if let Err(x) = bsp::cmdline::init() {
+        warn!("Error reading the kernel command line: {}", x);
+    }
+
+    if let Some(secs) = bsp::cmdline::cmdline().read(|cmdline| cmdline.parse::<u64>("epoch")) {
+        let since_epoch = Duration::from_secs(secs)
+            .checked_add(time::time_manager().uptime())
+            .ok_or("Wall-clock time out of range");
+
+        if let Err(x) = since_epoch.and_then(time::wall_clock::set) {
+            warn!("Error setting the wall clock: {}", x);
+        }
+    }
+
+    if bsp::cmdline::cmdline().read(|cmdline| cmdline.flag("trace")) {
+        trace::enable();
+    }
+
+    if config::BTI && cpu::features::features().bti {
+        if let Err(x) = memory::mmu::kernel_guard_code() {
+            warn!("Error enabling branch target identification: {}", x);
+        }
+    }
 
     // Now bring up the remaining drivers.
-    for i in bsp::driver::driver_manager()
-        .non_early_print_device_drivers()
-        .iter()
     {
-        if let Err(x) = i.init() {
-            panic!("Error loading driver: {}: {}", i.compatible(), x);
+        time::profile_scope!("Driver init");
+
+        for i in bsp::driver::driver_manager()
+            .non_early_print_device_drivers()
+            .iter()
+        {
+            if let Err(x) = i.driver.init() {
+                panic!("Error loading driver: {}: {:#}", i.driver.compatible(), x);
+            }
         }
     }
 
     // Let device drivers register and enable their handlers with the interrupt controller.
     for i in bsp::driver::driver_manager().all_device_drivers() {
-        if let Err(msg) = i.register_and_enable_irq_handler() {
-            warn!("Error registering IRQ handler: {}", msg);
+        if let Err(x) = i.driver.register_and_enable_irq_handler() {
+            warn!("Error registering IRQ handler: {}", x);
+            event::publish(event::Event::DriverFailed {
+                compatible: i.driver.compatible(),
+                error: x,
+            });
         }
     }
 
+    pmu::init();
+    if let Err(msg) = pmu::register_and_enable_irq_handler() {
+        warn!("Error registering PMU IRQ handler: {}", msg);
+    }
+    if let Err(msg) = time::alarm::register_and_enable_irq_handler() {
+        warn!("Error registering alarm IRQ handler: {}", msg);
+    }
+    time::boot::record(time::boot::Milestone::DriversUp);
+
+    rand::init();
+
+    if let Err(x) = event::init() {
+        warn!("Error starting event delivery: {}", x);
+    }
+
+    if let Err(x) = debug::gdbstub::init() {
+        warn!("Error enabling the GDB stub: {}", x);
+    }
+
+    if let Err(x) =
+        debug::invariant::register("Kernel mappings", memory::mmu::kernel_check_mappings)
+    {
+        warn!("Error registering invariant check: {}", x);
+    }
+
+    if let Err(x) = bsp::cpu::reboot_init() {
+        warn!("Error preparing reboot: {}", x);
+    }
+
+    // Prepare the bring-up of the secondary cores, which happens later in kernel_main().
+    if let Err(x) = cpu::smp::init() {
+        warn!("Error preparing SMP: {}", x);
+    }
+
     // Unmask interrupts on the boot CPU core.
     exception::asynchronous::local_irq_unmask();
 
@@ -74,9 +166,16 @@ unsafe fn kernel_init() -> ! {
 fn kernel_main() -> ! {
     use driver::interface::DriverManager;
     use exception::asynchronous::interface::IRQManager;
+    use synchronization::interface::ReadWriteEx;
+    use time::interface::TimeManager;
 
     info!("{}", libkernel::version());
-    info!("Booting on: {}", bsp::board_name());
+    bsp::board::print_info();
+
+    info!("Build configuration:");
+    config::print();
+
+    bsp::cmdline::cmdline().read(|cmdline| info!("Kernel command line: {}", cmdline.as_str()));
 
     info!("MMU online:");
     memory::mmu::kernel_print_mappings();
@@ -84,6 +183,15 @@ fn kernel_main() -> ! {
     let (_, privilege_level) = exception::current_privilege_level();
     info!("Current privilege level: {}", privilege_level);
 
+    info!("CPU features:");
+    cpu::features::print();
+
+    info!("Speculation mitigations:");
+    cpu::mitigations::print();
+
+    info!("Caches:");
+    cpu::cache::print();
+
     info!("Exception handling state:");
     exception::asynchronous::print_state();
 
@@ -91,19 +199,93 @@ fn kernel_main() -> ! {
         "Architectural timer resolution: {} ns",
         time::time_manager().resolution().as_nanos()
     );
+    info!(
+        "Time base: {} counter",
+        if time::uses_virtual_counter() {
+            "virtual"
+        } else {
+            "physical"
+        }
+    );
+
+    info!("PMU event counters: {}", pmu::num_event_counters());
 
     info!("Drivers loaded:");
-    for (i, driver) in bsp::driver::driver_manager()
+    for (i, descriptor) in bsp::driver::driver_manager()
         .all_device_drivers()
         .iter()
         .enumerate()
     {
-        info!("      {}. {}", i + 1, driver.compatible());
+        info!("      {}. {}", i + 1, descriptor.driver.compatible());
     }
 
     info!("Registered IRQ handlers:");
     bsp::exception::asynchronous::irq_manager().print_handler();
 
-    info!("Echoing input now");
-    cpu::wait_forever();
+    if bsp::cmdline::cmdline().read(|cmdline| cmdline.flag("nosmp")) {
+        info!("Not starting secondary cores (nosmp)");
+    } else {
+        info!("Starting secondary cores");
+        match cpu::smp::start_secondary_cores() {
+            Ok(num_cores) => info!(
+                "      {} of {} cores online",
+                num_cores,
+                bsp::cpu::NUM_CORES
+            ),
+            Err(x) => warn!("      {}", x),
+        }
+        time::boot::record(time::boot::Milestone::SecondaryCoresUp);
+    }
+
+    if let Some(core_id) =
+        bsp::cmdline::cmdline().read(|cmdline| cmdline.parse::<usize>("console_irq_core"))
+    {
+        info!("Routing the console IRQ to core {}", core_id);
+        let pinned = if cpu::smp::is_online(core_id) {
+            bsp::exception::asynchronous::set_console_irq_affinity(1 << core_id)
+        } else {
+            Err("Core is not online")
+        };
+        if let Err(x) = pinned {
+            warn!("      {}", x);
+        }
+    }
+
+    if let Some(period) = bsp::cmdline::cmdline().read(|cmdline| cmdline.parse::<u32>("profile")) {
+        info!(
+            "Sampling every {} cycles, press CTRL + T for the profile",
+            period
+        );
+        if let Err(x) = pmu::sampler::start(period) {
+            warn!("      {}", x);
+        }
+    }
+
+    if debug::gdbstub::is_enabled() {
+        info!("GDB stub enabled, waiting for GDB to attach");
+        debug::gdbstub::breakpoint();
+    }
+
+    info!("Profiled sections:");
+    time::profile::print();
+
+    time::boot::record(time::boot::Milestone::Ready);
+    info!("Boot timing:");
+    time::boot::print();
+
+    if let Some(ms) = bsp::cmdline::cmdline().read(|cmdline| cmdline.parse::<u64>("invariants")) {
+        info!("Checking invariants every {} ms", ms);
+        if let Err(x) = debug::invariant::check_periodically(Duration::from_millis(ms)) {
+            warn!("      {}", x);
+        }
+    }
+
+    if let Err(x) = bsp::board::start_monitor() {
+        info!("Board monitor not started: {}", x);
+    }
+
+    info!(
+        "Echoing input now, press CTRL + R to reboot, CTRL + T for status, CTRL + E for the trace"
+    );
+    cpu::idle::idle_loop();
 }
@@ -3,165 +3,88 @@
 // Copyright (c) 2018-2022 Andre Richter <andre.o.richter@gmail.com>
 
 //! Memory Management.
+//!
+//! The address types are plain computation and live in the `kernel-core` crate, where they are
+//! tested on the host.
+
+#[cfg(target_arch = "aarch64")]
+#[path = "_arch/aarch64/memory.rs"]
+mod arch_memory;
 
 pub mod mmu;
 
-use crate::{bsp, common};
-use core::{
-    fmt,
-    marker::PhantomData,
-    ops::{Add, Sub},
-};
+pub use kernel_core::memory::{Address, AddressType, Physical, Virtual};
 
 //--------------------------------------------------------------------------------------------------
-// Public Definitions
+// Architectural Public Reexports
 //--------------------------------------------------------------------------------------------------
-
-/// Metadata trait for marking the type of an address.
-pub trait AddressType: Copy + Clone + PartialOrd + PartialEq {}
-
-/// Zero-sized type to mark a physical address.
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
-pub enum Physical {}
-
-/// Zero-sized type to mark a virtual address.
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
-pub enum Virtual {}
-
-/// Generic address type.
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq)]
-pub struct Address<ATYPE: AddressType> {
-    value: usize,
-    _address_type: PhantomData<fn() -> ATYPE>,
-}
+pub use arch_memory::{kmemcpy, kmemmove, kmemset};
 
 //--------------------------------------------------------------------------------------------------
-// Public Code
+// Testing
 //--------------------------------------------------------------------------------------------------
 
-impl AddressType for Physical {}
-impl AddressType for Virtual {}
-
-impl<ATYPE: AddressType> Address<ATYPE> {
-    /// Create an instance.
-    pub const fn new(value: usize) -> Self {
-        Self {
-            value,
-            _address_type: PhantomData,
-        }
-    }
-
-    /// Convert to usize.
-    pub const fn as_usize(self) -> usize {
-        self.value
-    }
-
-    /// Align down to page size.
-    #[must_use]
-    pub const fn align_down_page(self) -> Self {
-        let aligned = common::align_down(self.value, bsp::memory::mmu::KernelGranule::SIZE);
-
-        Self::new(aligned)
-    }
-
-    /// Align up to page size.
-    #[must_use]
-    pub const fn align_up_page(self) -> Self {
-        let aligned = common::align_up(self.value, bsp::memory::mmu::KernelGranule::SIZE);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
 
-        Self::new(aligned)
-    }
+    const BUF_SIZE: usize = 512;
 
-    /// Checks if the address is page aligned.
-    pub const fn is_page_aligned(&self) -> bool {
-        common::is_aligned(self.value, bsp::memory::mmu::KernelGranule::SIZE)
-    }
+    fn pattern() -> [u8; BUF_SIZE] {
+        let mut buf = [0; BUF_SIZE];
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = (i * 7 + 1) as u8;
+        }
 
-    /// Return the address' offset into the corresponding page.
-    pub const fn offset_into_page(&self) -> usize {
-        self.value & bsp::memory::mmu::KernelGranule::MASK
+        buf
     }
-}
-
-impl<ATYPE: AddressType> Add<usize> for Address<ATYPE> {
-    type Output = Self;
 
-    #[inline(always)]
-    fn add(self, rhs: usize) -> Self::Output {
-        match self.value.checked_add(rhs) {
-            None => panic!("Overflow on Address::add"),
-            Some(x) => Self::new(x),
+    /// Check copies of all small lengths and alignments, and a few large ones.
+    #[kernel_test]
+    fn kmemcpy_copies_exactly() {
+        let src = pattern();
+
+        for offset in 0..16 {
+            for len in (0..160).chain([255, 384]) {
+                let mut dst = [0u8; BUF_SIZE];
+                unsafe { kmemcpy(dst.as_mut_ptr().add(offset), src.as_ptr(), len) };
+
+                assert!(dst[..offset].iter().all(|&b| b == 0));
+                assert_eq!(dst[offset..offset + len], src[..len]);
+                assert!(dst[offset + len..].iter().all(|&b| b == 0));
+            }
         }
     }
-}
 
-impl<ATYPE: AddressType> Sub<Address<ATYPE>> for Address<ATYPE> {
-    type Output = Self;
-
-    #[inline(always)]
-    fn sub(self, rhs: Address<ATYPE>) -> Self::Output {
-        match self.value.checked_sub(rhs.value) {
-            None => panic!("Overflow on Address::sub"),
-            Some(x) => Self::new(x),
+    /// Check overlapping moves in both directions.
+    #[kernel_test]
+    fn kmemmove_handles_overlap() {
+        let reference = pattern();
+
+        for distance in [1, 8, 63, 64, 65] {
+            for len in [0, 7, 64, 200, 300] {
+                let mut buf = pattern();
+                unsafe { kmemmove(buf.as_mut_ptr().add(distance), buf.as_ptr(), len) };
+                assert_eq!(buf[distance..distance + len], reference[..len]);
+
+                let mut buf = pattern();
+                unsafe { kmemmove(buf.as_mut_ptr(), buf.as_ptr().add(distance), len) };
+                assert_eq!(buf[..len], reference[distance..distance + len]);
+            }
         }
     }
-}
 
-impl fmt::Display for Address<Physical> {
-    // Don't expect to see physical addresses greater than 40 bit.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let q3: u8 = ((self.value >> 32) & 0xff) as u8;
-        let q2: u16 = ((self.value >> 16) & 0xffff) as u16;
-        let q1: u16 = (self.value & 0xffff) as u16;
-
-        write!(f, "0x")?;
-        write!(f, "{:02x}_", q3)?;
-        write!(f, "{:04x}_", q2)?;
-        write!(f, "{:04x}", q1)
-    }
-}
-
-impl fmt::Display for Address<Virtual> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let q4: u16 = ((self.value >> 48) & 0xffff) as u16;
-        let q3: u16 = ((self.value >> 32) & 0xffff) as u16;
-        let q2: u16 = ((self.value >> 16) & 0xffff) as u16;
-        let q1: u16 = (self.value & 0xffff) as u16;
-
-        write!(f, "0x")?;
-        write!(f, "{:04x}_", q4)?;
-        write!(f, "{:04x}_", q3)?;
-        write!(f, "{:04x}_", q2)?;
-        write!(f, "{:04x}", q1)
-    }
-}
-
-//--------------------------------------------------------------------------------------------------
-// Testing
-//--------------------------------------------------------------------------------------------------
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use test_macros::kernel_test;
-
-    /// Sanity of [Address] methods.
+    /// Check setting, including zeroing that is large enough for `DC ZVA`.
     #[kernel_test]
-    fn address_type_method_sanity() {
-        let addr = Address::<Virtual>::new(bsp::memory::mmu::KernelGranule::SIZE + 100);
-
-        assert_eq!(
-            addr.align_down_page().as_usize(),
-            bsp::memory::mmu::KernelGranule::SIZE
-        );
-
-        assert_eq!(
-            addr.align_up_page().as_usize(),
-            bsp::memory::mmu::KernelGranule::SIZE * 2
-        );
-
-        assert_eq!(addr.is_page_aligned(), false);
-
-        assert_eq!(addr.offset_into_page(), 100);
+    fn kmemset_sets_exactly() {
+        for (value, offset, len) in [(0xa5, 3, 100), (0, 0, BUF_SIZE - 16), (0, 5, 400)] {
+            let mut buf = pattern();
+            unsafe { kmemset(buf.as_mut_ptr().add(offset), value, len) };
+
+            assert_eq!(buf[..offset], pattern()[..offset]);
+            assert!(buf[offset..offset + len].iter().all(|&b| b == value));
+            assert_eq!(buf[offset + len..], pattern()[offset + len..]);
+        }
     }
 }
@@ -15,12 +15,16 @@ mod types;
 
 use crate::{
     bsp,
+    error::{ErrorKind, KernelError, ResultExt},
+    failpoint,
     memory::{Address, Physical, Virtual},
+    register_introspection_node,
     synchronization::{self, interface::Mutex},
     warn,
 };
 use core::{fmt, num::NonZeroUsize};
 
+pub use kernel_core::memory::mmu::{PageGranule, TranslationGranule};
 pub use types::*;
 
 //--------------------------------------------------------------------------------------------------
@@ -56,9 +60,6 @@ pub mod interface {
     }
 }
 
-/// Describes the characteristics of a translation granule.
-pub struct TranslationGranule<const GRANULE_SIZE: usize>;
-
 /// Describes properties of an address space.
 pub struct AddressSpace<const AS_SIZE: usize>;
 
@@ -75,6 +76,17 @@ pub trait AssociatedTranslationTable {
     type TableStartFromBottom;
 }
 
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+register_introspection_node!("memory/mmio/pages_total", |f| {
+    write!(f, "{}", kernel_mmio_va_usage().0)
+});
+register_introspection_node!("memory/mmio/pages_free", |f| {
+    write!(f, "{}", kernel_mmio_va_usage().1)
+});
+
 //--------------------------------------------------------------------------------------------------
 // Private Code
 //--------------------------------------------------------------------------------------------------
@@ -87,7 +99,10 @@ use translation_table::interface::TranslationTable;
 fn kernel_init_mmio_va_allocator() {
     let region = bsp::memory::mmu::virt_mmio_remap_region();
 
-    alloc::kernel_mmio_va_allocator().lock(|allocator| allocator.initialize(region));
+    let result = alloc::kernel_mmio_va_allocator().lock(|allocator| allocator.initialize(region));
+    if let Err(x) = result {
+        warn!("MMIO VA allocator: {}", x);
+    }
 }
 
 /// Map a region in the kernel's translation tables.
@@ -103,23 +118,25 @@ unsafe fn kernel_map_at_unchecked(
     virt_region: &MemoryRegion<Virtual>,
     phys_region: &MemoryRegion<Physical>,
     attr: &AttributeFields,
-) -> Result<(), &'static str> {
+) -> Result<(), KernelError> {
+    failpoint!("mmu::map", Err("Failpoint: mmu::map".into()));
+
     bsp::memory::mmu::kernel_translation_tables()
         .write(|tables| tables.map_at(virt_region, phys_region, attr))?;
 
+    kernel_tables_sync();
     kernel_add_mapping_record(name, virt_region, phys_region, attr);
 
     Ok(())
 }
 
-/// Try to translate a kernel virtual address to a physical address.
+/// Make changes to the kernel translation tables take effect.
 ///
-/// Will only succeed if there exists a valid mapping for the input address.
-fn try_kernel_virt_addr_to_phys_addr(
-    virt_addr: Address<Virtual>,
-) -> Result<Address<Physical>, &'static str> {
-    bsp::memory::mmu::kernel_translation_tables()
-        .read(|tables| tables.try_virt_addr_to_phys_addr(virt_addr))
+/// The tables sit behind an [InitStateLock](crate::synchronization::InitStateLock), so they only
+/// change during kernel init, while the boot core runs alone. Therefore, no other core has to be
+/// interrupted to resynchronize its context.
+fn kernel_tables_sync() {
+    arch_mmu::broadcast_tlb_and_icache_invalidation();
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -135,23 +152,6 @@ impl fmt::Display for MMUEnableError {
     }
 }
 
-impl<const GRANULE_SIZE: usize> TranslationGranule<GRANULE_SIZE> {
-    /// The granule's size.
-    pub const SIZE: usize = Self::size_checked();
-
-    /// The granule's mask.
-    pub const MASK: usize = Self::SIZE - 1;
-
-    /// The granule's shift, aka log2(size).
-    pub const SHIFT: usize = Self::SIZE.trailing_zeros() as usize;
-
-    const fn size_checked() -> usize {
-        assert!(GRANULE_SIZE.is_power_of_two());
-
-        GRANULE_SIZE
-    }
-}
-
 impl<const AS_SIZE: usize> AddressSpace<AS_SIZE> {
     /// The address space size.
     pub const SIZE: usize = Self::size_checked();
@@ -191,7 +191,7 @@ pub fn kernel_add_mapping_record(
 pub unsafe fn kernel_map_mmio(
     name: &'static str,
     mmio_descriptor: &MMIODescriptor,
-) -> Result<Address<Virtual>, &'static str> {
+) -> Result<Address<Virtual>, KernelError> {
     let phys_region = MemoryRegion::from(*mmio_descriptor);
     let offset_into_start_page = mmio_descriptor.start_addr().offset_into_page();
 
@@ -203,12 +203,23 @@ pub unsafe fn kernel_map_mmio(
     // Otherwise, allocate a new region and map it.
     } else {
         let num_pages = match NonZeroUsize::new(phys_region.num_pages()) {
-            None => return Err("Requested 0 pages"),
+            None => {
+                return Err(KernelError::new(
+                    ErrorKind::InvalidArgument,
+                    "Requested 0 pages",
+                ))
+            }
             Some(x) => x,
         };
 
-        let virt_region =
-            alloc::kernel_mmio_va_allocator().lock(|allocator| allocator.alloc(num_pages))?;
+        failpoint!(
+            "mmu::mmio_va_alloc",
+            Err("Failpoint: mmu::mmio_va_alloc".into())
+        );
+
+        let virt_region = alloc::kernel_mmio_va_allocator()
+            .lock(|allocator| allocator.alloc(num_pages))
+            .context("Allocating MMIO virtual addresses")?;
 
         kernel_map_at_unchecked(
             name,
@@ -227,6 +238,89 @@ pub unsafe fn kernel_map_mmio(
     Ok(virt_addr + offset_into_start_page)
 }
 
+/// Map a writable, non-executable alias of the kernel's code pages.
+///
+/// The code pages themselves are read-only. Debuggers write breakpoints through the alias. The
+/// alias is placed in the MMIO remap region, which is the only virtual address range that is
+/// available at runtime. Returns the address that aliases the start of the code.
+///
+/// # Safety
+///
+/// - Same as `kernel_map_at_unchecked()`.
+/// - Writes through the alias need cache maintenance before the changed code can be executed.
+pub unsafe fn kernel_map_code_alias(name: &'static str) -> Result<Address<Virtual>, KernelError> {
+    let code = bsp::memory::virt_code_range();
+    let virt_code_region =
+        MemoryRegion::new(PageAddress::from(code.start), PageAddress::from(code.end));
+
+    // The code is physically contiguous, like the rest of the kernel binary.
+    let phys_start_page_addr =
+        try_kernel_virt_page_addr_to_phys_page_addr(virt_code_region.start_page_addr())?;
+    let phys_end_exclusive_page_addr = phys_start_page_addr
+        .checked_offset(virt_code_region.num_pages() as isize)
+        .ok_or("Code region exceeds the physical address space")?;
+    let phys_region = MemoryRegion::new(phys_start_page_addr, phys_end_exclusive_page_addr);
+
+    let num_pages = match NonZeroUsize::new(phys_region.num_pages()) {
+        None => {
+            return Err(KernelError::new(
+                ErrorKind::InvalidArgument,
+                "Requested 0 pages",
+            ))
+        }
+        Some(x) => x,
+    };
+
+    let virt_region = alloc::kernel_mmio_va_allocator()
+        .lock(|allocator| allocator.alloc(num_pages))
+        .context("Allocating the code alias virtual addresses")?;
+
+    kernel_map_at_unchecked(
+        name,
+        &virt_region,
+        &phys_region,
+        &AttributeFields {
+            mem_attributes: MemAttributes::CacheableDRAM,
+            acc_perms: AccessPermissions::ReadWrite,
+            execute_never: true,
+        },
+    )?;
+
+    Ok(virt_region.start_addr())
+}
+
+/// Mark the kernel's code pages as guarded, which enables branch target identification for them.
+///
+/// The precomputed tables cannot do it, because the guarded bit is reserved on CPUs without
+/// branch target identification.
+///
+/// # Safety
+///
+/// - See `set_guarded()`. Everything linked into the kernel, including `core`, must have been built
+///   with landing pads.
+pub unsafe fn kernel_guard_code() -> Result<(), KernelError> {
+    let code = bsp::memory::virt_code_range();
+    let virt_code_region =
+        MemoryRegion::new(PageAddress::from(code.start), PageAddress::from(code.end));
+
+    bsp::memory::mmu::kernel_translation_tables()
+        .write(|tables| tables.set_guarded(&virt_code_region))?;
+
+    kernel_tables_sync();
+
+    Ok(())
+}
+
+/// Try to translate a kernel virtual address to a physical address.
+///
+/// Will only succeed if there exists a valid mapping for the input address.
+pub fn try_kernel_virt_addr_to_phys_addr(
+    virt_addr: Address<Virtual>,
+) -> Result<Address<Physical>, &'static str> {
+    bsp::memory::mmu::kernel_translation_tables()
+        .read(|tables| tables.try_virt_addr_to_phys_addr(virt_addr))
+}
+
 /// Try to translate a kernel virtual page address to a physical page address.
 ///
 /// Will only succeed if there exists a valid mapping for the input page.
@@ -264,7 +358,27 @@ pub fn post_enable_init() {
     kernel_init_mmio_va_allocator();
 }
 
+/// Drop the executing core's cached translation of the page that contains `virt_addr`.
+///
+/// The next access to the page walks the translation tables again.
+pub fn local_tlb_invalidate(virt_addr: Address<Virtual>) {
+    arch_mmu::local_tlb_invalidate_page(virt_addr.as_usize());
+}
+
+/// Check that the recorded kernel mappings match the kernel's translation tables.
+///
+/// An invariant check for [crate::debug::invariant].
+pub fn kernel_check_mappings() -> Result<(), &'static str> {
+    mapping_record::kernel_check()
+}
+
 /// Human-readable print of all recorded kernel mappings.
 pub fn kernel_print_mappings() {
     mapping_record::kernel_print()
 }
+
+/// The number of pages in the kernel's MMIO remap region, and how many of them are still free.
+pub fn kernel_mmio_va_usage() -> (usize, usize) {
+    alloc::kernel_mmio_va_allocator()
+        .lock(|allocator| (allocator.num_total_pages(), allocator.num_free_pages()))
+}
@@ -6,9 +6,9 @@
 
 use super::{
     AccessPermissions, Address, AttributeFields, MMIODescriptor, MemAttributes, MemoryRegion,
-    Physical, Virtual,
+    PageAddress, Physical, Virtual,
 };
-use crate::{bsp, info, synchronization, synchronization::InitStateLock, warn};
+use crate::{bsp, info, synchronization, synchronization::IRQSafeRwLock, warn};
 
 //--------------------------------------------------------------------------------------------------
 // Private Definitions
@@ -26,15 +26,16 @@ struct MappingRecordEntry {
 }
 
 struct MappingRecord {
-    inner: [Option<MappingRecordEntry>; 12],
+    inner: [Option<MappingRecordEntry>; 16],
 }
 
 //--------------------------------------------------------------------------------------------------
 // Global instances
 //--------------------------------------------------------------------------------------------------
 
-static KERNEL_MAPPING_RECORD: InitStateLock<MappingRecord> =
-    InitStateLock::new(MappingRecord::new());
+/// Read-mostly. Written when mappings are added, read for lookups and printing.
+static KERNEL_MAPPING_RECORD: IRQSafeRwLock<MappingRecord> =
+    IRQSafeRwLock::new(MappingRecord::new());
 
 //--------------------------------------------------------------------------------------------------
 // Private Code
@@ -73,7 +74,7 @@ impl MappingRecordEntry {
 
 impl MappingRecord {
     pub const fn new() -> Self {
-        Self { inner: [None; 12] }
+        Self { inner: [None; 16] }
     }
 
     fn find_next_free(&mut self) -> Result<&mut Option<MappingRecordEntry>, &'static str> {
@@ -90,8 +91,7 @@ impl MappingRecord {
     ) -> Option<&mut MappingRecordEntry> {
         self.inner
             .iter_mut()
-            .filter(|x| x.is_some())
-            .map(|x| x.as_mut().unwrap())
+            .filter_map(|x| x.as_mut())
             .filter(|x| x.attribute_fields.mem_attributes == MemAttributes::Device)
             .find(|x| {
                 if x.phys_start_addr != phys_region.start_addr() {
@@ -124,6 +124,30 @@ impl MappingRecord {
         Ok(())
     }
 
+    /// Check that every recorded page is mapped as recorded in the kernel's translation tables.
+    pub fn check(&self) -> Result<(), &'static str> {
+        for entry in self.inner.iter().flatten() {
+            let virt_start = PageAddress::<Virtual>::from(entry.virt_start_addr);
+            let phys_start = PageAddress::<Physical>::from(entry.phys_start_addr);
+
+            for i in 0..entry.num_pages as isize {
+                let overflow = "Recorded mapping overflows the address space";
+                let virt_page = virt_start.checked_offset(i).ok_or(overflow)?;
+                let phys_page = phys_start.checked_offset(i).ok_or(overflow)?;
+
+                if super::try_kernel_virt_page_addr_to_phys_page_addr(virt_page)? != phys_page {
+                    return Err("Recorded page is mapped to a different physical page");
+                }
+
+                if super::try_kernel_page_attributes(virt_page)? != entry.attribute_fields {
+                    return Err("Recorded page is mapped with different attributes");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn print(&self) {
         const KIB_RSHIFT: u32 = 10; // log2(1024).
         const MIB_RSHIFT: u32 = 20; // log2(1024 * 1024).
@@ -227,6 +251,11 @@ pub fn kernel_find_and_insert_mmio_duplicate(
     })
 }
 
+/// Check the record against the kernel's translation tables.
+pub fn kernel_check() -> Result<(), &'static str> {
+    KERNEL_MAPPING_RECORD.read(|mr| mr.check())
+}
+
 /// Human-readable print of all recorded kernel mappings.
 pub fn kernel_print() {
     KERNEL_MAPPING_RECORD.read(|mr| mr.print());
@@ -53,6 +53,18 @@ pub mod interface {
             attr: &AttributeFields,
         ) -> Result<(), &'static str>;
 
+        /// Mark the pages of the given region as guarded.
+        ///
+        /// Indirect branches into guarded pages must land on branch target instructions.
+        ///
+        /// # Safety
+        ///
+        /// - All code in the region must have landing pads at its indirect branch targets.
+        unsafe fn set_guarded(
+            &mut self,
+            virt_region: &MemoryRegion<Virtual>,
+        ) -> Result<(), &'static str>;
+
         /// Try to translate a virtual page address to a physical page address.
         ///
         /// Will only succeed if there exists a valid mapping for the input page.
@@ -133,5 +145,39 @@ mod tests {
         let virt_addr = virt_start_page_addr.into_inner() + 0x100;
         let phys_addr = phys_start_page_addr.into_inner() + 0x100;
         assert_eq!(tables.try_virt_addr_to_phys_addr(virt_addr), Ok(phys_addr));
+
+        // Only executable pages can be guarded, and guarding keeps their attributes.
+        unsafe {
+            assert_eq!(
+                tables.set_guarded(&virt_region),
+                Err("Page is not executable")
+            )
+        };
+
+        let code_region = MemoryRegion::new(
+            virt_start_page_addr.checked_offset(-1).unwrap(),
+            virt_start_page_addr,
+        );
+        let code_attr = AttributeFields {
+            mem_attributes: MemAttributes::CacheableDRAM,
+            acc_perms: AccessPermissions::ReadOnly,
+            execute_never: false,
+        };
+        let phys_code_region = MemoryRegion::new(
+            phys_end_exclusive_page_addr,
+            phys_end_exclusive_page_addr.checked_offset(1).unwrap(),
+        );
+
+        unsafe {
+            assert_eq!(
+                tables.map_at(&code_region, &phys_code_region, &code_attr),
+                Ok(())
+            );
+            assert_eq!(tables.set_guarded(&code_region), Ok(()));
+        }
+        assert_eq!(
+            tables.try_page_attributes(code_region.start_page_addr()),
+            Ok(code_attr)
+        );
     }
 }
@@ -4,7 +4,7 @@
 
 //! A panic handler that infinitely waits.
 
-use crate::{bsp, cpu, exception};
+use crate::{bsp, cpu, debug, exception};
 use core::{fmt, panic::PanicInfo};
 
 //--------------------------------------------------------------------------------------------------
@@ -54,5 +54,7 @@ fn panic(info: &PanicInfo) -> ! {
         panic_println!("\nKernel panic!");
     }
 
+    debug::crashdump::write(&mut unsafe { bsp::console::panic_console_out() }, info).ok();
+
     _panic_exit()
 }
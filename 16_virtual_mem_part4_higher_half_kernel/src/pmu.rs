@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Performance Monitors Unit.
+//!
+//! Every core has a cycle counter and a number of event counters, which count architectural events
+//! like cache refills or branch mispredictions. The counters of a core only count what happens on
+//! that core, so a [Counter] is bound to the core that created it.
+//!
+//! Event counters are only 32 bits wide. Their overflow raises an IRQ, in which the kernel
+//! accumulates the upper 32 bits in software, so that [Counter::read()] returns 64-bit values.
+
+#[cfg(target_arch = "aarch64")]
+#[path = "_arch/aarch64/pmu.rs"]
+mod arch_pmu;
+
+pub mod sampler;
+
+use crate::{bsp, error::KernelError, exception, per_cpu};
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Architectural Public Reexports
+//--------------------------------------------------------------------------------------------------
+pub use arch_pmu::{cycle_counter, num_event_counters};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The architectural maximum of event counters per core.
+const MAX_EVENT_COUNTERS: usize = 31;
+
+/// The PMU overflow IRQ handler.
+struct OverflowIRQHandler;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Common architectural events.
+#[allow(missing_docs)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum Event {
+    L1InstructionCacheRefill = 0x01,
+    L1InstructionTLBRefill = 0x02,
+    L1DataCacheRefill = 0x03,
+    L1DataCacheAccess = 0x04,
+    L1DataTLBRefill = 0x05,
+    InstructionsRetired = 0x08,
+    ExceptionsTaken = 0x09,
+    BranchesMispredicted = 0x10,
+    CpuCycles = 0x11,
+    BranchesPredicted = 0x12,
+    MemoryAccesses = 0x13,
+    L2DataCacheAccess = 0x16,
+    L2DataCacheRefill = 0x17,
+    BusAccesses = 0x19,
+}
+
+/// An event counter of the executing core.
+///
+/// The counter is released when dropped.
+pub struct Counter {
+    index: usize,
+
+    /// Counters are per-core, so they must not be handed to another core.
+    _not_send: PhantomData<*const ()>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[allow(clippy::declare_interior_mutable_const)]
+const OVERFLOWS_INIT: AtomicU32 = AtomicU32::new(0);
+
+per_cpu! {
+    /// The upper 32 bits of each event counter.
+    static OVERFLOWS: [AtomicU32; MAX_EVENT_COUNTERS] = [OVERFLOWS_INIT; MAX_EVENT_COUNTERS];
+}
+
+per_cpu! {
+    /// Bitmask of the event counters that are in use.
+    static IN_USE: AtomicU32 = AtomicU32::new(0);
+}
+
+static OVERFLOW_IRQ_HANDLER: OverflowIRQHandler = OverflowIRQHandler;
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Account for all pending overflows of the executing core's event counters.
+fn handle_overflows() {
+    let pending = arch_pmu::pending_overflows();
+    let sampled = sampler::handle_overflows(pending);
+    let overflows = OVERFLOWS.local();
+
+    for (i, overflow) in overflows.iter().enumerate() {
+        if (pending & !sampled) & (1 << i) != 0 {
+            overflow.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    arch_pmu::clear_overflows(pending);
+}
+
+impl exception::asynchronous::interface::IRQHandler for OverflowIRQHandler {
+    fn handle(&self) -> Result<(), &'static str> {
+        handle_overflows();
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Counter {
+    fn mask(&self) -> u32 {
+        1 << self.index
+    }
+
+    /// Claim a free event counter of the executing core and configure it to count `event`.
+    ///
+    /// The counter starts out stopped, at zero.
+    pub fn new(event: Event) -> Result<Self, &'static str> {
+        exception::asynchronous::exec_with_irq_masked(|| {
+            let in_use = IN_USE.local();
+            let implemented = (1_u64 << num_event_counters()) - 1;
+            let free = !in_use.load(Ordering::Relaxed) & implemented as u32;
+
+            if free == 0 {
+                return Err("No free PMU event counter");
+            }
+
+            let index = free.trailing_zeros() as usize;
+            in_use.fetch_or(1 << index, Ordering::Relaxed);
+
+            arch_pmu::set_event_type(index, event as u16);
+            arch_pmu::write_event_counter(index, 0);
+            arch_pmu::clear_overflows(1 << index);
+            OVERFLOWS.local()[index].store(0, Ordering::Relaxed);
+
+            Ok(Self {
+                index,
+                _not_send: PhantomData,
+            })
+        })
+    }
+
+    /// Start counting.
+    pub fn start(&self) {
+        arch_pmu::enable_event_counter(self.index);
+    }
+
+    /// Stop counting.
+    pub fn stop(&self) {
+        arch_pmu::disable_event_counter(self.index);
+    }
+
+    /// The number of events counted so far.
+    pub fn read(&self) -> u64 {
+        exception::asynchronous::exec_with_irq_masked(|| {
+            let high = u64::from(OVERFLOWS.local()[self.index].load(Ordering::Relaxed));
+
+            // With IRQs masked, an overflow can be pending without having been accounted yet. If
+            // none was pending after the first read, that read happened before any overflow.
+            // Otherwise, the second read happened after it.
+            let low_before = arch_pmu::read_event_counter(self.index);
+            let overflow_pending = arch_pmu::pending_overflows() & self.mask() != 0;
+            let low_after = arch_pmu::read_event_counter(self.index);
+
+            if overflow_pending {
+                ((high + 1) << 32) | u64::from(low_after)
+            } else {
+                (high << 32) | u64::from(low_before)
+            }
+        })
+    }
+}
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        self.stop();
+        IN_USE.local().fetch_and(!self.mask(), Ordering::Relaxed);
+    }
+}
+
+/// Enable and reset the executing core's PMU.
+///
+/// Must be called on every core before its counters are used.
+pub fn init() {
+    arch_pmu::init();
+}
+
+/// Register and enable the overflow IRQ handler with the BSP's interrupt controller.
+pub fn register_and_enable_irq_handler() -> Result<(), KernelError> {
+    use exception::asynchronous::IRQDescriptor;
+
+    let descriptor = IRQDescriptor {
+        name: "PMU overflow",
+        handler: &OVERFLOW_IRQ_HANDLER,
+    };
+
+    bsp::exception::asynchronous::register_and_enable_pmu_irq_handler(descriptor)
+}
+
+/// Handle a PMU overflow IRQ.
+///
+/// Called by interrupt controller drivers that dispatch the PMU IRQ directly instead of through
+/// their handler table.
+pub fn handle_overflow_irq(_ic: &exception::asynchronous::IRQContext) {
+    handle_overflows();
+}
@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Statistical sampling profiler.
+//!
+//! While sampling, every core dedicates one event counter to counting CPU cycles. The counter is
+//! preloaded so that it overflows after a sampling period. The overflow IRQ then records the
+//! address at which the core was interrupted, reloads the counter, and returns. Over many samples,
+//! the number of hits per address approximates where the cores spend their time.
+//!
+//! Hits are bucketed into small ranges of the kernel's code segment. The kernel does not embed a
+//! symbol table, so [print()] reports bucket addresses, which can be resolved on the host with
+//! `rust-addr2line` or by looking them up in the output of `rust-nm`.
+//!
+//! Code that runs with IRQs masked is never sampled. Its cycles are attributed to the first address
+//! after IRQs get unmasked again.
+//!
+//! `profile=<cycles>` on the kernel command line starts sampling at boot with the given period. The
+//! health report, see [crate::status], then includes the profile.
+
+use super::{arch_pmu, Counter, Event};
+use crate::{bsp, cpu, exception, info, per_cpu};
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Each bucket covers 2^BUCKET_SHIFT bytes of code.
+const BUCKET_SHIFT: usize = 6;
+
+/// Enough buckets to cover 256 KiB of code.
+const MAX_BUCKETS: usize = 4096;
+
+/// The number of buckets shown by [print()].
+const NUM_REPORTED: usize = 16;
+
+/// Marks a core that is not sampling.
+const NO_COUNTER: usize = usize::MAX;
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[allow(clippy::declare_interior_mutable_const)]
+const BUCKET_INIT: AtomicU32 = AtomicU32::new(0);
+
+static BUCKETS: [AtomicU32; MAX_BUCKETS] = [BUCKET_INIT; MAX_BUCKETS];
+
+/// Samples that were taken outside of the covered code range.
+static OUTSIDE: AtomicU64 = AtomicU64::new(0);
+
+/// The sampling period in CPU cycles.
+static PERIOD: AtomicU32 = AtomicU32::new(0);
+
+/// Whether sampling was started and not stopped since.
+static SAMPLING: AtomicBool = AtomicBool::new(false);
+
+/// The number of cores that could not start sampling.
+static START_FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+per_cpu! {
+    /// The index of the event counter that the core dedicates to sampling.
+    static SAMPLING_COUNTER: AtomicUsize = AtomicUsize::new(NO_COUNTER);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn reload(index: usize) {
+    arch_pmu::write_event_counter(index, 0_u32.wrapping_sub(PERIOD.load(Ordering::Relaxed)));
+}
+
+fn record(pc: usize) {
+    let code = bsp::memory::virt_code_range();
+
+    let bucket = pc
+        .checked_sub(code.start.as_usize())
+        .filter(|_| pc < code.end.as_usize())
+        .map(|offset| offset >> BUCKET_SHIFT)
+        .filter(|&bucket| bucket < MAX_BUCKETS);
+
+    match bucket {
+        Some(bucket) => {
+            BUCKETS[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        None => {
+            OUTSIDE.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Start sampling on the executing core.
+fn start_local() {
+    exception::asynchronous::exec_with_irq_masked(|| {
+        if SAMPLING_COUNTER.local().load(Ordering::Relaxed) != NO_COUNTER {
+            return;
+        }
+
+        let counter = match Counter::new(Event::CpuCycles) {
+            Ok(counter) => counter,
+            Err(_) => {
+                START_FAILURES.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+
+        reload(counter.index);
+        counter.start();
+
+        // Ownership of the counter is handed to SAMPLING_COUNTER. It is released in stop_local().
+        SAMPLING_COUNTER
+            .local()
+            .store(counter.index, Ordering::Relaxed);
+        core::mem::forget(counter);
+    })
+}
+
+/// Stop sampling on the executing core.
+fn stop_local() {
+    exception::asynchronous::exec_with_irq_masked(|| {
+        let index = SAMPLING_COUNTER.local().swap(NO_COUNTER, Ordering::Relaxed);
+        if index == NO_COUNTER {
+            return;
+        }
+
+        drop(Counter {
+            index,
+            _not_send: PhantomData,
+        });
+    })
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Take a sample if the executing core's sampling counter is among the `pending` overflows.
+///
+/// Returns the overflows that were handled. Called from the PMU overflow IRQ handler.
+pub(super) fn handle_overflows(pending: u32) -> u32 {
+    let index = SAMPLING_COUNTER.local().load(Ordering::Relaxed);
+    if index == NO_COUNTER || pending & (1 << index) == 0 {
+        return 0;
+    }
+
+    record(exception::asynchronous::interrupted_pc());
+    reload(index);
+
+    1 << index
+}
+
+/// Start sampling on all online cores, once every `period` CPU cycles.
+///
+/// The statistics of earlier runs are kept. Use [reset()] to discard them.
+pub fn start(period: u32) -> Result<(), &'static str> {
+    if period == 0 {
+        return Err("Sampling period must not be zero");
+    }
+
+    PERIOD.store(period, Ordering::Relaxed);
+    START_FAILURES.store(0, Ordering::Relaxed);
+    cpu::smp::call_on_each_cpu(start_local);
+
+    if START_FAILURES.load(Ordering::Relaxed) != 0 {
+        stop();
+        return Err("No free PMU event counter for sampling");
+    }
+
+    SAMPLING.store(true, Ordering::Relaxed);
+
+    Ok(())
+}
+
+/// Stop sampling on all online cores.
+pub fn stop() {
+    cpu::smp::call_on_each_cpu(stop_local);
+    SAMPLING.store(false, Ordering::Relaxed);
+}
+
+/// Whether sampling is running.
+pub fn is_sampling() -> bool {
+    SAMPLING.load(Ordering::Relaxed)
+}
+
+/// Discard all samples.
+pub fn reset() {
+    for bucket in BUCKETS.iter() {
+        bucket.store(0, Ordering::Relaxed);
+    }
+    OUTSIDE.store(0, Ordering::Relaxed);
+}
+
+/// Print a flat profile of the buckets with the most samples.
+pub fn print() {
+    let mut top = [(0_usize, 0_u32); NUM_REPORTED];
+    let mut total = OUTSIDE.load(Ordering::Relaxed);
+
+    for (i, bucket) in BUCKETS.iter().enumerate() {
+        let hits = bucket.load(Ordering::Relaxed);
+        total += u64::from(hits);
+
+        // The last entry holds the smallest number of hits.
+        if hits > top[NUM_REPORTED - 1].1 {
+            top[NUM_REPORTED - 1] = (i, hits);
+            top.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        }
+    }
+
+    info!("Samples: {}", total);
+    if total == 0 {
+        return;
+    }
+
+    info!("      Samples      %  Code address");
+
+    let code_start = bsp::memory::virt_code_range().start;
+    for (i, hits) in top.iter().filter(|(_, hits)| *hits != 0) {
+        info!(
+            "      {:>7} {:>5}.{}  {}",
+            hits,
+            u64::from(*hits) * 100 / total,
+            u64::from(*hits) * 1000 / total % 10,
+            code_start + (i << BUCKET_SHIFT)
+        );
+    }
+
+    let outside = OUTSIDE.load(Ordering::Relaxed);
+    if outside != 0 {
+        info!("      {:>7}          Outside of kernel code", outside);
+    }
+}
@@ -4,8 +4,8 @@
 
 //! Printing.
 
-use crate::{bsp, console};
-use core::fmt;
+use crate::{bsp, console, debug, time};
+use core::{fmt, time::Duration};
 
 //--------------------------------------------------------------------------------------------------
 // Public Code
@@ -15,9 +15,47 @@ use core::fmt;
 pub fn _print(args: fmt::Arguments) {
     use console::interface::Write;
 
+    debug::crashdump::log(args);
     bsp::console::console().write_fmt(args).unwrap();
 }
 
+/// The timestamp that prefixes log messages.
+///
+/// Shows the date and time once the wall clock is set, and the uptime before.
+#[doc(hidden)]
+pub fn _timestamp() -> impl fmt::Display {
+    use time::interface::TimeManager;
+
+    struct Timestamp {
+        uptime: Duration,
+        date_time: Option<time::wall_clock::DateTime>,
+    }
+
+    impl fmt::Display for Timestamp {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.date_time {
+                Some(dt) => write!(f, "{}.{:06}", dt, dt.nanosecond / 1_000),
+                None => {
+                    let subsec_us = self.uptime.subsec_micros();
+
+                    write!(
+                        f,
+                        "{:>3}.{:03}{:03}",
+                        self.uptime.as_secs(),
+                        subsec_us / 1_000,
+                        subsec_us % 1_000
+                    )
+                }
+            }
+        }
+    }
+
+    Timestamp {
+        uptime: time::time_manager().uptime(),
+        date_time: time::wall_clock::date_time(),
+    }
+}
+
 /// Prints without a newline.
 ///
 /// Carbon copy from <https://doc.rust-lang.org/src/std/macros.rs.html>
@@ -38,36 +76,26 @@ macro_rules! println {
 }
 
 /// Prints an info, with a newline.
+///
+/// Suppressed if the configured log level is below [LogLevel::Info](crate::config::LogLevel).
 #[macro_export]
 macro_rules! info {
     ($string:expr) => ({
-        #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
-
-        let timestamp = $crate::time::time_manager().uptime();
-        let timestamp_subsec_us = timestamp.subsec_micros();
-
-        $crate::print::_print(format_args_nl!(
-            concat!("[  {:>3}.{:03}{:03}] ", $string),
-            timestamp.as_secs(),
-            timestamp_subsec_us / 1_000,
-            timestamp_subsec_us % 1_000
-        ));
+        if $crate::config::LOG_LEVEL >= $crate::config::LogLevel::Info {
+            $crate::print::_print(format_args_nl!(
+                concat!("[  {}] ", $string),
+                $crate::print::_timestamp()
+            ));
+        }
     });
     ($format_string:expr, $($arg:tt)*) => ({
-        #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
-
-        let timestamp = $crate::time::time_manager().uptime();
-        let timestamp_subsec_us = timestamp.subsec_micros();
-
-        $crate::print::_print(format_args_nl!(
-            concat!("[  {:>3}.{:03}{:03}] ", $format_string),
-            timestamp.as_secs(),
-            timestamp_subsec_us / 1_000,
-            timestamp_subsec_us % 1_000,
-            $($arg)*
-        ));
+        if $crate::config::LOG_LEVEL >= $crate::config::LogLevel::Info {
+            $crate::print::_print(format_args_nl!(
+                concat!("[  {}] ", $format_string),
+                $crate::print::_timestamp(),
+                $($arg)*
+            ));
+        }
     })
 }
 
@@ -75,31 +103,15 @@ macro_rules! info {
 #[macro_export]
 macro_rules! warn {
     ($string:expr) => ({
-        #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
-
-        let timestamp = $crate::time::time_manager().uptime();
-        let timestamp_subsec_us = timestamp.subsec_micros();
-
         $crate::print::_print(format_args_nl!(
-            concat!("[W {:>3}.{:03}{:03}] ", $string),
-            timestamp.as_secs(),
-            timestamp_subsec_us / 1_000,
-            timestamp_subsec_us % 1_000
+            concat!("[W {}] ", $string),
+            $crate::print::_timestamp()
         ));
     });
     ($format_string:expr, $($arg:tt)*) => ({
-        #[allow(unused_imports)]
-        use crate::time::interface::TimeManager;
-
-        let timestamp = $crate::time::time_manager().uptime();
-        let timestamp_subsec_us = timestamp.subsec_micros();
-
         $crate::print::_print(format_args_nl!(
-            concat!("[W {:>3}.{:03}{:03}] ", $format_string),
-            timestamp.as_secs(),
-            timestamp_subsec_us / 1_000,
-            timestamp_subsec_us % 1_000,
+            concat!("[W {}] ", $format_string),
+            $crate::print::_timestamp(),
             $($arg)*
         ));
     })
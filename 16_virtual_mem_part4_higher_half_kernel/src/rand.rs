@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Random numbers.
+//!
+//! [fill()] hands out bytes from a ChaCha20-based pool. Entropy comes from three sources:
+//!
+//! - The hardware RNG, if the BSP registered a `hwrng` character device. [init()] seeds the pool
+//!   with it.
+//! - Timer jitter, which [init()] measures as well.
+//! - IRQ timings. Every core accumulates the arrival times of its IRQs without taking a lock, and
+//!   [fill()] mixes the accumulated values into the pool.
+//!
+//! Before [init()] ran, the output is predictable. [is_seeded()] tells whether it did.
+
+use crate::{
+    bsp, driver, per_cpu,
+    synchronization::{interface::Mutex, IRQSafeSpinLock},
+    time,
+};
+use core::{
+    hint,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+use kernel_core::chacha::{ChaChaRng, KEY_SIZE};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The number of timer jitter samples that seed the pool.
+const NUM_JITTER_SAMPLES: usize = 256;
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static POOL: IRQSafeSpinLock<ChaChaRng> = IRQSafeSpinLock::new(ChaChaRng::new());
+
+static SEEDED: AtomicBool = AtomicBool::new(false);
+
+per_cpu! {
+    /// The executing core's IRQ timings, folded into a single word.
+    static IRQ_TIMINGS: AtomicU64 = AtomicU64::new(0);
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Read up to a key's worth of bytes from the hardware RNG. Returns the number of bytes read.
+fn read_hwrng(buf: &mut [u8; KEY_SIZE]) -> usize {
+    let hwrng = match driver::chardev::find_by_name("hwrng") {
+        None => return 0,
+        Some(x) => x,
+    };
+
+    let mut len = 0;
+    while len < buf.len() {
+        match hwrng.device.read(&mut buf[len..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => len += n,
+        }
+    }
+
+    len
+}
+
+/// Sample how long a short, data-dependent busy loop takes.
+///
+/// The deltas vary with cache and bus state and with the timer's phase.
+fn sample_timer_jitter(samples: &mut [u8; NUM_JITTER_SAMPLES]) {
+    let mut previous = 0;
+
+    for sample in samples.iter_mut() {
+        let start = time::Instant::now();
+        for _ in 0..(previous & 0xf) + 1 {
+            hint::spin_loop();
+        }
+        let delta = time::Instant::now().ticks_since(start);
+
+        *sample = (delta ^ start.ticks()) as u8;
+        previous = delta;
+    }
+}
+
+/// Collect every core's IRQ timings.
+///
+/// The values are left in place. Mixing in an unchanged value again does no harm.
+fn irq_timings() -> [u8; 8 * bsp::cpu::NUM_CORES] {
+    let mut bytes = [0; 8 * bsp::cpu::NUM_CORES];
+
+    for (core_id, chunk) in bytes.chunks_mut(8).enumerate() {
+        let timings = IRQ_TIMINGS.remote(core_id).load(Ordering::Relaxed);
+        chunk.copy_from_slice(&timings.to_le_bytes());
+    }
+
+    bytes
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Seed the pool from the hardware RNG and from timer jitter.
+///
+/// Must be called after driver init, so that the hardware RNG is up.
+pub fn init() {
+    let mut hwrng = [0; KEY_SIZE];
+    let hwrng_len = read_hwrng(&mut hwrng);
+
+    let mut jitter = [0; NUM_JITTER_SAMPLES];
+    sample_timer_jitter(&mut jitter);
+
+    POOL.lock(|pool| {
+        pool.reseed(&hwrng[..hwrng_len]);
+        pool.reseed(&jitter);
+    });
+    SEEDED.store(true, Ordering::Release);
+
+    if hwrng_len == 0 {
+        crate::warn!("No hardware RNG, seeded the entropy pool from timer jitter only");
+    }
+}
+
+/// Whether [init()] seeded the pool.
+pub fn is_seeded() -> bool {
+    SEEDED.load(Ordering::Acquire)
+}
+
+/// Mix `data` into the pool.
+pub fn add_entropy(data: &[u8]) {
+    POOL.lock(|pool| pool.reseed(data));
+}
+
+/// Account for an IRQ that interrupted the executing core at `interrupted_pc`.
+///
+/// Called in IRQ context, where the executing core can not be interrupted. Other cores only read
+/// the value, so a plain load and store is enough.
+#[inline(always)]
+pub fn add_irq_timing(interrupted_pc: usize) {
+    let timings = IRQ_TIMINGS.local();
+    let sample = time::Instant::now().ticks() ^ (interrupted_pc as u64).rotate_left(32);
+
+    timings.store(
+        timings.load(Ordering::Relaxed).rotate_left(7) ^ sample,
+        Ordering::Relaxed,
+    );
+}
+
+/// Fill `buf` with random bytes.
+pub fn fill(buf: &mut [u8]) {
+    let irq_timings = irq_timings();
+
+    POOL.lock(|pool| {
+        pool.reseed(&irq_timings);
+        pool.fill(buf);
+    });
+}
+
+/// Return a random `u64`.
+pub fn next_u64() -> u64 {
+    let mut bytes = [0; 8];
+    fill(&mut bytes);
+
+    u64::from_le_bytes(bytes)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check that consecutive requests differ.
+    #[kernel_test]
+    fn rand_output_differs() {
+        let mut a = [0; 32];
+        let mut b = [0; 32];
+        fill(&mut a);
+        fill(&mut b);
+
+        assert_ne!(a, b);
+        assert_ne!(next_u64(), next_u64());
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Kernel health report.
+//!
+//! [print()] gathers what is known about the kernel's health in one place. Pressing `CTRL + T` on
+//! the console calls it.
+//!
+//! The share of time that each core spent idle stands in for a load average, since there is no
+//! scheduler whose run queue could be averaged. The values that subsystems publish in the
+//! introspection tree, see [crate::introspect], follow. While the sampling profiler runs, see
+//! [crate::pmu::sampler], its profile comes last.
+
+use crate::{cpu, info, introspect, pmu, time};
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Print the kernel's health report.
+pub fn print() {
+    use time::interface::TimeManager;
+
+    let uptime = time::time_manager().uptime();
+    info!(
+        "Uptime: {}.{:03}s",
+        uptime.as_secs(),
+        uptime.subsec_millis()
+    );
+
+    cpu::idle::print();
+
+    info!("Introspection:");
+    introspect::print("");
+
+    if pmu::sampler::is_sampling() {
+        info!("Sampling profile:");
+        pmu::sampler::print();
+    }
+}
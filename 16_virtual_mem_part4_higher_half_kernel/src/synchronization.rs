@@ -9,8 +9,26 @@
 //!   - <https://doc.rust-lang.org/book/ch16-04-extensible-concurrency-sync-and-send.html>
 //!   - <https://stackoverflow.com/questions/59428096/understanding-the-send-trait>
 //!   - <https://doc.rust-lang.org/std/cell/index.html>
+//!   - <https://mirrors.edge.kernel.org/pub/linux/kernel/people/paulmck/perfbook/perfbook.html>
 
-use core::cell::UnsafeCell;
+#[cfg(target_arch = "aarch64")]
+#[path = "_arch/aarch64/synchronization.rs"]
+mod arch_synchronization;
+
+#[cfg(feature = "lockdep")]
+mod lockdep;
+
+pub mod rcu;
+
+pub use kernel_core::ringbuffer;
+
+use core::{
+    cell::UnsafeCell,
+    hint,
+    panic::Location,
+    sync::atomic::{fence, AtomicU16, AtomicU32, AtomicU8, AtomicUsize, Ordering},
+    time::Duration,
+};
 
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
@@ -45,20 +63,107 @@ pub mod interface {
     }
 }
 
-/// A pseudo-lock for teaching purposes.
+/// Stand-ins for the lock dependency validation hooks, used when the `lockdep` feature is disabled.
+#[cfg(not(feature = "lockdep"))]
+mod lockdep {
+    use core::panic::Location;
+
+    #[derive(Copy, Clone)]
+    pub struct Class;
+
+    #[inline(always)]
+    pub const fn class() -> Class {
+        Class
+    }
+
+    #[inline(always)]
+    pub fn acquire(_class: Class, _site: &'static Location<'static>, _read: bool) {}
+
+    #[inline(always)]
+    pub fn release(_class: Class) {}
+}
+
+/// A ticket spinlock.
 ///
-/// In contrast to a real Mutex implementation, does not protect against concurrent access from
-/// other cores to the contained data. This part is preserved for later lessons.
+/// Cores that want to acquire the lock draw a ticket and wait until their number is served, which
+/// guarantees FIFO fairness between contending cores.
 ///
-/// The lock will only be used as long as it is safe to do so, i.e. as long as the kernel is
-/// executing on a single core.
-pub struct IRQSafeNullLock<T>
+/// Does not mask IRQs. Therefore, it must not be used for data that is also accessed from IRQ
+/// context, else an IRQ handler that contends for a lock held by the code it interrupted would
+/// deadlock the core. Use [IRQSafeSpinLock] for such cases.
+pub struct SpinLock<T>
 where
     T: ?Sized,
 {
+    next_ticket: AtomicU16,
+    now_serving: AtomicU16,
+    class: lockdep::Class,
+    data: UnsafeCell<T>,
+}
+
+/// A ticket spinlock that masks IRQs on the executing core while the lock is held.
+pub struct IRQSafeSpinLock<T>
+where
+    T: ?Sized,
+{
+    inner: SpinLock<T>,
+}
+
+/// A spinning reader-writer lock with writer preference.
+///
+/// As soon as a writer waits for the lock, new readers are held back until it got its turn. Cores
+/// that already hold the read lock are exempt: the writer can not get its turn before they are
+/// done, so a core may acquire the read lock recursively.
+///
+/// Like [SpinLock], it does not mask IRQs. Use [IRQSafeRwLock] for data that is also accessed from
+/// IRQ context.
+pub struct RwLock<T>
+where
+    T: ?Sized,
+{
+    /// The number of active readers, or [RwLock::WRITER] while a writer holds the lock.
+    state: AtomicU32,
+    writers_waiting: AtomicU32,
+
+    /// How often each core currently holds the read lock.
+    read_depth: [AtomicU8; bsp::cpu::NUM_CORES],
+    class: lockdep::Class,
     data: UnsafeCell<T>,
 }
 
+/// A reader-writer lock that masks IRQs on the executing core while the lock is held.
+pub struct IRQSafeRwLock<T>
+where
+    T: ?Sized,
+{
+    inner: RwLock<T>,
+}
+
+/// A sequence lock for small, read-mostly data.
+///
+/// Readers never block writers and never write to shared memory. Instead, they copy the data and
+/// retry if a writer was active in the meantime, which they detect through the sequence number. It
+/// is odd while a write is in progress and incremented again at its end.
+///
+/// Writers are serialized by a spinlock and mask IRQs, so a reader in IRQ context can never spin on
+/// a write that it interrupted on the same core.
+pub struct SeqLock<T>
+where
+    T: Copy,
+{
+    sequence: AtomicUsize,
+    writer: SpinLock<()>,
+    data: UnsafeCell<T>,
+}
+
+/// A counting semaphore.
+///
+/// Bounds the number of concurrent users of a resource. There is no scheduler to put waiters to
+/// sleep, so [Semaphore::acquire()] spins until a permit becomes available.
+pub struct Semaphore {
+    permits: AtomicUsize,
+}
+
 /// A pseudo-lock that is RW during the single-core kernel init phase and RO afterwards.
 ///
 /// Intended to encapsulate data that is populated during kernel init when no concurrency exists.
@@ -73,16 +178,257 @@ where
 // Public Code
 //--------------------------------------------------------------------------------------------------
 
-unsafe impl<T> Send for IRQSafeNullLock<T> where T: ?Sized + Send {}
-unsafe impl<T> Sync for IRQSafeNullLock<T> where T: ?Sized + Send {}
+unsafe impl<T> Send for SpinLock<T> where T: ?Sized + Send {}
+unsafe impl<T> Sync for SpinLock<T> where T: ?Sized + Send {}
+
+impl<T> SpinLock<T> {
+    /// Create an instance.
+    #[track_caller]
+    pub const fn new(data: T) -> Self {
+        Self {
+            next_ticket: AtomicU16::new(0),
+            now_serving: AtomicU16::new(0),
+            class: lockdep::class(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Lock on behalf of the given call site.
+    fn lock_from<R>(&self, site: &'static Location<'static>, f: impl FnOnce(&mut T) -> R) -> R {
+        lockdep::acquire(self.class, site, false);
+
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+
+        if self.now_serving.load(Ordering::Acquire) != ticket {
+            arch_synchronization::wait_until_equal(&self.now_serving, ticket);
+        }
+
+        let data = unsafe { &mut *self.data.get() };
+        let ret = f(data);
+
+        // Only the lock holder writes to now_serving, so a plain store suffices. It also wakes up
+        // the cores that wait for their ticket.
+        self.now_serving
+            .store(ticket.wrapping_add(1), Ordering::Release);
+
+        lockdep::release(self.class);
+
+        ret
+    }
+}
+
+impl<T> IRQSafeSpinLock<T> {
+    /// Create an instance.
+    #[track_caller]
+    pub const fn new(data: T) -> Self {
+        Self {
+            inner: SpinLock::new(data),
+        }
+    }
+}
+
+unsafe impl<T> Send for RwLock<T> where T: ?Sized + Send {}
+unsafe impl<T> Sync for RwLock<T> where T: ?Sized + Send + Sync {}
+
+impl<T> RwLock<T> {
+    const WRITER: u32 = u32::MAX;
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const READ_DEPTH_INIT: AtomicU8 = AtomicU8::new(0);
+
+    /// Create an instance.
+    #[track_caller]
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicU32::new(0),
+            writers_waiting: AtomicU32::new(0),
+            read_depth: [Self::READ_DEPTH_INIT; bsp::cpu::NUM_CORES],
+            class: lockdep::class(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// The read depth of the executing core.
+    fn local_read_depth(&self) -> &AtomicU8 {
+        &self.read_depth[cpu::smp::core_id::<usize>()]
+    }
+
+    fn acquire_read(&self) {
+        // A waiting writer can not get its turn before this core's outer read is done.
+        let nested = self.local_read_depth().load(Ordering::Relaxed) != 0;
+
+        loop {
+            let state = self.state.load(Ordering::Relaxed);
+
+            if state != Self::WRITER
+                && (nested || self.writers_waiting.load(Ordering::Relaxed) == 0)
+                && self
+                    .state
+                    .compare_exchange_weak(state, state + 1, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                self.local_read_depth().fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+
+            hint::spin_loop();
+        }
+    }
+
+    fn acquire_write(&self) {
+        self.writers_waiting.fetch_add(1, Ordering::Relaxed);
+
+        while self
+            .state
+            .compare_exchange_weak(0, Self::WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+
+        self.writers_waiting.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Write-lock on behalf of the given call site.
+    fn write_from<R>(&self, site: &'static Location<'static>, f: impl FnOnce(&mut T) -> R) -> R {
+        lockdep::acquire(self.class, site, false);
+        self.acquire_write();
+
+        let data = unsafe { &mut *self.data.get() };
+        let ret = f(data);
+
+        self.state.store(0, Ordering::Release);
+        lockdep::release(self.class);
+
+        ret
+    }
+
+    /// Read-lock on behalf of the given call site.
+    fn read_from<R>(&self, site: &'static Location<'static>, f: impl FnOnce(&T) -> R) -> R {
+        lockdep::acquire(self.class, site, true);
+        self.acquire_read();
+
+        let data = unsafe { &*self.data.get() };
+        let ret = f(data);
+
+        self.local_read_depth().fetch_sub(1, Ordering::Relaxed);
+        self.state.fetch_sub(1, Ordering::Release);
+        lockdep::release(self.class);
 
-impl<T> IRQSafeNullLock<T> {
+        ret
+    }
+}
+
+impl<T> IRQSafeRwLock<T> {
+    /// Create an instance.
+    #[track_caller]
+    pub const fn new(data: T) -> Self {
+        Self {
+            inner: RwLock::new(data),
+        }
+    }
+}
+
+unsafe impl<T> Send for SeqLock<T> where T: Copy + Send {}
+unsafe impl<T> Sync for SeqLock<T> where T: Copy + Send {}
+
+impl<T> SeqLock<T>
+where
+    T: Copy,
+{
     /// Create an instance.
+    #[track_caller]
     pub const fn new(data: T) -> Self {
         Self {
+            sequence: AtomicUsize::new(0),
+            writer: SpinLock::new(()),
             data: UnsafeCell::new(data),
         }
     }
+
+    /// Return a consistent copy of the data.
+    pub fn read(&self) -> T {
+        loop {
+            let start = self.sequence.load(Ordering::Acquire);
+
+            if start % 2 == 0 {
+                let data = unsafe { core::ptr::read_volatile(self.data.get()) };
+
+                // Order the data read before the second sequence read.
+                fence(Ordering::Acquire);
+
+                if self.sequence.load(Ordering::Relaxed) == start {
+                    return data;
+                }
+            }
+
+            hint::spin_loop();
+        }
+    }
+
+    /// Update the data.
+    #[track_caller]
+    pub fn write(&self, f: impl FnOnce(&mut T)) {
+        let site = Location::caller();
+
+        exception::asynchronous::exec_with_irq_masked(|| {
+            self.writer.lock_from(site, |_| {
+                self.sequence.fetch_add(1, Ordering::Relaxed);
+
+                // Order the odd sequence number before the data write.
+                fence(Ordering::Release);
+
+                f(unsafe { &mut *self.data.get() });
+
+                self.sequence.fetch_add(1, Ordering::Release);
+            })
+        })
+    }
+}
+
+impl Semaphore {
+    /// Create an instance with the given number of permits.
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: AtomicUsize::new(permits),
+        }
+    }
+
+    /// Take a permit if one is available. Returns whether a permit was taken.
+    pub fn try_acquire(&self) -> bool {
+        let mut permits = self.permits.load(Ordering::Relaxed);
+
+        while permits != 0 {
+            match self.permits.compare_exchange_weak(
+                permits,
+                permits - 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(x) => permits = x,
+            }
+        }
+
+        false
+    }
+
+    /// Take a permit, spinning until one is available.
+    pub fn acquire(&self) {
+        while !self.try_acquire() {
+            hint::spin_loop();
+        }
+    }
+
+    /// Take a permit, spinning for at most `timeout` until one is available.
+    pub fn acquire_timeout(&self, timeout: Duration) -> Result<(), &'static str> {
+        time::wait_for(timeout, || self.try_acquire()).map_err(|_| "Timeout waiting for semaphore")
+    }
+
+    /// Return a permit.
+    pub fn release(&self) {
+        self.permits.fetch_add(1, Ordering::Release);
+    }
 }
 
 unsafe impl<T> Send for InitStateLock<T> where T: ?Sized + Send {}
@@ -100,18 +446,59 @@ impl<T> InitStateLock<T> {
 //------------------------------------------------------------------------------
 // OS Interface Code
 //------------------------------------------------------------------------------
-use crate::{exception, state};
+use crate::{bsp, cpu, exception, state, time};
 
-impl<T> interface::Mutex for IRQSafeNullLock<T> {
+impl<T> interface::Mutex for SpinLock<T> {
     type Data = T;
 
+    #[track_caller]
     fn lock<R>(&self, f: impl FnOnce(&mut Self::Data) -> R) -> R {
-        // In a real lock, there would be code encapsulating this line that ensures that this
-        // mutable reference will ever only be given out once at a time.
-        let data = unsafe { &mut *self.data.get() };
+        self.lock_from(Location::caller(), f)
+    }
+}
+
+impl<T> interface::Mutex for IRQSafeSpinLock<T> {
+    type Data = T;
+
+    #[track_caller]
+    fn lock<R>(&self, f: impl FnOnce(&mut Self::Data) -> R) -> R {
+        let site = Location::caller();
 
-        // Execute the closure while IRQs are masked.
-        exception::asynchronous::exec_with_irq_masked(|| f(data))
+        // Mask IRQs before taking the lock, so that an IRQ handler can never contend for a lock
+        // held by the code it interrupted.
+        exception::asynchronous::exec_with_irq_masked(|| self.inner.lock_from(site, f))
+    }
+}
+
+impl<T> interface::ReadWriteEx for RwLock<T> {
+    type Data = T;
+
+    #[track_caller]
+    fn write<R>(&self, f: impl FnOnce(&mut Self::Data) -> R) -> R {
+        self.write_from(Location::caller(), f)
+    }
+
+    #[track_caller]
+    fn read<R>(&self, f: impl FnOnce(&Self::Data) -> R) -> R {
+        self.read_from(Location::caller(), f)
+    }
+}
+
+impl<T> interface::ReadWriteEx for IRQSafeRwLock<T> {
+    type Data = T;
+
+    #[track_caller]
+    fn write<R>(&self, f: impl FnOnce(&mut Self::Data) -> R) -> R {
+        let site = Location::caller();
+
+        exception::asynchronous::exec_with_irq_masked(|| self.inner.write_from(site, f))
+    }
+
+    #[track_caller]
+    fn read<R>(&self, f: impl FnOnce(&Self::Data) -> R) -> R {
+        let site = Location::caller();
+
+        exception::asynchronous::exec_with_irq_masked(|| self.inner.read_from(site, f))
     }
 }
 
@@ -124,7 +511,7 @@ impl<T> interface::ReadWriteEx for InitStateLock<T> {
             "InitStateLock::write called after kernel init phase"
         );
         assert!(
-            !exception::asynchronous::is_local_irq_masked(),
+            exception::asynchronous::is_local_irq_masked(),
             "InitStateLock::write called with IRQs unmasked"
         );
 
@@ -156,4 +543,82 @@ mod tests {
 
         assert_eq!(size_of::<InitStateLock<u64>>(), size_of::<u64>());
     }
+
+    /// Tickets must be drawn and served in order.
+    #[kernel_test]
+    fn spin_lock_serves_tickets_in_order() {
+        use interface::Mutex;
+
+        let lock = SpinLock::new(0_u64);
+
+        for i in 0..3 {
+            lock.lock(|data| *data += 1);
+            assert_eq!(lock.now_serving.load(Ordering::Relaxed), i + 1);
+        }
+
+        assert_eq!(lock.next_ticket.load(Ordering::Relaxed), 3);
+        assert_eq!(lock.lock(|data| *data), 3);
+    }
+
+    /// Readers must be counted, and the lock must be free again after each access.
+    #[kernel_test]
+    fn rw_lock_counts_readers() {
+        use interface::ReadWriteEx;
+
+        let lock = RwLock::new(0_u64);
+
+        lock.read(|_| {
+            assert_eq!(lock.state.load(Ordering::Relaxed), 1);
+
+            lock.read(|_| assert_eq!(lock.state.load(Ordering::Relaxed), 2));
+        });
+        assert_eq!(lock.state.load(Ordering::Relaxed), 0);
+
+        lock.write(|data| {
+            assert_eq!(lock.state.load(Ordering::Relaxed), RwLock::<u64>::WRITER);
+            *data = 1;
+        });
+        assert_eq!(lock.state.load(Ordering::Relaxed), 0);
+        assert_eq!(lock.read(|data| *data), 1);
+    }
+
+    /// A core that holds the read lock must get it again while a writer waits.
+    #[kernel_test]
+    fn rw_lock_nested_read_passes_waiting_writer() {
+        use interface::ReadWriteEx;
+
+        let lock = RwLock::new(0_u64);
+
+        lock.read(|_| {
+            // Pretend that another core waits for the write lock.
+            lock.writers_waiting.fetch_add(1, Ordering::Relaxed);
+            lock.read(|_| assert_eq!(lock.state.load(Ordering::Relaxed), 2));
+            lock.writers_waiting.fetch_sub(1, Ordering::Relaxed);
+        });
+        assert_eq!(lock.local_read_depth().load(Ordering::Relaxed), 0);
+    }
+
+    /// Each write must advance the sequence number by two, leaving it even.
+    #[kernel_test]
+    fn seq_lock_sanity() {
+        let lock = SeqLock::new((1_u64, 2_u64));
+
+        lock.write(|data| *data = (data.1, data.0));
+        assert_eq!(lock.sequence.load(Ordering::Relaxed), 2);
+        assert_eq!(lock.read(), (2, 1));
+    }
+
+    /// A semaphore must hand out no more than its permits.
+    #[kernel_test]
+    fn semaphore_bounds_permits() {
+        let sem = Semaphore::new(2);
+
+        assert!(sem.try_acquire());
+        sem.acquire();
+        assert!(!sem.try_acquire());
+        assert!(sem.acquire_timeout(Duration::from_millis(1)).is_err());
+
+        sem.release();
+        assert!(sem.acquire_timeout(Duration::from_millis(1)).is_ok());
+    }
 }
@@ -0,0 +1,499 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Lock dependency validation.
+//!
+//! Only compiled with the `lockdep` feature. Every acquisition of a [SpinLock](super::SpinLock) or
+//! [RwLock](super::RwLock), including their IRQ-safe variants, is recorded as a dependency on all
+//! locks that the executing core already holds. If an acquisition would close a cycle in the
+//! recorded dependencies, for example because one code path takes the console lock inside the IRQ
+//! manager lock and another path does the opposite, the kernel panics with the locks held on the
+//! executing core and the acquisition sites that established the opposite order.
+//!
+//! The cycle is reported the first time both orders are observed, even if the two paths never ran
+//! concurrently and therefore did not actually deadlock yet.
+//!
+//! A lock class is identified by the source location that created the lock, see [class()]. All
+//! instances of a driver share the class of the lock in the driver's constructor, for example, and
+//! a lock on the stack keeps its class when the memory is later reused for another lock. At most
+//! `MAX_CLASSES` classes are tracked. When a new class does not fit anymore, validation is disabled
+//! with a warning that names the site of the first untracked lock.
+//!
+//! Acquiring a lock class that the core already holds is reported as well, with one exception: a
+//! read lock may be taken again while the core holds it for reading. Such a nested read never
+//! waits, see [RwLock](super::RwLock), so it adds no dependencies. Other read acquisitions are
+//! treated like writes, because a waiting writer holds back new readers.
+
+use crate::{bsp, cpu, exception};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    panic::Location,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The maximum number of tracked lock classes.
+const MAX_CLASSES: usize = 64;
+
+/// The maximum number of locks that a single core holds at the same time.
+const MAX_HELD: usize = 16;
+
+type Site = &'static Location<'static>;
+
+/// A lock held by a core, and where it was acquired.
+#[derive(Copy, Clone)]
+struct HeldLock {
+    /// The index of the class in the graph.
+    index: usize,
+    class: Class,
+    site: Site,
+
+    /// Whether it is held for reading.
+    read: bool,
+}
+
+/// The locks held by a core, in acquisition order.
+#[derive(Copy, Clone)]
+struct HeldLocks {
+    depth: usize,
+    locks: [Option<HeldLock>; MAX_HELD],
+}
+
+/// The recorded dependency of one lock class on another.
+#[derive(Copy, Clone)]
+struct Dependency {
+    /// Where the lock that was held already had been acquired.
+    held_site: Site,
+
+    /// Where the dependent lock was acquired while the other one was held.
+    site: Site,
+}
+
+/// The global dependency graph.
+struct Graph {
+    num_classes: usize,
+    classes: [Option<Class>; MAX_CLASSES],
+
+    /// `after[a]` has bit `b` set if lock class `b` was acquired while class `a` was held.
+    after: [u64; MAX_CLASSES],
+
+    /// The first observation of each dependency, indexed like `after`.
+    dependencies: [[Option<Dependency>; MAX_CLASSES]; MAX_CLASSES],
+}
+
+/// Lockdep's global state.
+///
+/// It must not be protected by one of the kernel's locks, since those are the ones being tracked.
+/// Instead, the graph is guarded by `graph_taken`, and the held locks of a core are only ever
+/// accessed by the core itself. Both are only accessed with IRQs masked.
+struct LockDep {
+    disabled: AtomicBool,
+    graph_taken: AtomicBool,
+    graph: UnsafeCell<Graph>,
+    held: [UnsafeCell<HeldLocks>; bsp::cpu::NUM_CORES],
+}
+
+/// The report printed when a cycle is detected.
+struct CycleReport {
+    held: HeldLocks,
+    class: Class,
+    site: Site,
+    path: [Option<Dependency>; MAX_CLASSES],
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A lock class, identified by the location that created the lock.
+pub type Class = &'static Location<'static>;
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static LOCKDEP: LockDep = LockDep::new();
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl HeldLocks {
+    const fn new() -> Self {
+        Self {
+            depth: 0,
+            locks: [None; MAX_HELD],
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &HeldLock> {
+        self.locks[..self.depth].iter().flatten()
+    }
+}
+
+impl Graph {
+    const fn new() -> Self {
+        Self {
+            num_classes: 0,
+            classes: [None; MAX_CLASSES],
+            after: [0; MAX_CLASSES],
+            dependencies: [[None; MAX_CLASSES]; MAX_CLASSES],
+        }
+    }
+
+    /// The index of a lock class. Registers the class if it is new.
+    ///
+    /// Returns `None` if the class is new, but all [MAX_CLASSES] slots are taken.
+    fn index_of(&mut self, class: Class) -> Option<usize> {
+        if let Some(index) = self.classes[..self.num_classes]
+            .iter()
+            .position(|&x| x == Some(class))
+        {
+            return Some(index);
+        }
+
+        if self.num_classes == MAX_CLASSES {
+            return None;
+        }
+
+        self.classes[self.num_classes] = Some(class);
+        self.num_classes += 1;
+
+        Some(self.num_classes - 1)
+    }
+
+    /// Find a path of recorded dependencies from class `from` to class `to`.
+    ///
+    /// The path is returned as the sequence of dependencies that lead from `from` to `to`.
+    fn path(&self, from: usize, to: usize) -> Option<[Option<Dependency>; MAX_CLASSES]> {
+        // Breadth-first search, remembering through which class each class was reached.
+        let mut reached_via = [usize::MAX; MAX_CLASSES];
+        let mut visited: u64 = 1 << from;
+        let mut frontier: u64 = 1 << from;
+
+        while frontier != 0 && (visited & (1 << to)) == 0 {
+            let mut next = 0;
+
+            for class in (0..self.num_classes).filter(|&c| frontier & (1 << c) != 0) {
+                let new = self.after[class] & !visited & !next;
+
+                for reached in (0..self.num_classes).filter(|&c| new & (1 << c) != 0) {
+                    reached_via[reached] = class;
+                }
+                next |= new;
+            }
+
+            visited |= next;
+            frontier = next;
+        }
+
+        if (visited & (1 << to)) == 0 {
+            return None;
+        }
+
+        // Walk back from `to`, then reverse into forward order.
+        let mut path = [None; MAX_CLASSES];
+        let mut len = 0;
+        let mut class = to;
+        while class != from {
+            let prev = reached_via[class];
+
+            path[len] = self.dependencies[prev][class];
+            len += 1;
+            class = prev;
+        }
+        path[..len].reverse();
+
+        Some(path)
+    }
+}
+
+impl LockDep {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const HELD_INIT: UnsafeCell<HeldLocks> = UnsafeCell::new(HeldLocks::new());
+
+    const fn new() -> Self {
+        Self {
+            disabled: AtomicBool::new(false),
+            graph_taken: AtomicBool::new(false),
+            graph: UnsafeCell::new(Graph::new()),
+            held: [Self::HELD_INIT; bsp::cpu::NUM_CORES],
+        }
+    }
+
+    /// Run `f` with exclusive access to the dependency graph.
+    ///
+    /// Must be called with IRQs masked.
+    fn with_graph<R>(&self, f: impl FnOnce(&mut Graph) -> R) -> R {
+        while self
+            .graph_taken
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+
+        let ret = f(unsafe { &mut *self.graph.get() });
+
+        self.graph_taken.store(false, Ordering::Release);
+
+        ret
+    }
+
+    /// The held locks of the executing core.
+    ///
+    /// Must be called with IRQs masked.
+    #[allow(clippy::mut_from_ref)]
+    fn local_held(&self) -> &mut HeldLocks {
+        unsafe { &mut *self.held[cpu::smp::core_id::<usize>()].get() }
+    }
+
+    /// Stop tracking and panic with the given message.
+    fn report(&self, args: fmt::Arguments) -> ! {
+        // Tracking must stop before panicking, else the locks taken on the panic path would be
+        // checked against the broken state again.
+        self.disabled.store(true, Ordering::Relaxed);
+
+        panic!("{}", args)
+    }
+}
+
+unsafe impl Sync for LockDep {}
+
+impl fmt::Display for CycleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "lockdep: Possible deadlock detected")?;
+        writeln!(
+            f,
+            "      Acquiring lock created at {}, at {}",
+            self.class, self.site
+        )?;
+        writeln!(
+            f,
+            "      Locks held by core {}:",
+            cpu::smp::core_id::<usize>()
+        )?;
+        for held in self.held.iter() {
+            writeln!(
+                f,
+                "          Lock created at {}, acquired at {}",
+                held.class, held.site
+            )?;
+        }
+
+        writeln!(f, "      Previously recorded opposite order:")?;
+        for dependency in self.path.iter().flatten() {
+            writeln!(
+                f,
+                "          Held since {}, then acquired at {}",
+                dependency.held_site, dependency.site
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// The class of a lock created by the caller.
+#[track_caller]
+pub const fn class() -> Class {
+    Location::caller()
+}
+
+/// Record the acquisition of a lock of the given class by the executing core. `read` tells whether
+/// it is a read lock.
+///
+/// Must be called before the lock is actually taken, so that a cycle is reported instead of
+/// deadlocking.
+pub fn acquire(class: Class, site: Site, read: bool) {
+    if LOCKDEP.disabled.load(Ordering::Relaxed) {
+        return;
+    }
+
+    exception::asynchronous::exec_with_irq_masked(|| {
+        let held = LOCKDEP.local_held();
+
+        let index = match LOCKDEP.with_graph(|graph| graph.index_of(class)) {
+            Some(x) => x,
+            None => {
+                LOCKDEP.disabled.store(true, Ordering::Relaxed);
+                crate::warn!(
+                    "lockdep: More than {} lock classes, validation disabled at lock created at {}",
+                    MAX_CLASSES,
+                    class
+                );
+                return;
+            }
+        };
+
+        let prev = held.iter().find(|h| h.index == index).copied();
+        if let Some(prev) = prev {
+            if !(read && prev.read) {
+                LOCKDEP.report(format_args!(
+                    "lockdep: Recursive acquisition at {} of lock created at {}, held since {}",
+                    site, class, prev.site
+                ));
+            }
+        }
+
+        if held.depth == MAX_HELD {
+            LOCKDEP.report(format_args!(
+                "lockdep: More than {} locks held at {}",
+                MAX_HELD, site
+            ));
+        }
+
+        let lock = HeldLock {
+            index,
+            class,
+            site,
+            read,
+        };
+
+        // A nested read does not wait, so its dependencies are the ones of the outer read.
+        if prev.is_some() {
+            held.locks[held.depth] = Some(lock);
+            held.depth += 1;
+            return;
+        }
+
+        let cycle = LOCKDEP.with_graph(|graph| {
+            for h in held.iter() {
+                if let Some(path) = graph.path(index, h.index) {
+                    return Some(path);
+                }
+
+                if graph.after[h.index] & (1 << index) == 0 {
+                    graph.after[h.index] |= 1 << index;
+                    graph.dependencies[h.index][index] = Some(Dependency {
+                        held_site: h.site,
+                        site,
+                    });
+                }
+            }
+
+            None
+        });
+
+        if let Some(path) = cycle {
+            let report = CycleReport {
+                held: *held,
+                class,
+                site,
+                path,
+            };
+            LOCKDEP.report(format_args!("{}", report));
+        }
+
+        held.locks[held.depth] = Some(lock);
+        held.depth += 1;
+    });
+}
+
+/// Record the release of a lock of the given class by the executing core.
+pub fn release(class: Class) {
+    if LOCKDEP.disabled.load(Ordering::Relaxed) {
+        return;
+    }
+
+    exception::asynchronous::exec_with_irq_masked(|| {
+        let held = LOCKDEP.local_held();
+
+        // Lock guards are closures, so locks are always released in reverse acquisition order.
+        match held.depth.checked_sub(1) {
+            Some(top) if held.locks[top].map(|h| h.class) == Some(class) => {
+                held.locks[top] = None;
+                held.depth = top;
+            }
+            _ => LOCKDEP.report(format_args!(
+                "lockdep: Release of lock created at {}, which is not the most recently acquired",
+                class
+            )),
+        }
+    });
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            interface::{Mutex, ReadWriteEx},
+            IRQSafeRwLock, RwLock, SpinLock,
+        },
+        *,
+    };
+    use test_macros::kernel_test;
+
+    fn new_lock() -> SpinLock<()> {
+        SpinLock::new(())
+    }
+
+    /// Locks created at the same site share a class, others do not.
+    #[kernel_test]
+    fn lockdep_classes_follow_construction_site() {
+        let a = new_lock();
+        let b = new_lock();
+        let c = SpinLock::new(());
+
+        assert!(a.class == b.class);
+        assert!(a.class != c.class);
+    }
+
+    fn lock_first_then_second() {
+        let first = SpinLock::new(());
+        let second = SpinLock::new(());
+
+        first.lock(|_| second.lock(|_| ()));
+    }
+
+    fn lock_second_then_first() {
+        let first = SpinLock::new(());
+        let second = SpinLock::new(());
+
+        second.lock(|_| first.lock(|_| ()));
+    }
+
+    /// Locks that reuse the stack memory of other locks must not inherit their dependencies.
+    #[kernel_test]
+    fn lockdep_ignores_reused_addresses() {
+        lock_first_then_second();
+        lock_second_then_first();
+    }
+
+    /// A new class is rejected once all slots are taken, while the tracked ones are still found.
+    #[kernel_test]
+    fn lockdep_graph_full() {
+        let tracked = class();
+        let untracked = class();
+
+        // Occupy every slot.
+        let mut graph = Graph::new();
+        graph.classes = [Some(tracked); MAX_CLASSES];
+        graph.num_classes = MAX_CLASSES;
+
+        assert_eq!(graph.index_of(tracked), Some(0));
+        assert_eq!(graph.index_of(untracked), None);
+    }
+
+    /// Nested reads must pass validation.
+    #[kernel_test]
+    fn lockdep_allows_nested_reads() {
+        let lock = RwLock::new(0_u64);
+        let irq_safe_lock = IRQSafeRwLock::new(0_u64);
+
+        lock.read(|_| lock.read(|_| irq_safe_lock.read(|_| irq_safe_lock.read(|_| ()))));
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Read-copy-update.
+//!
+//! Readers of RCU-protected data never take a lock and never wait. They only mark the duration of
+//! their access, called a read-side critical section, by incrementing a counter of the executing
+//! core. Writers publish a new version of the data and then wait for a grace period, which ends
+//! once every core that was inside a read-side critical section at the time of publishing has left
+//! it. After the grace period, no reader can still see the old version.
+//!
+//! A core is in a quiescent state whenever it is outside of a read-side critical section. Since the
+//! kernel never blocks or migrates execution inside of one, each core tracks this with just a
+//! nesting counter and a generation counter that advances whenever the outermost section ends.
+//!
+//! The kernel does not have a heap, so [Rcu] holds exactly two versions of its data. An update
+//! copies the current version into the spare one, modifies the copy, publishes it and waits for the
+//! grace period, after which the old version becomes the new spare.
+//!
+//! # Resources
+//!
+//!   - <https://www.kernel.org/doc/html/latest/RCU/whatisRCU.html>
+
+use super::{interface::Mutex, SpinLock};
+use crate::{bsp, cpu};
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The read-side state of a core.
+struct CoreState {
+    /// The nesting depth of read-side critical sections.
+    nesting: AtomicUsize,
+
+    /// Advanced whenever the outermost read-side critical section ends.
+    generation: AtomicUsize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Data protected by read-copy-update.
+pub struct Rcu<T>
+where
+    T: Copy,
+{
+    /// Index of the version that readers see.
+    current: AtomicUsize,
+
+    /// Serializes updates.
+    writer: SpinLock<()>,
+
+    versions: [UnsafeCell<T>; 2],
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[allow(clippy::declare_interior_mutable_const)]
+const CORE_STATE_INIT: CoreState = CoreState {
+    nesting: AtomicUsize::new(0),
+    generation: AtomicUsize::new(0),
+};
+
+/// Indexed by core ID, so that read-side critical sections are possible before per-core data is
+/// set up.
+static CORE_STATES: [CoreState; bsp::cpu::NUM_CORES] = [CORE_STATE_INIT; bsp::cpu::NUM_CORES];
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn local_state() -> &'static CoreState {
+    &CORE_STATES[cpu::smp::core_id::<usize>()]
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Execute the provided closure inside a read-side critical section.
+///
+/// Sections may nest, also across IRQ context. The closure must not wait for a grace period.
+pub fn read_lock<R>(f: impl FnOnce() -> R) -> R {
+    let state = local_state();
+
+    // Pairs with the sequentially consistent load in synchronize(). Either the writer sees this
+    // core inside the section, or this core sees the writer's newly published version.
+    state.nesting.fetch_add(1, Ordering::SeqCst);
+
+    let ret = f();
+
+    if state.nesting.fetch_sub(1, Ordering::Release) == 1 {
+        state.generation.fetch_add(1, Ordering::Release);
+    }
+
+    ret
+}
+
+/// Wait for a grace period.
+///
+/// Returns after every core that was in a read-side critical section when this function was called
+/// has left that section.
+///
+/// # Panics
+///
+/// - If called from inside a read-side critical section, which would never end.
+pub fn synchronize() {
+    let self_id: usize = cpu::smp::core_id();
+
+    assert_eq!(
+        CORE_STATES[self_id].nesting.load(Ordering::Relaxed),
+        0,
+        "RCU synchronize() called inside a read-side critical section"
+    );
+
+    let others = CORE_STATES
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != self_id)
+        .map(|(_, state)| state);
+
+    for state in others {
+        let generation = state.generation.load(Ordering::Acquire);
+
+        if state.nesting.load(Ordering::SeqCst) == 0 {
+            continue;
+        }
+
+        // The core is inside a section. Wait until it either leaves it or is seen outside of any
+        // section.
+        while state.generation.load(Ordering::Acquire) == generation
+            && state.nesting.load(Ordering::Acquire) != 0
+        {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+unsafe impl<T> Send for Rcu<T> where T: Copy + Send {}
+unsafe impl<T> Sync for Rcu<T> where T: Copy + Send + Sync {}
+
+impl<T> Rcu<T>
+where
+    T: Copy,
+{
+    /// Create an instance.
+    #[track_caller]
+    pub const fn new(data: T) -> Self {
+        Self {
+            current: AtomicUsize::new(0),
+            writer: SpinLock::new(()),
+            versions: [UnsafeCell::new(data), UnsafeCell::new(data)],
+        }
+    }
+
+    /// Grants the closure temporary immutable access to the current version of the data.
+    ///
+    /// Never blocks, and is therefore safe to use from IRQ context.
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        read_lock(|| {
+            let current = self.current.load(Ordering::SeqCst);
+
+            f(unsafe { &*self.versions[current].get() })
+        })
+    }
+
+    /// Modify a copy of the data and publish it.
+    ///
+    /// Returns after the previous version is no longer visible to any reader. Must therefore not
+    /// be called from inside a read-side critical section.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.writer.lock(|_| {
+            let current = self.current.load(Ordering::Relaxed);
+            let spare = 1 - current;
+
+            // The spare version is not visible to readers since the grace period that ended the
+            // previous update.
+            let data = unsafe { &mut *self.versions[spare].get() };
+            *data = unsafe { *self.versions[current].get() };
+            let ret = f(data);
+
+            self.current.store(spare, Ordering::SeqCst);
+            synchronize();
+
+            ret
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Updates must be visible to subsequent readers, and leave no read-side section open.
+    #[kernel_test]
+    fn rcu_update_sanity() {
+        let rcu = Rcu::new(1_u64);
+
+        assert_eq!(rcu.update(|data| core::mem::replace(data, 2)), 1);
+        assert_eq!(rcu.read(|data| *data), 2);
+
+        rcu.update(|data| *data += 1);
+        assert_eq!(rcu.read(|data| *data), 3);
+
+        assert_eq!(local_state().nesting.load(Ordering::Relaxed), 0);
+    }
+}
@@ -8,10 +8,28 @@
 #[path = "_arch/aarch64/time.rs"]
 mod arch_time;
 
+pub mod alarm;
+pub mod boot;
+pub mod periodic;
+pub mod profile;
+pub mod wall_clock;
+
 //--------------------------------------------------------------------------------------------------
 // Architectural Public Reexports
 //--------------------------------------------------------------------------------------------------
-pub use arch_time::time_manager;
+pub use arch_time::{
+    init, physical_to_counter_ticks, ticks_to_duration, time_manager, uses_virtual_counter,
+};
+
+/// The profiler's scope macro, reachable as `time::profile_scope!`.
+pub use crate::profile_scope;
+
+use crate::register_introspection_node;
+use core::{
+    hint,
+    ops::{Add, Sub},
+    time::Duration,
+};
 
 //--------------------------------------------------------------------------------------------------
 // Public Definitions
@@ -35,3 +53,155 @@ pub mod interface {
         fn spin_for(&self, duration: Duration);
     }
 }
+
+/// A point in time, measured by the monotonically increasing system counter.
+///
+/// Keeps the raw counter value, so no precision is lost until an instant is converted into a
+/// [Duration].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant {
+    ticks: u64,
+}
+
+/// The error returned when a wait did not finish in time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Timeout;
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+register_introspection_node!("time/uptime_us", |f| {
+    use interface::TimeManager;
+
+    write!(f, "{}", time_manager().uptime().as_micros())
+});
+register_introspection_node!("time/counter", |f| {
+    f.write_str(if uses_virtual_counter() {
+        "virtual"
+    } else {
+        "physical"
+    })
+});
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Instant {
+    /// The current point in time.
+    #[inline(always)]
+    pub fn now() -> Self {
+        Self {
+            ticks: arch_time::counter_ticks(),
+        }
+    }
+
+    /// The point in time at which the system counter had the value `ticks`.
+    ///
+    /// Meant for hardware timestamps.
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Self { ticks }
+    }
+
+    /// The number of system counter ticks since power-on.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// The time that passed from `earlier` to `self`. Zero if `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        arch_time::ticks_to_duration(self.ticks.saturating_sub(earlier.ticks))
+    }
+
+    /// The number of system counter ticks from `earlier` to `self`. Zero if `earlier` is later
+    /// than `self`.
+    pub fn ticks_since(&self, earlier: Instant) -> u64 {
+        self.ticks.saturating_sub(earlier.ticks)
+    }
+
+    /// The time that passed since `self`.
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// The point in time `duration` after `self`, if it can be represented.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        let ticks = self
+            .ticks
+            .checked_add(arch_time::duration_to_ticks(duration)?)?;
+
+        Some(Self { ticks })
+    }
+
+    /// The point in time `duration` before `self`, if it can be represented.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        let ticks = self
+            .ticks
+            .checked_sub(arch_time::duration_to_ticks(duration)?)?;
+
+        Some(Self { ticks })
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("Overflow when adding duration to instant")
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("Overflow when subtracting duration from instant")
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+/// Spin until `deadline` has passed.
+pub fn spin_until(deadline: Instant) {
+    while Instant::now() < deadline {
+        hint::spin_loop();
+    }
+}
+
+/// Spin until `condition` becomes true, or until `deadline` has passed.
+///
+/// The condition is checked one last time after the deadline, so that a condition that became true
+/// while the executing core was interrupted is not reported as a timeout.
+pub fn wait_until(deadline: Instant, mut condition: impl FnMut() -> bool) -> Result<(), Timeout> {
+    loop {
+        let timed_out = Instant::now() >= deadline;
+
+        if condition() {
+            return Ok(());
+        }
+
+        if timed_out {
+            return Err(Timeout);
+        }
+
+        hint::spin_loop();
+    }
+}
+
+/// Spin until `condition` becomes true, giving up after `timeout`.
+pub fn wait_for(timeout: Duration, condition: impl FnMut() -> bool) -> Result<(), Timeout> {
+    let deadline = Instant::now()
+        .checked_add(timeout)
+        .expect("Timeout too long");
+
+    wait_until(deadline, condition)
+}
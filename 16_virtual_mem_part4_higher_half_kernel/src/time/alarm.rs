@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! One-shot alarms.
+//!
+//! Each core has one alarm, which calls a function in IRQ context once it expires. An alarm only
+//! fires on the core that set it, and setting it again replaces the pending one.
+
+use super::arch_time;
+use crate::{
+    bsp, cpu,
+    error::KernelError,
+    exception,
+    synchronization::{interface::Mutex, IRQSafeSpinLock},
+    time,
+};
+use core::time::Duration;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The alarm IRQ handler.
+struct AlarmIRQHandler;
+
+/// One alarm callback per core.
+type Callbacks = [Option<fn()>; bsp::cpu::NUM_CORES];
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static ALARM_IRQ_HANDLER: AlarmIRQHandler = AlarmIRQHandler;
+
+/// The function to call when a core's alarm expires.
+static CALLBACKS: IRQSafeSpinLock<Callbacks> = IRQSafeSpinLock::new([None; bsp::cpu::NUM_CORES]);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn handle_alarm() {
+    arch_time::cancel_alarm();
+
+    let core_id: usize = cpu::smp::core_id();
+    let callback = CALLBACKS.lock(|callbacks| callbacks[core_id].take());
+
+    // Called outside of the lock, so that the callback can set a new alarm.
+    if let Some(callback) = callback {
+        callback();
+    }
+}
+
+impl exception::asynchronous::interface::IRQHandler for AlarmIRQHandler {
+    fn handle(&self) -> Result<(), &'static str> {
+        handle_alarm();
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Call `callback` on the executing core once `duration` has passed.
+pub fn set(duration: Duration, callback: fn()) -> Result<(), &'static str> {
+    let core_id: usize = cpu::smp::core_id();
+
+    CALLBACKS.lock(|callbacks| {
+        callbacks[core_id] = Some(callback);
+
+        arch_time::set_alarm(duration)
+    })
+}
+
+/// Call `callback` on the executing core once `deadline` has been reached.
+pub fn set_at(deadline: time::Instant, callback: fn()) {
+    let core_id: usize = cpu::smp::core_id();
+
+    CALLBACKS.lock(|callbacks| {
+        callbacks[core_id] = Some(callback);
+
+        arch_time::set_alarm_at(deadline.ticks());
+    })
+}
+
+/// Cancel the executing core's pending alarm, if any.
+pub fn cancel() {
+    let core_id: usize = cpu::smp::core_id();
+
+    CALLBACKS.lock(|callbacks| {
+        arch_time::cancel_alarm();
+        callbacks[core_id] = None;
+    });
+}
+
+/// Register and enable the alarm IRQ handler with the BSP's interrupt controller.
+pub fn register_and_enable_irq_handler() -> Result<(), KernelError> {
+    use exception::asynchronous::IRQDescriptor;
+
+    let descriptor = IRQDescriptor {
+        name: "Alarm",
+        handler: &ALARM_IRQ_HANDLER,
+    };
+
+    bsp::exception::asynchronous::register_and_enable_alarm_irq_handler(descriptor)
+}
+
+/// Handle an alarm IRQ.
+///
+/// Called by interrupt controller drivers that dispatch the alarm IRQ directly instead of through
+/// their handler table.
+pub fn handle_alarm_irq(_ic: &exception::asynchronous::IRQContext) {
+    handle_alarm();
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use test_macros::kernel_test;
+
+    static FIRED: AtomicBool = AtomicBool::new(false);
+
+    /// An alarm must call its callback once it expires.
+    ///
+    /// This replaces the test runner's timeout for the rest of the test.
+    #[kernel_test]
+    fn alarm_fires() {
+        set(Duration::from_millis(10), || {
+            FIRED.store(true, Ordering::Relaxed)
+        })
+        .unwrap();
+
+        let fired = crate::time::wait_for(Duration::from_secs(1), || FIRED.load(Ordering::Relaxed));
+        assert!(fired.is_ok());
+    }
+}
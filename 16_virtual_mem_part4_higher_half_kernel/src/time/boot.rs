@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Boot timing.
+//!
+//! The boot core records the system counter at defined milestones of the boot flow. [print()]
+//! shows when each milestone was reached and how long the stage before it took, so that changes to
+//! the boot flow can be measured.
+
+use crate::{cpu, info, time};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The milestones of the boot flow, in the order they are reached.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Milestone {
+    /// `kernel_init()` runs with the MMU enabled.
+    MmuOn,
+
+    /// The console is usable.
+    ConsoleUp,
+
+    /// All drivers are initialized and their IRQ handlers registered.
+    DriversUp,
+
+    /// The secondary cores are online.
+    SecondaryCoresUp,
+
+    /// The kernel enters its main loop.
+    Ready,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+const NUM_MILESTONES: usize = 5;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const TIMESTAMP_INIT: AtomicU64 = AtomicU64::new(0);
+
+static TIMESTAMPS: [AtomicU64; NUM_MILESTONES] = [TIMESTAMP_INIT; NUM_MILESTONES];
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl Milestone {
+    const ALL: [Milestone; NUM_MILESTONES] = [
+        Milestone::MmuOn,
+        Milestone::ConsoleUp,
+        Milestone::DriversUp,
+        Milestone::SecondaryCoresUp,
+        Milestone::Ready,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Milestone::MmuOn => "MMU on",
+            Milestone::ConsoleUp => "Console up",
+            Milestone::DriversUp => "Drivers up",
+            Milestone::SecondaryCoresUp => "Secondary cores up",
+            Milestone::Ready => "Ready",
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Record that `milestone` was reached now.
+pub fn record(milestone: Milestone) {
+    TIMESTAMPS[milestone as usize].store(time::Instant::now().ticks(), Ordering::Relaxed);
+}
+
+/// Print the time since power-on for every milestone that was reached, and the duration of the
+/// stage that led to it.
+///
+/// Kernel entry is the first milestone. It is recorded by the boot code before the stack is set up.
+pub fn print() {
+    let us = |ticks: u64| time::ticks_to_duration(ticks).as_micros();
+
+    info!("        Uptime       Stage  Milestone");
+
+    let mut previous = time::physical_to_counter_ticks(cpu::boot_entry_ticks());
+    info!("      {:>10}us              Kernel entry", us(previous));
+
+    for milestone in Milestone::ALL {
+        let ticks = TIMESTAMPS[milestone as usize].load(Ordering::Relaxed);
+        if ticks == 0 {
+            continue;
+        }
+
+        info!(
+            "      {:>10}us {:>8}us  {}",
+            us(ticks),
+            us(ticks.saturating_sub(previous)),
+            milestone.name()
+        );
+        previous = ticks;
+    }
+}
@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Periodic tasks.
+//!
+//! Monitors and self-checks that run every so often [spawn()] a task instead of using an alarm
+//! directly, because each core only has a single alarm. All tasks run on the boot core, whose alarm
+//! is always set for the task that is due next.
+//!
+//! Tasks run in IRQ context, and should be short.
+
+use crate::{
+    bsp, cpu,
+    synchronization::{interface::Mutex, IRQSafeSpinLock},
+    time,
+};
+use core::time::Duration;
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+const MAX_TASKS: usize = 8;
+
+#[derive(Copy, Clone)]
+struct Task {
+    name: &'static str,
+    interval: Duration,
+    next: time::Instant,
+    run: fn(),
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+static TASKS: IRQSafeSpinLock<[Option<Task>; MAX_TASKS]> = IRQSafeSpinLock::new([None; MAX_TASKS]);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// Set the alarm for the task that is due next, or cancel it if there is none.
+fn arm(tasks: &[Option<Task>; MAX_TASKS]) {
+    match tasks.iter().flatten().map(|task| task.next).min() {
+        None => time::alarm::cancel(),
+        Some(next) => time::alarm::set_at(next, run_due),
+    }
+}
+
+fn run_due() {
+    let now = time::Instant::now();
+
+    let due = TASKS.lock(|tasks| {
+        let mut due = [None; MAX_TASKS];
+
+        for (task, run) in tasks.iter_mut().flatten().zip(due.iter_mut()) {
+            if task.next <= now {
+                *run = Some(task.run);
+
+                // Scheduled from now rather than from the missed deadline, so that a late alarm
+                // does not cause a burst of runs.
+                task.next = now + task.interval;
+            }
+        }
+
+        due
+    });
+
+    // Run outside of the lock, so that tasks can spawn and cancel tasks.
+    for run in due.iter().flatten() {
+        run();
+    }
+
+    TASKS.lock(|tasks| arm(tasks));
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Call `run` every `interval`, starting one `interval` from now.
+///
+/// Must be called on the boot core.
+pub fn spawn(name: &'static str, interval: Duration, run: fn()) -> Result<(), &'static str> {
+    if cpu::smp::core_id::<u64>() != bsp::cpu::BOOT_CORE_ID {
+        return Err("Periodic tasks must be spawned on the boot core");
+    }
+    if interval.is_zero() {
+        return Err("Periodic task interval must not be zero");
+    }
+
+    let next = time::Instant::now()
+        .checked_add(interval)
+        .ok_or("Periodic task interval too long")?;
+
+    TASKS.lock(|tasks| {
+        if tasks.iter().flatten().any(|task| task.name == name) {
+            return Err("Periodic task already spawned");
+        }
+
+        let slot = tasks
+            .iter_mut()
+            .find(|x| x.is_none())
+            .ok_or("Too many periodic tasks")?;
+        *slot = Some(Task {
+            name,
+            interval,
+            next,
+            run,
+        });
+
+        arm(tasks);
+
+        Ok(())
+    })
+}
+
+/// Stop the task `name`, if it exists.
+///
+/// Must be called on the boot core.
+pub fn cancel(name: &'static str) {
+    TASKS.lock(|tasks| {
+        for slot in tasks.iter_mut() {
+            if matches!(slot, Some(task) if task.name == name) {
+                *slot = None;
+            }
+        }
+
+        arm(tasks);
+    });
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use test_macros::kernel_test;
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    /// A task must run repeatedly until it is cancelled.
+    ///
+    /// This replaces the test runner's timeout for the rest of the test.
+    #[kernel_test]
+    fn periodic_task_runs_repeatedly() {
+        spawn("periodic::test", Duration::from_millis(5), || {
+            RUNS.fetch_add(1, Ordering::Relaxed);
+        })
+        .unwrap();
+        assert!(spawn("periodic::test", Duration::from_millis(5), || ()).is_err());
+
+        let ran = time::wait_for(Duration::from_secs(1), || RUNS.load(Ordering::Relaxed) >= 3);
+        cancel("periodic::test");
+
+        assert!(ran.is_ok());
+    }
+}
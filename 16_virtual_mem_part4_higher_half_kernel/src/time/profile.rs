@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Section timing.
+//!
+//! [time::profile_scope!](crate::time::profile_scope) measures the time from its invocation to the
+//! end of the enclosing scope, in ticks of the system counter. Every invocation site has its own
+//! statistics slot, which is registered in a global table on first use. Recording only uses
+//! atomics, so it is safe from any context, including IRQ handlers.
+//!
+//! ```
+//! {
+//!     time::profile_scope!("Driver init");
+//!
+//!     // Code to be measured.
+//! }
+//!
+//! time::profile::print();
+//! ```
+
+use crate::{info, time, warn};
+use core::{
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The maximum number of profiled sections.
+const MAX_SLOTS: usize = 32;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The statistics of a profiled section.
+///
+/// Only use through [profile_scope!](crate::profile_scope).
+#[doc(hidden)]
+pub struct Slot {
+    name: &'static str,
+    registered: AtomicBool,
+    count: AtomicU64,
+    total_ticks: AtomicU64,
+    max_ticks: AtomicU64,
+}
+
+/// Records the time until it is dropped.
+#[doc(hidden)]
+pub struct ScopeGuard {
+    slot: &'static Slot,
+    start: time::Instant,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[allow(clippy::declare_interior_mutable_const)]
+const SLOT_PTR_INIT: AtomicPtr<Slot> = AtomicPtr::new(ptr::null_mut());
+
+static SLOTS: [AtomicPtr<Slot>; MAX_SLOTS] = [SLOT_PTR_INIT; MAX_SLOTS];
+static NUM_SLOTS: AtomicUsize = AtomicUsize::new(0);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl Slot {
+    fn register(&'static self) {
+        if self.registered.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let index = NUM_SLOTS.fetch_add(1, Ordering::Relaxed);
+        if index >= MAX_SLOTS {
+            warn!("Profiling table full, not recording {}", self.name);
+            return;
+        }
+
+        SLOTS[index].store(self as *const Slot as *mut Slot, Ordering::Release);
+    }
+
+    fn record(&'static self, ticks: u64) {
+        self.register();
+
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_ticks.fetch_add(ticks, Ordering::Relaxed);
+        self.max_ticks.fetch_max(ticks, Ordering::Relaxed);
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Measure the time from here to the end of the enclosing scope.
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope_guard = {
+            static SLOT: $crate::time::profile::Slot = $crate::time::profile::Slot::new($name);
+
+            $crate::time::profile::ScopeGuard::new(&SLOT)
+        };
+    };
+}
+
+impl Slot {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            registered: AtomicBool::new(false),
+            count: AtomicU64::new(0),
+            total_ticks: AtomicU64::new(0),
+            max_ticks: AtomicU64::new(0),
+        }
+    }
+}
+
+impl ScopeGuard {
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn new(slot: &'static Slot) -> Self {
+        Self {
+            slot,
+            start: time::Instant::now(),
+        }
+    }
+}
+
+impl Drop for ScopeGuard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.slot
+            .record(time::Instant::now().ticks_since(self.start));
+    }
+}
+
+/// Print the statistics of all profiled sections.
+pub fn print() {
+    info!("      Count      Total        Avg        Max  Section");
+
+    let num_slots = NUM_SLOTS.load(Ordering::Relaxed).min(MAX_SLOTS);
+    let slots = SLOTS[..num_slots]
+        .iter()
+        .map(|x| x.load(Ordering::Acquire))
+        .filter(|x| !x.is_null())
+        .map(|x| unsafe { &*x });
+
+    for slot in slots {
+        let count = slot.count.load(Ordering::Relaxed);
+        let total = slot.total_ticks.load(Ordering::Relaxed);
+        let max = slot.max_ticks.load(Ordering::Relaxed);
+
+        let us = |ticks: u64| time::ticks_to_duration(ticks).as_micros();
+
+        info!(
+            "      {:>5} {:>8}us {:>8}us {:>8}us  {}",
+            count,
+            us(total),
+            us(total / count.max(1)),
+            us(max),
+            slot.name
+        );
+    }
+}
+
+/// Reset the statistics of all profiled sections.
+pub fn reset() {
+    let num_slots = NUM_SLOTS.load(Ordering::Relaxed).min(MAX_SLOTS);
+
+    for slot in SLOTS[..num_slots].iter().map(|x| x.load(Ordering::Acquire)) {
+        if let Some(slot) = unsafe { slot.as_ref() } {
+            slot.count.store(0, Ordering::Relaxed);
+            slot.total_ticks.store(0, Ordering::Relaxed);
+            slot.max_ticks.store(0, Ordering::Relaxed);
+        }
+    }
+}
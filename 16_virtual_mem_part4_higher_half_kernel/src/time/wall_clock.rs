@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Wall-clock time.
+//!
+//! The system counter only measures the time since power-on. The wall clock adds the offset of the
+//! counter's zero point from the UNIX epoch, which must be supplied by a time source such as a
+//! battery-backed RTC or a network time server. Until then, the wall clock is unset.
+//!
+//! The boards have no RTC, and the kernel has no network time client. The only time source is
+//! therefore `epoch=<seconds>` on the kernel command line, which is the UNIX time at power-on. For
+//! example, a boot script can write `epoch=$(date +%s)` into `cmdline.txt`.
+//!
+//! The offset is read by every log message, but written only when the time source is synchronized,
+//! so it is kept in a [SeqLock].
+
+use crate::{synchronization::SeqLock, time, time::interface::TimeManager};
+use core::{fmt, time::Duration};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A UTC calendar date and time of day.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    /// The year, e.g. 2022.
+    pub year: u32,
+
+    /// The month, starting at 1.
+    pub month: u8,
+
+    /// The day of the month, starting at 1.
+    pub day: u8,
+
+    /// The hour of the day.
+    pub hour: u8,
+
+    /// The minute of the hour.
+    pub minute: u8,
+
+    /// The second of the minute.
+    pub second: u8,
+
+    /// The fraction of the second.
+    pub nanosecond: u32,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+/// The time from the UNIX epoch to power-on.
+static EPOCH_OFFSET: SeqLock<Option<Duration>> = SeqLock::new(None);
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl DateTime {
+    const SECS_PER_DAY: u64 = 86_400;
+
+    /// Convert the time since the UNIX epoch into a date and time of day.
+    ///
+    /// Uses the days-to-civil algorithm of Howard Hinnant, which works on the proleptic Gregorian
+    /// calendar with years starting in March, so that leap days are the last day of a year.
+    ///
+    /// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    pub fn from_unix(since_epoch: Duration) -> Self {
+        let secs = since_epoch.as_secs();
+        let secs_of_day = secs % Self::SECS_PER_DAY;
+
+        // Shift the epoch to 0000-03-01.
+        let days = secs / Self::SECS_PER_DAY + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+
+        let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+        let month = if month_from_march < 10 {
+            month_from_march + 3
+        } else {
+            month_from_march - 9
+        };
+        let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+        Self {
+            year: year as u32,
+            month: month as u8,
+            day: day as u8,
+            hour: (secs_of_day / 3600) as u8,
+            minute: (secs_of_day % 3600 / 60) as u8,
+            second: (secs_of_day % 60) as u8,
+            nanosecond: since_epoch.subsec_nanos(),
+        }
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Set the wall clock.
+///
+/// `since_epoch` is the current time since the UNIX epoch, as reported by a time source.
+pub fn set(since_epoch: Duration) -> Result<(), &'static str> {
+    let offset = since_epoch
+        .checked_sub(time::time_manager().uptime())
+        .ok_or("Wall-clock time is earlier than power-on")?;
+
+    EPOCH_OFFSET.write(|x| *x = Some(offset));
+
+    Ok(())
+}
+
+/// The current time since the UNIX epoch, or `None` if the wall clock has not been set.
+pub fn now() -> Option<Duration> {
+    EPOCH_OFFSET
+        .read()
+        .map(|offset| offset + time::time_manager().uptime())
+}
+
+/// The current date and time, or `None` if the wall clock has not been set.
+pub fn date_time() -> Option<DateTime> {
+    now().map(DateTime::from_unix)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_macros::kernel_test;
+
+    /// Check the conversion around the epoch, leap days and the end of a century.
+    #[kernel_test]
+    fn date_time_from_unix_is_correct() {
+        let cases = [
+            (0, (1970, 1, 1, 0, 0, 0)),
+            (951_782_400, (2000, 2, 29, 0, 0, 0)),
+            (1_646_096_461, (2022, 3, 1, 1, 1, 1)),
+            (4_102_444_799, (2099, 12, 31, 23, 59, 59)),
+        ];
+
+        for (secs, (year, month, day, hour, minute, second)) in cases {
+            let dt = DateTime::from_unix(Duration::from_secs(secs));
+
+            assert_eq!(
+                (dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second),
+                (year, month, day, hour, minute, second)
+            );
+        }
+    }
+}
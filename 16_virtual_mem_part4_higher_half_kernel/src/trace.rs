@@ -0,0 +1,345 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Event tracing.
+//!
+//! Trace points write fixed-size binary records into a ring buffer of the executing core. Each
+//! record holds a system counter timestamp, the [Event] and one event-specific argument. Once a
+//! ring is full, the oldest records are overwritten. Recording is cheap enough for IRQ context, and
+//! does nothing while tracing is disabled.
+//!
+//! [dump()] prints the records of all cores as text lines, framed by `TRACE-BEGIN` and
+//! `TRACE-END`:
+//!
+//! ```text
+//! TRACE <core> <nanoseconds since power-on> <event> <argument>
+//! ```
+//!
+//! `common/trace2chrome.rb` converts a captured log into the Chrome trace event format, which can
+//! be viewed in `chrome://tracing` or <https://ui.perfetto.dev>.
+//!
+//! The `trace` flag on the kernel command line enables tracing at boot. Pressing `CTRL + E` on the
+//! console then dumps the records, see [dump_and_restart()].
+
+use crate::{bsp, cpu, exception, time};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The number of records per core.
+const RING_SIZE: usize = 1024;
+
+/// A binary trace record.
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct Record {
+    ticks: u64,
+    arg: u64,
+    event: Event,
+}
+
+/// The trace buffer of a core.
+struct Ring {
+    /// The number of records written since the ring was last cleared.
+    written: AtomicUsize,
+
+    records: UnsafeCell<[Record; RING_SIZE]>,
+}
+
+/// Writes to the console.
+struct Console;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The trace points.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Event {
+    /// The core took an IRQ. The argument is the interrupted address.
+    IrqEntry,
+
+    /// The core returns from an IRQ.
+    IrqExit,
+
+    /// An instruction or data abort. The argument is the faulting address.
+    PageFault,
+
+    /// A user-defined marker. The argument is chosen by the caller.
+    Mark,
+
+    /// A driver read an MMIO register. Only recorded with the `mmio_trace` feature. The argument
+    /// holds the value and the register's address.
+    MmioRead,
+
+    /// A driver wrote an MMIO register. Only recorded with the `mmio_trace` feature. The argument
+    /// holds the value and the register's address.
+    MmioWrite,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Global instances
+//--------------------------------------------------------------------------------------------------
+
+#[allow(clippy::declare_interior_mutable_const)]
+const RING_INIT: Ring = Ring {
+    written: AtomicUsize::new(0),
+    records: UnsafeCell::new(
+        [Record {
+            ticks: 0,
+            arg: 0,
+            event: Event::Mark,
+        }; RING_SIZE],
+    ),
+};
+
+/// Indexed by core ID, so that tracing is possible before per-core data is set up.
+static RINGS: [Ring; bsp::cpu::NUM_CORES] = [RING_INIT; bsp::cpu::NUM_CORES];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// A ring is only written by its own core, with IRQs masked, and only read while tracing is
+/// disabled.
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    fn push(&self, record: Record) {
+        let written = self.written.load(Ordering::Relaxed);
+
+        unsafe { (*self.records.get())[written % RING_SIZE] = record };
+        self.written.store(written + 1, Ordering::Release);
+    }
+
+    /// The records in the order they were written. Only valid while tracing is disabled.
+    fn records(&self) -> impl Iterator<Item = &Record> {
+        let written = self.written.load(Ordering::Acquire);
+        let records = unsafe { &*self.records.get() };
+
+        (written.saturating_sub(RING_SIZE)..written).map(move |i| &records[i % RING_SIZE])
+    }
+}
+
+/// Discard the records of all cores.
+fn clear_rings() {
+    for ring in RINGS.iter() {
+        ring.written.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Write the records of all cores, framed by `TRACE-BEGIN` and `TRACE-END`.
+fn write_records(w: &mut dyn fmt::Write) -> fmt::Result {
+    writeln!(w, "TRACE-BEGIN")?;
+
+    for (core, ring) in RINGS.iter().enumerate() {
+        let lost = ring
+            .written
+            .load(Ordering::Acquire)
+            .saturating_sub(RING_SIZE);
+        if lost != 0 {
+            writeln!(w, "TRACE-LOST {} {}", core, lost)?;
+        }
+
+        for record in ring.records() {
+            writeln!(
+                w,
+                "TRACE {} {} {} {:#x}",
+                core,
+                time::ticks_to_duration(record.ticks).as_nanos(),
+                record.event,
+                record.arg
+            )?;
+        }
+    }
+
+    writeln!(w, "TRACE-END")
+}
+
+impl fmt::Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        crate::print!("{}", s);
+        Ok(())
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Event::IrqEntry => "irq_entry",
+            Event::IrqExit => "irq_exit",
+            Event::PageFault => "page_fault",
+            Event::Mark => "mark",
+            Event::MmioRead => "mmio_read",
+            Event::MmioWrite => "mmio_write",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Record an event on the executing core.
+#[inline(always)]
+pub fn record(event: Event, arg: u64) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let record = Record {
+        ticks: time::Instant::now().ticks(),
+        arg,
+        event,
+    };
+
+    exception::asynchronous::exec_with_irq_masked(|| {
+        RINGS[cpu::smp::core_id::<usize>()].push(record)
+    });
+}
+
+/// Start recording events.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Stop recording events.
+///
+/// Records that are being written concurrently on other cores might still complete.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+/// Discard all records.
+pub fn clear() -> Result<(), &'static str> {
+    if ENABLED.load(Ordering::Acquire) {
+        return Err("Tracing must be disabled to clear the trace buffers");
+    }
+
+    clear_rings();
+
+    Ok(())
+}
+
+/// Print the records of all cores.
+pub fn dump() -> Result<(), &'static str> {
+    dump_to(&mut Console)
+}
+
+/// Print the records of all cores, even while tracing is enabled.
+///
+/// Tracing is paused for the dump. If it was enabled, it continues afterwards with empty trace
+/// buffers, so that the next dump only shows new records.
+pub fn dump_and_restart() {
+    let was_enabled = ENABLED.swap(false, Ordering::AcqRel);
+
+    // Printing to the console does not fail.
+    let _ = write_records(&mut Console);
+
+    if was_enabled {
+        clear_rings();
+        enable();
+    }
+}
+
+/// Write the records of all cores to `w`, in the format of [dump()].
+pub fn dump_to(w: &mut dyn fmt::Write) -> Result<(), &'static str> {
+    if ENABLED.load(Ordering::Acquire) {
+        return Err("Tracing must be disabled to dump the trace buffers");
+    }
+
+    write_records(w).map_err(|_| "Writing the trace records failed")
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+    use test_macros::kernel_test;
+
+    /// Counts the dumped records of one event.
+    struct EventCounter {
+        event: &'static str,
+        line: [u8; 128],
+        len: usize,
+        count: usize,
+    }
+
+    impl EventCounter {
+        fn new(event: &'static str) -> Self {
+            Self {
+                event,
+                line: [0; 128],
+                len: 0,
+                count: 0,
+            }
+        }
+    }
+
+    impl fmt::Write for EventCounter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for &byte in s.as_bytes() {
+                if byte != b'\n' {
+                    if self.len < self.line.len() {
+                        self.line[self.len] = byte;
+                        self.len += 1;
+                    }
+                    continue;
+                }
+
+                let line = core::str::from_utf8(&self.line[..self.len]).map_err(|_| fmt::Error)?;
+                if line.starts_with("TRACE ") && line.split(' ').nth(3) == Some(self.event) {
+                    self.count += 1;
+                }
+                self.len = 0;
+            }
+
+            Ok(())
+        }
+    }
+
+    static FIRED: AtomicBool = AtomicBool::new(false);
+
+    /// An IRQ taken while tracing is enabled must show up in the dump.
+    ///
+    /// This replaces the test runner's timeout for the rest of the test.
+    #[kernel_test]
+    fn trace_records_irqs() {
+        clear().unwrap();
+        enable();
+
+        time::alarm::set(Duration::from_millis(10), || {
+            FIRED.store(true, Ordering::Relaxed)
+        })
+        .unwrap();
+        let fired = time::wait_for(Duration::from_secs(1), || FIRED.load(Ordering::Relaxed));
+
+        disable();
+        assert!(fired.is_ok());
+
+        let mut entries = EventCounter::new("irq_entry");
+        let mut exits = EventCounter::new("irq_exit");
+        dump_to(&mut entries).unwrap();
+        dump_to(&mut exits).unwrap();
+        clear().unwrap();
+
+        assert!(entries.count >= 1);
+        assert_eq!(entries.count, exits.count);
+    }
+}
@@ -48,3 +48,37 @@ fn spin_accuracy_check_1_second() {
 
     assert_eq!((t2 - t1).as_secs(), 1)
 }
+
+/// Instants must be monotonic and convert durations without losing whole ticks.
+#[kernel_test]
+fn instant_arithmetic_is_consistent() {
+    let t1 = time::Instant::now();
+    time::time_manager().spin_for(Duration::from_millis(10));
+    let t2 = time::Instant::now();
+
+    assert!(t2 > t1);
+    assert!(t1.elapsed() >= Duration::from_millis(10));
+    assert_eq!(t1.duration_since(t2), Duration::ZERO);
+
+    // Long durations must not overflow the conversion.
+    let later = t1 + Duration::from_secs(3600);
+    assert_eq!((later - t1).as_secs(), 3600);
+    assert_eq!(later - Duration::from_secs(3600), t1);
+}
+
+/// Deadline-based waits must neither return early nor miss a condition.
+#[kernel_test]
+fn deadline_waits_are_accurate() {
+    let deadline = time::Instant::now() + Duration::from_millis(10);
+    time::spin_until(deadline);
+    assert!(time::Instant::now() >= deadline);
+
+    let start = time::Instant::now();
+    assert_eq!(
+        time::wait_for(Duration::from_millis(10), || false),
+        Err(time::Timeout)
+    );
+    assert!(start.elapsed() >= Duration::from_millis(10));
+
+    assert!(time::wait_for(Duration::from_secs(3600), || true).is_ok());
+}
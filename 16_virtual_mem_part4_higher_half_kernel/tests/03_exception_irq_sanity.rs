@@ -30,10 +30,10 @@ unsafe fn kernel_init() -> ! {
 #[kernel_test]
 fn local_irq_mask_works() {
     // Precondition: IRQs are unmasked.
-    assert!(exception::asynchronous::is_local_irq_masked());
+    assert!(!exception::asynchronous::is_local_irq_masked());
 
     unsafe { exception::asynchronous::local_irq_mask() };
-    assert!(!exception::asynchronous::is_local_irq_masked());
+    assert!(exception::asynchronous::is_local_irq_masked());
 
     // Restore earlier state.
     unsafe { exception::asynchronous::local_irq_unmask() };
@@ -44,24 +44,24 @@ fn local_irq_mask_works() {
 fn local_irq_unmask_works() {
     // Precondition: IRQs are masked.
     unsafe { exception::asynchronous::local_irq_mask() };
-    assert!(!exception::asynchronous::is_local_irq_masked());
+    assert!(exception::asynchronous::is_local_irq_masked());
 
     unsafe { exception::asynchronous::local_irq_unmask() };
-    assert!(exception::asynchronous::is_local_irq_masked());
+    assert!(!exception::asynchronous::is_local_irq_masked());
 }
 
 /// Check that IRQ mask save is saving "something".
 #[kernel_test]
 fn local_irq_mask_save_works() {
     // Precondition: IRQs are unmasked.
-    assert!(exception::asynchronous::is_local_irq_masked());
+    assert!(!exception::asynchronous::is_local_irq_masked());
 
     let first = unsafe { exception::asynchronous::local_irq_mask_save() };
-    assert!(!exception::asynchronous::is_local_irq_masked());
+    assert!(exception::asynchronous::is_local_irq_masked());
 
     let second = unsafe { exception::asynchronous::local_irq_mask_save() };
     assert_ne!(first, second);
 
     unsafe { exception::asynchronous::local_irq_restore(first) };
-    assert!(exception::asynchronous::is_local_irq_masked());
+    assert!(!exception::asynchronous::is_local_irq_masked());
 }
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Reading an unmapped page must result in a translation fault.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+/// Overwrites libkernel's `panic_wait::_panic_exit()` so that it returns a "success" code.
+///
+/// The console output decides whether the test passed, see the accompanying `.rb` file.
+mod panic_exit_success;
+
+use libkernel::{bsp, cpu, exception, memory, println};
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    // This line will be printed as the test header.
+    println!("Testing translation fault decoding");
+
+    // Core 0's exception stack is followed by the unmapped guard page of core 1's.
+    let guard_page = bsp::memory::virt_exception_stack_end_exclusive_addr(0).as_usize();
+
+    println!("Reading from guard page at {:#018x}", guard_page);
+    core::ptr::read_volatile(guard_page as *const u64);
+
+    // If execution reaches here, the memory access above did not cause a fault.
+    cpu::qemu_exit_failure()
+}
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Writing to a read-only page must result in a permission fault.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+/// Overwrites libkernel's `panic_wait::_panic_exit()` so that it returns a "success" code.
+///
+/// The console output decides whether the test passed, see the accompanying `.rb` file.
+mod panic_exit_success;
+
+use libkernel::{bsp, cpu, exception, memory, println};
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    // This line will be printed as the test header.
+    println!("Testing permission fault decoding");
+
+    // The code segment is mapped read-only.
+    let code = bsp::memory::virt_code_range().start.as_usize();
+
+    println!("Writing to code segment at {:#018x}", code);
+    core::ptr::write_volatile(code as *mut u64, 0);
+
+    // If execution reaches here, the memory access above did not cause a fault.
+    cpu::qemu_exit_failure()
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Error paths that are forced by failpoints.
+
+#![feature(custom_test_frameworks)]
+#![no_main]
+#![no_std]
+#![reexport_test_harness_main = "test_main"]
+#![test_runner(libkernel::test_runner)]
+
+use libkernel::{
+    bsp, cpu, exception,
+    failpoint::{self, Trigger},
+    memory,
+    memory::{mmu::MMIODescriptor, Address},
+};
+use test_macros::kernel_test;
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    test_main();
+
+    cpu::qemu_exit_success()
+}
+
+/// A page that no driver maps, so that each mapping attempt needs a new virtual region.
+fn unused_mmio_page(n: usize) -> MMIODescriptor {
+    MMIODescriptor::new(Address::new(0x1000_0000 + n * 0x1_0000), 0x1000)
+}
+
+/// A failing MMIO VA allocation must be reported to the caller of `kernel_map_mmio()`.
+#[kernel_test]
+fn mmio_va_alloc_failure_is_reported() {
+    failpoint::arm("mmu::mmio_va_alloc", Trigger::Once { skip: 0 }).unwrap();
+
+    let result = unsafe { memory::mmu::kernel_map_mmio("Failpoint test", &unused_mmio_page(0)) };
+    assert_eq!(
+        result.map_err(|e| e.message()),
+        Err("Failpoint: mmu::mmio_va_alloc")
+    );
+
+    // The failpoint disarmed itself and the mapping works now.
+    let result = unsafe { memory::mmu::kernel_map_mmio("Failpoint test", &unused_mmio_page(0)) };
+    assert!(result.is_ok());
+}
+
+/// Retries after failing map operations must work.
+#[kernel_test]
+fn map_failure_is_reported() {
+    failpoint::arm("mmu::map", Trigger::Always).unwrap();
+
+    for _ in 0..3 {
+        let result =
+            unsafe { memory::mmu::kernel_map_mmio("Failpoint test", &unused_mmio_page(1)) };
+        assert_eq!(result.map_err(|e| e.message()), Err("Failpoint: mmu::map"));
+    }
+
+    failpoint::disarm_all();
+
+    let result = unsafe { memory::mmu::kernel_map_mmio("Failpoint test", &unused_mmio_page(1)) };
+    assert!(result.is_ok());
+}
@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! IRQ latency measurement.
+//!
+//! Sets the alarm to a known deadline many times in a row. For every alarm IRQ, two latencies are
+//! recorded:
+//!
+//! - Entry: From the deadline to the timestamp that the exception vector stub took.
+//! - Dispatch: From the vector stub to the alarm callback, which covers the generic IRQ handling
+//!   and the interrupt controller driver.
+//!
+//! The test passes as long as every IRQ arrives. The numbers are printed for comparison, because
+//! they depend heavily on the host when running in QEMU.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+use core::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+use libkernel::{bsp, cpu, exception, info, memory, println, time};
+
+/// The number of IRQs to measure.
+const NUM_IRQS: u64 = 4000;
+
+/// The distance of each deadline from the point where it is set.
+const PERIOD: Duration = Duration::from_micros(200);
+
+/// How long to wait for a single IRQ before giving up.
+const IRQ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A statistic over latencies in nanoseconds.
+struct Stats {
+    min: AtomicU64,
+    max: AtomicU64,
+    sum: AtomicU64,
+}
+
+static DEADLINE: AtomicU64 = AtomicU64::new(0);
+static FIRED: AtomicBool = AtomicBool::new(false);
+
+static ENTRY: Stats = Stats::new();
+static DISPATCH: Stats = Stats::new();
+
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            min: AtomicU64::new(u64::MAX),
+            max: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, from: time::Instant, to: time::Instant) {
+        let ns = to.duration_since(from).as_nanos() as u64;
+
+        self.min.fetch_min(ns, Ordering::Relaxed);
+        self.max.fetch_max(ns, Ordering::Relaxed);
+        self.sum.fetch_add(ns, Ordering::Relaxed);
+    }
+
+    fn print(&self, name: &str) {
+        info!(
+            "{:<8} latency over {} IRQs: min {} ns, avg {} ns, max {} ns",
+            name,
+            NUM_IRQS,
+            self.min.load(Ordering::Relaxed),
+            self.sum.load(Ordering::Relaxed) / NUM_IRQS,
+            self.max.load(Ordering::Relaxed)
+        );
+    }
+}
+
+/// The alarm callback.
+fn alarm_fired() {
+    let now = time::Instant::now();
+    let deadline = time::Instant::from_ticks(DEADLINE.load(Ordering::Relaxed));
+
+    if let Some(entry) = exception::irq_entry_time() {
+        ENTRY.record(deadline, entry);
+        DISPATCH.record(entry, now);
+    }
+
+    FIRED.store(true, Ordering::Release);
+}
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    bsp::exception::asynchronous::qemu_bring_up_irqs();
+    time::alarm::register_and_enable_irq_handler().unwrap_or_else(|_| cpu::qemu_exit_failure());
+    exception::asynchronous::local_irq_unmask();
+
+    // This line will be printed as the test header.
+    println!("Measuring IRQ latency");
+
+    for _ in 0..NUM_IRQS {
+        let deadline = time::Instant::now() + PERIOD;
+
+        FIRED.store(false, Ordering::Relaxed);
+        DEADLINE.store(deadline.ticks(), Ordering::Relaxed);
+        time::alarm::set_at(deadline, alarm_fired);
+
+        if time::wait_for(IRQ_TIMEOUT, || FIRED.load(Ordering::Acquire)).is_err() {
+            println!("Alarm IRQ did not arrive");
+            cpu::qemu_exit_failure()
+        }
+    }
+
+    ENTRY.print("Entry");
+    DISPATCH.print("Dispatch");
+
+    cpu::qemu_exit_success()
+}
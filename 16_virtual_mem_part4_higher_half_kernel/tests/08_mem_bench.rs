@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Bulk memory operation throughput.
+//!
+//! Compares the kernel's assembly routines against the generic `core::ptr` operations on the same
+//! buffers. The test passes as long as the results are correct. The numbers are printed for
+//! comparison, because they depend heavily on the host when running in QEMU.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+use core::ptr;
+use libkernel::{bsp, cpu, exception, info, memory, println, time};
+
+/// The size of each buffer.
+const BUF_SIZE: usize = 64 * 1024;
+
+/// The number of times each operation is repeated.
+const ITERATIONS: usize = 64;
+
+#[repr(align(64))]
+struct Buffer([u8; BUF_SIZE]);
+
+static mut SRC: Buffer = Buffer([0; BUF_SIZE]);
+static mut DST: Buffer = Buffer([0; BUF_SIZE]);
+
+/// Run `f` on the buffers repeatedly and print the throughput.
+fn measure(name: &str, f: impl Fn(*mut u8, *mut u8)) {
+    let (src, dst) = unsafe { (SRC.0.as_mut_ptr(), DST.0.as_mut_ptr()) };
+
+    let start = time::Instant::now();
+    for _ in 0..ITERATIONS {
+        f(src, dst);
+    }
+    let elapsed = time::Instant::now().duration_since(start);
+
+    let bytes = (BUF_SIZE * ITERATIONS) as u128;
+    let mib_per_s = (bytes * 1_000_000_000) / (elapsed.as_nanos().max(1) * 1024 * 1024);
+    info!("{:<25} {:>6} MiB/s", name, mib_per_s);
+}
+
+fn check(expected: impl Fn(usize) -> u8) {
+    let dst = unsafe { &DST.0 };
+
+    if !dst.iter().enumerate().all(|(i, &b)| b == expected(i)) {
+        println!("Buffer content mismatch");
+        cpu::qemu_exit_failure()
+    }
+}
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    // This line will be printed as the test header.
+    println!("Measuring bulk memory operations");
+
+    for (i, b) in SRC.0.iter_mut().enumerate() {
+        *b = (i * 7 + 1) as u8;
+    }
+
+    measure("core::ptr::copy", |s, d| {
+        ptr::copy_nonoverlapping(s, d, BUF_SIZE)
+    });
+    measure("memory::kmemcpy", |s, d| memory::kmemcpy(d, s, BUF_SIZE));
+    check(|i| (i * 7 + 1) as u8);
+
+    measure("core::ptr::write_bytes", |_, d| {
+        ptr::write_bytes(d, 0xa5, BUF_SIZE)
+    });
+    measure("memory::kmemset", |_, d| memory::kmemset(d, 0xa5, BUF_SIZE));
+    check(|_| 0xa5);
+
+    measure("core::ptr::write_bytes 0", |_, d| {
+        ptr::write_bytes(d, 0, BUF_SIZE)
+    });
+    measure("memory::kmemset 0", |_, d| memory::kmemset(d, 0, BUF_SIZE));
+    check(|_| 0);
+
+    cpu::qemu_exit_success()
+}
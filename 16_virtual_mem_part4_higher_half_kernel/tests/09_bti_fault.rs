@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Indirect branches into guarded code must land on a landing pad.
+//!
+//! Only meaningful in builds with `BTI=y` on CPUs with branch target identification. Otherwise,
+//! the test reports that it was skipped and passes.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+/// Overwrites libkernel's `panic_wait::_panic_exit()` so that it returns a "success" code.
+///
+/// In this test, reaching the panic is a success, because it is called from the synchronous
+/// exception handler, which is what this test wants to achieve.
+///
+/// It also means that this integration test can not use any other code that calls panic!() directly
+/// or indirectly.
+mod panic_exit_success;
+
+use core::arch::{asm, global_asm};
+use libkernel::{bsp, config, cpu, exception, memory, println};
+
+// A function without a landing pad. It gets its own section, because with LTO, this assembly is
+// merged with the kernel's, whose vector table expects to be at the start of `.text`.
+global_asm!(
+    ".pushsection .text.__no_landing_pad, \"ax\"",
+    ".global __no_landing_pad",
+    "__no_landing_pad:",
+    "    ret",
+    ".popsection"
+);
+
+extern "C" {
+    fn __no_landing_pad();
+}
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    // This line will be printed as the test header.
+    println!("Testing branch target identification");
+
+    if !config::BTI || !cpu::features::features().bti {
+        println!("Not supported by this build or CPU, skipping");
+        cpu::qemu_exit_success()
+    }
+
+    if memory::mmu::kernel_guard_code().is_err() {
+        println!("Guarding the code pages failed");
+        cpu::qemu_exit_failure()
+    }
+
+    println!("Branching indirectly to code without a landing pad...");
+    asm!(
+        "blr {}",
+        in(reg) __no_landing_pad as usize,
+        clobber_abi("C")
+    );
+
+    // If execution reaches here, the branch above did not cause a branch target exception.
+    cpu::qemu_exit_failure()
+}
@@ -0,0 +1,17 @@
+# frozen_string_literal: true
+
+# SPDX-License-Identifier: MIT OR Apache-2.0
+#
+# Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+require_relative '../../common/tests/console_io_test'
+require_relative 'fault_subtests'
+
+##--------------------------------------------------------------------------------------------------
+## Test registration
+##--------------------------------------------------------------------------------------------------
+def subtest_collection
+    fault_subtests(/Reading from physical code address (0x\h{16})/,
+                   '0x4 - Translation fault, level 0',
+                   'Read')
+end
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! The kernel's physical addresses must not be identity mapped.
+//!
+//! Reading the code segment through its physical address must result in a translation fault at
+//! level 0, because walks of the lower half are disabled.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+/// Overwrites libkernel's `panic_wait::_panic_exit()` so that it returns a "success" code.
+///
+/// The console output decides whether the test passed, see the accompanying `.rb` file.
+mod panic_exit_success;
+
+use libkernel::{bsp, cpu, exception, memory, println};
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    // This line will be printed as the test header.
+    println!("Testing that the kernel is not identity mapped");
+
+    // Where the firmware loaded the code, and thus where an identity mapping would be.
+    let code = match memory::mmu::try_kernel_virt_addr_to_phys_addr(
+        bsp::memory::virt_code_range().start,
+    ) {
+        Err(_) => cpu::qemu_exit_failure(),
+        Ok(x) => x.as_usize(),
+    };
+
+    println!("Reading from physical code address {:#018x}", code);
+    core::ptr::read_volatile(code as *const u64);
+
+    // If execution reaches here, the memory access above did not cause a fault.
+    cpu::qemu_exit_failure()
+}
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Exception path overhead.
+//!
+//! Measures in CPU cycles:
+//!
+//! - SVC round trip: A supervisor call that returns right away, see [exception::null_svc()].
+//! - IRQ entry: From unmasking a pending alarm IRQ to its callback. This covers the vector stub,
+//!   the generic IRQ handling and the interrupt controller driver.
+//!
+//! The kernel has no scheduler yet, so there is no context switch to measure.
+//!
+//! The test passes as long as every IRQ arrives. The numbers are printed for comparison of changes
+//! to the exception paths, because they depend heavily on the host when running in QEMU.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use libkernel::{bsp, cpu, exception, info, memory, pmu, println, time};
+
+/// The number of supervisor calls to measure.
+const NUM_SVCS: u64 = 100_000;
+
+/// The number of IRQs to measure.
+const NUM_IRQS: u64 = 1000;
+
+/// How long the timer gets to assert an expired alarm's IRQ.
+const IRQ_ASSERT_DELAY: Duration = Duration::from_micros(20);
+
+/// How long to wait for a single IRQ before giving up.
+const IRQ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The cycle counter in the alarm callback, or zero before it ran.
+static HANDLER_CYCLES: AtomicU64 = AtomicU64::new(0);
+
+/// The alarm callback.
+fn alarm_fired() {
+    HANDLER_CYCLES.store(pmu::cycle_counter(), Ordering::Release);
+}
+
+fn print_row(name: &str, total_cycles: u64, count: u64) {
+    info!(
+        "{:<16} {:>8} cycles (over {})",
+        name,
+        total_cycles / count,
+        count
+    );
+}
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+    pmu::init();
+
+    bsp::exception::asynchronous::qemu_bring_up_irqs();
+    time::alarm::register_and_enable_irq_handler().unwrap_or_else(|_| cpu::qemu_exit_failure());
+
+    // This line will be printed as the test header.
+    println!("Measuring exception path overhead");
+
+    let start = pmu::cycle_counter();
+    for _ in 0..NUM_SVCS {
+        exception::null_svc();
+    }
+    let svc_cycles = pmu::cycle_counter() - start;
+
+    let mut irq_cycles = 0;
+    for _ in 0..NUM_IRQS {
+        // Let the IRQ become pending while it is masked, so that unmasking takes it right away.
+        HANDLER_CYCLES.store(0, Ordering::Relaxed);
+        time::alarm::set_at(time::Instant::now(), alarm_fired);
+        time::spin_until(time::Instant::now() + IRQ_ASSERT_DELAY);
+
+        let start = pmu::cycle_counter();
+        exception::asynchronous::local_irq_unmask();
+
+        if time::wait_for(IRQ_TIMEOUT, || HANDLER_CYCLES.load(Ordering::Acquire) != 0).is_err() {
+            println!("Alarm IRQ did not arrive");
+            cpu::qemu_exit_failure()
+        }
+        exception::asynchronous::local_irq_mask();
+
+        irq_cycles += HANDLER_CYCLES.load(Ordering::Relaxed) - start;
+    }
+
+    print_row("SVC round trip", svc_cycles, NUM_SVCS);
+    print_row("IRQ entry", irq_cycles, NUM_IRQS);
+
+    cpu::qemu_exit_success()
+}
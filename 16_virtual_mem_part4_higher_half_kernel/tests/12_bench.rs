@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Memory benchmarks.
+//!
+//! Runs [bench::run()]. The test passes as long as the benchmarks complete. The numbers are
+//! printed for comparison, and are only reproducible with `ICOUNT=y`.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+use libkernel::{bench, bsp, cpu, exception, memory, pmu, println};
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+    pmu::init();
+
+    // This line will be printed as the test header.
+    println!("Running the memory benchmarks");
+
+    if let Err(x) = bench::run() {
+        println!("Benchmarks failed: {}", x);
+        cpu::qemu_exit_failure()
+    }
+
+    cpu::qemu_exit_success()
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! The sampling profiler must take samples of a busy loop.
+//!
+//! The console output decides whether the test passed, see the accompanying `.rb` file.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+use core::time::Duration;
+use libkernel::{bsp, cpu, exception, memory, pmu, println, time};
+
+/// The sampling period in CPU cycles.
+const PERIOD: u32 = 100_000;
+
+/// How long the busy loop runs.
+const BUSY_TIME: Duration = Duration::from_millis(200);
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+    pmu::init();
+
+    bsp::exception::asynchronous::qemu_bring_up_irqs();
+    pmu::register_and_enable_irq_handler().unwrap_or_else(|_| cpu::qemu_exit_failure());
+    exception::asynchronous::local_irq_unmask();
+
+    // This line will be printed as the test header.
+    println!("Testing the sampling profiler");
+
+    pmu::sampler::reset();
+    if let Err(x) = pmu::sampler::start(PERIOD) {
+        println!("Starting the profiler failed: {}", x);
+        cpu::qemu_exit_failure()
+    }
+
+    let start = time::Instant::now();
+    while start.elapsed() < BUSY_TIME {
+        core::hint::spin_loop();
+    }
+
+    pmu::sampler::stop();
+    pmu::sampler::print();
+
+    cpu::qemu_exit_success()
+}
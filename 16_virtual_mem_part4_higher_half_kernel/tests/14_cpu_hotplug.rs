@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! A secondary core must go offline and come back online.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+use libkernel::{bsp, cpu, exception, memory, println, state};
+
+/// The core that is taken offline.
+const CORE: usize = 1;
+
+/// Exit QEMU with a failure if `condition` does not hold.
+fn check(condition: bool, what: &str) {
+    if !condition {
+        println!("Check failed: {}", what);
+        cpu::qemu_exit_failure()
+    }
+}
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    bsp::exception::asynchronous::qemu_bring_up_irqs();
+    cpu::smp::init().unwrap_or_else(|_| cpu::qemu_exit_failure());
+    exception::asynchronous::local_irq_unmask();
+    state::state_manager().transition_to_single_core_main();
+
+    // This line will be printed as the test header.
+    println!("Testing CPU hotplug");
+
+    let num_cores = cpu::smp::start_secondary_cores();
+    check(num_cores == Ok(bsp::cpu::NUM_CORES), "All cores online");
+
+    check(cpu::offline(CORE).is_ok(), "Offline succeeds");
+    check(!cpu::smp::is_online(CORE), "Core is offline");
+    check(
+        cpu::smp::num_cores_online() == bsp::cpu::NUM_CORES - 1,
+        "One core less online",
+    );
+    check(cpu::offline(CORE).is_err(), "Offline again fails");
+
+    check(cpu::online(CORE).is_ok(), "Online succeeds");
+    check(cpu::smp::is_online(CORE), "Core is online");
+    check(
+        cpu::smp::num_cores_online() == bsp::cpu::NUM_CORES,
+        "All cores online again",
+    );
+    check(cpu::online(CORE).is_err(), "Online again fails");
+
+    check(
+        cpu::offline(bsp::cpu::BOOT_CORE_ID as usize).is_err(),
+        "Boot core can not go offline",
+    );
+
+    cpu::qemu_exit_success()
+}
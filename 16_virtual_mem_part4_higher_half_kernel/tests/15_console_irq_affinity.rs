@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! The console's UART IRQ must be routable to a secondary core.
+
+#![feature(format_args_nl)]
+#![no_main]
+#![no_std]
+
+use libkernel::{bsp, cpu, exception, memory, println, state};
+
+#[no_mangle]
+unsafe fn kernel_init() -> ! {
+    use bsp::exception::asynchronous::{console_irq_affinity, set_console_irq_affinity};
+
+    exception::handling_init();
+    memory::mmu::post_enable_init();
+    bsp::console::qemu_bring_up_console();
+
+    bsp::exception::asynchronous::qemu_bring_up_irqs();
+    cpu::smp::init().unwrap_or_else(|_| cpu::qemu_exit_failure());
+    exception::asynchronous::local_irq_unmask();
+    state::state_manager().transition_to_single_core_main();
+
+    // This line will be printed as the test header.
+    println!("Testing console IRQ affinity");
+
+    if cpu::smp::start_secondary_cores() != Ok(bsp::cpu::NUM_CORES) {
+        cpu::qemu_exit_failure()
+    }
+
+    // The BCM interrupt controller routes all peripheral IRQs together.
+    #[cfg(feature = "bsp_rpi3")]
+    if set_console_irq_affinity(0b10).is_ok() || console_irq_affinity().is_ok() {
+        cpu::qemu_exit_failure()
+    }
+
+    // The GIC reads back the target that was written.
+    #[cfg(feature = "bsp_rpi4")]
+    for core_mask in [0b10, 0b01] {
+        if set_console_irq_affinity(core_mask).is_err() || console_irq_affinity() != Ok(core_mask) {
+            cpu::qemu_exit_failure()
+        }
+    }
+
+    cpu::qemu_exit_success()
+}
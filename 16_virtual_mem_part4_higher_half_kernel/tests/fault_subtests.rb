@@ -0,0 +1,65 @@
+# frozen_string_literal: true
+
+# SPDX-License-Identifier: MIT OR Apache-2.0
+#
+# Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+# Subtests shared by the fault tests. They check the decoded exception print of the fault that the
+# test binary causes.
+
+# Remember the address that the test binary announces.
+class FaultAddressTest < SubtestBase
+    attr_reader :address
+
+    def initialize(announcement)
+        super()
+        @announcement = announcement
+    end
+
+    def name
+        'Fault address announced'
+    end
+
+    def run(qemu_out, _qemu_in)
+        result = qemu_out.expect(@announcement, TIMEOUT_SECONDS)
+        raise ExpectTimeoutError, @announcement.source if result.nil?
+
+        @address = @announcement.match(result.first)[1]
+    end
+end
+
+# Expect a line of the exception print.
+class ExceptionPrintTest < SubtestBase
+    def initialize(name, expected)
+        super()
+        @name = name
+        @expected = expected
+    end
+
+    attr_reader :name
+
+    def run(qemu_out, _qemu_in)
+        expect_or_raise(qemu_out, expected)
+    end
+
+    private
+
+    def expected
+        @expected.respond_to?(:call) ? @expected.call : @expected
+    end
+end
+
+# The subtests for a fault that is announced by a print matching `announcement`, which captures the
+# address.
+def fault_subtests(announcement, fault_status, access)
+    address_test = FaultAddressTest.new(announcement)
+
+    [
+        address_test,
+        ExceptionPrintTest.new('Exception class',
+                               'Exception Class         (EC) : 0x25 - Data Abort, current EL'),
+        ExceptionPrintTest.new('Fault status code', "Fault Status Code (FSC): #{fault_status}"),
+        ExceptionPrintTest.new('Access type', "Write not Read    (WnR): #{access}"),
+        ExceptionPrintTest.new('Fault address', -> { "FAR_EL1: #{address_test.address}" })
+    ]
+end
@@ -0,0 +1,149 @@
+#!/usr/bin/env ruby
+# frozen_string_literal: true
+
+# SPDX-License-Identifier: MIT OR Apache-2.0
+#
+# Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+# Snapshot test for the translation table tool.
+#
+# Runs table generation and patching against a copy of a fixture kernel ELF, decodes every valid
+# descriptor in the patched file and compares the result with checked-in golden data.
+#
+# After an intended change of the generated tables, regenerate the golden data with:
+#
+#   UPDATE_GOLDEN=1 ruby translation_table_tool/test/tables_test.rb
+
+require 'rubygems'
+require 'bundler/setup'
+require 'colorize'
+require 'elftools'
+require 'fileutils'
+require 'minitest/autorun'
+require 'stringio'
+require 'tmpdir'
+
+FIXTURES_DIR = File.join(__dir__, 'fixtures')
+GOLDEN_PATH = File.join(FIXTURES_DIR, 'kernel_tables.golden')
+
+TARGET = :aarch64
+BSP_TYPE = :rpi3
+
+# The BSP reads the memory map relative to the tutorial's root directory.
+Dir.chdir(File.join(__dir__, '..', '..')) do
+    require_relative '../generic'
+    require_relative '../kernel_elf'
+    require_relative '../bsp'
+    require_relative '../arch'
+end
+
+KERNEL_ELF_PATH = File.join(Dir.mktmpdir, 'kernel.elf')
+FileUtils.cp(File.join(FIXTURES_DIR, 'kernel.elf'), KERNEL_ELF_PATH)
+
+KERNEL_ELF = KernelELF.new(KERNEL_ELF_PATH)
+BSP = RaspberryPi.new
+TRANSLATION_TABLES = Arch::ARMv8::TranslationTable.new
+
+# Decode patched translation tables into one line of text per valid descriptor.
+class TableDecoder
+    LVL3_ENTRIES = 8192
+
+    def initialize(kernel_elf_path)
+        num_lvl2_tables = BSP.kernel_virt_addr_space_size >> Granule512MiB::SHIFT
+        num_entries = (num_lvl2_tables * LVL3_ENTRIES) + num_lvl2_tables
+
+        data = File.binread(kernel_elf_path, num_entries * 8, BSP.kernel_tables_offset_in_file)
+        entries = data.unpack('Q<*')
+
+        @lvl3 = entries.first(num_lvl2_tables * LVL3_ENTRIES).each_slice(LVL3_ENTRIES).to_a
+        @lvl2 = entries.last(num_lvl2_tables)
+        @base_addr = File.binread(kernel_elf_path, 8,
+                                  BSP.phys_kernel_tables_base_addr_offset_in_file).unpack1('Q<')
+    end
+
+    def to_s
+        lines = ["base #{@base_addr.to_hex_underscore(with_leading_zeros: true)}"]
+
+        @lvl2.each_with_index do |desc, i|
+            lines << decode_table(i, desc) if valid?(desc)
+        end
+
+        @lvl3.each_with_index do |table, i|
+            table.each_with_index do |desc, j|
+                lines << decode_page(i, j, desc) if valid?(desc)
+            end
+        end
+
+        lines.map { |line| "#{line}\n" }.join
+    end
+
+    private
+
+    def field(desc, offset, num_bits)
+        (desc >> offset) & ((2**num_bits) - 1)
+    end
+
+    def valid?(desc)
+        field(desc, 0, 1) == 1
+    end
+
+    def output_addr(desc)
+        (field(desc, 16, 32) << Granule64KiB::SHIFT).to_hex_underscore(with_leading_zeros: true)
+    end
+
+    def raw(desc)
+        desc.to_hex_underscore(with_leading_zeros: true)
+    end
+
+    def decode_table(index, desc)
+        type = field(desc, 1, 1) == 1 ? 'table' : 'block'
+
+        "lvl2[#{index}]".ljust(14) + "#{raw(desc)}  #{type}  next #{output_addr(desc)}"
+    end
+
+    def decode_page(table, index, desc)
+        type = field(desc, 1, 1) == 1 ? 'page' : 'reserved'
+        ap = field(desc, 6, 2) == 0b10 ? 'RO' : 'RW'
+        attrs = ["AP=#{ap}",
+                 "SH=0b#{field(desc, 8, 2).to_s(2)}",
+                 "AttrIndx=#{field(desc, 2, 3)}",
+                 "AF=#{field(desc, 10, 1)}",
+                 "PXN=#{field(desc, 53, 1)}",
+                 "UXN=#{field(desc, 54, 1)}"]
+
+        "lvl3[#{table}][#{index}]".ljust(14) +
+            "#{raw(desc)}  #{type}  out #{output_addr(desc)}  #{attrs.join(' ')}"
+    end
+end
+
+# Compare the tool's output for the fixture with the golden data.
+class TablesTest < Minitest::Test
+    def self.generate
+        # Silence the tool's progress output.
+        stdout = $stdout
+        $stdout = StringIO.new
+
+        kernel_map_binary
+        kernel_patch_tables(KERNEL_ELF_PATH)
+        kernel_patch_base_addr(KERNEL_ELF_PATH)
+    ensure
+        $stdout = stdout
+    end
+
+    generate
+
+    def test_tables_match_golden
+        actual = TableDecoder.new(KERNEL_ELF_PATH).to_s
+
+        File.write(GOLDEN_PATH, actual) if ENV['UPDATE_GOLDEN']
+
+        assert_equal(File.read(GOLDEN_PATH), actual)
+    end
+
+    def test_tables_match_in_memory_binary
+        patched = File.binread(KERNEL_ELF_PATH, TRANSLATION_TABLES.to_binary.bytesize,
+                               BSP.kernel_tables_offset_in_file)
+
+        assert_equal(TRANSLATION_TABLES.to_binary, patched)
+    end
+end
@@ -11,9 +11,21 @@ require_relative 'minipush/progressbar_patch'
 require 'timeout'
 
 class ProtocolError < StandardError; end
+class ReloadRequest < StandardError; end
 
 # The main class
 class MiniPush < MiniTerm
+    # XMODEM control characters.
+    STX = 0x02
+    EOT = 0x04
+    ACK = 0x06
+    CAN = 0x18
+    CRC_MODE = 'C'
+
+    BLOCK_SIZE = 1024
+    BLOCK_PADDING = "\x1A"
+    MAX_RETRIES = 10
+
     def initialize(serial_name, payload_path)
         super(serial_name)
 
@@ -21,6 +33,8 @@ class MiniPush < MiniTerm
         @payload_path = payload_path
         @payload_size = nil
         @payload_data = nil
+        @after_request = ''
+        @request_token_count = 0
     end
 
     private
@@ -37,10 +51,14 @@ class MiniPush < MiniTerm
             loop do
                 raise ProtocolError if received.nil?
 
-                received.chars.each do |c|
+                received.chars.each_with_index do |c, i|
                     if c == "\u{3}"
                         count += 1
-                        return true if count == 3
+                        if count == 3
+                            # Keep what the target sent right after the request token.
+                            @after_request = received[(i + 1)..]
+                            return true
+                        end
                     else
                         # A normal character resets token counting.
                         count = 0
@@ -57,11 +75,66 @@ class MiniPush < MiniTerm
     def load_payload
         @payload_size = File.size(@payload_path)
         @payload_data = File.binread(@payload_path)
+
+        describe_elf_payload if @payload_data.start_with?("\x7FELF".b)
+    end
+
+    # The loader places ELF payloads according to their program headers. Show where they will go, so
+    # that a mismatch with the loader's load window is easy to spot.
+    def describe_elf_payload
+        entry, phoff = @payload_data.unpack('@24Q<Q<')
+        phentsize, phnum = @payload_data.unpack('@54S<S<')
+
+        puts "[#{@name_short}] 📦 ELF payload, entry point 0x#{entry.to_s(16)}"
+
+        phnum.times do |i|
+            type, _flags, _offset, _vaddr, paddr, filesz = @payload_data.unpack(
+                "@#{phoff + (i * phentsize)}L<L<Q<Q<Q<Q<"
+            )
+            next if type != 1 || filesz.zero?
+
+            puts "[#{@name_short}]    Segment 0x#{paddr.to_s(16)} - 0x#{(paddr + filesz).to_s(16)}"
+        end
+    end
+
+    # CRC-16/XMODEM: polynomial 0x1021, initial value zero.
+    def crc16(data)
+        data.each_byte.reduce(0) do |crc, byte|
+            crc ^= byte << 8
+            8.times { crc = (crc & 0x8000).zero? ? crc << 1 : (crc << 1) ^ 0x1021 }
+            crc & 0xFFFF
+        end
+    end
+
+    def read_response
+        Timeout.timeout(1) { @target_serial.read(1).ord }
+    rescue Timeout::Error
+        nil
+    end
+
+    # The receiver asks for a transfer in CRC mode.
+    def wait_for_crc_mode_request
+        return if @after_request.include?(CRC_MODE)
+
+        Timeout.timeout(10) do
+            loop { break if @target_serial.read(1) == CRC_MODE }
+        end
     end
 
-    def send_size
-        @target_serial.print([@payload_size].pack('L<'))
-        raise ProtocolError if @target_serial.read(2) != 'OK'
+    # Send a packet until the receiver acknowledges it. A missing response counts as a failure.
+    def send_with_retries(packet)
+        MAX_RETRIES.times do
+            @target_serial.write(packet)
+
+            case read_response
+            when ACK
+                return
+            when CAN
+                raise ProtocolError
+            end
+        end
+
+        raise ProtocolError
     end
 
     def send_payload
@@ -73,11 +146,43 @@ class MiniPush < MiniTerm
             output: $stdout
         )
 
-        # Send in 512 byte chunks.
-        while pb.progress < pb.total
-            part = @payload_data.slice(pb.progress, 512)
-            pb.progress += @target_serial.write(part)
+        wait_for_crc_mode_request
+
+        @payload_data.bytes.each_slice(BLOCK_SIZE).with_index(1) do |block, number|
+            data = block.pack('C*').ljust(BLOCK_SIZE, BLOCK_PADDING)
+            header = [STX, number % 256, 255 - (number % 256)].pack('C3')
+            packet = header + data + [crc16(data)].pack('S>')
+
+            send_with_retries(packet)
+            pb.progress += block.size
         end
+
+        send_with_retries(EOT.chr)
+    end
+
+    # A reboot of the target, for example through the kernel's reload key, starts the chainloader
+    # again. When its request token shows up in terminal mode, push the payload again.
+    #
+    # override
+    def handle_target_char(char)
+        if char == "\u{3}"
+            @request_token_count += 1
+            raise ReloadRequest if @request_token_count == 3
+
+            return
+        end
+
+        @request_token_count = 0
+        super
+    end
+
+    def handle_reload_request
+        @host_console.cooked!
+        @request_token_count = 0
+        @after_request = ''
+
+        puts
+        puts "[#{@name_short}] 🔁 Target requested the payload again"
     end
 
     # override
@@ -97,10 +202,15 @@ class MiniPush < MiniTerm
     def run
         open_serial
         wait_for_payload_request
-        load_payload
-        send_size
-        send_payload
-        terminal
+
+        loop do
+            load_payload
+            send_payload
+            terminal
+            break
+        rescue ReloadRequest
+            handle_reload_request
+        end
     rescue ConnectionError, EOFError, Errno::EIO, ProtocolError, Timeout::Error => e
         handle_reconnect(e)
         retry
@@ -55,6 +55,12 @@ class MiniTerm
         puts "[#{@name_short}] ✅ Serial connected"
     end
 
+    def handle_target_char(char)
+        # Translate incoming newline to newline + carriage return.
+        @host_console.putc("\r") if char == "\n"
+        @host_console.putc(char)
+    end
+
     def terminal
         @host_console.raw!
 
@@ -68,9 +74,7 @@ class MiniTerm
 
                 raise ConnectionError if char.nil?
 
-                # Translate incoming newline to newline + carriage return.
-                @host_console.putc("\r") if char == "\n"
-                @host_console.putc(char)
+                handle_target_char(char)
             end
         end
 
@@ -59,6 +59,7 @@ end
 # A test doing console I/O with the QEMU binary.
 class ConsoleIOTest < Test
     MAX_TIME_ALL_TESTS_SECONDS = 20
+    EXIT_WAIT_SECONDS = 1
 
     def initialize(qemu_cmd, test_name, console_subtests)
         super()
@@ -87,10 +88,20 @@ class ConsoleIOTest < Test
 
     # override
     def setup
-        qemu_out, @qemu_in = PTY.spawn(@qemu_cmd)
+        qemu_out, @qemu_in, @qemu_pid = PTY.spawn(@qemu_cmd)
         @qemu_out_wrapped = PTYLoggerWrapper.new(qemu_out)
     end
 
+    # Test binaries that report their result through semihosting make QEMU exit by themselves, and
+    # the exit status must then signal success. Binaries that keep running are left to the harness.
+    def check_exit_status
+        _, status = Timeout.timeout(EXIT_WAIT_SECONDS) { Process.wait2(@qemu_pid) }
+
+        @test_error = "QEMU exit status #{status.exitstatus} != 0" unless status.success?
+    rescue Timeout::Error
+        nil
+    end
+
     # override
     def finish
         @test_output << ''
@@ -107,6 +118,8 @@ class ConsoleIOTest < Test
                 run_subtest(t, i + 1, @qemu_out_wrapped, @qemu_in)
             end
         end
+
+        check_exit_status
     rescue Errno::EIO => e
         @test_error = "#{e.inspect} - QEMU might have quit early"
     rescue Timeout::Error
@@ -0,0 +1,60 @@
+#!/usr/bin/env ruby
+# frozen_string_literal: true
+
+# SPDX-License-Identifier: MIT OR Apache-2.0
+#
+# Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+# Convert the output of the kernel's trace::dump() into the Chrome trace event format.
+#
+# Usage: trace2chrome.rb <captured log> > trace.json
+
+require 'json'
+
+# IRQs become duration events, everything else instant events.
+PHASES = {
+    'irq_entry' => 'B',
+    'irq_exit' => 'E'
+}.freeze
+
+def to_event(core, nanoseconds, event, arg)
+    phase = PHASES.fetch(event, 'i')
+
+    {
+        name: phase == 'i' ? event : 'IRQ',
+        ph: phase,
+        ts: nanoseconds.to_i / 1000.0,
+        pid: 0,
+        tid: core.to_i,
+        s: 't',
+        args: { arg: arg }
+    }
+end
+
+def convert(lines)
+    events = []
+    in_trace = false
+
+    lines.each do |line|
+        # Strip anything that precedes the marker, e.g. terminal tool prefixes.
+        line = line[/TRACE.*/]
+        next if line.nil?
+
+        fields = line.split
+
+        case fields.first
+        when 'TRACE-BEGIN'
+            in_trace = true
+        when 'TRACE-END'
+            in_trace = false
+        when 'TRACE-LOST'
+            warn("Core #{fields[1]} lost #{fields[2]} records") if in_trace
+        when 'TRACE'
+            events << to_event(*fields[1..4]) if in_trace
+        end
+    end
+
+    { traceEvents: events, displayTimeUnit: 'ns' }
+end
+
+puts JSON.pretty_generate(convert(ARGF.each_line))
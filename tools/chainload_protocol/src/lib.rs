@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Definitions of the protocol spoken between the UART chainloader and the host's push tool.
+//!
+//! 1. The chainloader signals that it is ready with [REQUEST_TOKEN].
+//! 2. The payload is transferred with XMODEM-CRC. The receiver starts the transfer by sending
+//!    [CRC_MODE]. The sender transmits numbered blocks protected by a CRC-16, and ends the transfer
+//!    with [EOT].
+//!
+//! The crate is shared by the chainloader and the host tools, so it must stay `no_std`.
+//!
+//! # Resources
+//!
+//!   - <http://pauillac.inria.fr/~doligez/zmodem/ymodem.txt>
+
+#![no_std]
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Sent by the chainloader when it is ready to receive a payload.
+pub const REQUEST_TOKEN: [u8; 3] = [3, 3, 3];
+
+/// Starts a block of [SMALL_BLOCK_SIZE] bytes.
+pub const SOH: u8 = 0x01;
+
+/// Starts a block of [BLOCK_SIZE] bytes.
+pub const STX: u8 = 0x02;
+
+/// Ends the transfer.
+pub const EOT: u8 = 0x04;
+
+/// Acknowledges a block.
+pub const ACK: u8 = 0x06;
+
+/// Requests a retransmission of a block.
+pub const NAK: u8 = 0x15;
+
+/// Cancels the transfer.
+pub const CAN: u8 = 0x18;
+
+/// Requests a transfer in CRC mode.
+pub const CRC_MODE: u8 = b'C';
+
+/// The size of a block started with [SOH].
+pub const SMALL_BLOCK_SIZE: usize = 128;
+
+/// The size of a block started with [STX].
+pub const BLOCK_SIZE: usize = 1024;
+
+/// Pads the last block.
+pub const BLOCK_PADDING: u8 = 0x1A;
+
+/// The number of consecutive failures after which a transfer is cancelled.
+pub const MAX_RETRIES: usize = 10;
+
+/// The header of a block: start character, block number and its complement.
+pub type BlockHeader = [u8; 3];
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// CRC-16/XMODEM: polynomial 0x1021, initial value zero.
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// The header of a [BLOCK_SIZE] block. Block numbers start at one and wrap around.
+pub fn block_header(number: usize) -> BlockHeader {
+    let number = number as u8;
+
+    [STX, number, !number]
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc16_matches_reference() {
+        // The check value of CRC-16/XMODEM.
+        assert_eq!(crc16(b"123456789"), 0x31C3);
+        assert_eq!(crc16(&[]), 0);
+    }
+
+    #[test]
+    fn block_numbers_wrap() {
+        assert_eq!(block_header(1), [STX, 1, 254]);
+        assert_eq!(block_header(256), [STX, 0, 255]);
+    }
+}
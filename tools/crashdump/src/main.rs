@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Pretty-print a kernel crash dump from a captured console log.
+//!
+//! ```text
+//! crashdump [<log file>]
+//! ```
+//!
+//! Without a log file, the log is read from stdin. If the log holds more than one dump, the last
+//! one is printed. The format is described in the kernel's `debug::crashdump` module.
+
+use std::{
+    fmt, fs, io,
+    io::{BufRead, BufReader, Read},
+    process,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The dump format version that this tool understands.
+const FORMAT_VERSION: u32 = 1;
+
+const USAGE: &str = "Usage: crashdump [<log file>]";
+
+/// The register names, in the numbering of the kernel's GDB stub.
+const REGISTER_NAMES: [&str; 34] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+    "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
+    "x28", "x29", "x30", "sp", "pc", "cpsr",
+];
+
+/// The trace records shown at most.
+const MAX_TRACE_RECORDS: usize = 32;
+
+/// A parsed crash dump.
+#[derive(Debug, Default, PartialEq)]
+struct Dump {
+    version: u32,
+    kernel_version: String,
+    core: Option<u32>,
+    uptime_ns: Option<u64>,
+    location: Option<String>,
+    message: Vec<String>,
+    assertion: Option<String>,
+    assertion_values: Vec<String>,
+    registers: Vec<(usize, u64)>,
+    frames: Vec<u64>,
+    trace: Vec<String>,
+    log: Vec<String>,
+    mem: Vec<(u64, Vec<u8>)>,
+    mem_unmapped: Option<String>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+fn parse_bytes(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Add a tagged line to `dump`.
+fn parse_line(dump: &mut Dump, line: &str) -> Result<(), String> {
+    let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let invalid = || format!("Invalid line: {}", line);
+
+    match tag {
+        "CRASH-VERSION" => dump.kernel_version = rest.to_string(),
+        "CRASH-CORE" => dump.core = Some(rest.parse().map_err(|_| invalid())?),
+        "CRASH-UPTIME" => dump.uptime_ns = Some(rest.parse().map_err(|_| invalid())?),
+        "CRASH-LOCATION" => dump.location = Some(rest.to_string()),
+        "CRASH-MSG" => dump.message.push(rest.to_string()),
+        "CRASH-ASSERT" => dump.assertion = Some(rest.to_string()),
+        "CRASH-ASSERT-VALUE" => dump.assertion_values.push(rest.to_string()),
+        "CRASH-REG" => {
+            let (n, value) = rest.split_once(' ').ok_or_else(invalid)?;
+            let n = n.parse().map_err(|_| invalid())?;
+
+            dump.registers
+                .push((n, parse_hex(value).ok_or_else(invalid)?));
+        }
+        "CRASH-FRAME" => dump.frames.push(parse_hex(rest).ok_or_else(invalid)?),
+        "TRACE" | "TRACE-LOST" => dump.trace.push(line.to_string()),
+        "TRACE-BEGIN" | "TRACE-END" => (),
+        "CRASH-LOG" => dump.log.push(rest.to_string()),
+        "CRASH-MEM" => {
+            let (addr, bytes) = rest.split_once(' ').ok_or_else(invalid)?;
+
+            dump.mem.push((
+                parse_hex(addr).ok_or_else(invalid)?,
+                parse_bytes(bytes).ok_or_else(invalid)?,
+            ));
+        }
+        "CRASH-MEM-UNMAPPED" => dump.mem_unmapped = Some(rest.to_string()),
+        // Output of other cores can interleave with the dump.
+        _ => (),
+    }
+
+    Ok(())
+}
+
+/// Find the last complete dump in `log`.
+fn parse(log: impl BufRead) -> Result<Dump, String> {
+    let mut current: Option<Dump> = None;
+    let mut last = None;
+
+    for line in log.split(b'\n') {
+        let line = line.map_err(|e| format!("Cannot read the log: {}", e))?;
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+
+        if let Some(version) = line.strip_prefix("CRASHDUMP-BEGIN ") {
+            let version = version
+                .parse()
+                .map_err(|_| format!("Invalid line: {}", line))?;
+            if version != FORMAT_VERSION {
+                return Err(format!("Unsupported dump format version {}", version));
+            }
+
+            current = Some(Dump {
+                version,
+                ..Dump::default()
+            });
+            continue;
+        }
+
+        let dump = match current.as_mut() {
+            None => continue,
+            Some(x) => x,
+        };
+
+        if line == "CRASHDUMP-END" {
+            last = current.take();
+            continue;
+        }
+
+        parse_line(dump, line)?;
+    }
+
+    match (last, current) {
+        (Some(dump), _) => Ok(dump),
+        (None, Some(_)) => Err("The log ends in the middle of a crash dump".to_string()),
+        (None, None) => Err("The log holds no crash dump".to_string()),
+    }
+}
+
+fn register_name(n: usize) -> String {
+    REGISTER_NAMES
+        .get(n)
+        .map_or_else(|| format!("r{}", n), |name| name.to_string())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl fmt::Display for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Kernel panic")?;
+        if let Some(core) = self.core {
+            write!(f, " on core {}", core)?;
+        }
+        if let Some(ns) = self.uptime_ns {
+            write!(
+                f,
+                " after {}.{:06} s",
+                ns / 1_000_000_000,
+                ns % 1_000_000_000 / 1_000
+            )?;
+        }
+        writeln!(f)?;
+
+        if !self.kernel_version.is_empty() {
+            writeln!(f, "  {}", self.kernel_version)?;
+        }
+        if let Some(location) = &self.location {
+            writeln!(f, "  at {}", location)?;
+        }
+
+        writeln!(f, "\nMessage:")?;
+        for line in &self.message {
+            writeln!(f, "  {}", line)?;
+        }
+
+        if let Some(expr) = &self.assertion {
+            writeln!(f, "\nFailed assertion:")?;
+            writeln!(f, "  {}", expr)?;
+            for value in &self.assertion_values {
+                writeln!(f, "    {}", value)?;
+            }
+        }
+
+        if !self.registers.is_empty() {
+            writeln!(f, "\nRegisters:")?;
+            for pair in self.registers.chunks(2) {
+                for &(n, value) in pair {
+                    write!(f, "  {:>4}: {:#018x}", register_name(n), value)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        writeln!(f, "\nBacktrace:")?;
+        for (i, addr) in self.frames.iter().enumerate() {
+            writeln!(f, "  #{:<2} {:#018x}", i, addr)?;
+        }
+        if !self.frames.is_empty() {
+            let addrs: Vec<String> = self.frames.iter().map(|a| format!("{:#x}", a)).collect();
+
+            writeln!(f, "\n  Resolve with:")?;
+            writeln!(f, "  addr2line -fipC -e <kernel ELF> {}", addrs.join(" "))?;
+        }
+
+        if !self.trace.is_empty() {
+            let skipped = self.trace.len().saturating_sub(MAX_TRACE_RECORDS);
+
+            writeln!(
+                f,
+                "\nTrace ({} records, showing the last {}):",
+                self.trace.len(),
+                self.trace.len() - skipped
+            )?;
+            for record in &self.trace[skipped..] {
+                writeln!(f, "  {}", record)?;
+            }
+        }
+
+        if !self.log.is_empty() {
+            writeln!(f, "\nLog:")?;
+            for line in &self.log {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+
+        writeln!(f, "\nMemory:")?;
+        if let Some(unmapped) = &self.mem_unmapped {
+            writeln!(f, "  Not mapped: {}", unmapped)?;
+        }
+        for (addr, bytes) in &self.mem {
+            write!(f, "  {:016x} ", addr)?;
+            for i in 0..16 {
+                match bytes.get(i) {
+                    Some(b) => write!(f, " {:02x}", b)?,
+                    None => write!(f, "   ")?,
+                }
+            }
+
+            let ascii: String = bytes
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+                .collect();
+            writeln!(f, "  {}", ascii)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut argv = std::env::args().skip(1);
+    let path = argv.next();
+
+    if argv.next().is_some() || matches!(path.as_deref(), Some("-h" | "--help")) {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+
+    let log: Box<dyn Read> = match &path {
+        None => Box::new(io::stdin()),
+        Some(path) => Box::new(fs::File::open(path).unwrap_or_else(|e| {
+            eprintln!("Cannot open {}: {}", path, e);
+            process::exit(1);
+        })),
+    };
+
+    match parse(BufReader::new(log)) {
+        Ok(dump) => print!("{}", dump),
+        Err(msg) => {
+            eprintln!("{}", msg);
+            process::exit(1);
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Testing
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "[    0.123456] Booting\r\n\
+        CRASHDUMP-BEGIN 1\r\n\
+        CRASH-CORE 0\r\n\
+        CRASH-MSG first\r\n\
+        CRASHDUMP-END\r\n\
+        noise\n\
+        CRASHDUMP-BEGIN 1\n\
+        CRASH-CORE 2\n\
+        CRASH-UPTIME 1500000000\n\
+        CRASH-LOCATION src/main.rs:10:5\n\
+        CRASH-MSG CPU Exception!\n\
+        CRASH-MSG ESR_EL1: 0x96000004\n\
+        CRASH-ASSERT used <= total\n\
+        CRASH-ASSERT-VALUE used = 3\n\
+        CRASH-REG 0 0x1\n\
+        CRASH-REG 32 0xffffffffc0001234\n\
+        CRASH-FRAME 0xffffffffc0001234\n\
+        [  1.0] interleaved output of another core\n\
+        TRACE-BEGIN\n\
+        TRACE 0 100 mark 0x0\n\
+        TRACE-END\n\
+        CRASH-LOG [    0.123456] Booting\n\
+        CRASH-MEM 0xffffffffc0100000 41420001\n\
+        CRASHDUMP-END\n";
+
+    #[test]
+    fn last_dump_is_parsed() {
+        let dump = parse(LOG.as_bytes()).unwrap();
+
+        assert_eq!(dump.core, Some(2));
+        assert_eq!(dump.uptime_ns, Some(1_500_000_000));
+        assert_eq!(dump.location.as_deref(), Some("src/main.rs:10:5"));
+        assert_eq!(dump.message, ["CPU Exception!", "ESR_EL1: 0x96000004"]);
+        assert_eq!(dump.assertion.as_deref(), Some("used <= total"));
+        assert_eq!(dump.assertion_values, ["used = 3"]);
+        assert_eq!(dump.registers, [(0, 1), (32, 0xffff_ffff_c000_1234)]);
+        assert_eq!(dump.frames, [0xffff_ffff_c000_1234]);
+        assert_eq!(dump.trace, ["TRACE 0 100 mark 0x0"]);
+        assert_eq!(dump.log, ["[    0.123456] Booting"]);
+        assert_eq!(
+            dump.mem,
+            [(0xffff_ffff_c010_0000, vec![0x41, 0x42, 0x00, 0x01])]
+        );
+    }
+
+    #[test]
+    fn incomplete_dump_is_rejected() {
+        parse("CRASHDUMP-BEGIN 1\nCRASH-CORE 0\n".as_bytes()).unwrap_err();
+        parse("CRASHDUMP-BEGIN 2\nCRASHDUMP-END\n".as_bytes()).unwrap_err();
+        parse("no dump\n".as_bytes()).unwrap_err();
+    }
+
+    #[test]
+    fn registers_are_named() {
+        assert_eq!(register_name(29), "x29");
+        assert_eq!(register_name(31), "sp");
+        assert_eq!(register_name(33), "cpsr");
+        assert_eq!(register_name(40), "r40");
+    }
+}
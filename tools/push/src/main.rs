@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Push a kernel to the UART chainloader and open a terminal to the target.
+//!
+//! ```text
+//! push [--port <serial device>] [--baud <rate>] [<payload>]
+//! ```
+//!
+//! Without `--port`, the first USB serial adapter is used. Without a payload, only the terminal is
+//! opened, and `push` works like `miniterm`.
+//!
+//! If the target reboots into the chainloader while the terminal is open, the payload is pushed
+//! again.
+
+mod port;
+mod terminal;
+mod xmodem;
+
+use chainload_protocol::REQUEST_TOKEN;
+use serialport::SerialPort;
+use std::{
+    fmt, fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+const DEFAULT_BAUD_RATE: u32 = 921_600;
+
+const USAGE: &str = "Usage: push [--port <serial device>] [--baud <rate>] [<payload>]";
+
+struct Args {
+    port: Option<String>,
+    baud_rate: u32,
+    payload: Option<PathBuf>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Errors that end a session with the target.
+#[derive(Debug)]
+pub enum Error {
+    /// The serial device failed, most likely because it was unplugged.
+    Io(io::Error),
+
+    /// The chainloader did not follow the protocol.
+    Protocol(&'static str),
+}
+
+/// What a received byte means for [REQUEST_TOKEN].
+pub enum RequestToken {
+    /// The byte completed the token.
+    Complete,
+
+    /// The byte continued the token.
+    Partial,
+
+    /// The byte is regular output.
+    None,
+}
+
+/// Finds [REQUEST_TOKEN] in the target's output.
+#[derive(Default)]
+pub struct RequestTokenDetector {
+    matched: usize,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        port: None,
+        baud_rate: DEFAULT_BAUD_RATE,
+        payload: None,
+    };
+    let mut argv = std::env::args().skip(1);
+
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--port" => args.port = Some(argv.next().ok_or("--port needs a value")?),
+            "--baud" => {
+                args.baud_rate = argv
+                    .next()
+                    .and_then(|rate| rate.parse().ok())
+                    .ok_or("--baud needs a number")?
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if args.payload.is_none() && !arg.starts_with('-') => {
+                args.payload = Some(PathBuf::from(arg))
+            }
+            _ => return Err(format!("Unexpected argument: {}\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(args)
+}
+
+/// Print the target's output until it sends the request token.
+fn wait_for_payload_request(port: &mut dyn SerialPort) -> Result<(), Error> {
+    println!("[MP] 🔌 Please power the target now");
+
+    let mut token = RequestTokenDetector::new();
+    let mut stdout = io::stdout();
+
+    loop {
+        if let Some(byte) = read_byte(port)? {
+            match token.feed(byte) {
+                RequestToken::Complete => return Ok(()),
+                RequestToken::Partial => (),
+                RequestToken::None => {
+                    stdout.write_all(&[byte])?;
+                    stdout.flush()?;
+                }
+            }
+        }
+    }
+}
+
+/// Talk to the target until the user quits.
+fn session(
+    port: &mut dyn SerialPort,
+    terminal: &terminal::Terminal,
+    payload: Option<&Path>,
+) -> Result<(), Error> {
+    let path = match payload {
+        None => {
+            terminal.run(port, false)?;
+            return Ok(());
+        }
+        Some(path) => path,
+    };
+
+    wait_for_payload_request(port)?;
+
+    loop {
+        // Read the payload anew every time, so that a rebuilt kernel is picked up.
+        let data = fs::read(path)?;
+        xmodem::send(port, &data)?;
+
+        match terminal.run(port, true)? {
+            terminal::Exit::Quit => return Ok(()),
+            terminal::Exit::RequestToken => {
+                println!("\n[MP] 🔁 Target requested the payload again");
+            }
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "Connection error: {}", e),
+            Error::Protocol(msg) => write!(f, "Protocol error: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl RequestTokenDetector {
+    /// Create an instance.
+    pub fn new() -> Self {
+        Self { matched: 0 }
+    }
+
+    /// Check the next received byte.
+    ///
+    /// Any other byte resets the detection. Bytes of an incomplete token are dropped.
+    pub fn feed(&mut self, byte: u8) -> RequestToken {
+        if byte != REQUEST_TOKEN[self.matched] {
+            self.matched = 0;
+            return RequestToken::None;
+        }
+
+        self.matched += 1;
+        if self.matched < REQUEST_TOKEN.len() {
+            return RequestToken::Partial;
+        }
+
+        self.matched = 0;
+        RequestToken::Complete
+    }
+}
+
+/// Read a byte from the serial device, waiting at most [port::POLL_INTERVAL].
+pub fn read_byte(port: &mut dyn SerialPort) -> Result<Option<u8>, Error> {
+    let mut byte = [0];
+
+    match port.read(&mut byte) {
+        Ok(0) => Err(Error::Io(io::ErrorKind::UnexpectedEof.into())),
+        Ok(_) => Ok(Some(byte[0])),
+        Err(e) if e.kind() == io::ErrorKind::TimedOut => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|msg| {
+        eprintln!("{}", msg);
+        process::exit(1);
+    });
+
+    if let Some(path) = &args.payload {
+        if let Err(e) = fs::metadata(path) {
+            eprintln!("[MP] Cannot read {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+
+    println!("\nPush {}\n", env!("CARGO_PKG_VERSION"));
+
+    let terminal = terminal::Terminal::new();
+
+    loop {
+        let mut port = port::open(args.port.as_deref(), args.baud_rate).unwrap_or_else(|e| {
+            eprintln!("[MP] ⚡ Cannot open the serial device: {}", e);
+            process::exit(1);
+        });
+
+        match session(&mut *port, &terminal, args.payload.as_deref()) {
+            Ok(()) => break,
+            Err(e) => {
+                println!("\n[MP] ⚡ {}", e);
+                println!("[MP] ⚡ Remove power and USB serial. Reinsert serial first, then power");
+
+                if let Some(name) = port.name() {
+                    drop(port);
+                    port::wait_for_removal(&name);
+                }
+            }
+        }
+    }
+
+    println!("\n[MP] Bye 👋");
+}
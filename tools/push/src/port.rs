@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! Serial device detection.
+
+use serialport::{SerialPort, SerialPortType};
+use std::{path::Path, thread, time::Duration};
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Reads from the serial device wait at most this long for data.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+/// The first USB serial adapter, which is what the Raspberry's UART is usually connected with.
+fn detect() -> Option<String> {
+    serialport::available_ports()
+        .ok()?
+        .into_iter()
+        .find(|port| matches!(port.port_type, SerialPortType::UsbPort(_)))
+        .map(|port| port.port_name)
+}
+
+fn find(name: Option<&str>) -> Option<String> {
+    match name {
+        Some(name) => Path::new(name).exists().then(|| name.to_string()),
+        None => detect(),
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Open serial device `name`, or the first USB serial adapter if `name` is `None`.
+///
+/// Waits until the device shows up.
+pub fn open(name: Option<&str>, baud_rate: u32) -> serialport::Result<Box<dyn SerialPort>> {
+    let mut announced = false;
+
+    loop {
+        if let Some(path) = find(name) {
+            match serialport::new(&path, baud_rate)
+                .timeout(POLL_INTERVAL)
+                .open()
+            {
+                Ok(port) => {
+                    println!("[MP] ✅ Serial connected: {}", path);
+                    return Ok(port);
+                }
+                // The device might still be settling after it was plugged in.
+                Err(e) if e.kind() == serialport::ErrorKind::NoDevice => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !announced {
+            println!(
+                "[MP] ⏳ Waiting for {}",
+                name.unwrap_or("a USB serial device")
+            );
+            announced = true;
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Wait until serial device `name` is gone.
+pub fn wait_for_removal(name: &str) {
+    while Path::new(name).exists() {
+        thread::sleep(Duration::from_secs(1));
+    }
+}
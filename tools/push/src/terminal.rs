@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! A terminal to the target.
+//!
+//! Host console input is forwarded unchanged, so that control characters reach the target. `CTRL +
+//! C` quits.
+
+use crate::{read_byte, Error, RequestToken, RequestTokenDetector};
+use serialport::SerialPort;
+use std::{
+    io::{self, Read, Write},
+    sync::mpsc,
+    thread,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+const CTRL_C: u8 = 0x03;
+
+/// Restores the host console when going out of scope.
+struct RawMode;
+
+//--------------------------------------------------------------------------------------------------
+// Public Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Why the terminal was left.
+pub enum Exit {
+    /// The user pressed `CTRL + C`.
+    Quit,
+
+    /// The chainloader asked for a payload.
+    RequestToken,
+}
+
+/// Host console input.
+pub struct Terminal {
+    input: mpsc::Receiver<u8>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+fn print_target_byte(out: &mut impl Write, byte: u8) -> io::Result<()> {
+    // Translate incoming newline to newline + carriage return.
+    if byte == b'\n' {
+        out.write_all(b"\r")?;
+    }
+    out.write_all(&[byte])?;
+    out.flush()
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+impl Terminal {
+    /// Start reading the host console.
+    ///
+    /// Reading blocks, so it happens in a thread that outlives single terminal sessions.
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for byte in io::stdin().bytes() {
+                match byte {
+                    Ok(byte) if tx.send(byte).is_ok() => (),
+                    _ => break,
+                }
+            }
+        });
+
+        Self { input: rx }
+    }
+
+    /// Connect the host console with the target until the user quits or the chainloader requests
+    /// a payload.
+    ///
+    /// If `watch_for_request` is false, the request token is passed through like any other output.
+    pub fn run(&self, port: &mut dyn SerialPort, watch_for_request: bool) -> Result<Exit, Error> {
+        let _raw_mode = RawMode::enable()?;
+        let mut stdout = io::stdout();
+        let mut token = RequestTokenDetector::new();
+
+        // Drop keystrokes from before the terminal was opened.
+        while self.input.try_recv().is_ok() {}
+
+        loop {
+            while let Ok(byte) = self.input.try_recv() {
+                if byte == CTRL_C {
+                    return Ok(Exit::Quit);
+                }
+
+                port.write_all(&[byte])?;
+            }
+
+            if let Some(byte) = read_byte(port)? {
+                if !watch_for_request {
+                    print_target_byte(&mut stdout, byte)?;
+                    continue;
+                }
+
+                match token.feed(byte) {
+                    RequestToken::Complete => return Ok(Exit::RequestToken),
+                    RequestToken::Partial => (),
+                    RequestToken::None => print_target_byte(&mut stdout, byte)?,
+                }
+            }
+        }
+    }
+}
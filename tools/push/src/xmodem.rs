@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//
+// Copyright (c) 2022 Andre Richter <andre.o.richter@gmail.com>
+
+//! XMODEM-CRC sender.
+
+use crate::{read_byte, Error};
+use chainload_protocol::{
+    block_header, crc16, ACK, BLOCK_PADDING, BLOCK_SIZE, CAN, CRC_MODE, EOT, MAX_RETRIES,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use serialport::SerialPort;
+use std::time::{Duration, Instant};
+
+//--------------------------------------------------------------------------------------------------
+// Private Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// How long the receiver may take to request the transfer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the receiver may take to answer a block.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+//--------------------------------------------------------------------------------------------------
+// Private Code
+//--------------------------------------------------------------------------------------------------
+
+fn wait_for_crc_mode_request(port: &mut dyn SerialPort) -> Result<(), Error> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+
+    while Instant::now() < deadline {
+        if read_byte(port)? == Some(CRC_MODE) {
+            return Ok(());
+        }
+    }
+
+    Err(Error::Protocol("Timeout waiting for the transfer request"))
+}
+
+fn read_response(port: &mut dyn SerialPort) -> Result<Option<u8>, Error> {
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+
+    while Instant::now() < deadline {
+        if let Some(byte) = read_byte(port)? {
+            return Ok(Some(byte));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Send a packet until the receiver acknowledges it. A missing response counts as a failure.
+fn send_with_retries(port: &mut dyn SerialPort, packet: &[u8]) -> Result<(), Error> {
+    for _ in 0..MAX_RETRIES {
+        port.write_all(packet)?;
+
+        match read_response(port)? {
+            Some(ACK) => return Ok(()),
+            Some(CAN) => return Err(Error::Protocol("Transfer cancelled by the receiver")),
+            _ => (),
+        }
+    }
+
+    Err(Error::Protocol("Too many retries"))
+}
+
+//--------------------------------------------------------------------------------------------------
+// Public Code
+//--------------------------------------------------------------------------------------------------
+
+/// Send `payload` in blocks of [BLOCK_SIZE] bytes, showing the progress.
+pub fn send(port: &mut dyn SerialPort, payload: &[u8]) -> Result<(), Error> {
+    let pb = ProgressBar::new(payload.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(
+                "[MP] ⏩ Pushing {bytes} {wide_bar} {percent}% {binary_bytes_per_sec} {elapsed}",
+            )
+            .progress_chars("=> "),
+    );
+
+    wait_for_crc_mode_request(port)?;
+
+    for (i, block) in payload.chunks(BLOCK_SIZE).enumerate() {
+        let mut data = [BLOCK_PADDING; BLOCK_SIZE];
+        data[..block.len()].copy_from_slice(block);
+
+        let mut packet = Vec::with_capacity(BLOCK_SIZE + 5);
+        packet.extend_from_slice(&block_header(i + 1));
+        packet.extend_from_slice(&data);
+        packet.extend_from_slice(&crc16(&data).to_be_bytes());
+
+        send_with_retries(port, &packet)?;
+        pb.inc(block.len() as u64);
+    }
+
+    send_with_retries(port, &[EOT])?;
+    pb.finish();
+
+    Ok(())
+}